@@ -0,0 +1,35 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use meshtastic_connect::framing::{
+    FrameDecoder, MeshtasticStreamHeader, STREAM_PACKET_SIZE_MAX, StreamRecvData,
+};
+
+// Incomplete frame is the only data kept by decoder
+const BUFFERED_MAX: usize = size_of::<MeshtasticStreamHeader>() + STREAM_PACKET_SIZE_MAX as usize;
+
+// First byte sets chunk size: the same stream is cut in different ways,
+// as serial port or socket could deliver it
+fuzz_target!(|data: &[u8]| {
+    let Some((&chunk_size, stream)) = data.split_first() else {
+        return;
+    };
+
+    let mut decoder = FrameDecoder::new();
+    for chunk in stream.chunks(chunk_size.max(1) as usize) {
+        decoder.push(chunk);
+
+        loop {
+            let buffered = decoder.buffered().len();
+            let Some(recv_data) = decoder.decode() else {
+                break;
+            };
+            // Every decoded item consumes bytes
+            assert!(decoder.buffered().len() < buffered);
+            if let Ok(StreamRecvData::Unstructured(bytes)) = recv_data {
+                assert!(!bytes.is_empty());
+            }
+        }
+        assert!(decoder.buffered().len() < BUFFERED_MAX);
+    }
+});
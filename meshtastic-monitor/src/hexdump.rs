@@ -0,0 +1,154 @@
+// Serial frame: START1, START2, length (big endian u16), protobuf
+const FRAME_MAGIC: [u8; 2] = [0x94, 0xc3];
+const FRAME_HEADER_LEN: usize = 4;
+
+fn hex_lines(bytes: &[u8], base_offset: usize) -> Vec<String> {
+    bytes
+        .chunks(16)
+        .enumerate()
+        .map(|(index, chunk)| {
+            let hex = chunk
+                .iter()
+                .enumerate()
+                .map(|(i, b)| {
+                    // Extra space between halves of line
+                    if i == 8 {
+                        format!(" {:02x}", b)
+                    } else {
+                        format!("{:02x}", b)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            let ascii = chunk
+                .iter()
+                .map(|b| {
+                    if b.is_ascii_graphic() || *b == b' ' {
+                        *b as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect::<String>();
+            format!("{:08x}  {:<49} |{}|", base_offset + index * 16, hex, ascii)
+        })
+        .collect()
+}
+
+fn read_varint(bytes: &[u8], offset: &mut usize) -> Option<u64> {
+    let mut value: u64 = 0;
+    for shift in (0..64).step_by(7) {
+        let byte = *bytes.get(*offset)?;
+        *offset += 1;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+// Top level protobuf's fields, until the first malformed one
+fn protobuf_fields(bytes: &[u8], base_offset: usize) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut offset = 0;
+
+    while offset < bytes.len() {
+        let start = offset;
+        let Some(key) = read_varint(bytes, &mut offset) else {
+            fields.push(format!("{:08x}  <truncated key>", base_offset + start));
+            break;
+        };
+        let field_number = key >> 3;
+        let description = match key & 0x7 {
+            0 => read_varint(bytes, &mut offset).map(|v| format!("varint {}", v)),
+            1 => bytes.get(offset..offset + 8).map(|v| {
+                offset += 8;
+                format!("fixed64 {:02x?}", v)
+            }),
+            2 => read_varint(bytes, &mut offset).and_then(|len| {
+                let len = len as usize;
+                let value = bytes.get(offset..offset.checked_add(len)?)?;
+                offset += len;
+                Some(match std::str::from_utf8(value) {
+                    Ok(text) if !text.is_empty() && !text.contains(char::is_control) => {
+                        format!("len {} {:?}", len, text)
+                    }
+                    _ => format!("len {}", len),
+                })
+            }),
+            5 => bytes.get(offset..offset + 4).map(|v| {
+                offset += 4;
+                format!("fixed32 {:02x?}", v)
+            }),
+            wire_type => {
+                fields.push(format!(
+                    "{:08x}  #{} <unknown wire type {}>",
+                    base_offset + start,
+                    field_number,
+                    wire_type
+                ));
+                break;
+            }
+        };
+
+        match description {
+            Some(description) if field_number != 0 => fields.push(format!(
+                "{:08x}  #{} {}",
+                base_offset + start,
+                field_number,
+                description
+            )),
+            _ => {
+                fields.push(format!(
+                    "{:08x}  #{} <truncated>",
+                    base_offset + start,
+                    field_number
+                ));
+                break;
+            }
+        }
+    }
+    fields
+}
+
+// Hex and ASCII dump with offsets, followed by protobuf's fields
+// if `protobuf` is set (pointless for encrypted data).
+// Serial frame's header is annotated separately from the payload
+pub fn hexdump(bytes: &[u8], protobuf: bool) -> String {
+    let mut lines = Vec::new();
+    let mut payload = bytes;
+    let mut payload_offset = 0;
+
+    if bytes.len() >= FRAME_HEADER_LEN && bytes[..2] == FRAME_MAGIC {
+        let length = u16::from_be_bytes([bytes[2], bytes[3]]) as usize;
+        lines.push(format!(
+            "{:08x}  {:02x} {:02x} {:02x} {:02x}  [frame magic, length {}{}]",
+            0,
+            bytes[0],
+            bytes[1],
+            bytes[2],
+            bytes[3],
+            length,
+            if bytes.len() - FRAME_HEADER_LEN < length {
+                ", truncated"
+            } else {
+                ""
+            }
+        ));
+        payload = &bytes[FRAME_HEADER_LEN..];
+        payload_offset = FRAME_HEADER_LEN;
+    }
+
+    lines.extend(hex_lines(payload, payload_offset));
+    let fields = if protobuf {
+        protobuf_fields(payload, payload_offset)
+    } else {
+        Vec::new()
+    };
+    if !fields.is_empty() {
+        lines.push("  protobuf:".to_string());
+        lines.extend(fields.into_iter().map(|v| format!("  {}", v)));
+    }
+    lines.join("\n")
+}
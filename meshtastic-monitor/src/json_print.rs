@@ -0,0 +1,77 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use base64::{Engine, engine::general_purpose};
+use meshtastic_connect::stored::{DataVariant, DecryptTarget, StoredMeshPacket};
+use meshtastic_connect::{
+    keyring::{Keyring, node_id::NodeId},
+    meshtastic::MeshPacket,
+};
+use serde::Serialize;
+
+// Same shape as softnode's journal, plus fields resolved by monitor
+#[derive(Serialize)]
+struct JsonPacket {
+    #[serde(flatten)]
+    packet: StoredMeshPacket,
+    // Name of channel, which key is matched
+    channel_name: Option<String>,
+    portnum: Option<String>,
+}
+
+#[derive(Serialize)]
+struct JsonError {
+    error: String,
+    // Base64 encoded raw data
+    payload: String,
+}
+
+static SEQUENCE_NUMBER: AtomicU64 = AtomicU64::new(0);
+
+fn print_line<T: Serialize>(value: &T) {
+    match serde_json::to_string(value) {
+        Ok(line) => println!("{}", line),
+        Err(e) => log::error!("JSON serialization error: {}", e),
+    }
+}
+
+// One JSON object per line
+pub fn print_packet(
+    mesh_packet: &MeshPacket,
+    gateway: Option<NodeId>,
+    connection_hint: Option<String>,
+    keyring: &Keyring,
+) {
+    let sequence_number = SEQUENCE_NUMBER.fetch_add(1, Ordering::Relaxed);
+    let packet = StoredMeshPacket::from_mesh_packet(
+        sequence_number,
+        gateway,
+        "monitor".to_string(),
+        connection_hint,
+        mesh_packet,
+    )
+    .decrypt(keyring, false);
+
+    let (channel_name, portnum) = match &packet.data {
+        Some(DataVariant::Decrypted(target, data)) => {
+            let channel_name = match target {
+                DecryptTarget::Channel(name) | DecryptTarget::Guessed(name) => Some(name.clone()),
+                DecryptTarget::Direct(_) | DecryptTarget::PKI => None,
+            };
+            (channel_name, Some(data.portnum().as_str_name().to_string()))
+        }
+        _ => (None, None),
+    };
+
+    print_line(&JsonPacket {
+        packet,
+        channel_name,
+        portnum,
+    });
+}
+
+pub fn print_error(error: impl std::fmt::Display, payload: &[u8]) {
+    print_line(&JsonError {
+        error: error.to_string(),
+        payload: general_purpose::STANDARD.encode(payload),
+    });
+}
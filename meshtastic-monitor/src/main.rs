@@ -1,22 +1,31 @@
+mod hexdump;
+mod json_print;
 mod meshtastic_print;
+mod pcap;
+mod replay;
 
 use clap::Parser;
 use futures::{SinkExt, StreamExt};
 use meshtastic_connect::keyring;
-use meshtastic_connect::meshtastic::Heartbeat;
 use meshtastic_connect::meshtastic::to_radio::PayloadVariant;
-use meshtastic_connect::transport::stream::Stream;
+use meshtastic_connect::meshtastic::{self, Heartbeat};
+use meshtastic_connect::transport::stream::{RadioConfig, Stream};
 use meshtastic_connect::transport::udp::{Interface, Multicast};
 use meshtastic_connect::transport::{
     stream, stream::serial::SerialBuilder, stream::tcp::TcpBuilder, udp::UdpBuilder,
 };
-use meshtastic_print::{print_from_radio_payload, print_mesh_packet, print_service_envelope};
+use meshtastic_print::{
+    OutputFormat, PrintOptions, from_radio_summary, mesh_packet_summary, print_from_radio_payload,
+    print_mesh_packet, print_service_envelope, service_envelope_summary,
+};
+use pcap::PcapWriter;
+use prost::Message;
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use serde_yaml_ng::from_reader;
 
 use chrono::Local;
 use keyring::{
-    Keyring,
+    ChannelImport, Keyring,
     key::{K256, Key},
     node_id::NodeId,
 };
@@ -25,7 +34,6 @@ use std::net::{Ipv4Addr, SocketAddrV4};
 use std::process::exit;
 use std::time::Duration;
 use std::{fs::File, io::BufReader, net::SocketAddr};
-use tokio::io::AsyncWriteExt;
 use tokio::time::Instant;
 
 #[derive(clap::Parser, Debug)]
@@ -37,6 +45,32 @@ struct Args {
     // Path to file with keys to decode Peers and Channels messages
     #[arg(short, long, default_value_t = String::from("keys.yaml"))]
     keys_file: String,
+    // Level of diagnostics: error, warn, info, debug or trace.
+    // Trace level prints keys. `RUST_LOG` overrides it
+    #[arg(long, default_value_t = String::from("info"))]
+    log_level: String,
+    // Print raw frames, MQTT payloads and undecoded data as hex and ASCII
+    #[arg(long)]
+    hexdump: bool,
+    // Output format. In `json` mode stdout contains only packets,
+    // one JSON object per line, everything else goes to stderr
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+    // Write received frames to pcapng file, with decoded summary as comment
+    #[arg(long)]
+    write_pcap: Option<String>,
+    // Add channels' keys of connected radio (TCP, serial) to keyring.
+    // Keys from keys file are never replaced
+    #[arg(long)]
+    import_radio_keys: bool,
+}
+
+// Status lines must not mix with JSON output
+fn print_status(options: PrintOptions, line: &str) {
+    match options.format {
+        OutputFormat::Text => println!("{}", line),
+        OutputFormat::Json => eprintln!("{}", line),
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
@@ -69,9 +103,13 @@ impl Default for MulticastConfig {
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 struct SerialConfig {
+    // Path to device or `auto` to detect Meshtastic's device by USB ids
     tty: String,
     heartbeat_seconds: u64,
     baudrate: u32,
+    // Report silent device after this time, 0 disables
+    #[serde(default)]
+    read_timeout_seconds: u64,
 }
 
 impl Default for SerialConfig {
@@ -80,16 +118,48 @@ impl Default for SerialConfig {
             tty: "/dev/ttyS0".into(),
             heartbeat_seconds: 5,
             baudrate: 115200,
+            read_timeout_seconds: 0,
         }
     }
 }
 
+#[cfg(feature = "ble")]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+struct BleConfig {
+    // Advertised name or address of device
+    device: String,
+    heartbeat_seconds: u64,
+    #[serde(default = "BleConfig::default_scan_timeout_seconds")]
+    scan_timeout_seconds: u64,
+}
+
+#[cfg(feature = "ble")]
+impl BleConfig {
+    fn default_scan_timeout_seconds() -> u64 {
+        10
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+struct ReplayConfig {
+    // Captured serial frames, JSON lines with stored packets
+    // or text output of `--hexdump`
+    path: String,
+    // 1.0 reproduces pauses between stored packets, 2.0 is twice as fast.
+    // 0.0 prints packets as fast as possible
+    #[serde(default)]
+    speed: f64,
+}
+
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
 enum Mode {
     TCP(TCPConfig),
     Serial(SerialConfig),
+    #[cfg(feature = "ble")]
+    Ble(BleConfig),
     Multicast(MulticastConfig),
     MQTT(MQTTConfig),
+    Replay(ReplayConfig),
 }
 
 impl Default for Mode {
@@ -158,24 +228,24 @@ fn config_read<T>(path: &String) -> Option<T>
 where
     T: DeserializeOwned,
 {
-    println!("Try to read {}", path);
+    log::debug!("Try to read {}", path);
     match File::open(&path) {
         Ok(file) => {
             let reader = BufReader::new(file);
 
             match from_reader::<_, T>(reader) {
                 Ok(config) => {
-                    println!("... ok");
+                    log::debug!("... ok");
                     Some(config)
                 }
                 Err(e) => {
-                    println!("Config file `{}` not loaded: {}", path, e);
+                    log::error!("Config file `{}` not loaded: {}", path, e);
                     None
                 }
             }
         }
         Err(e) => {
-            println!("Config file `{}` is not accessible: {}", path, e);
+            log::error!("Config file `{}` is not accessible: {}", path, e);
             None
         }
     }
@@ -195,11 +265,36 @@ fn load_config(args: &Args) -> Option<Config> {
 #[tokio::main]
 async fn main() {
     let args = Args::parse();
+    env_logger::Builder::new()
+        .parse_filters(&args.log_level)
+        .parse_default_env()
+        .init();
     let config = load_config(&args).expect("Config file not loaded: try type `--help` to get help");
-
-    println!("=== loaded config ===");
-    println!("{}", serde_yaml_ng::to_string(&config).unwrap());
-    println!("=== ===");
+    let options = PrintOptions {
+        hexdump: args.hexdump,
+        format: args.format,
+    };
+    let mut pcap = args
+        .write_pcap
+        .as_deref()
+        .map(|path| PcapWriter::create(path).expect("pcap file is not created"));
+
+    // Keys are printed on trace level only
+    print_status(options, "=== loaded config ===");
+    print_status(
+        options,
+        &serde_yaml_ng::to_string(&config.connection).unwrap(),
+    );
+    print_status(
+        options,
+        &format!(
+            "keys: {} channels, {} peers",
+            config.keys.channels.len(),
+            config.keys.peers.len()
+        ),
+    );
+    print_status(options, "=== ===");
+    log::trace!("{}", serde_yaml_ng::to_string(&config).unwrap());
 
     let mut keyring = Keyring::new();
 
@@ -217,12 +312,15 @@ async fn main() {
         }
     }
 
-    println!();
+    print_status(options, "");
     match config.connection.mode {
         Mode::MQTT(mqtt) => {
-            println!(
-                "Connect to MQTT {} port {}: {:?}",
-                mqtt.server_addr, mqtt.server_port, mqtt.subscribe
+            print_status(
+                options,
+                &format!(
+                    "Connect to MQTT {} port {}: {:?}",
+                    mqtt.server_addr, mqtt.server_port, mqtt.subscribe
+                ),
             );
 
             let mut mqttoptions =
@@ -242,50 +340,103 @@ async fn main() {
                 if let rumqttc::Event::Incoming(packet) = notification {
                     match packet {
                         rumqttc::Packet::Publish(publish) => {
-                            println!("> {} [size: {}] ", publish.topic, publish.payload.len());
-                            print_service_envelope(publish.payload, &keyring).await;
+                            if options.format == OutputFormat::Text {
+                                println!("> {} [size: {}] ", publish.topic, publish.payload.len());
+                            }
+                            if let Some(pcap) = pcap.as_mut() {
+                                let comment = service_envelope_summary(&publish.payload, &keyring);
+                                pcap.write_mqtt(&publish.topic, &publish.payload, &comment)
+                                    .unwrap_or_else(|e| log::error!("pcap write error: {}", e));
+                            }
+                            print_service_envelope(publish.payload, &keyring, options).await;
                         }
                         rumqttc::Packet::PingReq => {}
                         rumqttc::Packet::PingResp => {}
-                        _ => {
-                            println!("> [{}] {:?}", system_time, packet)
-                        }
+                        _ => print_status(options, &format!("> [{}] {:?}", system_time, packet)),
                     }
                 }
             }
         }
         Mode::TCP(tcp) => {
-            println!("Connect to TCP {}", tcp.connect_to);
+            print_status(options, &format!("Connect to TCP {}", tcp.connect_to));
 
             let connection = TcpBuilder::new(tcp.connect_to).connect().await.unwrap();
 
             connect_to_stream(
                 connection,
                 Duration::from_secs(tcp.heartbeat_seconds),
-                &keyring,
+                keyring,
+                options,
+                pcap,
+                args.import_radio_keys,
             )
             .await;
         }
         Mode::Serial(serial) => {
-            println!(
-                "Connect to serial port {} with baudrate {}",
-                serial.tty, serial.baudrate
+            print_status(
+                options,
+                &format!(
+                    "Connect to serial port {} with baudrate {}",
+                    serial.tty, serial.baudrate
+                ),
             );
 
-            let connection = SerialBuilder::new(serial.tty, serial.baudrate)
+            let mut builder =
+                SerialBuilder::with_tty(serial.tty, serial.baudrate).unwrap_or_else(|e| {
+                    log::error!("{}", e);
+                    exit(1);
+                });
+            if serial.read_timeout_seconds > 0 {
+                builder = builder.read_timeout(Duration::from_secs(serial.read_timeout_seconds));
+            }
+            print_status(options, &format!("Serial port: {}", builder.tty));
+            let connection = builder.connect().await.unwrap();
+
+            connect_to_stream(
+                connection,
+                Duration::from_secs(serial.heartbeat_seconds),
+                keyring,
+                options,
+                pcap,
+                args.import_radio_keys,
+            )
+            .await;
+        }
+        #[cfg(feature = "ble")]
+        Mode::Ble(ble) => {
+            print_status(options, &format!("Connect to BLE device {}", ble.device));
+
+            let connection = meshtastic_connect::transport::ble::BleBuilder::new(ble.device)
+                .scan_timeout(Duration::from_secs(ble.scan_timeout_seconds))
                 .connect()
                 .await
-                .unwrap();
+                .unwrap_or_else(|e| {
+                    log::error!("BLE connect failed: {}", e);
+                    exit(1);
+                });
 
             connect_to_stream(
                 connection,
-                Duration::from_secs(serial.heartbeat_seconds),
-                &keyring,
+                Duration::from_secs(ble.heartbeat_seconds),
+                keyring,
+                options,
+                pcap,
+                args.import_radio_keys,
             )
             .await;
         }
+        Mode::Replay(replay) => {
+            print_status(
+                options,
+                &format!("Replay {} with speed {}", replay.path, replay.speed),
+            );
+            replay::replay(&replay.path, replay.speed, &keyring, options).await;
+        }
         Mode::Multicast(multicast) => {
-            println!("Listen multicast on {}", multicast.listen_address);
+            print_status(
+                options,
+                &format!("Listen multicast on {}", multicast.listen_address),
+            );
             let connection = UdpBuilder::new(
                 SocketAddr::V4(SocketAddrV4::new(
                     Ipv4Addr::UNSPECIFIED,
@@ -302,26 +453,100 @@ async fn main() {
             loop {
                 match connection.next().await {
                     Some(result) => {
-                        let (mesh_packet, _) = result.unwrap();
-                        print_mesh_packet(mesh_packet, &keyring).await;
+                        let (mesh_packet, source) = result.unwrap();
+                        if let Some(pcap) = pcap.as_mut() {
+                            // Socket returns decoded packet only: encoded back
+                            let comment = mesh_packet_summary(&mesh_packet, &keyring);
+                            pcap.write_udp(
+                                source,
+                                multicast.listen_address,
+                                &mesh_packet.encode_to_vec(),
+                                &comment,
+                            )
+                            .unwrap_or_else(|e| log::error!("pcap write error: {}", e));
+                        }
+                        print_mesh_packet(mesh_packet, &keyring, options).await;
                     }
                     None => {
-                        println!("Connection closed");
+                        print_status(options, "Connection closed");
                         break;
                     }
                 };
-                println!();
+                if options.format == OutputFormat::Text {
+                    println!();
+                }
             }
         }
     }
 }
 
+fn print_radio_config(options: PrintOptions, radio_config: &RadioConfig) {
+    print_status(
+        options,
+        &format!(
+            "Radio {} {:?}, firmware {:?}, region {:?}, preset {:?}",
+            radio_config
+                .node_id
+                .map(|node_id| node_id.to_string())
+                .unwrap_or_default(),
+            radio_config
+                .user
+                .as_ref()
+                .map(|user| user.long_name.as_str())
+                .unwrap_or_default(),
+            radio_config
+                .metadata
+                .as_ref()
+                .map(|metadata| metadata.firmware_version.as_str())
+                .unwrap_or_default(),
+            radio_config.region(),
+            radio_config.modem_preset()
+        ),
+    );
+    for channel in &radio_config.channels {
+        print_status(
+            options,
+            &format!(
+                "- channel {} {:?} {:?}",
+                channel.index, channel.name, channel.role
+            ),
+        );
+    }
+    print_status(options, &format!("- {} nodes", radio_config.nodes.len()));
+    log::trace!("{:?}", radio_config);
+}
+
 async fn connect_to_stream(
     mut connection: Stream,
     heartbeat_interval: Duration,
-    keyring: &Keyring,
+    mut keyring: Keyring,
+    options: PrintOptions,
+    mut pcap: Option<PcapWriter>,
+    import_radio_keys: bool,
 ) -> ! {
-    let _ = connection.send(PayloadVariant::WantConfigId(0)).await;
+    let radio_config = connection.configure().await.unwrap_or_else(|e| {
+        log::error!("Radio's configuration is not received: {}", e);
+        exit(1);
+    });
+    print_radio_config(options, &radio_config);
+    if import_radio_keys {
+        for import in keyring.import_from_radio(&radio_config) {
+            match import {
+                ChannelImport::Added(name) => {
+                    print_status(options, &format!("- key of {:?} imported", name))
+                }
+                ChannelImport::Known(name) => log::debug!("Key of {:?} is already known", name),
+                ChannelImport::Conflict(name) => log::warn!(
+                    "Key of {:?} differs from keys file, radio's key is not used",
+                    name
+                ),
+                ChannelImport::Skipped(name, reason) => {
+                    log::debug!("Key of {:?} is not imported: {}", name, reason)
+                }
+            }
+        }
+    }
+    let keyring = &keyring;
     let mut hb_interval =
         tokio::time::interval_at(Instant::now() + heartbeat_interval, heartbeat_interval);
 
@@ -333,18 +558,50 @@ async fn connect_to_stream(
             stream_data = connection.next() => {
                 match  stream_data {
                     // TODO: heartbeat
+                    Some(Err(e)) if e.kind() == std::io::ErrorKind::TimedOut => {
+                        log::warn!("Device is silent: {}", e);
+                    }
                     Some(stream_data) => match stream_data.unwrap() {
                         stream::StreamRecvData::FromRadio(packet_id, from_radio) => {
+                            if let Some(pcap) = pcap.as_mut() {
+                                let comment = from_radio_summary(&from_radio, keyring);
+                                let frame = match connection.last_frame() {
+                                    Some(frame) => frame.to_vec(),
+                                    // Transport without StreamAPI's framing: frame is built
+                                    None => {
+                                        let payload = meshtastic::FromRadio {
+                                            id: packet_id,
+                                            payload_variant: Some(from_radio.clone()),
+                                        }
+                                        .encode_to_vec();
+                                        let mut frame = vec![0x94, 0xc3];
+                                        frame.extend((payload.len() as u16).to_be_bytes());
+                                        frame.extend(payload);
+                                        frame
+                                    }
+                                };
+                                pcap.write_stream_frame(&frame, &comment)
+                                    .unwrap_or_else(|e| log::error!("pcap write error: {}", e));
+                            }
+                            if options.format == OutputFormat::Json {
+                                print_from_radio_payload(from_radio, keyring, options).await;
+                                continue;
+                            }
                             println!("> message id: {:x}", packet_id);
-                            print_from_radio_payload(from_radio, keyring).await;
+                            print_from_radio_payload(from_radio, keyring, options).await;
                             println!();
                         }
                         stream::StreamRecvData::Unstructured(bytes) => {
-                            tokio::io::stderr().write_all(&bytes).await.unwrap();
+                            if options.hexdump && options.format == OutputFormat::Text {
+                                println!("> unstructured [size: {}]", bytes.len());
+                                println!("{}", hexdump::hexdump(&bytes, true));
+                            } else {
+                                log::debug!("{}", String::from_utf8_lossy(&bytes).trim_end());
+                            }
                         }
                     },
                     None => {
-                        println!("Connection closed");
+                        print_status(options, "Connection closed");
                         exit(0);
                     }
                 }
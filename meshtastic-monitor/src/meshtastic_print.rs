@@ -2,12 +2,29 @@ use bytes::Bytes;
 use chrono::{TimeZone, Utc};
 use meshtastic_connect::keyring::cryptor::Decrypt;
 use meshtastic_connect::keyring::node_id::NodeId;
+use meshtastic_connect::stored::StoredMeshPacket;
 use meshtastic_connect::{
     keyring::Keyring,
     meshtastic::{self, Data, MeshPacket, from_radio},
+    mqtt::decode_service_envelope,
 };
 use prost::Message;
 
+use crate::{hexdump, json_print};
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    // One JSON object per packet, shaped as softnode's `StoredMeshPacket`
+    Json,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PrintOptions {
+    pub hexdump: bool,
+    pub format: OutputFormat,
+}
+
 async fn print_decoded(data: Data) -> Result<(), String> {
     println!(
         "- {:?} paylen={} source={:#x} dest={:#x} want_response={}, reply_id={}, request_id={} emoji={:#x}",
@@ -73,7 +90,79 @@ async fn print_decoded(data: Data) -> Result<(), String> {
     Ok(())
 }
 
-pub async fn print_mesh_packet(mesh_packet: MeshPacket, channel_list: &Keyring) {
+// One line description of packet for capture's comments
+pub fn mesh_packet_summary(mesh_packet: &MeshPacket, channel_list: &Keyring) -> String {
+    let payload = match &mesh_packet.payload_variant {
+        Some(meshtastic::mesh_packet::PayloadVariant::Decoded(data)) => {
+            data.portnum().as_str_name().to_string()
+        }
+        Some(meshtastic::mesh_packet::PayloadVariant::Encrypted(items)) => {
+            match channel_list.cryptor_for(
+                mesh_packet.from.into(),
+                mesh_packet.to.into(),
+                mesh_packet.channel.into(),
+            ) {
+                Some(decryptor) => match decryptor
+                    .decrypt(mesh_packet.id, items.clone())
+                    .ok()
+                    .and_then(|buffer| meshtastic::Data::decode(buffer.as_slice()).ok())
+                {
+                    Some(data) => format!("{} <{}>", data.portnum().as_str_name(), decryptor),
+                    None => format!("<decrypt error {}>", decryptor),
+                },
+                None => format!("<encrypted {} bytes>", items.len()),
+            }
+        }
+        None => "<no data>".to_string(),
+    };
+
+    format!(
+        "from={} to={} channel=0x{:0>2x} [id:{}] hop={{{}/{}}} {}",
+        NodeId::from(mesh_packet.from),
+        NodeId::from(mesh_packet.to),
+        mesh_packet.channel,
+        mesh_packet.id,
+        mesh_packet.hop_limit,
+        mesh_packet.hop_start,
+        payload
+    )
+}
+
+pub fn service_envelope_summary(packet: &[u8], channel_list: &Keyring) -> String {
+    match decode_service_envelope(None, packet) {
+        Ok((service, Some(mesh_packet))) => format!(
+            "chan={:?} gw={} {}",
+            service.channel_id,
+            service.gateway_id,
+            mesh_packet_summary(&mesh_packet, channel_list)
+        ),
+        Ok((service, None)) => format!(
+            "chan={:?} gw={} <no data>",
+            service.channel_id, service.gateway_id
+        ),
+        Err(e) => format!("<not a ServiceEnvelope: {}>", e),
+    }
+}
+
+pub fn from_radio_summary(payload: &from_radio::PayloadVariant, channel_list: &Keyring) -> String {
+    match payload {
+        from_radio::PayloadVariant::Packet(mesh_packet) => {
+            mesh_packet_summary(mesh_packet, channel_list)
+        }
+        other => format!("{:?}", other),
+    }
+}
+
+pub async fn print_mesh_packet(
+    mesh_packet: MeshPacket,
+    channel_list: &Keyring,
+    options: PrintOptions,
+) {
+    if options.format == OutputFormat::Json {
+        json_print::print_packet(&mesh_packet, None, None, channel_list);
+        return;
+    }
+
     let from_formatted = NodeId::from(mesh_packet.from).to_string();
     let to_formatted = NodeId::from(mesh_packet.to).to_string();
 
@@ -121,12 +210,24 @@ pub async fn print_mesh_packet(mesh_packet: MeshPacket, channel_list: &Keyring)
                         "Not found decoding info for <{} → {} chan {:#x}>",
                         from, to, mesh_packet.channel
                     );
+                    if options.hexdump {
+                        println!("{}", hexdump::hexdump(&items, false));
+                    }
                     return;
                 }
                 let decryptor = decryptor.unwrap();
                 println!("  <decrypting {} bytes for {}>", items.len(), decryptor);
+                if options.hexdump
+                    && let Some(nonce) = decryptor.nonce_for(mesh_packet.id, &items)
+                {
+                    let nonce = nonce
+                        .iter()
+                        .map(|v| format!("{:02x}", v))
+                        .collect::<String>();
+                    println!("  <nonce {}>", nonce);
+                }
 
-                match decryptor.decrypt(mesh_packet.id, items) {
+                match decryptor.decrypt(mesh_packet.id, items.clone()) {
                     Ok(buffer) => match meshtastic::Data::decode(buffer.as_slice()) {
                         Ok(data) => match print_decoded(data).await {
                             Ok(_) => {}
@@ -136,21 +237,48 @@ pub async fn print_mesh_packet(mesh_packet: MeshPacket, channel_list: &Keyring)
                         },
                         Err(e) => {
                             println!("! [construct error] Unable to construct `Data`: {:?}", e);
+                            if options.hexdump {
+                                println!("{}", hexdump::hexdump(&buffer, true));
+                            }
                         }
                     },
-                    Err(e) => println!("! [decode error] {:?}", e),
+                    Err(e) => {
+                        println!("! [decode error] {:?}", e);
+                        if options.hexdump {
+                            println!("{}", hexdump::hexdump(&items, false));
+                        }
+                    }
                 }
             }
         }
     }
 }
 
-pub async fn print_service_envelope(packet: Bytes, channel_list: &Keyring) {
-    if let Ok(service) = meshtastic::ServiceEnvelope::decode(packet.clone()) {
-        if let Some(mesh_packet) = service.packet {
+pub async fn print_service_envelope(packet: Bytes, channel_list: &Keyring, options: PrintOptions) {
+    if options.format == OutputFormat::Json {
+        match decode_service_envelope(None, &packet) {
+            Ok((service, Some(mesh_packet))) => json_print::print_packet(
+                &mesh_packet,
+                NodeId::try_from(service.gateway_id.as_str()).ok(),
+                Some(service.channel_id),
+                channel_list,
+            ),
+            Ok((_, None)) => json_print::print_error("Envelope has no packet", &packet),
+            Err(e) => json_print::print_error(e, &packet),
+        }
+        return;
+    }
+
+    if options.hexdump {
+        println!("{}", hexdump::hexdump(&packet, true));
+    }
+    let decoded = decode_service_envelope(None, &packet)
+        .inspect_err(|e| log::debug!("Not a ServiceEnvelope: {}", e));
+    if let Ok((service, mesh_packet)) = decoded {
+        if let Some(mesh_packet) = mesh_packet {
             println!("- chan={:?} gw={}", service.channel_id, service.gateway_id,);
 
-            print_mesh_packet(mesh_packet, channel_list).await;
+            print_mesh_packet(mesh_packet, channel_list, options).await;
         } else {
             println!(
                 "- chan={:?} gw={} <no data>",
@@ -161,10 +289,48 @@ pub async fn print_service_envelope(packet: Bytes, channel_list: &Keyring) {
     }
 }
 
-pub async fn print_from_radio_payload(payload: from_radio::PayloadVariant, channel_list: &Keyring) {
+pub async fn print_stored_packet(
+    packet: StoredMeshPacket,
+    channel_list: &Keyring,
+    options: PrintOptions,
+) {
+    let mesh_packet = packet.to_mesh_packet();
+    if options.format == OutputFormat::Json {
+        json_print::print_packet(
+            &mesh_packet,
+            packet.gateway,
+            packet.connection_hint,
+            channel_list,
+        );
+        return;
+    }
+
+    println!(
+        "- seq={} stored={} conn={:?} hint={:?} gw={}",
+        packet.sequence_number,
+        packet.store_timestamp,
+        packet.connection_name,
+        packet.connection_hint,
+        packet
+            .gateway
+            .map(|gateway| gateway.to_string())
+            .unwrap_or_default()
+    );
+    print_mesh_packet(mesh_packet, channel_list, options).await;
+}
+
+pub async fn print_from_radio_payload(
+    payload: from_radio::PayloadVariant,
+    channel_list: &Keyring,
+    options: PrintOptions,
+) {
     match payload {
         from_radio::PayloadVariant::Packet(mesh_packet) => {
-            print_mesh_packet(mesh_packet, channel_list).await
+            print_mesh_packet(mesh_packet, channel_list, options).await
+        }
+        // Only packets are printed as JSON
+        other if options.format == OutputFormat::Json => {
+            log::debug!("- {:?}", other);
         }
         from_radio::PayloadVariant::LogRecord(log_record) => {
             println!("- LogRecord {{ {:?} }}", log_record)
@@ -0,0 +1,341 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+// Block types of pcapng
+const SECTION_HEADER_BLOCK: u32 = 0x0a0d0d0a;
+const INTERFACE_DESCRIPTION_BLOCK: u32 = 0x00000001;
+const ENHANCED_PACKET_BLOCK: u32 = 0x00000006;
+const BYTE_ORDER_MAGIC: u32 = 0x1a2b3c4d;
+
+const OPT_ENDOFOPT: u16 = 0;
+const OPT_COMMENT: u16 = 1;
+const SHB_USERAPPL: u16 = 4;
+const IF_NAME: u16 = 2;
+
+// Wireshark's dissector for USER0 must be configured manually
+// (DLT_USER → protobuf `meshtastic.FromRadio` after 4 bytes of header)
+const LINKTYPE_USER0: u16 = 147;
+const LINKTYPE_IPV4: u16 = 228;
+const LINKTYPE_IPV6: u16 = 229;
+
+const IP_PROTO_TCP: u8 = 6;
+const IP_PROTO_UDP: u8 = 17;
+
+// MQTT's broker is known by name only, so addresses of MQTT frames
+// are taken from documentation range (RFC 5737)
+const MQTT_BROKER: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)), 1883);
+const MQTT_CLIENT: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2)), 49152);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Interface {
+    // Serial or TCP StreamAPI's frame: magic, length and `FromRadio`
+    Stream,
+    // Synthesized IP header followed by UDP or TCP
+    Ipv4,
+    Ipv6,
+}
+
+impl Interface {
+    fn link_type(&self) -> u16 {
+        match self {
+            Interface::Stream => LINKTYPE_USER0,
+            Interface::Ipv4 => LINKTYPE_IPV4,
+            Interface::Ipv6 => LINKTYPE_IPV6,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Interface::Stream => "meshtastic-stream",
+            Interface::Ipv4 => "ipv4",
+            Interface::Ipv6 => "ipv6",
+        }
+    }
+}
+
+// pcapng writer. Interface description blocks are written on first use
+pub struct PcapWriter {
+    file: BufWriter<File>,
+    interfaces: Vec<Interface>,
+    // Sequence number for synthesized MQTT's TCP stream
+    mqtt_sequence: u32,
+}
+
+fn option(block: &mut Vec<u8>, code: u16, value: &[u8]) {
+    block.extend(code.to_le_bytes());
+    block.extend((value.len() as u16).to_le_bytes());
+    block.extend(value);
+    pad(block);
+}
+
+fn pad(block: &mut Vec<u8>) {
+    block.resize(block.len().next_multiple_of(4), 0);
+}
+
+// Wraps block's body with type and total length
+fn block(block_type: u32, body: Vec<u8>) -> Vec<u8> {
+    let total_length = (body.len() + 12) as u32;
+    let mut block = Vec::with_capacity(total_length as usize);
+    block.extend(block_type.to_le_bytes());
+    block.extend(total_length.to_le_bytes());
+    block.extend(body);
+    block.extend(total_length.to_le_bytes());
+    block
+}
+
+fn checksum(bytes: &[u8]) -> u16 {
+    let mut sum: u32 = bytes
+        .chunks(2)
+        .map(|pair| u16::from_be_bytes([pair[0], *pair.get(1).unwrap_or(&0)]) as u32)
+        .sum();
+    while sum > 0xffff {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+// IP header for `payload` with transport's header already included.
+// Returns interface and packet
+fn ip_packet(
+    source: IpAddr,
+    destination: IpAddr,
+    protocol: u8,
+    payload: &[u8],
+) -> (Interface, Vec<u8>) {
+    match (source, destination) {
+        (IpAddr::V4(source), IpAddr::V4(destination)) => {
+            let mut packet = Vec::with_capacity(20 + payload.len());
+            packet.extend([0x45, 0x00]);
+            packet.extend(((20 + payload.len()) as u16).to_be_bytes());
+            // Identification, flags (don't fragment), TTL, protocol and checksum
+            packet.extend([0x00, 0x00, 0x40, 0x00, 64, protocol, 0x00, 0x00]);
+            packet.extend(source.octets());
+            packet.extend(destination.octets());
+            let header_checksum = checksum(&packet);
+            packet[10..12].copy_from_slice(&header_checksum.to_be_bytes());
+            packet.extend(payload);
+            (Interface::Ipv4, packet)
+        }
+        (source, destination) => {
+            let to_v6 = |address: IpAddr| match address {
+                IpAddr::V4(address) => address.to_ipv6_mapped(),
+                IpAddr::V6(address) => address,
+            };
+            let mut packet = Vec::with_capacity(40 + payload.len());
+            packet.extend([0x60, 0x00, 0x00, 0x00]);
+            packet.extend((payload.len() as u16).to_be_bytes());
+            packet.extend([protocol, 64]);
+            packet.extend(to_v6(source).octets());
+            packet.extend(to_v6(destination).octets());
+            packet.extend(payload);
+            (Interface::Ipv6, packet)
+        }
+    }
+}
+
+// Checksums of transport's headers are left zero: Wireshark doesn't
+// validate them by default
+fn udp_packet(source: SocketAddr, destination: SocketAddr, payload: &[u8]) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(8 + payload.len());
+    packet.extend(source.port().to_be_bytes());
+    packet.extend(destination.port().to_be_bytes());
+    packet.extend(((8 + payload.len()) as u16).to_be_bytes());
+    packet.extend([0x00, 0x00]);
+    packet.extend(payload);
+    packet
+}
+
+fn tcp_packet(
+    source: SocketAddr,
+    destination: SocketAddr,
+    sequence: u32,
+    payload: &[u8],
+) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(20 + payload.len());
+    packet.extend(source.port().to_be_bytes());
+    packet.extend(destination.port().to_be_bytes());
+    packet.extend(sequence.to_be_bytes());
+    // Acknowledgment number
+    packet.extend([0x00, 0x00, 0x00, 0x00]);
+    // Header's length (5 words) and flags: PSH, ACK
+    packet.extend([0x50, 0x18]);
+    // Window, checksum, urgent pointer
+    packet.extend([0xff, 0xff, 0x00, 0x00, 0x00, 0x00]);
+    packet.extend(payload);
+    packet
+}
+
+// MQTT's PUBLISH with QoS 0
+fn mqtt_publish(topic: &str, payload: &[u8]) -> Vec<u8> {
+    let mut remaining_length = 2 + topic.len() + payload.len();
+    let mut packet = vec![0x30];
+    loop {
+        let byte = (remaining_length % 128) as u8;
+        remaining_length /= 128;
+        if remaining_length > 0 {
+            packet.push(byte | 0x80);
+        } else {
+            packet.push(byte);
+            break;
+        }
+    }
+    packet.extend((topic.len() as u16).to_be_bytes());
+    packet.extend(topic.as_bytes());
+    packet.extend(payload);
+    packet
+}
+
+impl PcapWriter {
+    pub fn create(path: &str) -> std::io::Result<Self> {
+        let mut writer = Self {
+            file: BufWriter::new(File::create(path)?),
+            interfaces: Vec::new(),
+            mqtt_sequence: 1,
+        };
+
+        let mut body = Vec::new();
+        body.extend(BYTE_ORDER_MAGIC.to_le_bytes());
+        // Version 1.0
+        body.extend(1u16.to_le_bytes());
+        body.extend(0u16.to_le_bytes());
+        // Section length is not specified
+        body.extend((-1i64).to_le_bytes());
+        option(&mut body, SHB_USERAPPL, b"meshtastic-monitor");
+        option(&mut body, OPT_ENDOFOPT, &[]);
+        writer.file.write_all(&block(SECTION_HEADER_BLOCK, body))?;
+        writer.file.flush()?;
+
+        Ok(writer)
+    }
+
+    fn interface_id(&mut self, interface: Interface) -> std::io::Result<u32> {
+        if let Some(id) = self.interfaces.iter().position(|v| *v == interface) {
+            return Ok(id as u32);
+        }
+
+        let mut body = Vec::new();
+        body.extend(interface.link_type().to_le_bytes());
+        // Reserved
+        body.extend(0u16.to_le_bytes());
+        // Snapshot length is not limited
+        body.extend(0u32.to_le_bytes());
+        option(&mut body, IF_NAME, interface.name().as_bytes());
+        option(&mut body, OPT_ENDOFOPT, &[]);
+        self.file
+            .write_all(&block(INTERFACE_DESCRIPTION_BLOCK, body))?;
+        self.interfaces.push(interface);
+
+        Ok(self.interfaces.len() as u32 - 1)
+    }
+
+    // Every packet is flushed: capture must survive monitor's termination
+    fn write_packet(
+        &mut self,
+        interface: Interface,
+        data: &[u8],
+        comment: &str,
+    ) -> std::io::Result<()> {
+        let interface_id = self.interface_id(interface)?;
+        // Default resolution is microseconds
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_micros() as u64;
+
+        let mut body = Vec::new();
+        body.extend(interface_id.to_le_bytes());
+        body.extend(((timestamp >> 32) as u32).to_le_bytes());
+        body.extend((timestamp as u32).to_le_bytes());
+        body.extend((data.len() as u32).to_le_bytes());
+        body.extend((data.len() as u32).to_le_bytes());
+        body.extend(data);
+        pad(&mut body);
+        if !comment.is_empty() {
+            option(&mut body, OPT_COMMENT, comment.as_bytes());
+        }
+        option(&mut body, OPT_ENDOFOPT, &[]);
+        self.file.write_all(&block(ENHANCED_PACKET_BLOCK, body))?;
+        self.file.flush()
+    }
+
+    // Frame from serial or TCP StreamAPI, with magic and length
+    pub fn write_stream_frame(&mut self, frame: &[u8], comment: &str) -> std::io::Result<()> {
+        self.write_packet(Interface::Stream, frame, comment)
+    }
+
+    pub fn write_udp(
+        &mut self,
+        source: SocketAddr,
+        destination: SocketAddr,
+        payload: &[u8],
+        comment: &str,
+    ) -> std::io::Result<()> {
+        let (interface, packet) = ip_packet(
+            source.ip(),
+            destination.ip(),
+            IP_PROTO_UDP,
+            &udp_packet(source, destination, payload),
+        );
+        self.write_packet(interface, &packet, comment)
+    }
+
+    // Received PUBLISH as it is sent by broker
+    pub fn write_mqtt(
+        &mut self,
+        topic: &str,
+        payload: &[u8],
+        comment: &str,
+    ) -> std::io::Result<()> {
+        let publish = mqtt_publish(topic, payload);
+        let (interface, packet) = ip_packet(
+            MQTT_BROKER.ip(),
+            MQTT_CLIENT.ip(),
+            IP_PROTO_TCP,
+            &tcp_packet(MQTT_BROKER, MQTT_CLIENT, self.mqtt_sequence, &publish),
+        );
+        self.mqtt_sequence = self.mqtt_sequence.wrapping_add(publish.len() as u32);
+        self.write_packet(interface, &packet, comment)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ipv4_header_checksum() {
+        let (interface, packet) = ip_packet(
+            IpAddr::V4(Ipv4Addr::new(192, 168, 0, 1)),
+            IpAddr::V4(Ipv4Addr::new(224, 0, 0, 69)),
+            IP_PROTO_UDP,
+            &[0; 8],
+        );
+        assert_eq!(interface, Interface::Ipv4);
+        assert_eq!(packet.len(), 28);
+        // Checksum over header with checksum's field is zero
+        assert_eq!(checksum(&packet[..20]), 0);
+    }
+
+    #[test]
+    fn mqtt_remaining_length() {
+        let publish = mqtt_publish("msh/2/e", &[0; 200]);
+        // 2 + 7 + 200 = 209: two bytes of varint
+        assert_eq!(&publish[..3], &[0x30, 0xd1, 0x01]);
+        assert_eq!(publish.len(), 3 + 209);
+    }
+
+    #[test]
+    fn block_padding() {
+        let mut body = Vec::new();
+        option(&mut body, OPT_COMMENT, b"abc");
+        assert_eq!(body.len(), 8);
+        let block = block(ENHANCED_PACKET_BLOCK, body);
+        assert_eq!(block.len(), 20);
+        assert_eq!(&block[4..8], &20u32.to_le_bytes());
+        assert_eq!(&block[16..20], &20u32.to_le_bytes());
+    }
+}
@@ -0,0 +1,296 @@
+use std::time::Duration;
+
+use bytes::{Bytes, BytesMut};
+use chrono::{DateTime, Utc};
+use meshtastic_connect::stored::StoredMeshPacket;
+use meshtastic_connect::{
+    keyring::Keyring,
+    meshtastic::from_radio,
+    mqtt::decode_service_envelope,
+    transport::stream::codec::{MeshtasticStreamCodec, StreamRecvData},
+};
+use tokio_util::codec::Decoder;
+
+use crate::{
+    hexdump, json_print,
+    meshtastic_print::{
+        OutputFormat, PrintOptions, print_from_radio_payload, print_service_envelope,
+        print_stored_packet,
+    },
+};
+
+pub enum ReplayRecord {
+    // Frame captured from serial or TCP stream
+    FromRadio(from_radio::PayloadVariant),
+    // MQTT's payload, dumped by monitor's `--hexdump`
+    ServiceEnvelope(Bytes),
+    // Line of softnode's NDJSON or monitor's `--format json` output
+    Stored(StoredMeshPacket),
+    // Reason and raw data of record
+    Invalid(String, Vec<u8>),
+}
+
+// Format is detected by content: JSON lines start with `{`,
+// text with hexdump's rows is monitor's `--hexdump` output,
+// anything else is read as serial frames
+pub fn read_records(content: &[u8]) -> Vec<ReplayRecord> {
+    match content.iter().find(|b| !b.is_ascii_whitespace()) {
+        Some(b'{') => read_json_lines(content),
+        _ if content
+            .split(|b| *b == b'\n')
+            .any(|line| hexdump_row(line).is_some_and(|(offset, _)| offset == 0)) =>
+        {
+            read_hexdump(content)
+        }
+        _ => read_serial_frames(content),
+    }
+}
+
+// Offset and bytes of `hexdump::hexdump`'s row: `00000010  94 c3 ... |ascii|`.
+// Protobuf's fields are indented and are not rows
+fn hexdump_row(line: &[u8]) -> Option<(usize, Vec<u8>)> {
+    let line = std::str::from_utf8(line).ok()?.trim_end();
+    let (offset, rest) = line.split_at_checked(8)?;
+    if !offset.bytes().all(|b| b.is_ascii_hexdigit()) || !rest.starts_with("  ") {
+        return None;
+    }
+    let offset = usize::from_str_radix(offset, 16).ok()?;
+    // Hex is followed by ASCII `|...|` or frame's annotation `[...]`
+    let bytes = rest
+        .split_ascii_whitespace()
+        .map_while(|token| {
+            (token.len() == 2)
+                .then(|| u8::from_str_radix(token, 16).ok())
+                .flatten()
+        })
+        .collect::<Vec<_>>();
+    (!bytes.is_empty()).then_some((offset, bytes))
+}
+
+// Dumps are collected from rows, other lines of output are skipped.
+// Dump of serial frame is read as frame, others as MQTT's `ServiceEnvelope`
+fn read_hexdump(content: &[u8]) -> Vec<ReplayRecord> {
+    let mut dumps: Vec<Vec<u8>> = Vec::new();
+    let mut records = Vec::new();
+    for (offset, bytes) in content.split(|b| *b == b'\n').filter_map(hexdump_row) {
+        match dumps.last_mut() {
+            _ if offset == 0 => dumps.push(bytes),
+            Some(dump) if dump.len() == offset => dump.extend(bytes),
+            _ => records.push(ReplayRecord::Invalid(
+                format!("Hexdump's row at {:#x} has no beginning", offset),
+                bytes,
+            )),
+        }
+    }
+
+    for dump in dumps {
+        if dump.starts_with(&[0x94, 0xc3]) {
+            records.extend(read_serial_frames(&dump));
+        } else {
+            match decode_service_envelope(None, &dump) {
+                Ok((_, Some(_))) => records.push(ReplayRecord::ServiceEnvelope(dump.into())),
+                // Encrypted payloads and `Data` are dumped after their envelope
+                _ => log::debug!("Dump of {} bytes is not replayed", dump.len()),
+            }
+        }
+    }
+    records
+}
+
+fn read_json_lines(content: &[u8]) -> Vec<ReplayRecord> {
+    content
+        .split(|b| *b == b'\n')
+        .filter(|line| !line.trim_ascii().is_empty())
+        .map(
+            |line| match serde_json::from_slice::<StoredMeshPacket>(line) {
+                Ok(packet) => ReplayRecord::Stored(packet),
+                Err(e) => ReplayRecord::Invalid(e.to_string(), line.to_vec()),
+            },
+        )
+        .collect()
+}
+
+fn read_serial_frames(content: &[u8]) -> Vec<ReplayRecord> {
+    let mut buffer = BytesMut::from(content);
+    let mut codec = MeshtasticStreamCodec::default();
+    let mut records = Vec::new();
+
+    while !buffer.is_empty() {
+        let offset = content.len() - buffer.len();
+        match codec.decode(&mut buffer) {
+            Ok(Some(StreamRecvData::FromRadio(_, payload))) => {
+                records.push(ReplayRecord::FromRadio(payload))
+            }
+            Ok(Some(StreamRecvData::Unstructured(bytes))) => {
+                // Device's debug output between frames
+                log::debug!("{}", String::from_utf8_lossy(&bytes).trim_end());
+            }
+            Ok(None) => {
+                records.push(ReplayRecord::Invalid(
+                    "Truncated frame".into(),
+                    buffer.to_vec(),
+                ));
+                break;
+            }
+            Err(e) => {
+                // Invalid header is not consumed by codec: skip magic's byte
+                if content.len() - buffer.len() == offset {
+                    let _ = buffer.split_to(1);
+                }
+                let consumed = content.len() - buffer.len();
+                records.push(ReplayRecord::Invalid(
+                    e.to_string(),
+                    content[offset..consumed].to_vec(),
+                ));
+            }
+        }
+    }
+    records
+}
+
+// Runs records through the same print pipeline as live connection.
+// With `speed` above zero pauses between stored packets are reproduced
+// (divided by `speed`), otherwise records are printed as fast as possible.
+// Serial frames and hexdumps have no timestamps and are never delayed
+pub async fn replay(path: &str, speed: f64, keyring: &Keyring, options: PrintOptions) {
+    let content = match std::fs::read(path) {
+        Ok(content) => content,
+        Err(e) => {
+            log::error!("Replay file `{}` is not accessible: {}", path, e);
+            return;
+        }
+    };
+    let mut previous: Option<DateTime<Utc>> = None;
+
+    for record in read_records(&content) {
+        match record {
+            ReplayRecord::FromRadio(payload) => {
+                print_from_radio_payload(payload, keyring, options).await
+            }
+            ReplayRecord::ServiceEnvelope(payload) => {
+                print_service_envelope(payload, keyring, options).await
+            }
+            ReplayRecord::Stored(packet) => {
+                if speed > 0.0 {
+                    if let Some(previous) = previous {
+                        let delay = (packet.store_timestamp - previous)
+                            .to_std()
+                            .unwrap_or_default();
+                        tokio::time::sleep(Duration::from_secs_f64(delay.as_secs_f64() / speed))
+                            .await;
+                    }
+                    previous = Some(packet.store_timestamp);
+                }
+                print_stored_packet(packet, keyring, options).await
+            }
+            ReplayRecord::Invalid(reason, bytes) => match options.format {
+                OutputFormat::Json => json_print::print_error(reason, &bytes),
+                OutputFormat::Text => {
+                    println!("> invalid record [size: {}]: {}", bytes.len(), reason);
+                    if options.hexdump {
+                        println!("{}", hexdump::hexdump(&bytes, true));
+                    }
+                }
+            },
+        }
+        if options.format == OutputFormat::Text {
+            println!();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use meshtastic_connect::meshtastic::{self, mesh_packet};
+    use prost::Message;
+
+    use super::*;
+
+    fn frame(from_radio: &meshtastic::FromRadio) -> Vec<u8> {
+        let payload = from_radio.encode_to_vec();
+        let mut frame = vec![0x94, 0xc3];
+        frame.extend((payload.len() as u16).to_be_bytes());
+        frame.extend(payload);
+        frame
+    }
+
+    fn mesh_packet() -> meshtastic::MeshPacket {
+        meshtastic::MeshPacket {
+            from: 0x11223344,
+            to: 0xffffffff,
+            id: 42,
+            hop_limit: 3,
+            payload_variant: Some(mesh_packet::PayloadVariant::Encrypted(vec![1, 2, 3])),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn serial_frames() {
+        let mut content = b"INFO | boot\n".to_vec();
+        content.extend(frame(&meshtastic::FromRadio {
+            id: 1,
+            payload_variant: Some(from_radio::PayloadVariant::Packet(mesh_packet())),
+        }));
+        content.extend([0x94, 0xc3, 0x00, 0x10, 0x01]);
+
+        let records = read_records(&content);
+        assert_eq!(records.len(), 2);
+        assert!(matches!(
+            &records[0],
+            ReplayRecord::FromRadio(from_radio::PayloadVariant::Packet(packet))
+                if *packet == mesh_packet()
+        ));
+        assert!(matches!(&records[1], ReplayRecord::Invalid(_, bytes) if bytes.len() == 5));
+    }
+
+    #[test]
+    fn json_lines() {
+        let packet =
+            StoredMeshPacket::from_mesh_packet(7, None, "monitor".into(), None, &mesh_packet());
+        let content = format!(
+            "{}\n\n{{\"error\":\"broken\"}}\n",
+            serde_json::to_string(&packet).unwrap()
+        );
+
+        let records = read_records(content.as_bytes());
+        assert_eq!(records.len(), 2);
+        assert!(matches!(
+            &records[0],
+            ReplayRecord::Stored(stored) if stored.to_mesh_packet() == mesh_packet()
+        ));
+        assert!(matches!(&records[1], ReplayRecord::Invalid(_, _)));
+    }
+
+    #[test]
+    fn monitor_hexdump() {
+        let from_radio = meshtastic::FromRadio {
+            id: 1,
+            payload_variant: Some(from_radio::PayloadVariant::Packet(mesh_packet())),
+        };
+        let envelope = meshtastic::ServiceEnvelope {
+            packet: Some(mesh_packet()),
+            channel_id: "LongFast".into(),
+            gateway_id: "!11223344".into(),
+        }
+        .encode_to_vec();
+        // Output of monitor: dumps between printed packets
+        let content = format!(
+            "> message id: 1\n{}\n- chan=\"LongFast\"\n{}\n\n",
+            hexdump::hexdump(&frame(&from_radio), true),
+            hexdump::hexdump(&envelope, true)
+        );
+
+        let records = read_records(content.as_bytes());
+        assert_eq!(records.len(), 2);
+        assert!(matches!(
+            &records[0],
+            ReplayRecord::FromRadio(from_radio::PayloadVariant::Packet(packet))
+                if *packet == mesh_packet()
+        ));
+        assert!(matches!(
+            &records[1],
+            ReplayRecord::ServiceEnvelope(payload) if *payload == envelope
+        ));
+    }
+}
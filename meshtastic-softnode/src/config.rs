@@ -5,9 +5,12 @@ use serde::{
 };
 use serde_yaml_ng::{from_reader, to_writer};
 
-use meshtastic_connect::keyring::{
-    key::{K256, Key},
-    node_id::NodeId,
+use meshtastic_connect::{
+    keyring::{
+        key::{K256, Key},
+        node_id::NodeId,
+    },
+    meshtastic::PortNum,
 };
 use std::{
     fs::File,
@@ -17,6 +20,7 @@ use std::{
 };
 
 use crate::publish;
+use softnode_client::app::api_auth::ApiAuth;
 
 #[derive(clap::Parser, Debug)]
 #[command(version, about, long_about = None)]
@@ -29,6 +33,14 @@ pub(crate) struct Args {
     // This file is rewrite if new nodes are coming
     #[arg(short, long, default_value_t = String::from("keys.yaml"))]
     pub(crate) keys_file: String,
+    // Level of diagnostics: error, warn, info, debug or trace.
+    // Trace level prints keys and packets' contents
+    #[arg(long, default_value_t = String::from("info"))]
+    pub(crate) log_level: String,
+    // Receive and journal packets, but never transmit.
+    // Overrides `read_only` from config file
+    #[arg(long)]
+    pub(crate) read_only: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
@@ -191,6 +203,7 @@ impl Default for StreamMethod {
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub(crate) struct SerialConfig {
+    // Path to device or `auto` to detect Meshtastic's device by USB ids
     pub(crate) port: String,
     pub(crate) baudrate: u32,
     #[serde(default)]
@@ -208,11 +221,24 @@ pub(crate) struct TCPConfig {
     pub(crate) method: StreamMethod,
 }
 
+#[cfg(feature = "ble")]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub(crate) struct BLEConfig {
+    // Advertised name or address of device
+    pub(crate) device: String,
+    #[serde(default)]
+    pub(crate) heartbeat_interval: DurationString,
+    #[serde(default)]
+    pub(crate) method: StreamMethod,
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub(crate) enum SoftNodeVariant {
     UDP(Udp),
     TCP(TCPConfig),
     SERIAL(SerialConfig),
+    #[cfg(feature = "ble")]
+    BLE(BLEConfig),
     MQTT(MQTTConfig),
 }
 
@@ -281,6 +307,9 @@ pub(crate) struct WebConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) tls_acme: Option<TlsAcme>,
     pub(crate) select_limit: usize,
+    // Credentials required by `/sync`, API is open if empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) authorizations: Vec<ApiAuth>,
 }
 
 impl Default for WebConfig {
@@ -291,10 +320,178 @@ impl Default for WebConfig {
             serve_dir: "./web/".to_string(),
             tls_acme: None,
             select_limit: 1000,
+            authorizations: Vec::new(),
         }
     }
 }
 
+// Forwarding of received packets to other connections.
+// `from` and `to` are allow-lists, empty list allows any node.
+// When both are set, packet is forwarded only if it matches both of them
+#[derive(Default, Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub(crate) struct TransitConfig {
+    #[serde(default)]
+    pub(crate) enabled: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) from: Vec<NodeId>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) to: Vec<NodeId>,
+}
+
+impl TransitConfig {
+    pub(crate) fn allows(&self, from: NodeId, to: NodeId) -> bool {
+        self.enabled
+            && (self.from.is_empty() || self.from.contains(&from))
+            && (self.to.is_empty() || self.to.contains(&to))
+    }
+}
+
+// SQLite's `PRAGMA synchronous`
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+pub(crate) enum JournalSynchronous {
+    Off,
+    // Syncs on checkpoints only in WAL mode: the last commits
+    // could be lost on power loss, but database is not corrupted
+    Normal,
+    // Syncs on every commit
+    Full,
+    Extra,
+}
+
+impl JournalSynchronous {
+    pub(crate) fn pragma_value(&self) -> &'static str {
+        match self {
+            JournalSynchronous::Off => "OFF",
+            JournalSynchronous::Normal => "NORMAL",
+            JournalSynchronous::Full => "FULL",
+            JournalSynchronous::Extra => "EXTRA",
+        }
+    }
+}
+
+// Packets' filter, empty list allows all.
+// When both are set, packet passes only if it matches both of them.
+// Packets which are not decrypted have no port number and channel name,
+// so they are dropped if any of lists is set
+#[derive(Default, Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(default)]
+pub(crate) struct PacketFilter {
+    // Port numbers' names, like `TEXT_MESSAGE_APP`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) portnums: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) channels: Vec<String>,
+}
+
+impl PacketFilter {
+    pub(crate) fn allows(&self, port_num: Option<PortNum>, channel_name: Option<&str>) -> bool {
+        let portnum_allowed = self.portnums.is_empty()
+            || port_num.is_some_and(|v| self.portnums.iter().any(|p| p == v.as_str_name()));
+        let channel_allowed = self.channels.is_empty()
+            || channel_name.is_some_and(|v| self.channels.iter().any(|c| c == v));
+        portnum_allowed && channel_allowed
+    }
+}
+
+// Packets saved to journal are filtered by `filter`
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(default)]
+pub(crate) struct JournalConfig {
+    #[serde(flatten)]
+    pub(crate) filter: PacketFilter,
+    // Write-ahead log instead of rollback journal:
+    // commits are appended to `-wal` file without waiting for fsync of database
+    pub(crate) wal: bool,
+    pub(crate) synchronous: JournalSynchronous,
+    // Interval of WAL's checkpoints, zero to leave it to SQLite
+    pub(crate) checkpoint_interval: DurationString,
+    // Packets are written in one transaction,
+    // when `batch_size` is reached or `batch_interval` is passed
+    pub(crate) batch_size: usize,
+    pub(crate) batch_interval: DurationString,
+    // Packets waiting to be written, newer packets are dropped on overflow
+    pub(crate) queue_size: usize,
+}
+
+impl Default for JournalConfig {
+    fn default() -> Self {
+        Self {
+            filter: PacketFilter::default(),
+            wal: true,
+            synchronous: JournalSynchronous::Normal,
+            checkpoint_interval: Duration::from_secs(60).into(),
+            batch_size: 100,
+            batch_interval: Duration::from_millis(500).into(),
+            queue_size: 10000,
+        }
+    }
+}
+
+// Received and sent packets are POSTed to `url` as JSON, shaped as journal's packets.
+// `filter` is the same as journal's one
+#[cfg(feature = "webhook")]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(default)]
+pub(crate) struct WebhookConfig {
+    pub(crate) url: String,
+    #[serde(flatten)]
+    pub(crate) filter: PacketFilter,
+    // Key of HMAC-SHA256 of body, sent as `X-Softnode-Signature: sha256=<hex>`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) secret: Option<String>,
+    // Packets waiting to be sent, newer packets are dropped on overflow
+    pub(crate) queue_size: usize,
+    // Failed event is sent again `retries` times,
+    // interval between attempts is doubled from `retry_interval`
+    pub(crate) retries: u32,
+    pub(crate) retry_interval: DurationString,
+    pub(crate) timeout: DurationString,
+}
+
+#[cfg(feature = "webhook")]
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            filter: PacketFilter::default(),
+            secret: None,
+            queue_size: 1000,
+            retries: 3,
+            retry_interval: Duration::from_secs(1).into(),
+            timeout: Duration::from_secs(10).into(),
+        }
+    }
+}
+
+#[cfg(feature = "relay")]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub(crate) enum RelayTarget {
+    // Channel's webhook, messages are only posted
+    Discord { webhook_url: String },
+    // Bot posts to chat, and reads chat's messages for `inbound_channel`
+    Telegram { bot_token: String, chat_id: i64 },
+}
+
+// Text messages of mesh's `channels` are posted to chat.
+// Chat's messages are published to `inbound_channel` if it is set,
+// one per `inbound_interval` to leave airtime for the mesh
+#[cfg(feature = "relay")]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub(crate) struct RelayConfig {
+    pub(crate) channels: Vec<String>,
+    #[serde(flatten)]
+    pub(crate) target: RelayTarget,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) inbound_channel: Option<String>,
+    #[serde(default = "default_inbound_interval")]
+    pub(crate) inbound_interval: DurationString,
+}
+
+#[cfg(feature = "relay")]
+fn default_inbound_interval() -> DurationString {
+    Duration::from_secs(30).into()
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub(crate) struct SoftNodeConfig {
     pub(crate) transport: Vec<SoftNodeTransport>,
@@ -314,6 +511,19 @@ pub(crate) struct SoftNodeConfig {
     pub(crate) default_channel: String,
     #[serde(default)]
     pub(crate) channels: Vec<SoftNodeChannel>,
+    #[serde(default)]
+    pub(crate) transit: TransitConfig,
+    #[serde(default)]
+    pub(crate) journal: JournalConfig,
+    #[cfg(feature = "webhook")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) webhook: Option<WebhookConfig>,
+    #[cfg(feature = "relay")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) relays: Vec<RelayConfig>,
+    // Receive and journal packets, but never transmit
+    #[serde(default)]
+    pub(crate) read_only: bool,
 }
 
 impl Default for SoftNodeConfig {
@@ -347,6 +557,13 @@ impl Default for SoftNodeConfig {
                     }),
                 ],
             }],
+            transit: Default::default(),
+            journal: Default::default(),
+            #[cfg(feature = "webhook")]
+            webhook: None,
+            #[cfg(feature = "relay")]
+            relays: Vec::new(),
+            read_only: false,
         }
     }
 }
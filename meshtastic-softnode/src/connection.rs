@@ -44,11 +44,11 @@ impl Sender {
         let (channel_id, mesh_packet) = send_data;
         match self {
             Sender::UDP(udp) => {
-                println!("UDP: Sending...");
+                log::debug!("UDP: Sending...");
                 udp.send(mesh_packet).await
             }
             Sender::Stream(stream) => {
-                println!("STREAM MQTT: Sending to {}...", channel_id);
+                log::debug!("STREAM MQTT: Sending to {}...", channel_id);
                 stream
                     .send(mqtt_stream::MqttStreamSendData::MeshPacket(
                         channel_id,
@@ -57,11 +57,19 @@ impl Sender {
                     .await
             }
             Sender::MQTT(mqtt) => {
-                println!("MQTT: Sending to {}...", channel_id);
+                log::debug!("MQTT: Sending to {}...", channel_id);
                 mqtt.send((channel_id, mesh_packet)).await
             }
         }
     }
+
+    pub async fn disconnect(&mut self) -> Result<(), std::io::Error> {
+        match self {
+            Sender::UDP(udp) => udp.close().await,
+            Sender::Stream(stream) => stream.close().await,
+            Sender::MQTT(mqtt) => mqtt.disconnect().await,
+        }
+    }
 }
 
 async fn udp_next(udp: &mut SplitStream<udp::Udp>) -> Result<Incoming, std::io::Error> {
@@ -222,7 +230,7 @@ pub async fn build(
                 .connect()
                 .await
                 .inspect_err(|e| {
-                    println!("TCP connect failed: {e}");
+                    log::error!("TCP connect failed: {e}");
                     exit(1);
                 })
                 .unwrap();
@@ -260,13 +268,23 @@ pub async fn build(
                 serial_config.port, serial_config.baudrate
             );
 
-            let mut connection = stream::serial::SerialBuilder::new(
+            let builder = stream::serial::SerialBuilder::with_tty(
                 serial_config.port.clone(),
                 serial_config.baudrate,
             )
-            .connect()
-            .await
-            .unwrap();
+            .unwrap_or_else(|e| {
+                log::error!("Serial port autodetect failed: {e}");
+                exit(1);
+            });
+            println!("Serial port: {}", builder.tty);
+            let mut connection = builder
+                .connect()
+                .await
+                .inspect_err(|e| {
+                    log::error!("SERIAL connect failed: {e}");
+                    exit(1);
+                })
+                .unwrap();
 
             connection.send(BytesSequence::Wakeup).await.unwrap();
             connection
@@ -295,6 +313,46 @@ pub async fn build(
                 heartbeat,
             )
         }
+        #[cfg(feature = "ble")]
+        config::SoftNodeVariant::BLE(ref ble_config) => {
+            println!("Connect BLE to {}", ble_config.device);
+
+            let mut connection =
+                meshtastic_connect::transport::ble::BleBuilder::new(ble_config.device.clone())
+                    .connect()
+                    .await
+                    .inspect_err(|e| {
+                        log::error!("BLE connect failed: {e}");
+                        exit(1);
+                    })
+                    .unwrap();
+
+            connection
+                .send(to_radio::PayloadVariant::WantConfigId(0))
+                .await
+                .unwrap();
+
+            let connection =
+                build_mqtt_stream_for_method(soft_node, connection, &ble_config.method);
+
+            let (sender, receiver) = connection.split();
+            let heartbeat = if ble_config.heartbeat_interval.is_zero() {
+                None
+            } else {
+                Some(Heartbeat {
+                    interval: tokio::time::interval_at(
+                        tokio::time::Instant::now() + ble_config.heartbeat_interval.into(),
+                        ble_config.heartbeat_interval.into(),
+                    ),
+                })
+            };
+
+            (
+                Sender::Stream(sender),
+                Receiver::Stream(receiver),
+                heartbeat,
+            )
+        }
         config::SoftNodeVariant::MQTT(mqttconfig) => {
             println!(
                 "Connect MQTT to {}@{} {:?}",
@@ -313,7 +371,7 @@ pub async fn build(
                 .connect()
                 .await
                 .inspect_err(|e| {
-                    println!("MQTT connect failed: {e}");
+                    log::error!("MQTT connect failed: {e}");
                     exit(1);
                 })
                 .unwrap();
@@ -337,3 +395,110 @@ fn build_mqtt_stream_for_method(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use meshtastic_connect::transport::loopback;
+    use prost::Message;
+
+    fn soft_node() -> SoftNodeConfig {
+        SoftNodeConfig {
+            node_id: 0x1234abcd.into(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn force_method_proxies_to_topic() {
+        let (stream, mut radio) = loopback::pair();
+        let method = config::StreamMethod::FORCE("msh/test".into());
+        let (mut sender, mut receiver) =
+            build_mqtt_stream_for_method(&soft_node(), stream, &method).split();
+
+        let mesh_packet = meshtastic::MeshPacket {
+            from: 0xda5ad56c,
+            id: 1,
+            ..Default::default()
+        };
+        sender
+            .send(mqtt_stream::MqttStreamSendData::MeshPacket(
+                "LongFast".into(),
+                mesh_packet.clone(),
+            ))
+            .await
+            .unwrap();
+        let [to_radio::PayloadVariant::MqttClientProxyMessage(mqtt_proxy)] =
+            &radio.drain_to_radio()[..]
+        else {
+            panic!("MqttClientProxyMessage is expected");
+        };
+        assert_eq!(mqtt_proxy.topic, "msh/test/2/e/LongFast/!1234abcd");
+
+        // Radio echoes proxied message back, as MQTT broker does
+        radio.send(
+            meshtastic::from_radio::PayloadVariant::MqttClientProxyMessage(mqtt_proxy.clone()),
+        );
+        let incoming = stream_next(&mut receiver).await.unwrap();
+        assert_eq!(
+            incoming.connection_hint.as_deref(),
+            Some("msh/test/2/e/LongFast/!1234abcd")
+        );
+        assert_eq!(incoming.gateway_id, Some(0x1234abcd.into()));
+        assert!(matches!(incoming.data, DataVariant::MeshPacket(p) if p == mesh_packet));
+    }
+
+    #[tokio::test]
+    async fn force_method_keeps_radio_packets_unstructured() {
+        let (stream, mut radio) = loopback::pair();
+        let method = config::StreamMethod::FORCE("msh".into());
+        let (_, mut receiver) = build_mqtt_stream_for_method(&soft_node(), stream, &method).split();
+
+        radio.send_packet(meshtastic::MeshPacket::default());
+        let incoming = stream_next(&mut receiver).await.unwrap();
+        assert!(incoming.connection_hint.is_none());
+        assert!(matches!(incoming.data, DataVariant::Unstructured(_)));
+
+        let envelope = meshtastic::ServiceEnvelope {
+            packet: None,
+            channel_id: "LongFast".into(),
+            gateway_id: "!1234abcd".into(),
+        };
+        radio.send(
+            meshtastic::from_radio::PayloadVariant::MqttClientProxyMessage(
+                meshtastic::MqttClientProxyMessage {
+                    topic: "msh/2/e/LongFast/!1234abcd".into(),
+                    retained: false,
+                    payload_variant: Some(
+                        meshtastic::mqtt_client_proxy_message::PayloadVariant::Data(
+                            envelope.encode_to_vec(),
+                        ),
+                    ),
+                },
+            ),
+        );
+        assert!(stream_next(&mut receiver).await.is_err());
+
+        drop(radio);
+        assert_eq!(
+            stream_next(&mut receiver).await.err().unwrap().kind(),
+            std::io::ErrorKind::NotConnected
+        );
+    }
+
+    #[tokio::test]
+    async fn heartbeat_is_sent_to_radio() {
+        let (stream, mut radio) = loopback::pair();
+        let method = config::StreamMethod::FORCE("msh".into());
+        let (sender, _) = build_mqtt_stream_for_method(&soft_node(), stream, &method).split();
+        let heartbeat = Heartbeat {
+            interval: tokio::time::interval(std::time::Duration::from_secs(1)),
+        };
+
+        heartbeat.send(&mut Sender::Stream(sender)).await.unwrap();
+        assert!(matches!(
+            radio.drain_to_radio()[..],
+            [to_radio::PayloadVariant::Heartbeat(_)]
+        ));
+    }
+}
@@ -1,18 +1,23 @@
 mod config;
 mod connection;
+mod metrics;
+mod observer;
 mod publish;
+#[cfg(feature = "relay")]
+mod relay;
 mod router;
 mod schedule;
+// Webhook's signature, tested without `webhook` feature
+#[cfg(any(feature = "webhook", test))]
+mod signature;
 mod sqlite;
 mod web;
+#[cfg(feature = "webhook")]
+mod webhook;
 
 use clap::Parser;
 use meshtastic_connect::{
-    keyring::{
-        Keyring,
-        cryptor::{Decrypt, Encrypt},
-        node_id::NodeId,
-    },
+    keyring::{Keyring, cryptor::Encrypt, node_id::NodeId},
     meshtastic::{self, mesh_packet},
 };
 use prost::Message;
@@ -24,14 +29,14 @@ use std::{
     time::Duration,
 };
 use tokio::{
-    io::AsyncWriteExt,
+    sync::mpsc,
     time::{Instant, sleep_until},
 };
+use tracing_subscriber::EnvFilter;
 
 use crate::config::{Args, SoftNodeConfig, load_config};
 
 async fn handle_timer_event(
-    sqlite: &sqlite::SQLite,
     schedule: &mut schedule::Schedule,
     soft_node: &SoftNodeConfig,
     keyring: &Keyring,
@@ -40,203 +45,191 @@ async fn handle_timer_event(
     while let Some((_, (channel_idx, publish_idx))) = schedule.pop_if_completed() {
         let channel = &soft_node.channels[channel_idx];
         let publish_descriptor = &channel.publish[publish_idx];
-
-        println!(
-            "Publishing {:?} to channel {}",
-            publish_descriptor, channel.name
-        );
-        let (port_num, data_payload) = publish_descriptor.pack_to_data(&soft_node);
-        let packet_id: u32 = rand::rng().random();
-        let dest_node: NodeId = NodeId::broadcast();
-        let data = meshtastic::Data {
-            portnum: port_num.into(),
-            payload: data_payload,
-            ..Default::default()
-        };
-
-        let (channel_hash, payload_variant) = if channel.disable_encryption {
-            (
-                channel_idx as u32,
-                mesh_packet::PayloadVariant::Decoded(data.clone()),
-            )
-        } else {
-            let (cryptor, channel_hash) = keyring
-                .cryptor_for_channel_name(soft_node.node_id, &channel.name)
-                .unwrap();
-
-            let encrypted_data = cryptor.encrypt(packet_id, data.encode_to_vec()).unwrap();
-            (
-                channel_hash.into(),
-                mesh_packet::PayloadVariant::Encrypted(encrypted_data),
-            )
-        };
-
-        let mesh_packet = meshtastic::MeshPacket {
-            from: soft_node.node_id.into(),
-            to: dest_node.into(),
-            channel: channel_hash,
-            id: packet_id,
-            hop_limit: channel.hop_start.into(),
-            priority: meshtastic::mesh_packet::Priority::Default.into(),
-            hop_start: channel.hop_start.into(),
-            payload_variant: Some(payload_variant),
-            ..Default::default()
-        };
-
-        println!("send mesh: {:?}", mesh_packet);
-        sqlite
-            .insert_packet(
-                soft_node.node_id.into(),
-                &soft_node.node_id.into(),
-                Some("self-generated".to_string()),
-                &mesh_packet,
-                Some(channel.name.clone()),
-                Some(port_num),
-                Some(&data.encode_to_vec()),
-            )
-            .await
-            .unwrap();
-        router
-            .send_mesh(Some(channel.name.clone()), mesh_packet)
-            .await;
-
         let interval = publish_descriptor.interval();
         if !interval.is_zero() {
             schedule.add(Instant::now() + interval, (channel_idx, publish_idx));
         }
+
+        if soft_node.read_only {
+            log::info!(
+                "Read-only: skip publishing {:?} to channel {}",
+                publish_descriptor,
+                channel.name
+            );
+            continue;
+        }
+
+        log::info!(
+            "Publishing {:?} to channel {}",
+            publish_descriptor,
+            channel.name
+        );
+        publish_to_channel(soft_node, keyring, router, channel_idx, publish_descriptor).await;
     }
 }
 
-async fn handle_network_event(
-    sqlite: &sqlite::SQLite,
+async fn publish_to_channel(
+    soft_node: &SoftNodeConfig,
     keyring: &Keyring,
     router: &mut router::Router,
-    recv_capsule: router::ReceiveCapsule,
+    channel_idx: usize,
+    publish_descriptor: &publish::Publish,
 ) {
-    let gateway = recv_capsule.incoming.gateway_id;
-
-    match &recv_capsule.incoming.data {
-        connection::DataVariant::MeshPacket(mesh_packet) => {
-            if let Some(ref payload_variant) = mesh_packet.payload_variant {
-                match payload_variant {
-                    mesh_packet::PayloadVariant::Decoded(data) => {
-                        sqlite
-                            .insert_packet(
-                                gateway,
-                                &recv_capsule.source_connection_name,
-                                recv_capsule.incoming.connection_hint,
-                                &mesh_packet,
-                                None,
-                                Some(data.portnum()),
-                                Some(&data.encode_to_vec()),
-                            )
-                            .await
-                            .unwrap();
-                        // router: get channel name by mesh_packet.channel (number of channel)
-                    }
-                    mesh_packet::PayloadVariant::Encrypted(encrypted_data) => {
-                        if let Some((cryptor, data)) = match keyring.cryptor_for(
-                            NodeId::from(mesh_packet.from),
-                            NodeId::from(mesh_packet.to),
-                            mesh_packet.channel.into(),
-                        ) {
-                            Some(cryptor) => {
-                                match cryptor.decrypt(mesh_packet.id, encrypted_data.clone()) {
-                                    Ok(decrypted_data) => {
-                                        match meshtastic::Data::decode(decrypted_data.as_slice()) {
-                                            Ok(data) => Some((cryptor, data)),
-                                            Err(err) => {
-                                                println!("Failed to construct data: {}", err);
-                                                None
-                                            }
-                                        }
-                                    }
-                                    Err(err) => {
-                                        println!("Failed to decrypt encrypted data: {}", err);
-                                        None
-                                    }
-                                }
-                            }
-                            None => {
-                                println!("No cryptor found for packet: {:?}", mesh_packet);
-                                None
-                            }
-                        } {
-                            sqlite
-                                .insert_packet(
-                                    gateway,
-                                    &recv_capsule.source_connection_name,
-                                    recv_capsule.incoming.connection_hint,
-                                    &mesh_packet,
-                                    Some(cryptor.to_string()),
-                                    Some(data.portnum()),
-                                    Some(&data.encode_to_vec()),
-                                )
-                                .await
-                                .unwrap();
-                        } else {
-                            sqlite
-                                .insert_packet(
-                                    gateway,
-                                    &recv_capsule.source_connection_name,
-                                    recv_capsule.incoming.connection_hint,
-                                    &mesh_packet,
-                                    None,
-                                    None,
-                                    Some(encrypted_data),
-                                )
-                                .await
-                                .unwrap();
-                        };
-                    }
-                }
-            } else {
-                println!("No data received: {:?}", mesh_packet);
-                sqlite
-                    .insert_packet(
-                        gateway,
-                        &recv_capsule.source_connection_name,
-                        recv_capsule.incoming.connection_hint,
-                        &mesh_packet,
-                        None,
-                        None,
-                        None,
-                    )
-                    .await
-                    .unwrap();
-            }
-        }
-        connection::DataVariant::Unstructured(items) => {
-            tokio::io::stderr().write_all(&items).await.unwrap()
-        }
+    let channel = &soft_node.channels[channel_idx];
+    let (port_num, data_payload) = publish_descriptor.pack_to_data(soft_node);
+    let packet_id: u32 = rand::rng().random();
+    let dest_node: NodeId = NodeId::broadcast();
+    let data = meshtastic::Data {
+        portnum: port_num.into(),
+        payload: data_payload,
+        ..Default::default()
+    };
+
+    let (channel_hash, payload_variant) = if channel.disable_encryption {
+        (
+            channel_idx as u32,
+            mesh_packet::PayloadVariant::Decoded(data.clone()),
+        )
+    } else {
+        let (cryptor, channel_hash) = keyring
+            .cryptor_for_channel_name(soft_node.node_id, &channel.name)
+            .unwrap();
+
+        let encrypted_data = cryptor.encrypt(packet_id, data.encode_to_vec()).unwrap();
+        (
+            channel_hash.into(),
+            mesh_packet::PayloadVariant::Encrypted(encrypted_data),
+        )
+    };
+
+    let mesh_packet = meshtastic::MeshPacket {
+        from: soft_node.node_id.into(),
+        to: dest_node.into(),
+        channel: channel_hash,
+        id: packet_id,
+        hop_limit: channel.hop_start.into(),
+        priority: meshtastic::mesh_packet::Priority::Default.into(),
+        hop_start: channel.hop_start.into(),
+        payload_variant: Some(payload_variant),
+        ..Default::default()
+    };
+
+    log::trace!("send mesh: {:?}", mesh_packet);
+    router
+        .send_mesh(Some(channel.name.clone()), mesh_packet)
+        .await;
+}
+
+// Text of relayed chat, published as `Publish::Text`
+async fn handle_inbound_text(
+    soft_node: &SoftNodeConfig,
+    keyring: &Keyring,
+    router: &mut router::Router,
+    (channel_name, text): (String, String),
+) {
+    let Some(channel_idx) = soft_node
+        .channels
+        .iter()
+        .position(|channel| channel.name == channel_name)
+    else {
+        log::error!("Relayed text is not published: no channel {}", channel_name);
+        return;
+    };
+    if soft_node.read_only {
+        log::info!("Read-only: skip relayed text to channel {}", channel_name);
+        return;
+    }
+
+    log::info!("Publishing relayed text to channel {}", channel_name);
+    let publish_descriptor = publish::Publish::Text(publish::PublishText {
+        interval: Duration::ZERO.into(),
+        text,
+    });
+    publish_to_channel(soft_node, keyring, router, channel_idx, &publish_descriptor).await;
+}
+
+async fn handle_network_event(router: &mut router::Router, recv_capsule: router::ReceiveCapsule) {
+    // Mesh packets are saved by router's observers
+    router.route_next(&recv_capsule).await;
+    if let connection::DataVariant::Unstructured(items) = &recv_capsule.incoming.data {
+        log::debug!(
+            "{}: {}",
+            recv_capsule.source_connection_name,
+            String::from_utf8_lossy(items).trim_end()
+        );
     }
 }
 
+// `RUST_LOG` overrides `--log-level`
+fn init_logging(log_level: &str) {
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(format!("{},tower_http=debug", log_level)));
+    tracing_subscriber::fmt().with_env_filter(filter).init();
+}
+
 #[tokio::main]
 async fn main() {
     let args = Args::parse();
-    let config = load_config(&args).unwrap_or_else(|| {
+    let mut config = load_config(&args).unwrap_or_else(|| {
         println!("Config file not loaded: try type `--help` to get help");
         process::exit(1)
     });
 
+    config.soft_node.read_only |= args.read_only;
+
+    init_logging(&args.log_level);
+
+    // Keys are printed on trace level only
+    let mut banner = serde_yaml_ng::to_value(&config.soft_node).unwrap();
+    if let Some(banner) = banner.as_mapping_mut() {
+        banner.remove("private_key");
+        if let Some(web) = banner.get_mut("web").and_then(|v| v.as_mapping_mut())
+            && let Some(authorizations) = web.remove("authorizations")
+        {
+            let count = authorizations.as_sequence().map_or(0, |v| v.len());
+            web.insert("authorizations".into(), count.into());
+        }
+        if let Some(webhook) = banner.get_mut("webhook").and_then(|v| v.as_mapping_mut()) {
+            webhook.remove("secret");
+        }
+        if let Some(relays) = banner.get_mut("relays").and_then(|v| v.as_sequence_mut()) {
+            for relay in relays {
+                for (target, secret) in [("Discord", "webhook_url"), ("Telegram", "bot_token")] {
+                    if let Some(target) = relay.get_mut(target).and_then(|v| v.as_mapping_mut()) {
+                        target.remove(secret);
+                    }
+                }
+            }
+        }
+    }
     println!("=== loaded config ===");
-    println!("{}", serde_yaml_ng::to_string(&config).unwrap());
+    println!("{}", serde_yaml_ng::to_string(&banner).unwrap());
+    println!(
+        "keys: {} channels, {} peers",
+        config.keys.channels.len(),
+        config.keys.peers.len()
+    );
     println!("=== ===");
+    log::trace!("{}", serde_yaml_ng::to_string(&config).unwrap());
+    if config.soft_node.read_only {
+        println!("=== READ-ONLY: nothing will be transmitted ===");
+    }
 
     let soft_node = config.soft_node;
     let mut schedule = schedule::Schedule::new(&soft_node.channels);
     let mut router = router::Router::default();
     let sqlite_name = format!("journal-{:x}.sqlite", soft_node.node_id);
-    let sqlite = sqlite::SQLite::new(sqlite_name.as_str()).await.unwrap();
+    let sqlite = sqlite::SQLite::new(sqlite_name.as_str(), &soft_node.journal)
+        .await
+        .unwrap();
 
     let web_config = soft_node.web.clone();
     if web_config.enabled {
         println!("Webserver on {}...", web_config.http_listen);
-        web::init();
         let web_sqlite = sqlite.clone();
+        let web_metrics = router.metrics();
 
-        tokio::spawn(async move { web::start(web_config.clone(), web_sqlite).await });
+        tokio::spawn(async move { web::start(web_config.clone(), web_sqlite, web_metrics).await });
     }
 
     let mut keyring = Keyring::new();
@@ -257,6 +250,53 @@ async fn main() {
 
     println!();
 
+    router.set_node_id(soft_node.node_id);
+    router.set_read_only(soft_node.read_only);
+    router.set_transit(soft_node.transit.clone());
+    let (sqlite_observer, journal_writer) = observer::SqliteObserver::new(
+        sqlite,
+        keyring.clone(),
+        soft_node.node_id,
+        soft_node.journal.clone(),
+        router.metrics(),
+    );
+    router.add_observer(Box::new(sqlite_observer));
+
+    #[cfg(feature = "webhook")]
+    if let Some(webhook) = &soft_node.webhook {
+        match webhook::WebhookObserver::new(
+            webhook.clone(),
+            keyring.clone(),
+            soft_node.node_id,
+            router.metrics(),
+        ) {
+            Ok(webhook_observer) => {
+                println!("Webhook to {}", webhook.url);
+                router.add_observer(Box::new(webhook_observer));
+            }
+            Err(e) => println!("Webhook is not started: {}", e),
+        }
+    }
+
+    // Texts of relayed chats to publish, closed if no relay is started
+    let (inbound_tx, mut inbound_rx) = mpsc::channel::<(String, String)>(1);
+    #[cfg(feature = "relay")]
+    for relay_config in &soft_node.relays {
+        match relay::RelayObserver::new(
+            relay_config.clone(),
+            keyring.clone(),
+            soft_node.node_id,
+            inbound_tx.clone(),
+        ) {
+            Ok(relay_observer) => {
+                println!("Relay of channels {:?}", relay_config.channels);
+                router.add_observer(Box::new(relay_observer));
+            }
+            Err(e) => println!("Relay is not started: {}", e),
+        }
+    }
+    drop(inbound_tx);
+
     for transport in &soft_node.transport {
         router.add_connection(
             transport.name.clone(),
@@ -270,13 +310,16 @@ async fn main() {
 
     let handle_error = |err: Error| {
         if err.kind() == ErrorKind::InvalidData {
-            println!("Invalid data received: {}", err);
+            log::warn!("Invalid data received: {}", err);
         } else {
-            println!("exit on error {:?}: {}", err.kind(), err);
+            log::error!("exit on error {:?}: {}", err.kind(), err);
             exit(1);
         }
     };
 
+    let started = Instant::now();
+    let mut processed: u64 = 0;
+
     loop {
         let next_wakeup = schedule.next_wakeup().unwrap_or_else(|| {
             Instant::now() + Duration::from_secs(60 * 60 * 24) // 1 day
@@ -284,14 +327,38 @@ async fn main() {
 
         tokio::select! {
             _ = sleep_until(next_wakeup) => {
-                handle_timer_event(&sqlite, &mut schedule, &soft_node, &keyring, &mut router).await;
+                handle_timer_event(&mut schedule, &soft_node, &keyring, &mut router).await;
             },
             result = router.recv_mesh() => {
                 match result {
-                    Ok(recv_capsule) => { handle_network_event(&sqlite, &keyring, &mut router, recv_capsule).await; }
+                    Ok(recv_capsule) => {
+                        processed += 1;
+                        handle_network_event(&mut router, recv_capsule).await;
+                    }
                     Err(err) => handle_error(err),
                 }
             }
+            Some(inbound_text) = inbound_rx.recv() => {
+                handle_inbound_text(&soft_node, &keyring, &mut router, inbound_text).await;
+            }
+            _ = tokio::signal::ctrl_c() => {
+                println!("Interrupted");
+                break;
+            }
         }
     }
+
+    router.disconnect().await;
+    // Observers are dropped with router, journal's writer flushes the last batch
+    drop(router);
+    println!("Flush journal...");
+    if let Err(e) = journal_writer.await {
+        println!("Journal writer error: {}", e);
+    }
+
+    println!(
+        "Processed {} packets, uptime {:?}",
+        processed,
+        Duration::from_secs(started.elapsed().as_secs())
+    );
 }
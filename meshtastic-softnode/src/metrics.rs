@@ -0,0 +1,166 @@
+use std::{collections::BTreeMap, fmt::Write, sync::Mutex};
+
+pub(crate) const PACKETS_RECEIVED: &str = "softnode_packets_received_total";
+pub(crate) const PACKETS_SENT: &str = "softnode_packets_sent_total";
+pub(crate) const PACKETS_BY_PORTNUM: &str = "softnode_packets_by_portnum_total";
+pub(crate) const DECRYPT_FAILURES: &str = "softnode_decrypt_failures_total";
+pub(crate) const DUPLICATE_DROPS: &str = "softnode_duplicate_drops_total";
+pub(crate) const SEND_IN_FLIGHT: &str = "softnode_send_in_flight";
+pub(crate) const JOURNAL_QUEUE_DEPTH: &str = "softnode_journal_queue_depth";
+pub(crate) const JOURNAL_DROPS: &str = "softnode_journal_drops_total";
+#[cfg(feature = "webhook")]
+pub(crate) const WEBHOOK_SENT: &str = "softnode_webhook_sent_total";
+#[cfg(feature = "webhook")]
+pub(crate) const WEBHOOK_DROPS: &str = "softnode_webhook_drops_total";
+#[cfg(feature = "webhook")]
+pub(crate) const WEBHOOK_QUEUE_DEPTH: &str = "softnode_webhook_queue_depth";
+
+// name, type, help
+const DESCRIPTIONS: &[(&str, &str, &str)] = &[
+    (
+        PACKETS_RECEIVED,
+        "counter",
+        "Mesh packets received from connection",
+    ),
+    (
+        PACKETS_SENT,
+        "counter",
+        "Mesh packets passed to connection for sending",
+    ),
+    (
+        PACKETS_BY_PORTNUM,
+        "counter",
+        "Decoded mesh packets by port number",
+    ),
+    (
+        DECRYPT_FAILURES,
+        "counter",
+        "Encrypted mesh packets not decoded, by reason",
+    ),
+    (
+        DUPLICATE_DROPS,
+        "counter",
+        "Packets not forwarded because they are already forwarded",
+    ),
+    (
+        SEND_IN_FLIGHT,
+        "gauge",
+        "Packets waiting for connection's sender",
+    ),
+    (
+        JOURNAL_QUEUE_DEPTH,
+        "gauge",
+        "Packets waiting to be written to journal",
+    ),
+    (
+        JOURNAL_DROPS,
+        "counter",
+        "Packets not written to journal because its queue is full",
+    ),
+    #[cfg(feature = "webhook")]
+    (WEBHOOK_SENT, "counter", "Events accepted by webhook"),
+    #[cfg(feature = "webhook")]
+    (
+        WEBHOOK_DROPS,
+        "counter",
+        "Events not delivered to webhook, by reason",
+    ),
+    #[cfg(feature = "webhook")]
+    (
+        WEBHOOK_QUEUE_DEPTH,
+        "gauge",
+        "Packets waiting to be sent to webhook",
+    ),
+];
+
+type Labels = Vec<(&'static str, String)>;
+
+// Registry for `/metrics` in Prometheus text format.
+// Shared between router, observers and webserver
+#[derive(Default)]
+pub(crate) struct Metrics {
+    values: Mutex<BTreeMap<&'static str, BTreeMap<Labels, i64>>>,
+}
+
+impl Metrics {
+    pub(crate) fn add(&self, name: &'static str, labels: &[(&'static str, &str)], delta: i64) {
+        let labels = labels
+            .iter()
+            .map(|(key, value)| (*key, value.to_string()))
+            .collect();
+        let mut values = self.values.lock().unwrap();
+        *values.entry(name).or_default().entry(labels).or_default() += delta;
+    }
+
+    pub(crate) fn inc(&self, name: &'static str, labels: &[(&'static str, &str)]) {
+        self.add(name, labels, 1);
+    }
+
+    pub(crate) fn render(&self) -> String {
+        let values = self.values.lock().unwrap();
+        let mut output = String::new();
+
+        for (name, metric_type, help) in DESCRIPTIONS {
+            let _ = writeln!(output, "# HELP {} {}", name, help);
+            let _ = writeln!(output, "# TYPE {} {}", name, metric_type);
+            let Some(series) = values.get(name) else {
+                continue;
+            };
+            for (labels, value) in series {
+                output.push_str(name);
+                if !labels.is_empty() {
+                    let labels = labels
+                        .iter()
+                        .map(|(key, value)| format!("{}=\"{}\"", key, escape_label(value)))
+                        .collect::<Vec<_>>();
+                    let _ = write!(output, "{{{}}}", labels.join(","));
+                }
+                let _ = writeln!(output, " {}", value);
+            }
+        }
+        output
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_series() {
+        let metrics = Metrics::default();
+        metrics.inc(PACKETS_RECEIVED, &[("connection", "udp")]);
+        metrics.add(PACKETS_RECEIVED, &[("connection", "udp")], 2);
+        metrics.inc(PACKETS_RECEIVED, &[("connection", "say \"hi\"\\\n")]);
+        metrics.add(JOURNAL_QUEUE_DEPTH, &[], 5);
+
+        let output = metrics.render();
+        let lines = output.lines().collect::<Vec<_>>();
+        let at = |line: &str| lines.iter().position(|v| *v == line).unwrap();
+
+        let help =
+            at("# HELP softnode_packets_received_total Mesh packets received from connection");
+        assert_eq!(
+            lines[help + 1],
+            "# TYPE softnode_packets_received_total counter"
+        );
+        assert!(at("softnode_packets_received_total{connection=\"udp\"} 3") > help);
+        at(r#"softnode_packets_received_total{connection="say \"hi\"\\\n"} 1"#);
+        at("# TYPE softnode_journal_queue_depth gauge");
+        at("softnode_journal_queue_depth 5");
+        // Metric without series is described only
+        at("# TYPE softnode_duplicate_drops_total counter");
+        assert!(
+            !lines
+                .iter()
+                .any(|v| v.starts_with("softnode_duplicate_drops_total"))
+        );
+    }
+}
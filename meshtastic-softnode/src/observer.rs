@@ -0,0 +1,326 @@
+use meshtastic_connect::{
+    keyring::{Keyring, cryptor::Decrypt, node_id::NodeId},
+    meshtastic::{self, mesh_packet},
+    transport::mqtt::ConnectionHint,
+};
+use prost::Message;
+use std::{sync::Arc, time::Duration};
+use tokio::{
+    sync::mpsc,
+    task::JoinHandle,
+    time::{Instant, sleep_until},
+};
+
+use crate::{
+    config::JournalConfig,
+    connection,
+    metrics::{self, Metrics},
+    router::ConnectionName,
+    sqlite,
+};
+
+// Sink for packets passed through the router.
+// Called from the router's loop: implementations must not block,
+// long work should be moved to task via channel (see `SqliteObserver`)
+pub trait PacketObserver: Send {
+    // Packet received from connection, after input quirks applied
+    fn on_received(&mut self, connection_name: &ConnectionName, incoming: &connection::Incoming);
+    // Packet originated by softnode, before output quirks applied
+    fn on_sent(&mut self, channel: Option<&ConnectionHint>, mesh_packet: &meshtastic::MeshPacket);
+}
+
+struct Record {
+    gateway: Option<NodeId>,
+    connection_name: ConnectionName,
+    connection_hint: Option<ConnectionHint>,
+    // Known channel's name, when packet is not encrypted
+    channel_name: Option<String>,
+    mesh_packet: meshtastic::MeshPacket,
+}
+
+// Writes packets to the journal.
+// Queue is bounded: packets are dropped while journal is slow, so router's loop is never blocked.
+// Writer's task is finished when observer is dropped and all packets are saved
+pub struct SqliteObserver {
+    node_id: NodeId,
+    records: mpsc::Sender<Record>,
+    metrics: Arc<Metrics>,
+}
+
+impl SqliteObserver {
+    pub fn new(
+        sqlite: sqlite::SQLite,
+        keyring: Keyring,
+        node_id: NodeId,
+        journal: JournalConfig,
+        metrics: Arc<Metrics>,
+    ) -> (Self, JoinHandle<()>) {
+        let (records, records_rx) = mpsc::channel::<Record>(journal.queue_size.max(1));
+        let writer = tokio::spawn(write_journal(
+            sqlite,
+            keyring,
+            journal,
+            metrics.clone(),
+            records_rx,
+        ));
+
+        (
+            Self {
+                node_id,
+                records,
+                metrics,
+            },
+            writer,
+        )
+    }
+
+    fn push(&self, record: Record) {
+        match self.records.try_send(record) {
+            Ok(()) => self.metrics.add(metrics::JOURNAL_QUEUE_DEPTH, &[], 1),
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                log::debug!("Journal's queue is full, packet is dropped");
+                self.metrics.inc(metrics::JOURNAL_DROPS, &[]);
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => {
+                log::error!("Journal writer is stopped, packet is not saved");
+            }
+        }
+    }
+}
+
+impl PacketObserver for SqliteObserver {
+    fn on_received(&mut self, connection_name: &ConnectionName, incoming: &connection::Incoming) {
+        if let connection::DataVariant::MeshPacket(mesh_packet) = &incoming.data {
+            self.push(Record {
+                gateway: incoming.gateway_id,
+                connection_name: connection_name.clone(),
+                connection_hint: incoming.connection_hint.clone(),
+                // router: get channel name by mesh_packet.channel (number of channel)
+                channel_name: None,
+                mesh_packet: mesh_packet.clone(),
+            });
+        }
+    }
+
+    fn on_sent(&mut self, channel: Option<&ConnectionHint>, mesh_packet: &meshtastic::MeshPacket) {
+        self.push(Record {
+            gateway: Some(self.node_id),
+            connection_name: self.node_id.into(),
+            connection_hint: Some("self-generated".to_string()),
+            channel_name: channel.cloned(),
+            mesh_packet: mesh_packet.clone(),
+        });
+    }
+}
+
+// Returns reason of failure for metrics
+fn decrypt(
+    keyring: &Keyring,
+    mesh_packet: &meshtastic::MeshPacket,
+    encrypted_data: &[u8],
+) -> Result<(String, meshtastic::Data), &'static str> {
+    let Some(cryptor) = keyring.cryptor_for(
+        NodeId::from(mesh_packet.from),
+        NodeId::from(mesh_packet.to),
+        mesh_packet.channel.into(),
+    ) else {
+        log::debug!(
+            "No cryptor found for packet {:#x} from {}",
+            mesh_packet.id,
+            NodeId::from(mesh_packet.from)
+        );
+        log::trace!("Packet without cryptor: {:?}", mesh_packet);
+        return Err("no_cryptor");
+    };
+
+    let decrypted_data = cryptor
+        .decrypt(mesh_packet.id, encrypted_data.to_vec())
+        .map_err(|err| {
+            log::debug!("Failed to decrypt encrypted data: {}", err);
+            "decrypt"
+        })?;
+    let data = meshtastic::Data::decode(decrypted_data.as_slice()).map_err(|err| {
+        log::debug!("Failed to construct data: {}", err);
+        "decode"
+    })?;
+
+    Ok((cryptor.to_string(), data))
+}
+
+// Decrypts packet and applies journal's filter
+fn journal_record(
+    keyring: &Keyring,
+    journal: &JournalConfig,
+    metrics: &Metrics,
+    record: Record,
+) -> Option<sqlite::JournalRecord> {
+    let mesh_packet = &record.mesh_packet;
+    let (channel_name, port_num, data) = match &mesh_packet.payload_variant {
+        Some(mesh_packet::PayloadVariant::Decoded(data)) => (
+            record.channel_name,
+            Some(data.portnum()),
+            Some(data.encode_to_vec()),
+        ),
+        Some(mesh_packet::PayloadVariant::Encrypted(encrypted_data)) => {
+            match decrypt(keyring, mesh_packet, encrypted_data) {
+                Ok((cryptor_name, data)) => (
+                    record.channel_name.or(Some(cryptor_name)),
+                    Some(data.portnum()),
+                    Some(data.encode_to_vec()),
+                ),
+                Err(reason) => {
+                    metrics.inc(
+                        metrics::DECRYPT_FAILURES,
+                        &[("connection", &record.connection_name), ("reason", reason)],
+                    );
+                    (None, None, Some(encrypted_data.clone()))
+                }
+            }
+        }
+        None => {
+            log::debug!("No data received: {:?}", mesh_packet);
+            (None, None, None)
+        }
+    };
+
+    if let Some(port_num) = port_num {
+        metrics.inc(
+            metrics::PACKETS_BY_PORTNUM,
+            &[
+                ("connection", &record.connection_name),
+                ("channel", channel_name.as_deref().unwrap_or_default()),
+                ("portnum", port_num.as_str_name()),
+            ],
+        );
+    }
+
+    if !journal.filter.allows(port_num, channel_name.as_deref()) {
+        return None;
+    }
+
+    Some(sqlite::JournalRecord {
+        gateway: record.gateway,
+        connection_name: record.connection_name,
+        connection_hint: record.connection_hint,
+        packet: record.mesh_packet,
+        channel_name,
+        port_num,
+        data,
+    })
+}
+
+async fn flush(sqlite: &sqlite::SQLite, batch: &mut Vec<sqlite::JournalRecord>) {
+    if batch.is_empty() {
+        return;
+    }
+    if let Err(e) = sqlite.insert_packets(std::mem::take(batch)).await {
+        log::error!("Journal write error: {}", e);
+    }
+}
+
+// Records are written in batches: one transaction per `batch_size` records
+// or per `batch_interval` since the first record in batch.
+// Batch is flushed when all senders are dropped
+async fn write_journal(
+    sqlite: sqlite::SQLite,
+    keyring: Keyring,
+    journal: JournalConfig,
+    metrics: Arc<Metrics>,
+    mut records_rx: mpsc::Receiver<Record>,
+) {
+    let batch_interval: Duration = journal.batch_interval.into();
+    let mut batch = Vec::new();
+    let mut deadline: Option<Instant> = None;
+
+    loop {
+        tokio::select! {
+            record = records_rx.recv() => {
+                let Some(record) = record else {
+                    break;
+                };
+                metrics.add(metrics::JOURNAL_QUEUE_DEPTH, &[], -1);
+                if let Some(record) = journal_record(&keyring, &journal, &metrics, record) {
+                    batch.push(record);
+                    deadline.get_or_insert_with(|| Instant::now() + batch_interval);
+                }
+                if batch.len() >= journal.batch_size {
+                    flush(&sqlite, &mut batch).await;
+                    deadline = None;
+                }
+            }
+            _ = sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => {
+                flush(&sqlite, &mut batch).await;
+                deadline = None;
+            }
+        }
+    }
+
+    flush(&sqlite, &mut batch).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Record, write_journal};
+    use crate::{config::JournalConfig, metrics::Metrics, sqlite::SQLite};
+    use futures::StreamExt;
+    use meshtastic_connect::{keyring::Keyring, meshtastic};
+    use std::{sync::Arc, time::Duration};
+    use tokio::{sync::mpsc, time::sleep};
+
+    fn record(id: u32) -> Record {
+        Record {
+            gateway: None,
+            connection_name: "test".into(),
+            connection_hint: None,
+            channel_name: None,
+            mesh_packet: meshtastic::MeshPacket {
+                id,
+                ..Default::default()
+            },
+        }
+    }
+
+    async fn stored(sqlite: &SQLite) -> usize {
+        sqlite.packets_since(0, 100).count().await
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn batches_are_flushed() {
+        let journal = JournalConfig {
+            wal: false,
+            batch_size: 3,
+            batch_interval: Duration::from_secs(1).into(),
+            ..Default::default()
+        };
+        let sqlite = SQLite::new(":memory:", &journal).await.unwrap();
+        let (records, records_rx) = mpsc::channel(10);
+        let writer = tokio::spawn(write_journal(
+            sqlite.clone(),
+            Keyring::default(),
+            journal,
+            Arc::new(Metrics::default()),
+            records_rx,
+        ));
+
+        // Flushed by `batch_size`
+        for id in 1..=3 {
+            records.send(record(id)).await.unwrap();
+        }
+        sleep(Duration::from_millis(10)).await;
+        assert_eq!(stored(&sqlite).await, 3);
+
+        // Flushed by `batch_interval`
+        records.send(record(4)).await.unwrap();
+        sleep(Duration::from_millis(500)).await;
+        assert_eq!(stored(&sqlite).await, 3);
+        sleep(Duration::from_millis(600)).await;
+        assert_eq!(stored(&sqlite).await, 4);
+
+        // Flushed when sender is dropped
+        records.send(record(5)).await.unwrap();
+        records.send(record(6)).await.unwrap();
+        drop(records);
+        writer.await.unwrap();
+        assert_eq!(stored(&sqlite).await, 6);
+    }
+}
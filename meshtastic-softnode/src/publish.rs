@@ -1,6 +1,9 @@
 use crate::{config::SoftNodeConfig, meshtastic};
 use duration_string::DurationString;
-use meshtastic_connect::keyring::{key::Key, node_id::NodeId};
+use meshtastic_connect::{
+    geo::PositionBuilder,
+    keyring::{key::Key, node_id::NodeId},
+};
 use prost::Message;
 use serde::{Deserialize, Serialize, de};
 use std::time::Duration;
@@ -165,14 +168,13 @@ impl Publishable for PublishPosition {
 
     fn pack_to_data(&self, _soft_node: &SoftNodeConfig) -> (meshtastic::PortNum, Vec<u8>) {
         let position = meshtastic::Position {
-            latitude_i: Some((self.lat / 1e-7).round() as i32),
-            longitude_i: Some((self.lon / 1e-7).round() as i32),
-            altitude_hae: Some(self.alt),
             location_source: meshtastic::position::LocSource::LocManual.into(),
             altitude_source: meshtastic::position::AltSource::AltManual.into(),
-            timestamp: chrono::Utc::now().timestamp() as u32,
             next_update: self.interval.as_secs() as u32,
-            ..Default::default()
+            ..PositionBuilder::new(self.lat, self.lon)
+                .altitude_hae(self.alt)
+                .timestamp(chrono::Utc::now().timestamp() as u32)
+                .build()
         };
 
         (meshtastic::PortNum::PositionApp, position.encode_to_vec())
@@ -233,7 +235,24 @@ impl Publishable for PublishText {
     fn pack_to_data(&self, _: &SoftNodeConfig) -> (meshtastic::PortNum, Vec<u8>) {
         (
             meshtastic::PortNum::TextMessageApp,
-            self.text.encode_to_vec(),
+            self.text.as_bytes().to_vec(),
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_is_raw_utf8() {
+        let text = PublishText {
+            interval: Duration::ZERO.into(),
+            text: "привет".to_string(),
+        };
+        // Firmware reads payload of TextMessageApp as is, without protobuf's length prefix
+        let (port_num, payload) = text.pack_to_data(&SoftNodeConfig::default());
+        assert_eq!(port_num, meshtastic::PortNum::TextMessageApp);
+        assert_eq!(payload, "привет".as_bytes());
+    }
+}
@@ -0,0 +1,428 @@
+use meshtastic_connect::{
+    keyring::{Keyring, node_id::NodeId},
+    meshtastic,
+    transport::mqtt::ConnectionHint,
+};
+use prost::Message;
+use serde::Deserialize;
+use softnode_client::app::data::{DataVariant, DecryptTarget, StoredMeshPacket};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    time::Duration,
+};
+use tokio::{sync::mpsc, time::sleep};
+
+use crate::{
+    config::{RelayConfig, RelayTarget},
+    connection,
+    observer::PacketObserver,
+    router::ConnectionName,
+};
+
+const TELEGRAM_API: &str = "https://api.telegram.org/bot";
+// Bytes of text published in one packet, Data's payload is limited to 233 bytes
+const TEXT_LIMIT: usize = 200;
+const ELLIPSIS: &str = "…";
+// Packets waiting to be relayed to chat
+const QUEUE_SIZE: usize = 100;
+// Chat's messages waiting for airtime, newer ones are dropped
+const INBOUND_QUEUE: usize = 10;
+// Same packet is heard via several gateways
+const SEEN_LIMIT: usize = 1000;
+const POLL_TIMEOUT_SECS: u64 = 30;
+const POLL_RETRY_INTERVAL: Duration = Duration::from_secs(10);
+// Rate limit's delay is capped, queue of relay is not held longer
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+// Channel's name and text to publish to mesh
+type InboundText = (String, String);
+
+// Relays text messages of channels to Discord or Telegram chat, and back.
+// Relayed texts are received via `inbound`, paced by relay's `inbound_interval`
+pub struct RelayObserver {
+    records: mpsc::Sender<(Option<String>, meshtastic::MeshPacket)>,
+}
+
+impl RelayObserver {
+    pub fn new(
+        relay: RelayConfig,
+        keyring: Keyring,
+        node_id: NodeId,
+        inbound: mpsc::Sender<InboundText>,
+    ) -> Result<Self, String> {
+        if relay.channels.is_empty() {
+            return Err("no channels to relay".into());
+        }
+        // Long polling holds the request for `POLL_TIMEOUT_SECS`
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(POLL_TIMEOUT_SECS * 2))
+            .build()
+            .map_err(|e| e.to_string())?;
+
+        if let Some(channel) = &relay.inbound_channel {
+            match &relay.target {
+                RelayTarget::Discord { .. } => {
+                    return Err("Discord's webhook does not receive messages, \
+                                unset `inbound_channel`"
+                        .into());
+                }
+                RelayTarget::Telegram { bot_token, chat_id } => {
+                    let (pending, pending_rx) = mpsc::channel(INBOUND_QUEUE);
+                    tokio::spawn(poll_telegram(
+                        client.clone(),
+                        bot_token.clone(),
+                        *chat_id,
+                        pending,
+                    ));
+                    tokio::spawn(pace(
+                        pending_rx,
+                        channel.clone(),
+                        relay.inbound_interval.into(),
+                        inbound,
+                    ));
+                }
+            }
+        }
+
+        let (records, records_rx) = mpsc::channel(QUEUE_SIZE);
+        tokio::spawn(forward(client, relay, keyring, node_id, records_rx));
+        Ok(Self { records })
+    }
+
+    fn push(&self, channel_name: Option<String>, mesh_packet: &meshtastic::MeshPacket) {
+        if self
+            .records
+            .try_send((channel_name, mesh_packet.clone()))
+            .is_err()
+        {
+            log::debug!("Relay's queue is full, packet is dropped");
+        }
+    }
+}
+
+impl PacketObserver for RelayObserver {
+    fn on_received(&mut self, _: &ConnectionName, incoming: &connection::Incoming) {
+        if let connection::DataVariant::MeshPacket(mesh_packet) = &incoming.data {
+            self.push(None, mesh_packet);
+        }
+    }
+
+    fn on_sent(&mut self, channel: Option<&ConnectionHint>, mesh_packet: &meshtastic::MeshPacket) {
+        // Softnode's own texts are not relayed by `forward`
+        self.push(channel.cloned(), mesh_packet);
+    }
+}
+
+#[derive(Default)]
+struct SeenPackets {
+    seen: HashSet<(NodeId, u32)>,
+    order: VecDeque<(NodeId, u32)>,
+}
+
+impl SeenPackets {
+    // False if packet is already relayed
+    fn insert(&mut self, key: (NodeId, u32)) -> bool {
+        if !self.seen.insert(key) {
+            return false;
+        }
+        self.order.push_back(key);
+        if self.order.len() > SEEN_LIMIT
+            && let Some(oldest) = self.order.pop_front()
+        {
+            self.seen.remove(&oldest);
+        }
+        true
+    }
+}
+
+// Broadcast texts of relay's channels are posted to chat,
+// senders are named by their NodeInfo when it is heard
+async fn forward(
+    client: reqwest::Client,
+    relay: RelayConfig,
+    keyring: Keyring,
+    node_id: NodeId,
+    mut records_rx: mpsc::Receiver<(Option<String>, meshtastic::MeshPacket)>,
+) {
+    let mut names: HashMap<NodeId, String> = HashMap::new();
+    let mut seen = SeenPackets::default();
+
+    while let Some((channel_name, mesh_packet)) = records_rx.recv().await {
+        let packet = StoredMeshPacket::from_mesh_packet(0, None, String::new(), None, &mesh_packet)
+            .decrypt(&keyring, false);
+        let Some(DataVariant::Decrypted(target, data)) = &packet.data else {
+            continue;
+        };
+        let from = packet.header.from;
+
+        match data.portnum() {
+            meshtastic::PortNum::NodeinfoApp => {
+                if let Ok(user) = meshtastic::User::decode(data.payload.as_slice())
+                    && !user.long_name.is_empty()
+                {
+                    names.insert(from, user.long_name);
+                }
+            }
+            meshtastic::PortNum::TextMessageApp => {
+                let channel_name = channel_name.or(match target {
+                    DecryptTarget::Channel(name) | DecryptTarget::Guessed(name) => {
+                        Some(name.clone())
+                    }
+                    DecryptTarget::Direct(_) | DecryptTarget::PKI => None,
+                });
+                // Relayed chat's messages are softnode's own: not sent back
+                if from == node_id
+                    || packet.header.to != NodeId::broadcast()
+                    || !channel_name.is_some_and(|name| relay.channels.contains(&name))
+                    || !seen.insert((from, packet.header.id))
+                {
+                    continue;
+                }
+
+                let sender = names
+                    .get(&from)
+                    .cloned()
+                    .unwrap_or_else(|| from.to_string());
+                let text = String::from_utf8_lossy(&data.payload);
+                if let Err(e) = send(&client, &relay.target, &sender, &text).await {
+                    log::warn!("Message from {} is not relayed: {}", from, e);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+async fn send(
+    client: &reqwest::Client,
+    target: &RelayTarget,
+    sender: &str,
+    text: &str,
+) -> Result<(), String> {
+    let request = match target {
+        RelayTarget::Discord { webhook_url } => client.post(webhook_url).body(
+            serde_json::json!({
+                "username": sender,
+                "content": text,
+                // Mesh's texts never ping chat's users or roles
+                "allowed_mentions": { "parse": [] },
+            })
+            .to_string(),
+        ),
+        RelayTarget::Telegram { bot_token, chat_id } => client
+            .post(format!("{}{}/sendMessage", TELEGRAM_API, bot_token))
+            .body(
+                serde_json::json!({
+                    "chat_id": chat_id,
+                    "text": format!("{}: {}", sender, text),
+                })
+                .to_string(),
+            ),
+    }
+    .header(reqwest::header::CONTENT_TYPE, "application/json");
+
+    // Rate limited message is sent again once, after the delay asked by chat
+    let retry = request.try_clone();
+    let mut response = execute(request).await?;
+    if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS
+        && let Some(retry) = retry
+    {
+        let delay = retry_after(response).await;
+        log::warn!("Chat is rate limited, retry in {:?}", delay);
+        sleep(delay).await;
+        response = execute(retry).await?;
+    }
+
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("chat responded with {}", response.status()))
+    }
+}
+
+// URLs hold tokens: they are not logged
+async fn execute(request: reqwest::RequestBuilder) -> Result<reqwest::Response, String> {
+    request
+        .send()
+        .await
+        .map_err(|e| e.without_url().to_string())
+}
+
+// Discord tells the delay in `retry_after` of body, Telegram in `parameters`.
+// `Retry-After` header is used when body has none
+async fn retry_after(response: reqwest::Response) -> Duration {
+    let header = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<f64>().ok());
+    let body = response
+        .bytes()
+        .await
+        .ok()
+        .and_then(|body| serde_json::from_slice::<serde_json::Value>(&body).ok())
+        .and_then(|body| {
+            body.get("retry_after")
+                .or_else(|| body.pointer("/parameters/retry_after"))
+                .and_then(|value| value.as_f64())
+        });
+
+    body.or(header)
+        .filter(|secs| secs.is_finite() && *secs >= 0.0)
+        .map(|secs| Duration::from_secs_f64(secs).min(MAX_RETRY_AFTER))
+        .unwrap_or(POLL_RETRY_INTERVAL)
+}
+
+#[derive(Deserialize)]
+struct TelegramResponse {
+    ok: bool,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    result: Vec<TelegramUpdate>,
+}
+
+#[derive(Deserialize)]
+struct TelegramUpdate {
+    update_id: i64,
+    message: Option<TelegramMessage>,
+}
+
+#[derive(Deserialize)]
+struct TelegramMessage {
+    chat: TelegramChat,
+    from: Option<TelegramUser>,
+    text: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TelegramChat {
+    id: i64,
+}
+
+#[derive(Deserialize)]
+struct TelegramUser {
+    first_name: String,
+    username: Option<String>,
+}
+
+async fn telegram_updates(
+    client: &reqwest::Client,
+    bot_token: &str,
+    offset: i64,
+    timeout: u64,
+) -> Result<Vec<TelegramUpdate>, String> {
+    let response = client
+        .get(format!(
+            "{}{}/getUpdates?offset={}&timeout={}",
+            TELEGRAM_API, bot_token, offset, timeout
+        ))
+        .send()
+        .await
+        .map_err(|e| e.without_url().to_string())?;
+    let body = response
+        .bytes()
+        .await
+        .map_err(|e| e.without_url().to_string())?;
+    let response: TelegramResponse = serde_json::from_slice(&body).map_err(|e| e.to_string())?;
+    if !response.ok {
+        return Err(response.description);
+    }
+    Ok(response.result)
+}
+
+// Chat's messages are read by long polling.
+// Messages sent while softnode was stopped are skipped
+async fn poll_telegram(
+    client: reqwest::Client,
+    bot_token: String,
+    chat_id: i64,
+    pending: mpsc::Sender<String>,
+) {
+    // Offset -1 returns the last update only, to find the end of backlog
+    let mut offset = -1;
+    let mut skip_backlog = true;
+
+    loop {
+        let timeout = if skip_backlog { 0 } else { POLL_TIMEOUT_SECS };
+        let updates = match telegram_updates(&client, &bot_token, offset, timeout).await {
+            Ok(updates) => updates,
+            Err(e) => {
+                log::warn!("Telegram's updates are not received: {}", e);
+                sleep(POLL_RETRY_INTERVAL).await;
+                continue;
+            }
+        };
+
+        for update in updates {
+            offset = offset.max(update.update_id + 1);
+            if skip_backlog {
+                continue;
+            }
+            let Some(message) = update.message else {
+                continue;
+            };
+            let Some(text) = message.text else {
+                continue;
+            };
+            if message.chat.id != chat_id {
+                continue;
+            }
+            let sender = message
+                .from
+                .map(|from| from.username.unwrap_or(from.first_name))
+                .unwrap_or_default();
+
+            match pending.try_send(truncate(&format!("{}: {}", sender, text), TEXT_LIMIT)) {
+                Ok(()) => {}
+                Err(mpsc::error::TrySendError::Full(_)) => {
+                    log::warn!("Chat is faster than airtime allows, message is dropped");
+                }
+                Err(mpsc::error::TrySendError::Closed(_)) => return,
+            }
+        }
+        skip_backlog = false;
+    }
+}
+
+// One text per `interval`, to leave airtime for the mesh
+async fn pace(
+    mut pending_rx: mpsc::Receiver<String>,
+    channel: String,
+    interval: Duration,
+    inbound: mpsc::Sender<InboundText>,
+) {
+    while let Some(text) = pending_rx.recv().await {
+        if inbound.send((channel.clone(), text)).await.is_err() {
+            break;
+        }
+        sleep(interval).await;
+    }
+}
+
+// Cuts text to `limit` bytes on chars' boundary, the cut is marked
+fn truncate(text: &str, limit: usize) -> String {
+    if text.len() <= limit {
+        return text.to_string();
+    }
+    let mut end = limit - ELLIPSIS.len();
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}{}", &text[..end], ELLIPSIS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncate_on_char_boundary() {
+        assert_eq!(truncate("short", 10), "short");
+        assert_eq!(truncate("0123456789ab", 10), "0123456…");
+        // Two bytes per char: cut is moved back to the char's start
+        let text = truncate("привет мир", 10);
+        assert!(text.len() <= 10);
+        assert_eq!(text, "при…");
+    }
+}
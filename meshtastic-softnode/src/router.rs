@@ -1,13 +1,23 @@
-use std::sync::Arc;
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::Arc,
+    time::Duration,
+};
 
-use meshtastic_connect::transport::mqtt;
+use meshtastic_connect::{keyring::node_id::NodeId, meshtastic::MeshPacket, transport::mqtt};
 use tokio::{sync::Mutex, task::JoinSet};
 
 pub type ConnectionName = String;
 type ConnectionId = usize;
+// Forwarded packets to drop duplicates, received from other connections
+const FORWARDED_MAX: usize = 1024;
+// Time to deliver MQTT's DISCONNECT on exit
+const DISCONNECT_TIMEOUT: Duration = Duration::from_secs(3);
 use crate::{
-    config::{TransportQuirk, TransportQuirks},
+    config::{TransitConfig, TransportQuirk, TransportQuirks},
     connection,
+    metrics::{self, Metrics},
+    observer::PacketObserver,
 };
 
 impl Router {
@@ -40,6 +50,49 @@ impl Router {
         }
     }
 
+    pub async fn disconnect(&mut self) {
+        // MQTT's DISCONNECT is only queued, receivers' tasks send it
+        let mut pending = HashSet::new();
+        for capsule in &self.connections {
+            log::info!("Disconnect {}...", capsule.name);
+            let mut send = capsule.send.lock().await;
+            match send.disconnect().await {
+                Ok(()) if matches!(*send, connection::Sender::MQTT(_)) => {
+                    pending.insert(capsule.id);
+                }
+                Ok(()) => {}
+                Err(e) => log::warn!("Disconnect {} error: {}", capsule.name, e),
+            }
+        }
+
+        let wait = async {
+            while !pending.is_empty()
+                && let Some(res) = self.recv_set.join_next().await
+            {
+                let Ok((id, result, recv)) = res else {
+                    continue;
+                };
+                if result.is_ok() && pending.contains(&id) {
+                    // Packets received meanwhile are dropped
+                    set_wait_data(&mut self.recv_set, recv, id);
+                } else {
+                    pending.remove(&id);
+                }
+            }
+        };
+        if tokio::time::timeout(DISCONNECT_TIMEOUT, wait)
+            .await
+            .is_err()
+        {
+            log::warn!("Disconnect is not confirmed in {:?}", DISCONNECT_TIMEOUT);
+        }
+    }
+
+    // Observers are called in order of registration
+    pub fn add_observer(&mut self, observer: Box<dyn PacketObserver>) {
+        self.observers.push(observer);
+    }
+
     // Send a mesh packet to all connections except the one specified by `from`
     async fn send_mesh_except(
         &mut self,
@@ -53,22 +106,51 @@ impl Router {
                     continue;
                 }
             }
-            println!("> {:?} send: {:?}", capsule.name, mesh_packet);
+            log::trace!("> {:?} send: {:?}", capsule.name, mesh_packet);
             let mut mesh_packet = mesh_packet.clone();
             let channel = if let Some(channel) = channel.clone() {
                 channel
             } else {
-                println!(
+                log::debug!(
                     "> {:?} use default channel: {}",
-                    capsule.name, capsule.default_channel
+                    capsule.name,
+                    capsule.default_channel
                 );
                 capsule.default_channel.clone()
             };
 
             apply_quirk_to_packet(&mut mesh_packet, &capsule.quirks.output);
 
+            if self.read_only {
+                log::info!(
+                    "> {:?} read-only, not sent to {}: id={:#x}",
+                    capsule.name,
+                    channel,
+                    mesh_packet.id
+                );
+                log::trace!("> {:?} not sent: {:?}", capsule.name, mesh_packet);
+                continue;
+            }
+
+            self.metrics.inc(
+                metrics::PACKETS_SENT,
+                &[("connection", &capsule.name), ("channel", &channel)],
+            );
+            self.metrics
+                .add(metrics::SEND_IN_FLIGHT, &[("connection", &capsule.name)], 1);
+
             let send = capsule.send.clone();
-            tokio::spawn(async move { send.lock().await.send((channel, mesh_packet)).await });
+            let send_metrics = self.metrics.clone();
+            let connection_name = capsule.name.clone();
+            tokio::spawn(async move {
+                let result = send.lock().await.send((channel, mesh_packet)).await;
+                send_metrics.add(
+                    metrics::SEND_IN_FLIGHT,
+                    &[("connection", &connection_name)],
+                    -1,
+                );
+                result
+            });
         }
     }
 }
@@ -106,15 +188,111 @@ pub struct Router {
 
     // Interrupting set
     interrupt_set: InterruptSet,
+
+    observers: Vec<Box<dyn PacketObserver>>,
+
+    metrics: Arc<Metrics>,
+
+    node_id: NodeId,
+    // Don't transmit anything
+    read_only: bool,
+    transit: TransitConfig,
+    // (from, id) of forwarded packets
+    forwarded: HashSet<(u32, u32)>,
+    forwarded_order: VecDeque<(u32, u32)>,
 }
 
 impl Router {
+    pub fn metrics(&self) -> Arc<Metrics> {
+        self.metrics.clone()
+    }
+
+    pub fn set_transit(&mut self, transit: TransitConfig) {
+        self.transit = transit;
+    }
+
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    // Own node id: own packets are not relayed, used as `relay_node`
+    pub fn set_node_id(&mut self, node_id: NodeId) {
+        self.node_id = node_id;
+    }
+
+    // Returns packet to rebroadcast with meshtastic's hop semantics:
+    // `hop_limit` is decremented, `hop_start` is kept as is,
+    // `relay_node` is set to the last byte of own node id
+    fn relay_packet(
+        &mut self,
+        source_connection_name: &ConnectionName,
+        mesh_packet: &MeshPacket,
+    ) -> Option<MeshPacket> {
+        if !self
+            .transit
+            .allows(mesh_packet.from.into(), mesh_packet.to.into())
+        {
+            return None;
+        }
+        if NodeId::from(mesh_packet.from) == self.node_id {
+            return None;
+        }
+
+        let key = (mesh_packet.from, mesh_packet.id);
+        if !self.forwarded.insert(key) {
+            self.metrics.inc(
+                metrics::DUPLICATE_DROPS,
+                &[("connection", source_connection_name)],
+            );
+            return None;
+        }
+        self.forwarded_order.push_back(key);
+        if self.forwarded_order.len() > FORWARDED_MAX
+            && let Some(key) = self.forwarded_order.pop_front()
+        {
+            self.forwarded.remove(&key);
+        }
+
+        if mesh_packet.hop_limit == 0 {
+            return None;
+        }
+
+        let mut mesh_packet = mesh_packet.clone();
+        mesh_packet.hop_limit -= 1;
+        mesh_packet.relay_node = u32::from(self.node_id) & 0xff;
+        Some(mesh_packet)
+    }
+
+    // Forward received packet to all connections except the source one,
+    // if it is allowed by transit rules
+    pub async fn route_next(&mut self, recv_capsule: &ReceiveCapsule) {
+        let connection::DataVariant::MeshPacket(ref mesh_packet) = recv_capsule.incoming.data
+        else {
+            return;
+        };
+        let Some(mesh_packet) =
+            self.relay_packet(&recv_capsule.source_connection_name, mesh_packet)
+        else {
+            return;
+        };
+
+        self.send_mesh_except(
+            recv_capsule.incoming.connection_hint.clone(),
+            &mesh_packet,
+            Some(recv_capsule.source_connection_id),
+        )
+        .await;
+    }
+
     // Send to all connections
     pub async fn send_mesh(
         &mut self,
         channel: Option<mqtt::ConnectionHint>,
         mesh_packet: meshtastic_connect::meshtastic::MeshPacket,
     ) {
+        for observer in self.observers.iter_mut() {
+            observer.on_sent(channel.as_ref(), &mesh_packet);
+        }
         self.send_mesh_except(channel, &mesh_packet, None).await;
     }
 
@@ -188,10 +366,16 @@ impl Router {
         let mut incoming = incoming?;
 
         if let connection::DataVariant::MeshPacket(ref mut mesh_packet) = incoming.data {
-            println!("> {:?} received: {:?}", capsule.name, mesh_packet);
+            log::trace!("> {:?} received: {:?}", capsule.name, mesh_packet);
+            self.metrics
+                .inc(metrics::PACKETS_RECEIVED, &[("connection", &capsule.name)]);
             apply_quirk_to_packet(mesh_packet, &capsule.quirks.input);
         }
 
+        for observer in self.observers.iter_mut() {
+            observer.on_received(&capsule.name, &incoming);
+        }
+
         return Ok(ReceiveCapsule {
             source_connection_name: capsule.name.clone(),
             source_connection_id: capsule.id,
@@ -239,3 +423,176 @@ fn apply_quirk_to_packet(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Router;
+    use crate::{
+        config::{TransitConfig, TransportQuirks},
+        connection,
+    };
+    use futures::StreamExt;
+    use meshtastic_connect::{
+        meshtastic::{self, MeshPacket},
+        transport::{
+            loopback::{self, Radio, Sent},
+            mqtt_stream::MqttStream,
+        },
+    };
+    use prost::Message;
+    use std::time::Duration;
+
+    fn router() -> Router {
+        let mut router = Router::default();
+        router.set_node_id(0x1234abcd.into());
+        router.set_transit(TransitConfig {
+            enabled: true,
+            ..Default::default()
+        });
+        router
+    }
+
+    fn packet(from: u32, id: u32, hop_limit: u32) -> MeshPacket {
+        MeshPacket {
+            from,
+            to: 0xffffffff,
+            id,
+            hop_limit,
+            hop_start: 7,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn relay_decrements_hop_limit() {
+        let mut router = router();
+        let relayed = router
+            .relay_packet(&"udp".into(), &packet(0xda5ad56c, 1, 3))
+            .unwrap();
+        assert_eq!(relayed.hop_limit, 2);
+        assert_eq!(relayed.hop_start, 7);
+        assert_eq!(relayed.relay_node, 0xcd);
+    }
+
+    #[test]
+    fn relay_drops_hop_limit_0() {
+        let mut router = router();
+        assert_eq!(
+            router.relay_packet(&"udp".into(), &packet(0xda5ad56c, 1, 0)),
+            None
+        );
+    }
+
+    #[test]
+    fn relay_drops_self_originated() {
+        let mut router = router();
+        assert_eq!(
+            router.relay_packet(&"udp".into(), &packet(0x1234abcd, 1, 3)),
+            None
+        );
+    }
+
+    #[test]
+    fn relay_drops_duplicates() {
+        let mut router = router();
+        assert!(
+            router
+                .relay_packet(&"udp".into(), &packet(0xda5ad56c, 1, 3))
+                .is_some()
+        );
+        assert_eq!(
+            router.relay_packet(&"mqtt".into(), &packet(0xda5ad56c, 1, 2)),
+            None
+        );
+        assert!(
+            router
+                .relay_packet(&"udp".into(), &packet(0xda5ad56c, 2, 3))
+                .is_some()
+        );
+    }
+
+    // Connection over loopback, radio proxies MQTT on `msh` topic
+    fn add_loopback(router: &mut Router, name: &str) -> Radio {
+        let (stream, radio) = loopback::pair();
+        let (sender, receiver) = MqttStream::new(stream, 0x1234abcd.into(), "msh".into()).split();
+        router.add_connection(
+            name.into(),
+            TransportQuirks {
+                input: vec![],
+                output: vec![],
+            },
+            "LongFast".into(),
+            (
+                connection::Sender::Stream(sender),
+                connection::Receiver::Stream(receiver),
+                None,
+            ),
+        );
+        radio
+    }
+
+    fn proxy_message(
+        gateway_id: &str,
+        mesh_packet: MeshPacket,
+    ) -> meshtastic::from_radio::PayloadVariant {
+        let envelope = meshtastic::ServiceEnvelope {
+            packet: Some(mesh_packet),
+            channel_id: "LongFast".into(),
+            gateway_id: gateway_id.into(),
+        };
+        meshtastic::from_radio::PayloadVariant::MqttClientProxyMessage(
+            meshtastic::MqttClientProxyMessage {
+                topic: format!("msh/2/e/LongFast/{}", gateway_id),
+                retained: false,
+                payload_variant: Some(meshtastic::mqtt_client_proxy_message::PayloadVariant::Data(
+                    envelope.encode_to_vec(),
+                )),
+            },
+        )
+    }
+
+    // Packet forwarded to radio, decoded from proxied envelope
+    async fn forwarded(radio: &mut Radio) -> Option<MeshPacket> {
+        let sent = tokio::time::timeout(Duration::from_millis(100), radio.recv())
+            .await
+            .ok()??;
+        let Sent::ToRadio(meshtastic::to_radio::PayloadVariant::MqttClientProxyMessage(proxy)) =
+            sent
+        else {
+            return None;
+        };
+        let Some(meshtastic::mqtt_client_proxy_message::PayloadVariant::Data(data)) =
+            proxy.payload_variant
+        else {
+            return None;
+        };
+        meshtastic::ServiceEnvelope::decode(data.as_slice())
+            .ok()?
+            .packet
+    }
+
+    #[tokio::test]
+    async fn route_over_loopback() {
+        let mut router = router();
+        let mut radio_a = add_loopback(&mut router, "a");
+        let mut radio_b = add_loopback(&mut router, "b");
+
+        radio_a.send(proxy_message("!da5ad56c", packet(0xda5ad56c, 1, 3)));
+        let recv_capsule = router.recv_mesh().await.unwrap();
+        assert_eq!(recv_capsule.source_connection_name, "a");
+        router.route_next(&recv_capsule).await;
+
+        let relayed = forwarded(&mut radio_b).await.unwrap();
+        assert_eq!(relayed.id, 1);
+        assert_eq!(relayed.hop_limit, 2);
+        assert_eq!(relayed.relay_node, 0xcd);
+        assert!(forwarded(&mut radio_a).await.is_none());
+
+        // Same packet, heard back on other connection, is not relayed again
+        radio_b.send(proxy_message("!da5ad56c", relayed));
+        let recv_capsule = router.recv_mesh().await.unwrap();
+        assert_eq!(recv_capsule.source_connection_name, "b");
+        router.route_next(&recv_capsule).await;
+        assert!(forwarded(&mut radio_a).await.is_none());
+    }
+}
@@ -1,37 +1,35 @@
 use std::sync::Arc;
 
+use axum::body::Body;
 use axum::extract::{Query, State};
-use axum::http::StatusCode;
-use axum::{Json, Router, routing};
-use futures::StreamExt;
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use axum::{BoxError, Router, routing};
+use chrono::{DateTime, TimeDelta, Utc};
+use futures::stream::{self, BoxStream};
+use futures::{StreamExt, TryStreamExt};
+use meshtastic_connect::keyring::node_id::NodeId;
 use rustls_acme::AcmeConfig;
 use rustls_acme::caches::DirCache;
 use serde::Deserialize;
+use subtle::{Choice, ConstantTimeEq};
+use tower_http::compression::CompressionLayer;
 use tower_http::cors;
 use tower_http::services::ServeDir;
 use tower_http::trace::TraceLayer;
 
 use crate::config::WebConfig;
+use crate::metrics::Metrics;
 use crate::sqlite::SQLite;
-
-use tracing_subscriber::EnvFilter;
-
-pub(crate) fn init() {
-    tracing_subscriber::fmt()
-        // This allows you to use, e.g., `RUST_LOG=info` or `RUST_LOG=debug`
-        // when running the app to set log levels.
-        .with_env_filter(
-            EnvFilter::try_from_default_env()
-                .or_else(|_| EnvFilter::try_new("tower_http=debug"))
-                .unwrap(),
-        )
-        .init();
-}
+use softnode_client::app::{NDJSON_CONTENT_TYPE, data::StoredMeshPacket};
 
 #[derive(Clone)]
 struct Web {
     pub select_limit: usize,
     pub sqlite: SQLite,
+    // Accepted `Authorization` headers' values, empty if API is open
+    pub authorizations: Vec<String>,
+    pub metrics: Arc<Metrics>,
 }
 
 #[derive(Deserialize)]
@@ -39,33 +37,173 @@ struct SyncParams {
     start: Option<u64>,
 }
 
+type PacketStream = BoxStream<'static, Result<StoredMeshPacket, BoxError>>;
+
+// One record per line, so client could commit records before the end of response.
+// Records are serialized while the body is sent
+fn ndjson_response(packets: PacketStream) -> Response {
+    let lines = packets.map(|packet| Ok::<_, BoxError>(ndjson_line(&packet?)?));
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, NDJSON_CONTENT_TYPE)],
+        Body::from_stream(lines),
+    )
+        .into_response()
+}
+
+// JSON array, written element by element
+fn json_response(packets: PacketStream) -> Response {
+    let elements = packets.enumerate().map(|(index, packet)| {
+        let element = serde_json::to_string(&packet?)?;
+        let separator = if index == 0 { "" } else { "," };
+        Ok::<_, BoxError>(format!("{}{}", separator, element))
+    });
+    let array = stream::once(async { Ok("[".to_string()) })
+        .chain(elements)
+        .chain(stream::once(async { Ok("]".to_string()) }));
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "application/json")],
+        Body::from_stream(array),
+    )
+        .into_response()
+}
+
+fn ndjson_line(packet: &StoredMeshPacket) -> Result<String, serde_json::Error> {
+    let mut line = serde_json::to_string(packet).inspect_err(|e| {
+        log::error!("Packet serialization error: {}", e);
+    })?;
+    line.push('\n');
+    Ok(line)
+}
+
+// Compared in constant time, every accepted value is checked
+fn is_authorized(state: &Web, headers: &HeaderMap) -> bool {
+    state.authorizations.is_empty()
+        || headers.get(header::AUTHORIZATION).is_some_and(|v| {
+            state
+                .authorizations
+                .iter()
+                .fold(Choice::from(0), |matched, a| {
+                    matched | a.as_bytes().ct_eq(v.as_bytes())
+                })
+                .into()
+        })
+}
+
 async fn api_softnode(
     State(state): State<Arc<Web>>,
+    headers: HeaderMap,
     params: Query<SyncParams>,
-) -> (
-    StatusCode,
-    Json<Vec<softnode_client::app::data::StoredMeshPacket>>,
-) {
-    if let Ok(packets) = state
+) -> Response {
+    if !is_authorized(&state, &headers) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    let packets = match params.start {
+        Some(start) => state.sqlite.packets_since(start, state.select_limit),
+        None => state.sqlite.packets_recent(state.select_limit),
+    };
+    packets_response(&headers, packets).await
+}
+
+#[derive(Deserialize)]
+struct HistoryParams {
+    node_id: String,
+    // Unix timestamps, last day by default
+    start: Option<i64>,
+    end: Option<i64>,
+}
+
+// Packets sent by node
+async fn api_history(
+    State(state): State<Arc<Web>>,
+    headers: HeaderMap,
+    params: Query<HistoryParams>,
+) -> Response {
+    if !is_authorized(&state, &headers) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    let Ok(node_id) = NodeId::try_from(params.node_id.as_str()) else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+    let end = params
+        .end
+        .and_then(|v| DateTime::from_timestamp(v, 0))
+        .unwrap_or_else(Utc::now);
+    let start = params
+        .start
+        .and_then(|v| DateTime::from_timestamp(v, 0))
+        .unwrap_or_else(|| end - TimeDelta::days(1));
+
+    let packets = state
         .sqlite
-        .select_packets(params.start, state.select_limit)
-        .await
-    {
-        (StatusCode::OK, Json(packets))
+        .packets_for(node_id, start..end, state.select_limit);
+    packets_response(&headers, packets).await
+}
+
+// Rows are read from journal while the response is sent:
+// on journal's error the response is cut
+async fn packets_response(
+    headers: &HeaderMap,
+    packets: BoxStream<'static, tokio_rusqlite::Result<StoredMeshPacket>>,
+) -> Response {
+    let packets = packets
+        .map_err(|e| {
+            log::error!("Journal read error: {}", e);
+            BoxError::from(e)
+        })
+        .boxed();
+
+    // Clients without NDJSON support get JSON array
+    let accept_ndjson = headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains(NDJSON_CONTENT_TYPE));
+    if accept_ndjson {
+        ndjson_response(packets)
     } else {
-        (StatusCode::INTERNAL_SERVER_ERROR, Json(Vec::new()))
+        json_response(packets)
     }
 }
 
-pub(crate) async fn start(config: WebConfig, sqlite: SQLite) -> Result<(), std::io::Error> {
+// Prometheus text format
+async fn api_metrics(State(state): State<Arc<Web>>, headers: HeaderMap) -> Response {
+    if !is_authorized(&state, &headers) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.metrics.render(),
+    )
+        .into_response()
+}
+
+pub(crate) async fn start(
+    config: WebConfig,
+    sqlite: SQLite,
+    metrics: Arc<Metrics>,
+) -> Result<(), std::io::Error> {
     let state = Arc::new(Web {
         select_limit: config.select_limit,
         sqlite,
+        authorizations: config
+            .authorizations
+            .iter()
+            .filter_map(|v| v.header())
+            .collect(),
+        metrics,
     });
     let cors = cors::CorsLayer::new()
         .allow_origin(cors::Any)
         .allow_methods([axum::http::Method::GET, axum::http::Method::POST])
-        .allow_headers([axum::http::header::CONTENT_TYPE]);
+        .allow_headers([
+            axum::http::header::CONTENT_TYPE,
+            axum::http::header::AUTHORIZATION,
+        ]);
     let acme = if let Some(acme) = config.tls_acme {
         let acme_state = AcmeConfig::new(acme.domains)
             .contact(acme.emails.iter().map(|e| format!("mailto:{}", e)))
@@ -80,15 +218,19 @@ pub(crate) async fn start(config: WebConfig, sqlite: SQLite) -> Result<(), std::
 
     let app = Router::new()
         .fallback_service(ServeDir::new(config.serve_dir))
+        .route("/metrics", routing::get(api_metrics))
         .nest(
             "/api",
             Router::new().nest(
                 "/softnode",
-                Router::new().route("/sync", routing::get(api_softnode)),
+                Router::new()
+                    .route("/sync", routing::get(api_softnode))
+                    .route("/history", routing::get(api_history)),
             ),
         )
         .with_state(state)
         .layer(cors)
+        .layer(CompressionLayer::new())
         .layer(TraceLayer::new_for_http());
 
     if let Some(mut acme_state) = acme {
@@ -97,8 +239,8 @@ pub(crate) async fn start(config: WebConfig, sqlite: SQLite) -> Result<(), std::
         tokio::spawn(async move {
             loop {
                 match acme_state.next().await.unwrap() {
-                    Ok(ok) => println!("tlsacme event: {:?}", ok),
-                    Err(err) => println!("tlsacme error: {:?}", err),
+                    Ok(ok) => log::info!("tlsacme event: {:?}", ok),
+                    Err(err) => log::error!("tlsacme error: {:?}", err),
                 }
             }
         });
@@ -113,3 +255,32 @@ pub(crate) async fn start(config: WebConfig, sqlite: SQLite) -> Result<(), std::
             .await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use meshtastic_connect::meshtastic;
+
+    async fn body_of(packets: Vec<StoredMeshPacket>) -> serde_json::Value {
+        let packets = stream::iter(packets.into_iter().map(Ok)).boxed();
+        let body = axum::body::to_bytes(json_response(packets).into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn json_array_is_streamed() {
+        assert_eq!(body_of(vec![]).await, serde_json::json!([]));
+
+        let packet = |id| {
+            let mesh_packet = meshtastic::MeshPacket {
+                id,
+                ..Default::default()
+            };
+            StoredMeshPacket::from_mesh_packet(id as u64, None, String::new(), None, &mesh_packet)
+        };
+        let body = body_of(vec![packet(1), packet(2)]).await;
+        assert_eq!(body.as_array().map(Vec::len), Some(2));
+    }
+}
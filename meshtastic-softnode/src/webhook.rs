@@ -0,0 +1,329 @@
+use meshtastic_connect::{
+    keyring::{Keyring, node_id::NodeId},
+    meshtastic,
+    transport::mqtt::ConnectionHint,
+};
+use serde::Serialize;
+use softnode_client::app::data::{DataVariant, DecryptTarget, StoredMeshPacket};
+use std::{sync::Arc, time::Duration};
+use tokio::{sync::mpsc, time::sleep};
+
+use crate::{
+    config::WebhookConfig,
+    connection,
+    metrics::{self, Metrics},
+    observer::PacketObserver,
+    router::ConnectionName,
+    signature::signature,
+};
+
+const SIGNATURE_HEADER: &str = "X-Softnode-Signature";
+// Backoff is doubled on every retry up to this limit
+const MAX_RETRY_INTERVAL: Duration = Duration::from_secs(300);
+
+// Same shape as softnode's journal, plus fields resolved by webhook's filter.
+// Event is not journal's record: its `sequence_number` is 0
+#[derive(Serialize)]
+struct WebhookEvent {
+    #[serde(flatten)]
+    packet: StoredMeshPacket,
+    // Name of channel, which key is matched
+    channel_name: Option<String>,
+    portnum: Option<String>,
+}
+
+struct Record {
+    gateway: Option<NodeId>,
+    connection_name: ConnectionName,
+    connection_hint: Option<ConnectionHint>,
+    channel_name: Option<String>,
+    mesh_packet: meshtastic::MeshPacket,
+}
+
+// POSTs packets to webhook as JSON.
+// Queue is bounded: packets are dropped while endpoint is slow or unreachable,
+// so router's loop is never blocked
+pub struct WebhookObserver {
+    node_id: NodeId,
+    records: mpsc::Sender<Record>,
+    metrics: Arc<Metrics>,
+}
+
+impl WebhookObserver {
+    pub fn new(
+        webhook: WebhookConfig,
+        keyring: Keyring,
+        node_id: NodeId,
+        metrics: Arc<Metrics>,
+    ) -> Result<Self, String> {
+        if webhook.url.is_empty() {
+            return Err("URL is not set".into());
+        }
+        let client = reqwest::Client::builder()
+            .timeout(webhook.timeout.into())
+            .build()
+            .map_err(|e| e.to_string())?;
+        let (records, records_rx) = mpsc::channel::<Record>(webhook.queue_size.max(1));
+        tokio::spawn(deliver(
+            client,
+            webhook,
+            keyring,
+            metrics.clone(),
+            records_rx,
+        ));
+
+        Ok(Self {
+            node_id,
+            records,
+            metrics,
+        })
+    }
+
+    fn push(&self, record: Record) {
+        match self.records.try_send(record) {
+            Ok(()) => self.metrics.add(metrics::WEBHOOK_QUEUE_DEPTH, &[], 1),
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                log::debug!("Webhook's queue is full, packet is dropped");
+                self.metrics
+                    .inc(metrics::WEBHOOK_DROPS, &[("reason", "queue_full")]);
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => {
+                log::error!("Webhook's sender is stopped, packet is dropped");
+            }
+        }
+    }
+}
+
+impl PacketObserver for WebhookObserver {
+    fn on_received(&mut self, connection_name: &ConnectionName, incoming: &connection::Incoming) {
+        if let connection::DataVariant::MeshPacket(mesh_packet) = &incoming.data {
+            self.push(Record {
+                gateway: incoming.gateway_id,
+                connection_name: connection_name.clone(),
+                connection_hint: incoming.connection_hint.clone(),
+                channel_name: None,
+                mesh_packet: mesh_packet.clone(),
+            });
+        }
+    }
+
+    fn on_sent(&mut self, channel: Option<&ConnectionHint>, mesh_packet: &meshtastic::MeshPacket) {
+        self.push(Record {
+            gateway: Some(self.node_id),
+            connection_name: self.node_id.into(),
+            connection_hint: Some("self-generated".to_string()),
+            channel_name: channel.cloned(),
+            mesh_packet: mesh_packet.clone(),
+        });
+    }
+}
+
+// Decrypts packet and applies webhook's filter
+fn webhook_event(
+    keyring: &Keyring,
+    webhook: &WebhookConfig,
+    record: Record,
+) -> Option<WebhookEvent> {
+    let packet = StoredMeshPacket::from_mesh_packet(
+        0,
+        record.gateway,
+        record.connection_name,
+        record.connection_hint,
+        &record.mesh_packet,
+    )
+    .decrypt(keyring, false);
+
+    let (channel_name, port_num) = match &packet.data {
+        Some(DataVariant::Decrypted(target, data)) => {
+            let channel_name = match target {
+                DecryptTarget::Channel(name) | DecryptTarget::Guessed(name) => Some(name.clone()),
+                DecryptTarget::Direct(_) | DecryptTarget::PKI => None,
+            };
+            (record.channel_name.or(channel_name), Some(data.portnum()))
+        }
+        _ => (record.channel_name, None),
+    };
+
+    if !webhook.filter.allows(port_num, channel_name.as_deref()) {
+        return None;
+    }
+
+    Some(WebhookEvent {
+        packet,
+        channel_name,
+        portnum: port_num.map(|port_num| port_num.as_str_name().to_string()),
+    })
+}
+
+// Client's errors mean the endpoint rejects the event:
+// sending it again gives the same answer
+fn is_retryable(status: reqwest::StatusCode) -> bool {
+    !status.is_client_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+}
+
+// Error is true if the request is worth retrying
+async fn post(client: &reqwest::Client, webhook: &WebhookConfig, body: &[u8]) -> Result<(), bool> {
+    let mut request = client
+        .post(&webhook.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(body.to_vec());
+    if let Some(secret) = &webhook.secret {
+        request = request.header(
+            SIGNATURE_HEADER,
+            format!("sha256={}", signature(secret, body)),
+        );
+    }
+
+    match request.send().await {
+        Ok(response) if response.status().is_success() => Ok(()),
+        Ok(response) => {
+            let status = response.status();
+            log::warn!("Webhook {} responded with {}", webhook.url, status);
+            Err(is_retryable(status))
+        }
+        Err(e) => {
+            log::warn!("Webhook {} is not reached: {}", webhook.url, e);
+            Err(true)
+        }
+    }
+}
+
+// Events are sent one by one, in order of receiving.
+// Finished when observer is dropped
+async fn deliver(
+    client: reqwest::Client,
+    webhook: WebhookConfig,
+    keyring: Keyring,
+    metrics: Arc<Metrics>,
+    mut records_rx: mpsc::Receiver<Record>,
+) {
+    let retry_interval: Duration = webhook.retry_interval.into();
+
+    while let Some(record) = records_rx.recv().await {
+        metrics.add(metrics::WEBHOOK_QUEUE_DEPTH, &[], -1);
+        let Some(event) = webhook_event(&keyring, &webhook, record) else {
+            continue;
+        };
+        let body = match serde_json::to_vec(&event) {
+            Ok(body) => body,
+            Err(e) => {
+                log::error!("Webhook's event is not serialized: {}", e);
+                continue;
+            }
+        };
+
+        let mut backoff = retry_interval;
+        let mut attempt = 0;
+        loop {
+            match post(&client, &webhook, &body).await {
+                Ok(()) => {
+                    metrics.inc(metrics::WEBHOOK_SENT, &[]);
+                    break;
+                }
+                Err(retry) if retry && attempt < webhook.retries => {
+                    attempt += 1;
+                    sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_RETRY_INTERVAL);
+                }
+                Err(_) => {
+                    log::error!(
+                        "Webhook's event for packet {:#x} is dropped after {} attempts",
+                        event.packet.header.id,
+                        attempt + 1
+                    );
+                    metrics.inc(metrics::WEBHOOK_DROPS, &[("reason", "failed")]);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PacketFilter;
+    use prost::Message;
+
+    fn text_record(channel_name: Option<&str>) -> Record {
+        let data = meshtastic::Data {
+            portnum: meshtastic::PortNum::TextMessageApp.into(),
+            payload: b"hello".to_vec(),
+            ..Default::default()
+        };
+        Record {
+            gateway: None,
+            connection_name: "test".into(),
+            connection_hint: None,
+            channel_name: channel_name.map(String::from),
+            mesh_packet: meshtastic::MeshPacket {
+                payload_variant: Some(meshtastic::mesh_packet::PayloadVariant::Decoded(data)),
+                ..Default::default()
+            },
+        }
+    }
+
+    fn encrypted_record() -> Record {
+        Record {
+            gateway: None,
+            connection_name: "test".into(),
+            connection_hint: None,
+            channel_name: None,
+            mesh_packet: meshtastic::MeshPacket {
+                payload_variant: Some(meshtastic::mesh_packet::PayloadVariant::Encrypted(
+                    meshtastic::Data::default().encode_to_vec(),
+                )),
+                ..Default::default()
+            },
+        }
+    }
+
+    fn config(portnums: &[&str], channels: &[&str]) -> WebhookConfig {
+        WebhookConfig {
+            filter: PacketFilter {
+                portnums: portnums.iter().map(|v| v.to_string()).collect(),
+                channels: channels.iter().map(|v| v.to_string()).collect(),
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn event_is_filtered() {
+        let keyring = Keyring::default();
+
+        let event = webhook_event(&keyring, &config(&[], &[]), encrypted_record()).unwrap();
+        assert_eq!(event.portnum, None);
+        assert!(
+            webhook_event(
+                &keyring,
+                &config(&["TEXT_MESSAGE_APP"], &[]),
+                encrypted_record()
+            )
+            .is_none()
+        );
+
+        let webhook = config(&["TEXT_MESSAGE_APP"], &["LongFast"]);
+        let event = webhook_event(&keyring, &webhook, text_record(Some("LongFast"))).unwrap();
+        assert_eq!(event.portnum.as_deref(), Some("TEXT_MESSAGE_APP"));
+        assert_eq!(event.channel_name.as_deref(), Some("LongFast"));
+        assert!(webhook_event(&keyring, &webhook, text_record(Some("Other"))).is_none());
+        assert!(webhook_event(&keyring, &webhook, text_record(None)).is_none());
+
+        let webhook = config(&["POSITION_APP"], &[]);
+        assert!(webhook_event(&keyring, &webhook, text_record(Some("LongFast"))).is_none());
+    }
+
+    #[test]
+    fn retry_decision() {
+        use reqwest::StatusCode;
+
+        assert!(!is_retryable(StatusCode::BAD_REQUEST));
+        assert!(!is_retryable(StatusCode::UNAUTHORIZED));
+        assert!(!is_retryable(StatusCode::NOT_FOUND));
+        assert!(is_retryable(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_retryable(StatusCode::INTERNAL_SERVER_ERROR));
+        assert!(is_retryable(StatusCode::BAD_GATEWAY));
+        assert!(is_retryable(StatusCode::SERVICE_UNAVAILABLE));
+    }
+}
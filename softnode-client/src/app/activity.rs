@@ -0,0 +1,133 @@
+use std::collections::BTreeMap;
+
+use chrono::DateTime;
+use egui::Color32;
+use egui_plot::{Bar, BarChart};
+
+use crate::app::data::{NodeInfo, NodePacketType};
+
+#[derive(Default, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum BucketSize {
+    #[default]
+    Hour,
+    SixHours,
+    Day,
+}
+
+impl BucketSize {
+    fn seconds(&self) -> i64 {
+        match self {
+            BucketSize::Hour => 60 * 60,
+            BucketSize::SixHours => 6 * 60 * 60,
+            BucketSize::Day => 24 * 60 * 60,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            BucketSize::Hour => "1h",
+            BucketSize::SixHours => "6h",
+            BucketSize::Day => "24h",
+        }
+    }
+}
+
+// Packets' counts per `NodePacketType`
+#[derive(Default)]
+struct BucketCounters {
+    normal: usize,
+    cannot_decrypt: usize,
+    error: usize,
+    empty: usize,
+}
+
+// Histogram of node's packets per time bucket
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+pub struct Activity {
+    bucket_size: BucketSize,
+}
+
+impl Activity {
+    pub fn ui(&mut self, ui: &mut egui::Ui, node_info: &NodeInfo) {
+        let bucket_seconds = self.bucket_size.seconds();
+        // Only non-empty buckets are stored, so gaps in data cost nothing
+        let mut buckets: BTreeMap<i64, BucketCounters> = BTreeMap::new();
+        for packet in &node_info.packet_statistics {
+            let counters = buckets
+                .entry(packet.timestamp.timestamp().div_euclid(bucket_seconds))
+                .or_default();
+            match packet.packet_type {
+                NodePacketType::Normal(_) => counters.normal += 1,
+                NodePacketType::CannotDecrypt => counters.cannot_decrypt += 1,
+                NodePacketType::Error => counters.error += 1,
+                NodePacketType::Empty => counters.empty += 1,
+            }
+        }
+
+        ui.horizontal(|ui| {
+            let title = if let Some(extended_info) = node_info.extended_info_history.last() {
+                format!("{} {}", node_info.node_id, extended_info.short_name)
+            } else {
+                node_info.node_id.to_string()
+            };
+            ui.heading(title);
+            ui.separator();
+            ui.label("Bucket:");
+            for bucket_size in [BucketSize::Hour, BucketSize::SixHours, BucketSize::Day] {
+                ui.selectable_value(&mut self.bucket_size, bucket_size, bucket_size.label());
+            }
+        });
+
+        if buckets.is_empty() {
+            ui.label("No data");
+            return;
+        }
+
+        // X axis is in hours since epoch
+        let bucket_hours = bucket_seconds as f64 / 3600.0;
+        let bars = |value: fn(&BucketCounters) -> usize| {
+            buckets
+                .iter()
+                .map(|(bucket, counters)| {
+                    Bar::new(
+                        (*bucket as f64 + 0.5) * bucket_hours,
+                        value(counters) as f64,
+                    )
+                    .width(bucket_hours * 0.9)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let normal = BarChart::new("Normal", bars(|v| v.normal)).color(Color32::LIGHT_GREEN);
+        let cannot_decrypt = BarChart::new("Cannot decrypt", bars(|v| v.cannot_decrypt))
+            .color(Color32::YELLOW)
+            .stack_on(&[&normal]);
+        let error = BarChart::new("Error", bars(|v| v.error))
+            .color(Color32::LIGHT_RED)
+            .stack_on(&[&normal, &cannot_decrypt]);
+        let empty = BarChart::new("Empty", bars(|v| v.empty))
+            .color(Color32::GRAY)
+            .stack_on(&[&normal, &cannot_decrypt, &error]);
+
+        let x_axis = egui_plot::AxisHints::new_x().formatter(|mark, _range| {
+            DateTime::from_timestamp((mark.value * 3600.0) as i64, 0)
+                .map(|v| v.format("%d/%m/%Y %H:%M").to_string())
+                .unwrap_or_default()
+        });
+        let legend = egui_plot::Legend::default()
+            .position(egui_plot::Corner::LeftTop)
+            .follow_insertion_order(true);
+
+        egui_plot::Plot::new("activity_plot")
+            .legend(legend)
+            .custom_x_axes(vec![x_axis])
+            .y_axis_label("Packets")
+            .allow_scroll(false)
+            .show(ui, |plot_ui| {
+                plot_ui.bar_chart(normal);
+                plot_ui.bar_chart(cannot_decrypt);
+                plot_ui.bar_chart(error);
+                plot_ui.bar_chart(empty);
+            });
+    }
+}
@@ -0,0 +1,518 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Duration, Utc};
+use egui::Color32;
+use meshtastic_connect::keyring::node_id::NodeId;
+
+use crate::app::{
+    alerts::{AlertKind, Alerts},
+    data::{NodeInfo, TelemetryVariant},
+};
+
+// Telemetry offered by the rules' editor
+const RULE_TELEMETRY: [TelemetryVariant; 9] = [
+    TelemetryVariant::BatteryLevel,
+    TelemetryVariant::Voltage,
+    TelemetryVariant::ChannelUtilization,
+    TelemetryVariant::AirUtilTx,
+    TelemetryVariant::EnvironmentTemperature,
+    TelemetryVariant::Humidity,
+    TelemetryVariant::BarometricPressure,
+    TelemetryVariant::Radiation,
+    TelemetryVariant::AirCo2,
+];
+// Rules are checked not more often, also to notice silence of nodes
+const EVALUATE_INTERVAL_SECS: i64 = 10;
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct BoundingBox {
+    pub min_latitude: f64,
+    pub max_latitude: f64,
+    pub min_longitude: f64,
+    pub max_longitude: f64,
+}
+
+impl BoundingBox {
+    fn contains(&self, latitude: f64, longitude: f64) -> bool {
+        (self.min_latitude..=self.max_latitude).contains(&latitude)
+            && (self.min_longitude..=self.max_longitude).contains(&longitude)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum RuleCondition {
+    // Newest value of the telemetry
+    TelemetryBelow(TelemetryVariant, f64),
+    TelemetryAbove(TelemetryVariant, f64),
+    // Node is not heard longer than the minutes
+    NotHeard(u32),
+    // Node is unknown when the rules are armed, within the box if it's set
+    NewNode(Option<BoundingBox>),
+}
+
+impl RuleCondition {
+    // Details of the match, None if the condition is false
+    fn matches(
+        &self,
+        node_info: &NodeInfo,
+        known_nodes: &HashSet<NodeId>,
+        now: DateTime<Utc>,
+    ) -> Option<String> {
+        match self {
+            RuleCondition::TelemetryBelow(variant, threshold)
+            | RuleCondition::TelemetryAbove(variant, threshold) => {
+                let value = node_info.telemetry.get(variant)?.values.last()?.value;
+                let below = matches!(self, RuleCondition::TelemetryBelow(_, _));
+                ((below && value < *threshold) || (!below && value > *threshold))
+                    .then(|| format!("{}: {}", variant, value))
+            }
+            RuleCondition::NotHeard(minutes) => {
+                let last_heard = node_info.packet_statistics.last()?.timestamp;
+                (now - last_heard > Duration::minutes(*minutes as i64))
+                    .then(|| format!("Last heard at {}", last_heard.format("%Y-%m-%d %H:%M")))
+            }
+            RuleCondition::NewNode(bounding_box) => {
+                if known_nodes.contains(&node_info.node_id) {
+                    return None;
+                }
+                match bounding_box {
+                    Some(bounding_box) => {
+                        let position = node_info.position.last()?;
+                        bounding_box
+                            .contains(position.latitude, position.longitude)
+                            .then(|| {
+                                format!(
+                                    "New node at {:.5}, {:.5}",
+                                    position.latitude, position.longitude
+                                )
+                            })
+                    }
+                    None => Some("New node".into()),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct AlertRule {
+    pub name: String,
+    pub enabled: bool,
+    // Node id the rule is limited to, all nodes if empty
+    pub node: String,
+    pub condition: RuleCondition,
+}
+
+impl AlertRule {
+    fn applies_to(&self, node_id: NodeId) -> bool {
+        self.node.trim().is_empty()
+            || NodeId::try_from(self.node.trim()).is_ok_and(|rule_node_id| rule_node_id == node_id)
+    }
+}
+
+// Observer's alert rules, persistent
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct AlertRules {
+    pub rules: Vec<AlertRule>,
+    // Rule is raised again for the node not earlier
+    pub debounce_minutes: u32,
+    // Native only
+    pub desktop_notifications: bool,
+}
+
+impl Default for AlertRules {
+    fn default() -> Self {
+        Self {
+            rules: vec![AlertRule {
+                name: "Battery low".into(),
+                enabled: false,
+                node: String::new(),
+                condition: RuleCondition::TelemetryBelow(TelemetryVariant::BatteryLevel, 10.0),
+            }],
+            debounce_minutes: 30,
+            desktop_notifications: false,
+        }
+    }
+}
+
+impl AlertRules {
+    // True if rules are changed
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let previous = self.clone();
+
+        ui.horizontal(|ui| {
+            ui.label("Repeat alert not earlier than");
+            ui.add(
+                egui::DragValue::new(&mut self.debounce_minutes)
+                    .range(1..=1440)
+                    .suffix(" min"),
+            );
+            #[cfg(not(target_arch = "wasm32"))]
+            ui.checkbox(&mut self.desktop_notifications, "Desktop notifications");
+        });
+
+        let mut remove = None;
+        egui::Grid::new("alert_rules").striped(true).show(ui, |ui| {
+            for (index, rule) in self.rules.iter_mut().enumerate() {
+                ui.checkbox(&mut rule.enabled, "");
+                ui.add(egui::TextEdit::singleline(&mut rule.name).desired_width(120.0));
+                let node_is_valid =
+                    rule.node.trim().is_empty() || NodeId::try_from(rule.node.trim()).is_ok();
+                let mut node = egui::TextEdit::singleline(&mut rule.node)
+                    .hint_text("All nodes")
+                    .desired_width(90.0);
+                if !node_is_valid {
+                    node = node.text_color(Color32::LIGHT_RED);
+                }
+                ui.add(node).on_hover_text("Node id, like !deadbeef");
+                Self::condition_ui(ui, index, &mut rule.condition);
+                if ui.small_button("🗑").clicked() {
+                    remove = Some(index);
+                }
+                ui.end_row();
+            }
+        });
+        if let Some(index) = remove {
+            self.rules.remove(index);
+        }
+        if ui.button("➕ Add rule").clicked() {
+            self.rules.push(AlertRule {
+                name: "Node offline".into(),
+                enabled: true,
+                node: String::new(),
+                condition: RuleCondition::NotHeard(60),
+            });
+        }
+
+        *self != previous
+    }
+
+    fn condition_ui(ui: &mut egui::Ui, index: usize, condition: &mut RuleCondition) {
+        ui.horizontal(|ui| {
+            let kinds = [
+                (
+                    "Telemetry below",
+                    matches!(condition, RuleCondition::TelemetryBelow(_, _)),
+                ),
+                (
+                    "Telemetry above",
+                    matches!(condition, RuleCondition::TelemetryAbove(_, _)),
+                ),
+                ("Not heard", matches!(condition, RuleCondition::NotHeard(_))),
+                ("New node", matches!(condition, RuleCondition::NewNode(_))),
+            ];
+            let selected = kinds
+                .iter()
+                .find(|(_, selected)| *selected)
+                .map(|(title, _)| *title)
+                .unwrap_or_default();
+            egui::ComboBox::from_id_salt(("alert_condition", index))
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    for (title, selected) in kinds {
+                        if ui.selectable_label(selected, title).clicked() && !selected {
+                            *condition = match title {
+                                "Telemetry below" => RuleCondition::TelemetryBelow(
+                                    TelemetryVariant::BatteryLevel,
+                                    10.0,
+                                ),
+                                "Telemetry above" => RuleCondition::TelemetryAbove(
+                                    TelemetryVariant::ChannelUtilization,
+                                    50.0,
+                                ),
+                                "Not heard" => RuleCondition::NotHeard(60),
+                                _ => RuleCondition::NewNode(None),
+                            };
+                        }
+                    }
+                });
+
+            match condition {
+                RuleCondition::TelemetryBelow(variant, threshold)
+                | RuleCondition::TelemetryAbove(variant, threshold) => {
+                    egui::ComboBox::from_id_salt(("alert_telemetry", index))
+                        .selected_text(variant.to_string())
+                        .show_ui(ui, |ui| {
+                            for telemetry in RULE_TELEMETRY {
+                                ui.selectable_value(variant, telemetry, telemetry.to_string());
+                            }
+                        });
+                    ui.add(egui::DragValue::new(threshold).speed(0.1));
+                }
+                RuleCondition::NotHeard(minutes) => {
+                    ui.add(
+                        egui::DragValue::new(minutes)
+                            .range(1..=10080)
+                            .suffix(" min"),
+                    );
+                }
+                RuleCondition::NewNode(bounding_box) => {
+                    let mut within = bounding_box.is_some();
+                    if ui.checkbox(&mut within, "Within box").changed() {
+                        *bounding_box = within.then(|| BoundingBox {
+                            min_latitude: -90.0,
+                            max_latitude: 90.0,
+                            min_longitude: -180.0,
+                            max_longitude: 180.0,
+                        });
+                    }
+                    if let Some(bounding_box) = bounding_box {
+                        ui.label("lat");
+                        ui.add(egui::DragValue::new(&mut bounding_box.min_latitude).speed(0.01));
+                        ui.add(egui::DragValue::new(&mut bounding_box.max_latitude).speed(0.01));
+                        ui.label("lon");
+                        ui.add(egui::DragValue::new(&mut bounding_box.min_longitude).speed(0.01));
+                        ui.add(egui::DragValue::new(&mut bounding_box.max_longitude).speed(0.01));
+                    }
+                }
+            }
+        });
+    }
+}
+
+// Raises alerts when rules' conditions become true. Conditions which are
+// already true when the rules are armed or edited, are not raised
+#[derive(Default)]
+pub struct RuleEngine {
+    // Nodes known when the rules are armed first, None until then
+    known_nodes: Option<HashSet<NodeId>>,
+    armed: bool,
+    // Condition state by rule's index and node
+    active: HashMap<(usize, NodeId), bool>,
+    raised: HashMap<(usize, NodeId), DateTime<Utc>>,
+    last_evaluated: Option<DateTime<Utc>>,
+}
+
+impl RuleEngine {
+    // Rules are changed: states are indexed by rule
+    pub fn reset_states(&mut self) {
+        self.armed = false;
+        self.active.clear();
+        self.raised.clear();
+        self.last_evaluated = None;
+    }
+
+    // Data is downloaded again: everything is new, the rules are armed again
+    pub fn clear(&mut self) {
+        *self = Default::default();
+    }
+
+    // Called when the received backlog is processed, so history raises nothing
+    pub fn evaluate(
+        &mut self,
+        rules: &AlertRules,
+        nodes: &HashMap<NodeId, NodeInfo>,
+        alerts: &mut Alerts,
+    ) {
+        self.evaluate_at(Utc::now(), rules, nodes, alerts);
+    }
+
+    fn evaluate_at(
+        &mut self,
+        now: DateTime<Utc>,
+        rules: &AlertRules,
+        nodes: &HashMap<NodeId, NodeInfo>,
+        alerts: &mut Alerts,
+    ) {
+        if self
+            .last_evaluated
+            .is_some_and(|last| now - last < Duration::seconds(EVALUATE_INTERVAL_SECS))
+        {
+            return;
+        }
+        self.last_evaluated = Some(now);
+
+        // Arming evaluation only remembers conditions' states
+        let arming = !std::mem::replace(&mut self.armed, true);
+        let known_nodes = self
+            .known_nodes
+            .get_or_insert_with(|| nodes.keys().copied().collect());
+        let debounce = Duration::minutes(rules.debounce_minutes as i64);
+
+        for (index, rule) in rules.rules.iter().enumerate() {
+            if !rule.enabled {
+                continue;
+            }
+            for node_info in nodes.values() {
+                if !rule.applies_to(node_info.node_id) {
+                    continue;
+                }
+                let key = (index, node_info.node_id);
+                let details = rule.condition.matches(node_info, known_nodes, now);
+                let was_active = self.active.insert(key, details.is_some()).unwrap_or(false);
+                let Some(details) = details else {
+                    continue;
+                };
+                // Flapping condition is raised once per debounce period
+                if arming
+                    || was_active
+                    || self
+                        .raised
+                        .get(&key)
+                        .is_some_and(|raised| now - *raised < debounce)
+                {
+                    continue;
+                }
+                self.raised.insert(key, now);
+                #[cfg(not(target_arch = "wasm32"))]
+                if rules.desktop_notifications {
+                    notify_desktop(
+                        format!("{}: {}", rule.name, node_info.node_id),
+                        details.clone(),
+                    );
+                }
+                alerts.push(
+                    node_info.node_id,
+                    AlertKind::Rule {
+                        name: rule.name.clone(),
+                        details,
+                    },
+                );
+            }
+        }
+    }
+}
+
+// Shown by the system in background thread, failures are only logged
+#[cfg(not(target_arch = "wasm32"))]
+fn notify_desktop(title: String, body: String) {
+    std::thread::spawn(move || {
+        if let Err(error) = notify_rust::Notification::new()
+            .summary(&title)
+            .body(&body)
+            .show()
+        {
+            log::error!("Desktop notification is not shown: {}", error);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::data::{NodeTelemetry, TelemetryValue};
+
+    fn rules(condition: RuleCondition) -> AlertRules {
+        AlertRules {
+            rules: vec![AlertRule {
+                name: "Test".into(),
+                enabled: true,
+                node: String::new(),
+                condition,
+            }],
+            debounce_minutes: 30,
+            desktop_notifications: false,
+        }
+    }
+
+    fn set_battery(nodes: &mut HashMap<NodeId, NodeInfo>, node_id: u32, value: f64) {
+        let node_info = nodes.entry(node_id.into()).or_insert_with(|| NodeInfo {
+            node_id: node_id.into(),
+            ..Default::default()
+        });
+        node_info.telemetry.insert(
+            TelemetryVariant::BatteryLevel,
+            NodeTelemetry {
+                values: vec![TelemetryValue {
+                    timestamp: Utc::now(),
+                    value,
+                }],
+                ..Default::default()
+            },
+        );
+    }
+
+    #[test]
+    fn arming_raises_nothing() {
+        let rules = rules(RuleCondition::TelemetryBelow(
+            TelemetryVariant::BatteryLevel,
+            10.0,
+        ));
+        let mut nodes = HashMap::new();
+        set_battery(&mut nodes, 0x1, 5.0);
+        let mut engine = RuleEngine::default();
+        let mut alerts = Alerts::default();
+        let start = Utc::now();
+
+        engine.evaluate_at(start, &rules, &nodes, &mut alerts);
+        // Still active since arming: not raised
+        engine.evaluate_at(start + Duration::minutes(1), &rules, &nodes, &mut alerts);
+        assert_eq!(alerts.unseen(), 0);
+
+        // Edited rules are armed again
+        set_battery(&mut nodes, 0x1, 50.0);
+        engine.reset_states();
+        engine.evaluate_at(start + Duration::minutes(2), &rules, &nodes, &mut alerts);
+        set_battery(&mut nodes, 0x1, 5.0);
+        engine.evaluate_at(start + Duration::minutes(3), &rules, &nodes, &mut alerts);
+        assert_eq!(alerts.unseen(), 1);
+    }
+
+    #[test]
+    fn flapping_is_debounced() {
+        let rules = rules(RuleCondition::TelemetryBelow(
+            TelemetryVariant::BatteryLevel,
+            10.0,
+        ));
+        let mut nodes = HashMap::new();
+        set_battery(&mut nodes, 0x1, 50.0);
+        let mut engine = RuleEngine::default();
+        let mut alerts = Alerts::default();
+        let start = Utc::now();
+        let mut evaluate_with = |battery: f64, minutes: i64, alerts: &mut Alerts| {
+            set_battery(&mut nodes, 0x1, battery);
+            engine.evaluate_at(start + Duration::minutes(minutes), &rules, &nodes, alerts);
+        };
+
+        evaluate_with(50.0, 0, &mut alerts);
+        evaluate_with(5.0, 1, &mut alerts);
+        assert_eq!(alerts.unseen(), 1);
+        evaluate_with(50.0, 2, &mut alerts);
+        evaluate_with(5.0, 3, &mut alerts);
+        assert_eq!(alerts.unseen(), 1);
+        evaluate_with(50.0, 40, &mut alerts);
+        evaluate_with(5.0, 41, &mut alerts);
+        assert_eq!(alerts.unseen(), 2);
+    }
+
+    #[test]
+    fn evaluation_is_throttled() {
+        let rules = rules(RuleCondition::TelemetryBelow(
+            TelemetryVariant::BatteryLevel,
+            10.0,
+        ));
+        let mut nodes = HashMap::new();
+        set_battery(&mut nodes, 0x1, 50.0);
+        let mut engine = RuleEngine::default();
+        let mut alerts = Alerts::default();
+        let start = Utc::now();
+
+        engine.evaluate_at(start, &rules, &nodes, &mut alerts);
+        set_battery(&mut nodes, 0x1, 5.0);
+        engine.evaluate_at(start + Duration::seconds(1), &rules, &nodes, &mut alerts);
+        assert_eq!(alerts.unseen(), 0);
+        engine.evaluate_at(
+            start + Duration::seconds(EVALUATE_INTERVAL_SECS),
+            &rules,
+            &nodes,
+            &mut alerts,
+        );
+        assert_eq!(alerts.unseen(), 1);
+    }
+
+    #[test]
+    fn new_node_after_arming() {
+        let rules = rules(RuleCondition::NewNode(None));
+        let mut nodes = HashMap::new();
+        set_battery(&mut nodes, 0x1, 50.0);
+        let mut engine = RuleEngine::default();
+        let mut alerts = Alerts::default();
+        let start = Utc::now();
+
+        engine.evaluate_at(start, &rules, &nodes, &mut alerts);
+        set_battery(&mut nodes, 0x2, 50.0);
+        engine.evaluate_at(start + Duration::minutes(1), &rules, &nodes, &mut alerts);
+        engine.evaluate_at(start + Duration::minutes(2), &rules, &nodes, &mut alerts);
+        assert_eq!(alerts.unseen(), 1);
+    }
+}
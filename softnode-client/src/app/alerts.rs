@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+use egui::{Color32, RichText};
+use meshtastic_connect::keyring::{key::Key, node_id::NodeId};
+
+use crate::app::{data::NodeInfo, time_format::format_timediff};
+
+// Seconds to show a raised alert over the panels
+const TOAST_SECS: i64 = 8;
+const TOASTS_LIMIT: usize = 3;
+
+#[derive(Clone, PartialEq)]
+pub enum AlertKind {
+    // Node announced another public key than before: device reset or MITM.
+    // `verified` if the node has a verified key, and it is not the new one
+    KeyChanged {
+        previous: Key,
+        current: Key,
+        verified: bool,
+    },
+    // Condition of observer's alert rule became true
+    Rule {
+        name: String,
+        details: String,
+    },
+}
+
+pub struct Alert {
+    pub timestamp: DateTime<Utc>,
+    // When the alert is raised, differs from `timestamp` for received backlog
+    pub raised: DateTime<Utc>,
+    pub node_id: NodeId,
+    pub kind: AlertKind,
+}
+
+impl Alert {
+    fn is_loud(&self) -> bool {
+        match self.kind {
+            AlertKind::KeyChanged { verified, .. } => verified,
+            AlertKind::Rule { .. } => true,
+        }
+    }
+
+    fn title(&self) -> String {
+        match &self.kind {
+            AlertKind::KeyChanged { verified, .. } if *verified => "Verified key changed".into(),
+            AlertKind::KeyChanged { .. } => "Key changed".into(),
+            AlertKind::Rule { name, .. } => name.clone(),
+        }
+    }
+
+    fn details(&self) -> String {
+        match &self.kind {
+            AlertKind::KeyChanged {
+                previous, current, ..
+            } => format!("{} ➡ {}", previous, current),
+            AlertKind::Rule { details, .. } => details.clone(),
+        }
+    }
+}
+
+// Events worth the observer's attention, newest last
+#[derive(Default)]
+pub struct Alerts {
+    alerts: Vec<Alert>,
+    // Alerts seen in the panel, the rest are counted as new
+    seen: usize,
+}
+
+impl Alerts {
+    // Checks the newest announced key against the previous one
+    pub fn check_key_change(&mut self, node_info: &NodeInfo, verified_key: Option<Key>) {
+        let Some((previous, current)) = node_info.key_change() else {
+            return;
+        };
+        let kind = AlertKind::KeyChanged {
+            previous,
+            current,
+            verified: verified_key.is_some_and(|verified_key| verified_key != current),
+        };
+        // Same announcement is received via several gateways
+        if self
+            .alerts
+            .iter()
+            .rev()
+            .find(|alert| alert.node_id == node_info.node_id)
+            .is_some_and(|alert| alert.kind == kind)
+        {
+            return;
+        }
+
+        log::warn!("Public key of {} is changed", node_info.node_id);
+        self.alerts.push(Alert {
+            timestamp: node_info
+                .extended_info_history
+                .last()
+                .map(|extended| extended.timestamp)
+                .unwrap_or_else(Utc::now),
+            raised: Utc::now(),
+            node_id: node_info.node_id,
+            kind,
+        });
+    }
+
+    pub fn push(&mut self, node_id: NodeId, kind: AlertKind) {
+        let alert = Alert {
+            timestamp: Utc::now(),
+            raised: Utc::now(),
+            node_id,
+            kind,
+        };
+        log::warn!(
+            "Alert for {}: {}, {}",
+            node_id,
+            alert.title(),
+            alert.details()
+        );
+        self.alerts.push(alert);
+    }
+
+    pub fn unseen(&self) -> usize {
+        self.alerts.len() - self.seen
+    }
+
+    pub fn clear(&mut self) {
+        self.alerts.clear();
+        self.seen = 0;
+    }
+
+    // Newest recently raised alerts over the panels, true if a toast is clicked
+    pub fn toasts_ui(&self, ctx: &egui::Context) -> bool {
+        let now = Utc::now();
+        let toasts = self
+            .alerts
+            .iter()
+            .rev()
+            .take_while(|alert| now - alert.raised < Duration::seconds(TOAST_SECS))
+            .take(TOASTS_LIMIT)
+            .collect::<Vec<_>>();
+        if toasts.is_empty() {
+            return false;
+        }
+        ctx.request_repaint_after(std::time::Duration::from_secs(1));
+
+        let mut clicked = false;
+        egui::Area::new(egui::Id::new("alert_toasts"))
+            .anchor(egui::Align2::RIGHT_BOTTOM, [-10.0, -10.0])
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                for alert in toasts {
+                    let response = egui::Frame::popup(ui.style())
+                        .show(ui, |ui| {
+                            let title = RichText::new(format!("🔔 {}", alert.title())).strong();
+                            ui.label(if alert.is_loud() {
+                                title.color(Color32::RED)
+                            } else {
+                                title.color(Color32::YELLOW)
+                            });
+                            ui.label(format!("{}: {}", alert.node_id, alert.details()));
+                        })
+                        .response
+                        .interact(egui::Sense::click());
+                    clicked |= response.on_hover_text("Show alerts").clicked();
+                }
+            });
+        clicked
+    }
+
+    // Returns node to show in the roster
+    pub fn ui(&mut self, ui: &mut egui::Ui, nodes: &HashMap<NodeId, NodeInfo>) -> Option<NodeId> {
+        self.seen = self.alerts.len();
+        if self.alerts.is_empty() {
+            ui.label("No alerts");
+            return None;
+        }
+
+        let mut show_node = None;
+        let current_datetime = Utc::now();
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("alerts").striped(true).show(ui, |ui| {
+                for alert in self.alerts.iter().rev() {
+                    let title = RichText::new(alert.title());
+                    ui.label(if alert.is_loud() {
+                        title.color(Color32::RED).strong()
+                    } else {
+                        title.color(Color32::YELLOW)
+                    });
+                    let name = nodes
+                        .get(&alert.node_id)
+                        .and_then(|node_info| node_info.extended_info_history.last())
+                        .map(|extended| format!("{} {}", alert.node_id, extended.short_name))
+                        .unwrap_or(alert.node_id.to_string());
+                    if ui
+                        .selectable_label(false, name)
+                        .on_hover_text("Show in roster")
+                        .clicked()
+                    {
+                        show_node = Some(alert.node_id);
+                    }
+                    ui.label(RichText::new(alert.details()).monospace().small());
+                    ui.label(
+                        format_timediff(alert.timestamp, current_datetime).unwrap_or_default(),
+                    )
+                    .on_hover_text(alert.timestamp.to_string());
+                    ui.end_row();
+                }
+            });
+        });
+        show_node
+    }
+}
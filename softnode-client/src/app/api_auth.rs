@@ -0,0 +1,27 @@
+use base64::{Engine, engine::general_purpose};
+
+// Credentials for softnode's API. Also used by softnode to check requests
+#[derive(Debug, Default, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum ApiAuth {
+    #[default]
+    None,
+    Bearer(String),
+    Basic {
+        username: String,
+        password: String,
+    },
+}
+
+impl ApiAuth {
+    // Value of the `Authorization` header
+    pub fn header(&self) -> Option<String> {
+        match self {
+            ApiAuth::None => None,
+            ApiAuth::Bearer(token) => Some(format!("Bearer {}", token)),
+            ApiAuth::Basic { username, password } => Some(format!(
+                "Basic {}",
+                general_purpose::STANDARD.encode(format!("{}:{}", username, password))
+            )),
+        }
+    }
+}
@@ -1,6 +1,9 @@
 // stoled from https://github.com/emilk/egui_plot/blob/a5c5a623de5b30e6a84831f23e199bcd979e38f9/egui_plot/src/plot_ui.rs#L23
 
+use std::collections::HashMap;
+
 use egui::{Color32, epaint::Hsva};
+use meshtastic_connect::keyring::node_id::NodeId;
 
 #[derive(Default)]
 pub struct ColorGenerator {
@@ -16,3 +19,37 @@ impl ColorGenerator {
         Hsva::new(h, 0.85, 0.5, 1.0).into() // TODO(emilk): OkLab or some other perspective color space
     }
 }
+
+// Color derived from node's id: same for the node between frames and sessions
+pub fn node_color(node_id: NodeId) -> Color32 {
+    let hash = u32::from(node_id).wrapping_mul(0x9E37_79B1);
+    let h = hash as f32 / u32::MAX as f32;
+    Hsva::new(h, 0.85, 0.5, 1.0).into()
+}
+
+// Per-node colors, could be overridden by user
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+pub struct NodeColors {
+    overrides: HashMap<NodeId, Color32>,
+}
+
+impl NodeColors {
+    pub fn get(&self, node_id: NodeId) -> Color32 {
+        self.overrides
+            .get(&node_id)
+            .copied()
+            .unwrap_or_else(|| node_color(node_id))
+    }
+
+    pub fn is_overridden(&self, node_id: NodeId) -> bool {
+        self.overrides.contains_key(&node_id)
+    }
+
+    pub fn set(&mut self, node_id: NodeId, color: Color32) {
+        self.overrides.insert(node_id, color);
+    }
+
+    pub fn reset(&mut self, node_id: NodeId) {
+        self.overrides.remove(&node_id);
+    }
+}
@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+
+use egui::{Event, Key, KeyboardShortcut, Modifiers, RichText};
+use meshtastic_connect::keyring::node_id::NodeId;
+
+use crate::app::data::NodeInfo;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Command {
+    Journal,
+    Telemetry,
+    Map,
+    Topology,
+    Dashboard,
+    DecryptFailures,
+    Alerts,
+    Session,
+    Settings,
+    ToggleRoster,
+    FocusSearch,
+    GoToNode(NodeId),
+    Help,
+}
+
+pub const PALETTE_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::K);
+// Seconds to wait the second key after `g`
+const GO_TIMEOUT: f64 = 1.5;
+// Nodes shown in the palette by name
+const NODES_LIMIT: usize = 8;
+
+// Command, its keys and title
+const COMMANDS: [(Command, &str, &str); 12] = [
+    (Command::Journal, "g j", "Show journal"),
+    (Command::Telemetry, "g t", "Show telemetry"),
+    (Command::Map, "g m", "Show map"),
+    (Command::Topology, "g o", "Show topology"),
+    (Command::Dashboard, "g d", "Show dashboard"),
+    (Command::DecryptFailures, "g f", "Show decrypt failures"),
+    (Command::Alerts, "g a", "Show alerts"),
+    (Command::Session, "g e", "Show session"),
+    (Command::Settings, "g s", "Show settings"),
+    (Command::ToggleRoster, "r", "Toggle roster"),
+    (Command::FocusSearch, "/", "Search node"),
+    (Command::Help, "?", "Keyboard shortcuts"),
+];
+
+fn command_for_keys(keys: &str) -> Option<Command> {
+    COMMANDS
+        .iter()
+        .find(|(_, command_keys, _)| *command_keys == keys)
+        .map(|(command, _, _)| *command)
+}
+
+#[derive(Default)]
+pub struct CommandPalette {
+    show: bool,
+    query: String,
+    // Highlighted line of the palette, Enter runs it
+    selected: usize,
+    show_help: bool,
+    // Time when `g` is typed, the second key is awaited
+    go_pressed: Option<f64>,
+}
+
+impl CommandPalette {
+    pub fn toggle_help(&mut self) {
+        self.show_help = !self.show_help;
+    }
+
+    // Command chosen by shortcut or in the palette. Help is handled here
+    pub fn ui(
+        &mut self,
+        ctx: &egui::Context,
+        nodes: &HashMap<NodeId, NodeInfo>,
+    ) -> Option<Command> {
+        let mut command = self.shortcuts(ctx);
+        if self.show {
+            command = command.or_else(|| self.palette_ui(ctx, nodes));
+        }
+        if command == Some(Command::Help) {
+            self.toggle_help();
+            return None;
+        }
+        if self.show_help {
+            self.help_ui(ctx);
+        }
+        command
+    }
+
+    fn shortcuts(&mut self, ctx: &egui::Context) -> Option<Command> {
+        if ctx.input_mut(|input| input.consume_shortcut(&PALETTE_SHORTCUT)) {
+            self.show = !self.show;
+            self.query.clear();
+            self.selected = 0;
+            return None;
+        }
+        if self.show_help && ctx.input(|input| input.key_pressed(Key::Escape)) {
+            self.show_help = false;
+        }
+        // Typed text belongs to the focused text field
+        if self.show || ctx.wants_keyboard_input() {
+            self.go_pressed = None;
+            return None;
+        }
+
+        let (now, typed) = ctx.input(|input| {
+            let typed = input
+                .events
+                .iter()
+                .filter_map(|event| match event {
+                    Event::Text(text) => Some(text.clone()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            (input.time, typed)
+        });
+        for text in typed {
+            if let Some(go_pressed) = self.go_pressed.take()
+                && now - go_pressed < GO_TIMEOUT
+            {
+                if let Some(command) = command_for_keys(format!("g {}", text).as_str()) {
+                    return Some(command);
+                }
+                continue;
+            }
+            if text == "g" {
+                self.go_pressed = Some(now);
+            } else if let Some(command) = command_for_keys(text.as_str()) {
+                return Some(command);
+            }
+        }
+        None
+    }
+
+    fn palette_ui(
+        &mut self,
+        ctx: &egui::Context,
+        nodes: &HashMap<NodeId, NodeInfo>,
+    ) -> Option<Command> {
+        let mut chosen = None;
+        let query = self.query.trim().to_lowercase();
+
+        // Exact node id first, then nodes by name, then commands
+        let mut lines = Vec::new();
+        if let Ok(node_id) = NodeId::try_from(self.query.trim())
+            && nodes.contains_key(&node_id)
+        {
+            lines.push((Command::GoToNode(node_id), format!("Go to {}", node_id), ""));
+        }
+        if query.len() > 1 {
+            let mut by_name = nodes
+                .values()
+                .filter_map(|node_info| {
+                    let extended = node_info.extended_info_history.last()?;
+                    (extended.short_name.to_lowercase().contains(&query)
+                        || extended.long_name.to_lowercase().contains(&query))
+                    .then(|| {
+                        (
+                            Command::GoToNode(node_info.node_id),
+                            format!(
+                                "Go to {} {} ({})",
+                                node_info.node_id, extended.short_name, extended.long_name
+                            ),
+                            "",
+                        )
+                    })
+                })
+                .collect::<Vec<_>>();
+            by_name.sort_by(|a, b| a.1.cmp(&b.1));
+            lines.extend(by_name.into_iter().take(NODES_LIMIT));
+        }
+        lines.extend(
+            COMMANDS
+                .iter()
+                .filter(|(_, _, title)| title.to_lowercase().contains(&query))
+                .map(|(command, keys, title)| (*command, title.to_string(), *keys)),
+        );
+
+        let (step, enter) = ctx.input_mut(|input| {
+            let mut step = 0_i32;
+            if input.consume_key(Modifiers::NONE, Key::ArrowDown) {
+                step += 1;
+            }
+            if input.consume_key(Modifiers::NONE, Key::ArrowUp) {
+                step -= 1;
+            }
+            (step, input.consume_key(Modifiers::NONE, Key::Enter))
+        });
+        self.selected =
+            (self.selected as i32 + step).clamp(0, lines.len().saturating_sub(1) as i32) as usize;
+
+        let modal = egui::Modal::new(egui::Id::new("command_palette")).show(ctx, |ui| {
+            ui.set_width(400.0);
+            let edit = ui.add(
+                egui::TextEdit::singleline(&mut self.query)
+                    .desired_width(f32::INFINITY)
+                    .hint_text("Command, node id or name"),
+            );
+            if edit.changed() {
+                self.selected = 0;
+            }
+            edit.request_focus();
+
+            ui.separator();
+            if lines.is_empty() {
+                ui.label("Nothing found");
+            }
+            for (index, (command, title, keys)) in lines.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    if ui.selectable_label(index == self.selected, title).clicked() {
+                        chosen = Some(*command);
+                    }
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        ui.label(RichText::new(*keys).monospace().weak());
+                    });
+                });
+            }
+        });
+
+        if enter {
+            chosen = chosen.or_else(|| lines.get(self.selected).map(|(command, _, _)| *command));
+        }
+        if chosen.is_some() || modal.should_close() {
+            self.show = false;
+        }
+        chosen
+    }
+
+    fn help_ui(&mut self, ctx: &egui::Context) {
+        egui::Window::new("Keyboard shortcuts")
+            .open(&mut self.show_help)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label("Shortcuts are ignored while a text field is edited");
+                egui::Grid::new("shortcuts_help")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.label(RichText::new(ctx.format_shortcut(&PALETTE_SHORTCUT)).monospace());
+                        ui.label("Command palette");
+                        ui.end_row();
+                        for (_, keys, title) in COMMANDS {
+                            ui.label(RichText::new(keys).monospace());
+                            ui.label(title);
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+}
@@ -0,0 +1,141 @@
+use std::collections::{BTreeSet, HashMap};
+
+use egui::{Color32, RichText};
+use meshtastic_connect::keyring::node_id::NodeId;
+
+use crate::app::data::{GatewayInfo, NodeInfo, PublicKey};
+
+fn node_title(node_id: NodeId, nodes: &HashMap<NodeId, NodeInfo>) -> String {
+    if let Some(extended) = nodes
+        .get(&node_id)
+        .and_then(|v| v.extended_info_history.last())
+    {
+        format!("{} {}", node_id, extended.short_name)
+    } else {
+        node_id.to_string()
+    }
+}
+
+fn pkey_text(pkey: &PublicKey) -> RichText {
+    match pkey {
+        PublicKey::None => RichText::new("🔓 no public key").color(Color32::LIGHT_RED),
+        PublicKey::Key(key) => RichText::new(format!("🔒 {}", key)).color(Color32::LIGHT_GREEN),
+        PublicKey::Compromised(key) => {
+            RichText::new(format!("🔒 {} (compromised)", key)).color(Color32::YELLOW)
+        }
+    }
+}
+
+fn gateway_text(gateway_info: &GatewayInfo) -> String {
+    let rssi = gateway_info
+        .rx_info
+        .as_ref()
+        .map(|v| format!("{} dBm, {} SNR", v.rx_rssi, v.rx_snr))
+        .unwrap_or("no rx info".into());
+    let hops = gateway_info
+        .hop_distance
+        .map(|v| format!("{} hops", v))
+        .unwrap_or(format!("hop limit {}", gateway_info.hop_limit));
+    format!("{}, {}", rssi, hops)
+}
+
+fn identity_ui(ui: &mut egui::Ui, node_info: &NodeInfo, nodes: &HashMap<NodeId, NodeInfo>) {
+    ui.heading(node_title(node_info.node_id, nodes));
+
+    let Some(extended) = node_info.extended_info_history.last() else {
+        ui.label("No node info received");
+        return;
+    };
+
+    ui.label(pkey_text(&extended.pkey));
+    ui.label(format!("Licensed: {}", extended.is_licensed));
+    ui.label(format!(
+        "Unmessagable: {}",
+        extended
+            .is_unmessagable
+            .map(|v| v.to_string())
+            .unwrap_or("unknown".into())
+    ));
+    ui.label(format!("Announced as: {}", extended.announced_node_id));
+
+    ui.separator();
+    ui.label(format!(
+        "Names history ({} records)",
+        node_info.extended_info_history.len()
+    ));
+    egui::Grid::new(ui.id().with(("compare_history", node_info.node_id)))
+        .striped(true)
+        .show(ui, |ui| {
+            for extended in node_info.extended_info_history.iter().rev() {
+                ui.label(extended.timestamp.format("%d/%m/%Y %H:%M").to_string());
+                ui.label(&extended.short_name);
+                ui.label(&extended.long_name);
+                ui.label(pkey_text(&extended.pkey).small());
+                ui.end_row();
+            }
+        });
+}
+
+// Side-by-side comparison of two nodes' identities
+pub fn ui(
+    ui: &mut egui::Ui,
+    first: &NodeInfo,
+    second: &NodeInfo,
+    nodes: &HashMap<NodeId, NodeInfo>,
+) {
+    let first_pkey = first.extended_info_history.last().map(|v| &v.pkey);
+    let second_pkey = second.extended_info_history.last().map(|v| &v.pkey);
+    let same_key = match (first_pkey, second_pkey) {
+        (
+            Some(PublicKey::Key(a) | PublicKey::Compromised(a)),
+            Some(PublicKey::Key(b) | PublicKey::Compromised(b)),
+        ) => a == b,
+        _ => false,
+    };
+    if same_key {
+        ui.label(RichText::new("⚠ Nodes share the same public key").color(Color32::YELLOW));
+    }
+
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        ui.columns(2, |columns| {
+            identity_ui(&mut columns[0], first, nodes);
+            identity_ui(&mut columns[1], second, nodes);
+        });
+
+        ui.separator();
+        let first_gateways = first.gatewayed_by.keys().collect::<BTreeSet<_>>();
+        let second_gateways = second.gatewayed_by.keys().collect::<BTreeSet<_>>();
+        let overlapping = first_gateways
+            .intersection(&second_gateways)
+            .collect::<Vec<_>>();
+
+        ui.label(format!(
+            "Gateways: {} / {}, overlapping: {}",
+            first_gateways.len(),
+            second_gateways.len(),
+            overlapping.len()
+        ));
+        egui::Grid::new("compare_gateways")
+            .striped(true)
+            .show(ui, |ui| {
+                for gateway_id in overlapping {
+                    ui.label(node_title(**gateway_id, nodes));
+                    ui.label(
+                        first
+                            .gatewayed_by
+                            .get(*gateway_id)
+                            .map(gateway_text)
+                            .unwrap_or_default(),
+                    );
+                    ui.label(
+                        second
+                            .gatewayed_by
+                            .get(*gateway_id)
+                            .map(gateway_text)
+                            .unwrap_or_default(),
+                    );
+                    ui.end_row();
+                }
+            });
+    });
+}
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use meshtastic_connect::keyring::node_id::NodeId;
+
+use crate::app::{
+    data::NodeInfo,
+    map::{fix_or_position, opaque_by_timedelta},
+    node_book::NodeBook,
+};
+
+// One heard node in the gateway's coverage
+struct CoverageRecord {
+    node_id: NodeId,
+    name: String,
+    // Fixed/received position or assumed one
+    position: Option<walkers::Position>,
+    assumed: bool,
+    timestamp: DateTime<Utc>,
+    rssi: Option<i32>,
+    snr: Option<f32>,
+    hop_distance: Option<u32>,
+    // 1.0 - just heard, 0.0 - heard a day or more ago
+    freshness: f32,
+}
+
+fn collect_records(
+    gateway_info: &NodeInfo,
+    nodes: &HashMap<NodeId, NodeInfo>,
+    nodebook: &NodeBook,
+) -> Vec<CoverageRecord> {
+    let current_datetime = Utc::now();
+    let mut records = Vec::new();
+
+    for (node_id, gateway_infos) in &gateway_info.gateway_for {
+        let Some(last) = gateway_infos.last() else {
+            continue;
+        };
+        let node_info = nodes.get(node_id);
+        let mesh_position = node_info
+            .and_then(|node_info| fix_or_position(nodebook, *node_id, &node_info.position));
+        let assumed_position = node_info
+            .and_then(|v| v.assumed_position)
+            .map(|v| v.position);
+        let name = node_info
+            .and_then(|v| v.extended_info_history.last())
+            .map(|v| v.short_name.clone())
+            .unwrap_or_default();
+
+        records.push(CoverageRecord {
+            node_id: *node_id,
+            name,
+            position: mesh_position.or(assumed_position),
+            assumed: mesh_position.is_none() && assumed_position.is_some(),
+            timestamp: last.timestamp,
+            rssi: last.rx_info.as_ref().map(|v| v.rx_rssi),
+            snr: last.rx_info.as_ref().map(|v| v.rx_snr),
+            hop_distance: last.hop_distance,
+            freshness: opaque_by_timedelta(current_datetime, last.timestamp),
+        });
+    }
+
+    records.sort_by_key(|v| v.node_id);
+    records
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Export nodes heard by the gateway as KML placemarks
+pub fn export_kml(
+    gateway_info: &NodeInfo,
+    nodes: &HashMap<NodeId, NodeInfo>,
+    nodebook: &NodeBook,
+) -> String {
+    let records = collect_records(gateway_info, nodes, nodebook);
+    let mut kml = String::new();
+
+    kml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    kml.push_str("<kml xmlns=\"http://www.opengis.net/kml/2.2\">\n<Document>\n");
+    kml.push_str(&format!(
+        "<name>{} RSSI</name>\n",
+        xml_escape(&gateway_info.node_id.to_string())
+    ));
+
+    let write_placemark = |kml: &mut String, record: &CoverageRecord| {
+        kml.push_str("<Placemark>\n");
+        kml.push_str(&format!(
+            "<name>{}</name>\n",
+            xml_escape(format!("{} {}", record.node_id, record.name).trim_end())
+        ));
+        kml.push_str("<ExtendedData>\n");
+        for (name, value) in [
+            ("node_id", record.node_id.to_string()),
+            ("rssi", optional(record.rssi)),
+            ("snr", optional(record.snr)),
+            ("hop_distance", optional(record.hop_distance)),
+            ("timestamp", record.timestamp.to_rfc3339()),
+            ("freshness", format!("{:.2}", record.freshness)),
+            ("assumed", record.assumed.to_string()),
+        ] {
+            kml.push_str(&format!(
+                "<Data name=\"{}\"><value>{}</value></Data>\n",
+                name,
+                xml_escape(&value)
+            ));
+        }
+        kml.push_str("</ExtendedData>\n");
+        if let Some(position) = record.position {
+            kml.push_str(&format!(
+                "<Point><coordinates>{:.7},{:.7}</coordinates></Point>\n",
+                position.x(),
+                position.y()
+            ));
+        }
+        kml.push_str("</Placemark>\n");
+    };
+
+    kml.push_str("<Folder>\n<name>located</name>\n");
+    for record in records.iter().filter(|v| v.position.is_some()) {
+        write_placemark(&mut kml, record);
+    }
+    kml.push_str("</Folder>\n");
+
+    kml.push_str("<Folder>\n<name>no-location</name>\n");
+    for record in records.iter().filter(|v| v.position.is_none()) {
+        write_placemark(&mut kml, record);
+    }
+    kml.push_str("</Folder>\n");
+
+    kml.push_str("</Document>\n</kml>\n");
+    kml
+}
+
+// Export nodes heard by the gateway as CSV, suitable for heatmap tools
+pub fn export_csv(
+    gateway_info: &NodeInfo,
+    nodes: &HashMap<NodeId, NodeInfo>,
+    nodebook: &NodeBook,
+) -> String {
+    let records = collect_records(gateway_info, nodes, nodebook);
+    let header =
+        "node_id,name,latitude,longitude,assumed,rssi,snr,hop_distance,timestamp,freshness\n";
+    let mut csv = String::from(header);
+
+    let write_row = |csv: &mut String, record: &CoverageRecord| {
+        csv.push_str(&format!(
+            "{},\"{}\",{},{},{},{},{},{},{},{:.2}\n",
+            record.node_id,
+            record.name.replace('"', "\"\""),
+            optional(record.position.map(|v| format!("{:.7}", v.y()))),
+            optional(record.position.map(|v| format!("{:.7}", v.x()))),
+            record.assumed,
+            optional(record.rssi),
+            optional(record.snr),
+            optional(record.hop_distance),
+            record.timestamp.to_rfc3339(),
+            record.freshness,
+        ));
+    };
+
+    for record in records.iter().filter(|v| v.position.is_some()) {
+        write_row(&mut csv, record);
+    }
+
+    if records.iter().any(|v| v.position.is_none()) {
+        csv.push_str("# no-location\n");
+        csv.push_str(header);
+        for record in records.iter().filter(|v| v.position.is_none()) {
+            write_row(&mut csv, record);
+        }
+    }
+
+    csv
+}
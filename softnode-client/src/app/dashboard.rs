@@ -0,0 +1,186 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use egui::{Color32, RichText};
+use egui_plot::{Bar, BarChart};
+use meshtastic_connect::keyring::node_id::NodeId;
+
+use crate::app::data::{DataVariant, NodeInfo, PublicKey, StoredMeshPacket};
+
+#[derive(Default)]
+struct KeyCounters {
+    valid: usize,
+    compromised: usize,
+    none: usize,
+    // NodeInfo is not received
+    unknown: usize,
+}
+
+// Mesh-wide counters, updated on every received packet
+#[derive(Default)]
+pub struct Dashboard {
+    last_seen: HashMap<NodeId, DateTime<Utc>>,
+    gateways: HashSet<NodeId>,
+    keys: KeyCounters,
+    packets_total: usize,
+    packets_by_portnum: BTreeMap<String, usize>,
+    // Indexed by hours since epoch
+    packets_per_hour: BTreeMap<i64, usize>,
+}
+
+const HOURS_ON_CHART: i64 = 48;
+
+impl Dashboard {
+    pub fn push(&mut self, stored_mesh_packet: &StoredMeshPacket) {
+        let timestamp = stored_mesh_packet.store_timestamp;
+        self.last_seen
+            .entry(stored_mesh_packet.header.from)
+            .and_modify(|v| *v = (*v).max(timestamp))
+            .or_insert(timestamp);
+        if let Some(gateway) = stored_mesh_packet.gateway {
+            self.gateways.insert(gateway);
+        }
+
+        let portnum = match &stored_mesh_packet.data {
+            Some(DataVariant::Decrypted(_, data)) => data.portnum().as_str_name().to_string(),
+            Some(DataVariant::Encrypted(_)) => "<encrypted>".into(),
+            Some(DataVariant::DecryptError(_, _)) => "<decrypt error>".into(),
+            None => "<empty>".into(),
+        };
+        *self.packets_by_portnum.entry(portnum).or_default() += 1;
+        *self
+            .packets_per_hour
+            .entry(timestamp.timestamp().div_euclid(3600))
+            .or_default() += 1;
+        self.packets_total += 1;
+    }
+
+    // Keys' statuses are changed only with NodeInfo packets
+    pub fn update_keys(&mut self, nodes: &HashMap<NodeId, NodeInfo>) {
+        self.keys = Default::default();
+        for node_info in nodes.values() {
+            match node_info.extended_info_history.last().map(|v| &v.pkey) {
+                Some(PublicKey::Key(_)) => self.keys.valid += 1,
+                Some(PublicKey::Compromised(_)) => self.keys.compromised += 1,
+                Some(PublicKey::None) => self.keys.none += 1,
+                None => self.keys.unknown += 1,
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Default::default();
+    }
+
+    fn card(ui: &mut egui::Ui, title: &str, value: usize, color: Option<Color32>) {
+        egui::Frame::group(ui.style()).show(ui, |ui| {
+            ui.set_min_width(110.0);
+            ui.vertical(|ui| {
+                let text = RichText::new(value.to_string()).heading().strong();
+                ui.label(if let Some(color) = color {
+                    text.color(color)
+                } else {
+                    text
+                });
+                ui.label(RichText::new(title).small());
+            });
+        });
+    }
+
+    fn seen_since(&self, since: DateTime<Utc>) -> usize {
+        self.last_seen.values().filter(|v| **v >= since).count()
+    }
+
+    pub fn ui(&self, ui: &mut egui::Ui) {
+        let current_datetime = Utc::now();
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui.heading("Nodes");
+            ui.horizontal_wrapped(|ui| {
+                Self::card(ui, "Total", self.last_seen.len(), None);
+                Self::card(
+                    ui,
+                    "Seen in 15 minutes",
+                    self.seen_since(current_datetime - TimeDelta::minutes(15)),
+                    None,
+                );
+                Self::card(
+                    ui,
+                    "Seen in 1 hour",
+                    self.seen_since(current_datetime - TimeDelta::hours(1)),
+                    None,
+                );
+                Self::card(
+                    ui,
+                    "Seen in 24 hours",
+                    self.seen_since(current_datetime - TimeDelta::hours(24)),
+                    None,
+                );
+                Self::card(ui, "Gateways", self.gateways.len(), None);
+            });
+
+            ui.heading("Public keys");
+            ui.horizontal_wrapped(|ui| {
+                Self::card(ui, "Valid", self.keys.valid, Some(Color32::LIGHT_GREEN));
+                Self::card(
+                    ui,
+                    "Compromised",
+                    self.keys.compromised,
+                    Some(Color32::YELLOW),
+                );
+                Self::card(ui, "No key", self.keys.none, Some(Color32::LIGHT_RED));
+                Self::card(ui, "Unknown", self.keys.unknown, None);
+            });
+
+            ui.heading(format!("Packets: {}", self.packets_total));
+            let since_hour = current_datetime.timestamp().div_euclid(3600) - HOURS_ON_CHART;
+            let hourly_bars = self
+                .packets_per_hour
+                .range(since_hour..)
+                .map(|(hour, count)| Bar::new(*hour as f64 + 0.5, *count as f64).width(0.9))
+                .collect::<Vec<_>>();
+            let x_axis = egui_plot::AxisHints::new_x().formatter(|mark, _range| {
+                DateTime::from_timestamp((mark.value * 3600.0) as i64, 0)
+                    .map(|v| v.format("%d/%m %H:%M").to_string())
+                    .unwrap_or_default()
+            });
+            ui.label(format!("Per hour, last {} hours", HOURS_ON_CHART));
+            egui_plot::Plot::new("dashboard_packets_per_hour")
+                .height(160.0)
+                .custom_x_axes(vec![x_axis])
+                .allow_scroll(false)
+                .show(ui, |plot_ui| {
+                    plot_ui.bar_chart(BarChart::new("Packets", hourly_bars));
+                });
+
+            let mut by_portnum = self.packets_by_portnum.iter().collect::<Vec<_>>();
+            by_portnum.sort_by(|a, b| b.1.cmp(a.1));
+            let portnum_bars = by_portnum
+                .iter()
+                .enumerate()
+                .map(|(index, (portnum, count))| {
+                    Bar::new(index as f64, **count as f64)
+                        .name(portnum.as_str())
+                        .width(0.8)
+                })
+                .collect::<Vec<_>>();
+            ui.label("By port");
+            egui_plot::Plot::new("dashboard_packets_by_portnum")
+                .height(160.0)
+                .show_x(false)
+                .allow_scroll(false)
+                .show(ui, |plot_ui| {
+                    plot_ui.bar_chart(BarChart::new("Packets", portnum_bars));
+                });
+            egui::Grid::new("dashboard_portnums")
+                .striped(true)
+                .show(ui, |ui| {
+                    for (portnum, count) in by_portnum {
+                        ui.label(portnum);
+                        ui.label(count.to_string());
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+}
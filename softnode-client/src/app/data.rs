@@ -1,15 +1,26 @@
 use chrono::{DateTime, Utc};
+use geo::{Distance, Haversine};
+pub use meshtastic_connect::stored::{
+    DataVariant, DecryptError, DecryptTarget, StoreMeshRxInfo, StoredMeshHeader, StoredMeshPacket,
+};
 use meshtastic_connect::{
-    keyring::{Keyring, channel::ChannelHash, cryptor::Decrypt, key::Key, node_id::NodeId},
+    geo::decode_coord,
+    keyring::{channel::ChannelHash, key::Key, node_id::NodeId},
     meshtastic,
+    portnum::{PortCategory, portnum_info},
 };
 use prost::Message;
-use std::{collections::HashMap, fmt::Display};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt::Display,
+};
 
-use crate::app::node_book::NodeBook;
+use crate::app::node_book::{NodeBook, ZoneId};
+use crate::app::radio_center::AssumedPosition;
 
 use super::byte_node_id::ByteNodeId;
 
+#[derive(serde::Deserialize, serde::Serialize)]
 pub struct JournalData {
     pub timestamp: DateTime<Utc>,
     pub hop_start: u32,
@@ -25,6 +36,12 @@ pub struct JournalData {
     pub relay: ByteNodeId,
     pub message_type: String,
     pub message_hint: String,
+    // None if packet is not decrypted
+    pub port_category: Option<PortCategory>,
+    pub portnum: Option<i32>,
+    // Encoded `meshtastic::Data` if decrypted, payload as received otherwise
+    pub raw: Vec<u8>,
+    pub is_decrypted: bool,
 }
 
 impl From<StoredMeshPacket> for JournalData {
@@ -32,22 +49,32 @@ impl From<StoredMeshPacket> for JournalData {
         let message_type;
         let is_encrypted;
         let message_hint;
+        let mut port_category = None;
+        let mut portnum = None;
+        let mut raw = Vec::new();
+        let mut is_decrypted = false;
 
         if let Some(data) = stored_mesh_packet.data {
             match data {
-                DataVariant::Encrypted(_) => {
+                DataVariant::Encrypted(encrypted) => {
+                    raw = encrypted;
                     message_type = "<encrypted>".into();
                     message_hint = "".into();
                     is_encrypted = true;
                 }
                 DataVariant::Decrypted(decrypt_target, data) => {
-                    match decrypt_target {
+                    match &decrypt_target {
                         DecryptTarget::Direct(_) => is_encrypted = false,
                         DecryptTarget::PKI => is_encrypted = true,
                         DecryptTarget::Channel(_) => is_encrypted = true,
+                        DecryptTarget::Guessed(_) => is_encrypted = true,
                     }
+                    raw = data.encode_to_vec();
+                    is_decrypted = true;
+                    portnum = Some(data.portnum);
                     message_type = data.portnum().as_str_name().into();
-                    message_hint = match data.portnum() {
+                    port_category = Some(portnum_info(data.portnum()).category);
+                    let hint = match data.portnum() {
                         meshtastic::PortNum::TextMessageApp => {
                             String::from_utf8_lossy(data.payload.as_slice()).into()
                         }
@@ -139,8 +166,14 @@ impl From<StoredMeshPacket> for JournalData {
                         }
                         _ => "".into(),
                     };
+                    message_hint = if let DecryptTarget::Guessed(channel_name) = &decrypt_target {
+                        format!("<key guessed: {}> {}", channel_name, hint)
+                    } else {
+                        hint
+                    };
                 }
-                DataVariant::DecryptError(decrypt_error, _) => {
+                DataVariant::DecryptError(decrypt_error, encrypted) => {
+                    raw = encrypted;
                     is_encrypted = true;
                     match decrypt_error {
                         DecryptError::DecryptorNotFound => {
@@ -179,150 +212,56 @@ impl From<StoredMeshPacket> for JournalData {
             relay: stored_mesh_packet.header.relay_node,
             message_type,
             message_hint,
+            port_category,
+            portnum,
+            raw,
+            is_decrypted,
         }
     }
 }
 
-#[derive(Clone, serde::Deserialize, serde::Serialize)]
-pub enum DecryptTarget {
-    Direct(u32),
-    PKI,
-    Channel(String),
-}
-
-#[derive(Clone)]
-pub enum DataVariant {
-    Encrypted(Vec<u8>),
-    Decrypted(DecryptTarget, meshtastic::Data),
-    DecryptError(DecryptError, Vec<u8>),
-}
+// Sender, packet id and gateway
+type SeenPacketKey = (NodeId, u32, Option<NodeId>);
 
-#[derive(Clone, serde::Deserialize, serde::Serialize)]
-pub enum DecryptError {
-    DecryptorNotFound,
-    DecryptFailed,
-    ConstructFailed,
-}
+// Window to remember packets in, by the server's store time
+const SEEN_PACKETS_WINDOW: chrono::Duration = chrono::Duration::hours(1);
+const SEEN_PACKETS_LIMIT: usize = 100_000;
 
-#[derive(serde::Deserialize, serde::Serialize)]
-enum DataVariantSerdeHelper {
-    Encrypted(Vec<u8>),
-    Decrypted(DecryptTarget, Vec<u8>),
-    DecryptError(DecryptError, Vec<u8>),
+// Recently ingested packets, to skip ones sent by server again.
+// Same packet heard by other gateway is not a duplicate
+#[derive(Default)]
+pub struct SeenPackets {
+    seen: HashSet<SeenPacketKey>,
+    order: VecDeque<(DateTime<Utc>, SeenPacketKey)>,
 }
 
-impl serde::Serialize for DataVariant {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        match self {
-            DataVariant::Encrypted(data) => DataVariantSerdeHelper::Encrypted(data.clone()),
-            DataVariant::Decrypted(target, data) => {
-                DataVariantSerdeHelper::Decrypted(target.clone(), data.encode_to_vec())
-            }
-            DataVariant::DecryptError(reason, data) => {
-                DataVariantSerdeHelper::DecryptError(reason.clone(), data.clone())
-            }
+impl SeenPackets {
+    // False if packet is already ingested
+    pub fn insert(&mut self, stored_mesh_packet: &StoredMeshPacket) -> bool {
+        let timestamp = stored_mesh_packet.store_timestamp;
+        while let Some((seen_timestamp, key)) = self.order.front().copied()
+            && (timestamp - seen_timestamp > SEEN_PACKETS_WINDOW
+                || self.order.len() >= SEEN_PACKETS_LIMIT)
+        {
+            self.seen.remove(&key);
+            self.order.pop_front();
         }
-        .serialize(serializer)
-    }
-}
-
-impl<'de> serde::Deserialize<'de> for DataVariant {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        let helper = DataVariantSerdeHelper::deserialize(deserializer)?;
-
-        match helper {
-            DataVariantSerdeHelper::Encrypted(items) => Ok(DataVariant::Encrypted(items)),
-            DataVariantSerdeHelper::Decrypted(target, items) => {
-                let data =
-                    meshtastic::Data::decode(items.as_slice()).map_err(serde::de::Error::custom)?;
 
-                Ok(DataVariant::Decrypted(target, data))
-            }
-            DataVariantSerdeHelper::DecryptError(reason, data) => {
-                Ok(DataVariant::DecryptError(reason, data))
-            }
+        let key = (
+            stored_mesh_packet.header.from,
+            stored_mesh_packet.header.id,
+            stored_mesh_packet.gateway,
+        );
+        if !self.seen.insert(key) {
+            return false;
         }
+        self.order.push_back((timestamp, key));
+        true
     }
-}
-
-#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, PartialOrd)]
-pub struct StoreMeshRxInfo {
-    pub rx_time: DateTime<Utc>,
-    pub rx_snr: f32,
-    pub rx_rssi: i32,
-}
 
-#[derive(serde::Deserialize, serde::Serialize, Clone)]
-pub struct StoredMeshHeader {
-    pub from: NodeId,
-    pub to: NodeId,
-    pub channel: u32,
-    pub id: u32,
-    pub priority: String,
-    pub via_mqtt: bool,
-    pub rx: Option<StoreMeshRxInfo>,
-    pub hop_limit: u32,
-    pub hop_start: u32,
-    pub pki_encrypted: bool,
-    pub next_hop: ByteNodeId,
-    pub relay_node: ByteNodeId,
-}
-
-#[derive(serde::Deserialize, serde::Serialize, Clone)]
-pub struct StoredMeshPacket {
-    pub sequence_number: u64,
-    pub store_timestamp: DateTime<chrono::Utc>,
-    pub gateway: Option<NodeId>,
-    pub connection_name: String,
-    pub connection_hint: Option<String>,
-    pub header: StoredMeshHeader,
-    pub data: Option<DataVariant>,
-}
-
-impl StoredMeshPacket {
-    // Decrypt data if possible or return error
-    pub fn decrypt(mut self, keyring: &Keyring) -> Self {
-        if let Some(data) = self.data {
-            let data = match data {
-                DataVariant::Encrypted(items) | DataVariant::DecryptError(_, items) => {
-                    if let Some(cryptor) = keyring.cryptor_for(
-                        self.header.from,
-                        self.header.to,
-                        self.header.channel.into(),
-                    ) {
-                        if let Ok(decrypted) = cryptor.decrypt(self.header.id, items.clone()) {
-                            if let Ok(data) = meshtastic::Data::decode(decrypted.as_slice()) {
-                                match cryptor {
-                                    meshtastic_connect::keyring::cryptor::Cryptor::Symmetric(
-                                        name,
-                                        _,
-                                    ) => DataVariant::Decrypted(DecryptTarget::Channel(name), data),
-                                    meshtastic_connect::keyring::cryptor::Cryptor::PKI(_) => {
-                                        DataVariant::Decrypted(DecryptTarget::PKI, data)
-                                    }
-                                }
-                            } else {
-                                DataVariant::DecryptError(DecryptError::ConstructFailed, items)
-                            }
-                        } else {
-                            DataVariant::DecryptError(DecryptError::DecryptFailed, items)
-                        }
-                    } else {
-                        DataVariant::DecryptError(DecryptError::DecryptorNotFound, items)
-                    }
-                }
-                DataVariant::Decrypted(target, items) => DataVariant::Decrypted(target, items),
-            };
-
-            self.data = Some(data);
-        }
-        self
+    pub fn clear(&mut self) {
+        self.seen.clear();
+        self.order.clear();
     }
 }
 
@@ -504,19 +443,20 @@ impl NodeTelemetry {
     }
 }
 
-#[derive(serde::Deserialize, serde::Serialize, PartialEq)]
+#[derive(Clone, serde::Deserialize, serde::Serialize, PartialEq)]
 pub struct Position {
     pub seq_number: u32,
     pub timestamp: DateTime<Utc>,
     pub latitude: f64,
     pub longitude: f64,
-    pub altitude: i32,
+    // None if altitude is not reported
+    pub altitude: Option<i32>,
     pub speed: u32,
     pub precision_bits: u32,
     pub precision_bounds: Vec<geo::Point>,
 }
 
-#[derive(serde::Deserialize, serde::Serialize, PartialEq, PartialOrd)]
+#[derive(Clone, serde::Deserialize, serde::Serialize, PartialEq, PartialOrd)]
 pub enum NodePacketType {
     Normal(String),
     CannotDecrypt,
@@ -524,7 +464,7 @@ pub enum NodePacketType {
     Empty,
 }
 
-#[derive(serde::Deserialize, serde::Serialize, PartialEq, PartialOrd)]
+#[derive(Clone, serde::Deserialize, serde::Serialize, PartialEq, PartialOrd)]
 pub struct NodePacket {
     pub timestamp: DateTime<Utc>,
     pub packet_type: NodePacketType,
@@ -550,7 +490,17 @@ pub enum PublicKey {
     Compromised(Key),
 }
 
-#[derive(Default, serde::Deserialize, serde::Serialize, PartialEq)]
+impl PublicKey {
+    // Announced key, compromised or not
+    pub fn key(&self) -> Option<Key> {
+        match self {
+            PublicKey::None => None,
+            PublicKey::Key(key) | PublicKey::Compromised(key) => Some(*key),
+        }
+    }
+}
+
+#[derive(Clone, Default, serde::Deserialize, serde::Serialize, PartialEq)]
 pub struct NodeInfoExtended {
     pub timestamp: DateTime<Utc>,
     pub announced_node_id: String,
@@ -561,7 +511,7 @@ pub struct NodeInfoExtended {
     pub is_unmessagable: Option<bool>,
 }
 
-#[derive(serde::Deserialize, serde::Serialize, PartialEq)]
+#[derive(Clone, serde::Deserialize, serde::Serialize, PartialEq)]
 pub struct GatewayInfo {
     pub timestamp: DateTime<Utc>,
     pub rx_info: Option<StoreMeshRxInfo>,
@@ -571,19 +521,97 @@ pub struct GatewayInfo {
     pub packet_id: u32,
 }
 
-impl From<&StoredMeshPacket> for GatewayInfo {
-    fn from(stored_mesh_packet: &StoredMeshPacket) -> Self {
-        let rx_info = if let Some(rx_info) = &stored_mesh_packet.header.rx {
-            if rx_info.rx_rssi > RSSI_UPPER_THRESHOLD || rx_info.rx_rssi < RSSI_LOWER_THRESHOLD {
-                None
-            } else if rx_info.rx_snr > SNR_UPPER_THRESHOLD || rx_info.rx_snr < SNR_LOWER_THRESHOLD {
-                None
-            } else {
-                Some(rx_info.clone())
-            }
+// Limits to discard bogus radio values reported by gateways
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct RadioThresholds {
+    // Disabled filter keeps all samples
+    pub enabled: bool,
+    // dBm
+    pub rssi_upper: i32,
+    pub rssi_lower: i32,
+    // dB
+    pub snr_upper: f32,
+    pub snr_lower: f32,
+}
+
+impl Default for RadioThresholds {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            rssi_upper: 50,
+            rssi_lower: -200,
+            snr_upper: 30.0,
+            snr_lower: -200.0,
+        }
+    }
+}
+
+impl RadioThresholds {
+    // Returns None if RSSI or SNR is out of the limits
+    pub fn filter(&self, rx_info: &StoreMeshRxInfo) -> Option<StoreMeshRxInfo> {
+        let out_of_limits = rx_info.rx_rssi > self.rssi_upper
+            || rx_info.rx_rssi < self.rssi_lower
+            || rx_info.rx_snr > self.snr_upper
+            || rx_info.rx_snr < self.snr_lower;
+        if self.enabled && out_of_limits {
+            None
+        } else {
+            Some(rx_info.clone())
+        }
+    }
+}
+
+// Use of time sent in packets: position's `timestamp` and telemetry's `time`.
+// Nodes' clocks may be wrong, so time is accepted only near to time of storing
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct PacketTime {
+    // Positions are placed at packet's time instead of time of storing
+    pub position: bool,
+    // Telemetry is placed at packet's time instead of time of storing
+    pub telemetry: bool,
+    // Allowed difference with time of storing, in both directions
+    pub window_hours: u32,
+}
+
+impl Default for PacketTime {
+    fn default() -> Self {
+        Self {
+            position: true,
+            telemetry: false,
+            window_hours: 24,
+        }
+    }
+}
+
+impl PacketTime {
+    // None if time is not set (zero) or out of the window
+    pub fn accept(
+        &self,
+        packet_time: u32,
+        stored_timestamp: DateTime<Utc>,
+    ) -> Option<DateTime<Utc>> {
+        if packet_time == 0 {
+            return None;
+        }
+        let timestamp = DateTime::from_timestamp(packet_time as i64, 0)?;
+        let window = chrono::Duration::hours(self.window_hours as i64);
+        if (timestamp - stored_timestamp).abs() <= window {
+            Some(timestamp)
         } else {
             None
-        };
+        }
+    }
+}
+
+impl GatewayInfo {
+    fn new(stored_mesh_packet: &StoredMeshPacket, thresholds: &RadioThresholds) -> Self {
+        let rx_info = stored_mesh_packet
+            .header
+            .rx
+            .as_ref()
+            .and_then(|rx_info| thresholds.filter(rx_info));
 
         let hop_distance =
             if stored_mesh_packet.header.hop_start >= stored_mesh_packet.header.hop_limit {
@@ -603,7 +631,7 @@ impl From<&StoredMeshPacket> for GatewayInfo {
     }
 }
 
-#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[derive(Clone, Default, serde::Deserialize, serde::Serialize)]
 pub struct NeighborInfo {
     pub node_id: NodeId,
     pub snr: f32,
@@ -614,7 +642,7 @@ pub struct NodeInfo {
     pub node_id: NodeId,
     pub extended_info_history: Vec<NodeInfoExtended>,
     pub position: Vec<Position>,
-    pub assumed_position: Option<geo::Point>,
+    pub assumed_position: Option<AssumedPosition>,
     pub telemetry: HashMap<TelemetryVariant, NodeTelemetry>,
     pub packet_statistics: Vec<NodePacket>,
     pub gateway_for: HashMap<NodeId, Vec<GatewayInfo>>,
@@ -625,9 +653,157 @@ pub struct NodeInfo {
     /// Список каналов, в которых узел был замечен
     /// и время последнего присутствия в канале
     pub seen_in_channels: HashMap<u32, DateTime<Utc>>,
+    /// Количество позиций, отброшенных зонами игнорирования GNSS
+    #[serde(default)]
+    pub ignored_positions: HashMap<ZoneId, usize>,
+    /// Portnum'ы, отправленные узлом (`Data::portnum` как есть),
+    /// и время первого получения
+    #[serde(default)]
+    pub seen_portnums: HashMap<i32, DateTime<Utc>>,
 }
 
 impl NodeInfo {
+    // Announced keys in order, announcements without key are skipped
+    fn announced_keys(&self) -> impl DoubleEndedIterator<Item = Key> {
+        self.extended_info_history
+            .iter()
+            .filter_map(|extended| extended.pkey.key())
+    }
+
+    // Previous and newest announced keys, if the newest one differs
+    pub fn key_change(&self) -> Option<(Key, Key)> {
+        let mut keys = self.announced_keys().rev();
+        let current = keys.next()?;
+        let previous = keys.next()?;
+        (previous != current).then_some((previous, current))
+    }
+
+    // Key is changed at any time of the history
+    pub fn has_key_changed(&self) -> bool {
+        let mut keys = self.announced_keys();
+        keys.next()
+            .is_some_and(|first| keys.any(|key| key != first))
+    }
+
+    // Fix time of the newest position, None if it's not reported
+    pub fn position_fixed_at(&self) -> Option<DateTime<Utc>> {
+        self.position
+            .last()
+            .map(|position| position.timestamp)
+            .filter(|timestamp| *timestamp != DateTime::<Utc>::default())
+    }
+
+    // Marks positions reached from the last trusted one faster than
+    // `max_speed_kmh`: GPS glitches. Positions without time are not checked
+    pub fn position_glitches(&self, max_speed_kmh: f64) -> Vec<bool> {
+        let mut trusted: Option<&Position> = None;
+        self.position
+            .iter()
+            .map(|position| {
+                if position.timestamp == DateTime::<Utc>::default() {
+                    return false;
+                }
+                let Some(previous) = trusted else {
+                    trusted = Some(position);
+                    return false;
+                };
+                let distance = Haversine.distance(
+                    geo::Point::new(previous.longitude, previous.latitude),
+                    geo::Point::new(position.longitude, position.latitude),
+                );
+                let seconds = (position.timestamp - previous.timestamp)
+                    .num_seconds()
+                    .abs()
+                    .max(1);
+                let speed_kmh = distance / seconds as f64 * 3.6;
+                if speed_kmh > max_speed_kmh {
+                    true
+                } else {
+                    trusted = Some(position);
+                    false
+                }
+            })
+            .collect()
+    }
+
+    // Copy of the node's data received before the timestamp.
+    // None if node is not heard yet
+    pub fn until(&self, before: DateTime<Utc>) -> Option<NodeInfo> {
+        let first_seen = self.packet_statistics.first()?.timestamp;
+        if first_seen > before {
+            return None;
+        }
+
+        fn truncated<T: Clone>(values: &[T], is_before: impl Fn(&T) -> bool) -> Vec<T> {
+            values[..values.partition_point(is_before)].to_vec()
+        }
+
+        let telemetry = self
+            .telemetry
+            .iter()
+            .map(|(variant, telemetry)| {
+                let is_before = |v: &TelemetryValue| v.timestamp <= before;
+                (
+                    *variant,
+                    NodeTelemetry {
+                        values: truncated(&telemetry.values, is_before),
+                        min_peaks: truncated(&telemetry.min_peaks, is_before),
+                        max_peaks: truncated(&telemetry.max_peaks, is_before),
+                    },
+                )
+            })
+            .filter(|(_, telemetry)| !telemetry.values.is_empty())
+            .collect();
+
+        let gateway_for = self
+            .gateway_for
+            .iter()
+            .map(|(node_id, gateway_infos)| {
+                (
+                    *node_id,
+                    truncated(gateway_infos, |v| v.timestamp <= before),
+                )
+            })
+            .filter(|(_, gateway_infos)| !gateway_infos.is_empty())
+            .collect();
+
+        Some(NodeInfo {
+            node_id: self.node_id,
+            extended_info_history: truncated(&self.extended_info_history, |v| {
+                v.timestamp <= before
+            }),
+            position: truncated(&self.position, |v| v.timestamp <= before),
+            assumed_position: self.assumed_position,
+            telemetry,
+            packet_statistics: truncated(&self.packet_statistics, |v| v.timestamp <= before),
+            gateway_for,
+            gatewayed_by: self
+                .gatewayed_by
+                .iter()
+                .filter(|(_, gateway_info)| gateway_info.timestamp <= before)
+                .map(|(node_id, gateway_info)| (*node_id, gateway_info.clone()))
+                .collect(),
+            neighbor_info: self
+                .neighbor_info
+                .as_ref()
+                .filter(|(timestamp, _)| *timestamp <= before)
+                .cloned(),
+            seen_in_channels: self
+                .seen_in_channels
+                .iter()
+                .filter(|(_, timestamp)| **timestamp <= before)
+                .map(|(channel, timestamp)| (*channel, *timestamp))
+                .collect(),
+            ignored_positions: self.ignored_positions.clone(),
+            seen_portnums: self
+                .seen_portnums
+                .iter()
+                .filter(|(_, timestamp)| **timestamp <= before)
+                .map(|(portnum, timestamp)| (*portnum, *timestamp))
+                .collect(),
+        })
+    }
+
     fn push_telemetry(
         &mut self,
         timestamp: DateTime<Utc>,
@@ -644,8 +820,14 @@ impl NodeInfo {
         stored_timestamp: DateTime<Utc>,
         data: &meshtastic::Data,
         nodebook: &NodeBook,
+        packet_time: &PacketTime,
         is_duplicate: bool,
     ) -> Result<meshtastic::PortNum, String> {
+        self.seen_portnums
+            .entry(data.portnum)
+            .and_modify(|v| *v = (*v).min(stored_timestamp))
+            .or_insert(stored_timestamp);
+
         match data.portnum() {
             meshtastic::PortNum::NeighborinfoApp => {
                 let received_neighbor_info =
@@ -667,25 +849,27 @@ impl NodeInfo {
                     .map_err(|e| e.to_string())?;
 
                 if !is_duplicate {
-                    let altitude = if let Some(altitude) = mesh_position.altitude {
-                        altitude
-                    } else if let Some(altitude) = mesh_position.altitude_hae {
-                        altitude
-                    } else if let Some(altitude) = mesh_position.altitude_geoidal_separation {
-                        altitude
-                    } else {
-                        0
-                    };
+                    let altitude = mesh_position
+                        .altitude
+                        .or(mesh_position.altitude_hae)
+                        .or(mesh_position.altitude_geoidal_separation);
 
-                    let mut latitude = mesh_position.latitude_i() as f64 * 1e-7;
-                    let mut longitude = mesh_position.longitude_i() as f64 * 1e-7;
+                    let mut latitude = decode_coord(mesh_position.latitude_i());
+                    let mut longitude = decode_coord(mesh_position.longitude_i());
                     let point = geo::Point::new(longitude, latitude);
 
-                    if let Some(zone_name) = nodebook.point_in_zone(point) {
-                        log::info!("Skip point in zone id: {:?}", zone_name);
+                    if let Some(zone_id) = nodebook.is_ignored(point) {
+                        log::info!("Skip point in zone id: {:?}", zone_id);
+                        *self.ignored_positions.entry(zone_id).or_default() += 1;
                     } else {
-                        let timestamp = DateTime::from_timestamp(mesh_position.timestamp as i64, 0)
-                            .unwrap_or(Default::default());
+                        // Time out of the window is replaced by the stored one
+                        let timestamp = if packet_time.position {
+                            packet_time
+                                .accept(mesh_position.timestamp, stored_timestamp)
+                                .unwrap_or(stored_timestamp)
+                        } else {
+                            stored_timestamp
+                        };
 
                         let precision_bounds = if mesh_position.precision_bits < 32
                             && mesh_position.precision_bits != 0
@@ -781,18 +965,15 @@ impl NodeInfo {
             meshtastic::PortNum::TelemetryApp => {
                 let telemetry = meshtastic::Telemetry::decode(data.payload.as_slice())
                     .map_err(|e| e.to_string())?;
-                // let timestamp = DateTime::from_timestamp(telemetry.time as i64, 0)
-                //     .map(|v| {
-                //         if v == DateTime::<Utc>::default() {
-                //             stored_timestamp
-                //         } else {
-                //             v
-                //         }
-                //     })
-                //     .unwrap_or_else(|| stored_timestamp);
-                //
-                // Received timestamp may be buggy, so use the stored timestamp
-                let timestamp = stored_timestamp;
+                // Received timestamp may be buggy, so the stored timestamp
+                // is used unless packet's time is enabled and looks sane
+                let timestamp = if packet_time.telemetry {
+                    packet_time
+                        .accept(telemetry.time, stored_timestamp)
+                        .unwrap_or(stored_timestamp)
+                } else {
+                    stored_timestamp
+                };
 
                 if !is_duplicate {
                     match telemetry.variant.ok_or(format!("Telemetry is empty"))? {
@@ -1092,9 +1273,13 @@ impl NodeInfo {
         Ok(data.portnum())
     }
 
-    pub fn update_as_gateway(&mut self, stored_mesh_packet: &StoredMeshPacket) {
+    pub fn update_as_gateway(
+        &mut self,
+        stored_mesh_packet: &StoredMeshPacket,
+        thresholds: &RadioThresholds,
+    ) {
         if self.node_id != stored_mesh_packet.header.from {
-            let gateway_info = stored_mesh_packet.into();
+            let gateway_info = GatewayInfo::new(stored_mesh_packet, thresholds);
 
             let list = self
                 .gateway_for
@@ -1105,7 +1290,13 @@ impl NodeInfo {
         }
     }
 
-    pub fn update(&mut self, stored_mesh_packet: &StoredMeshPacket, nodebook: &NodeBook) {
+    pub fn update(
+        &mut self,
+        stored_mesh_packet: &StoredMeshPacket,
+        nodebook: &NodeBook,
+        thresholds: &RadioThresholds,
+        packet_time: &PacketTime,
+    ) {
         let current_time = chrono::Utc::now();
         let timestamp = stored_mesh_packet.store_timestamp;
         let is_duplicate = self
@@ -1131,7 +1322,13 @@ impl NodeInfo {
             match data {
                 DataVariant::Encrypted(_) => NodePacketType::CannotDecrypt,
                 DataVariant::Decrypted(_, data) => {
-                    match self.update_using_data(timestamp, data, nodebook, is_duplicate) {
+                    match self.update_using_data(
+                        timestamp,
+                        data,
+                        nodebook,
+                        packet_time,
+                        is_duplicate,
+                    ) {
                         Ok(portnum) => NodePacketType::Normal(format!("{}", portnum.as_str_name())),
                         Err(e) => {
                             log::error!("Failed to update using data: {}", e);
@@ -1154,8 +1351,8 @@ impl NodeInfo {
             if gateway != stored_mesh_packet.header.from {
                 self.gatewayed_by
                     .entry(gateway)
-                    .and_modify(|v| *v = stored_mesh_packet.into())
-                    .or_insert_with(|| stored_mesh_packet.into());
+                    .and_modify(|v| *v = GatewayInfo::new(stored_mesh_packet, thresholds))
+                    .or_insert_with(|| GatewayInfo::new(stored_mesh_packet, thresholds));
             }
         }
 
@@ -1183,8 +1380,3 @@ impl NodeInfo {
         push_statistic!(self.packet_statistics, packet);
     }
 }
-
-const RSSI_UPPER_THRESHOLD: i32 = 50;
-const RSSI_LOWER_THRESHOLD: i32 = -200;
-const SNR_UPPER_THRESHOLD: f32 = 30.0;
-const SNR_LOWER_THRESHOLD: f32 = -200.0;
@@ -0,0 +1,162 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Utc};
+use egui::{Color32, RichText};
+use meshtastic_connect::keyring::{channel::ChannelHash, node_id::NodeId};
+
+use crate::app::{
+    data::{DataVariant, DecryptError, NodeInfo, StoredMeshPacket},
+    time_format::format_timediff,
+};
+
+#[derive(Default)]
+struct FailureCounters {
+    // No key for the channel's hash or the node's pkey in the keyring
+    not_found: usize,
+    // Key is found but decryption is failed
+    decrypt_failed: usize,
+    // Decrypted data is not a valid protobuf: wrong key with same hash
+    construct_failed: usize,
+    pki: usize,
+    last_seen: DateTime<Utc>,
+}
+
+impl FailureCounters {
+    fn total(&self) -> usize {
+        self.not_found + self.decrypt_failed + self.construct_failed
+    }
+
+    fn add(&mut self, other: &FailureCounters) {
+        self.not_found += other.not_found;
+        self.decrypt_failed += other.decrypt_failed;
+        self.construct_failed += other.construct_failed;
+        self.pki += other.pki;
+        self.last_seen = self.last_seen.max(other.last_seen);
+    }
+
+    fn hint(&self) -> &'static str {
+        if self.not_found >= self.decrypt_failed + self.construct_failed {
+            "Key is missing: add channel's key in the Settings"
+        } else {
+            "Key is found but does not fit: check the channel's PSK"
+        }
+    }
+}
+
+// Undecryptable packets grouped by channel hash and sender
+#[derive(Default)]
+pub struct DecryptFailures {
+    // Indexed by channel's hash
+    failures: BTreeMap<u32, BTreeMap<NodeId, FailureCounters>>,
+}
+
+impl DecryptFailures {
+    pub fn push(&mut self, stored_mesh_packet: &StoredMeshPacket) {
+        let Some(DataVariant::DecryptError(reason, _)) = &stored_mesh_packet.data else {
+            return;
+        };
+
+        let counters = self
+            .failures
+            .entry(stored_mesh_packet.header.channel)
+            .or_default()
+            .entry(stored_mesh_packet.header.from)
+            .or_default();
+
+        match reason {
+            DecryptError::DecryptorNotFound => counters.not_found += 1,
+            DecryptError::DecryptFailed => counters.decrypt_failed += 1,
+            DecryptError::ConstructFailed => counters.construct_failed += 1,
+        }
+        if stored_mesh_packet.header.pki_encrypted {
+            counters.pki += 1;
+        }
+        counters.last_seen = counters.last_seen.max(stored_mesh_packet.store_timestamp);
+    }
+
+    pub fn clear(&mut self) {
+        self.failures.clear();
+    }
+
+    pub fn ui(&self, ui: &mut egui::Ui, nodes: &HashMap<NodeId, NodeInfo>) {
+        if self.failures.is_empty() {
+            ui.label("No decryption failures");
+            return;
+        }
+
+        let current_datetime = Utc::now();
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for (channel, senders) in &self.failures {
+                let channel = ChannelHash::from(*channel);
+                let mut channel_counters = FailureCounters::default();
+                for counters in senders.values() {
+                    channel_counters.add(counters);
+                }
+
+                ui.horizontal(|ui| {
+                    ui.heading(format!("Channel {}", channel));
+                    if ui
+                        .small_button("📋")
+                        .on_hover_text("Copy channel's hash")
+                        .clicked()
+                    {
+                        ui.ctx().copy_text(channel.to_string());
+                    }
+                    if ui
+                        .small_button("📋 counts")
+                        .on_hover_text("Copy failures' counts")
+                        .clicked()
+                    {
+                        ui.ctx().copy_text(format!(
+                            "channel: {}, senders: {}, not found: {}, decrypt failed: {}, construct failed: {}",
+                            channel,
+                            senders.len(),
+                            channel_counters.not_found,
+                            channel_counters.decrypt_failed,
+                            channel_counters.construct_failed,
+                        ));
+                    }
+                });
+                ui.label(RichText::new(channel_counters.hint()).color(Color32::YELLOW));
+                ui.label(format!(
+                    "Packets: {}, senders: {}, last: {}",
+                    channel_counters.total(),
+                    senders.len(),
+                    format_timediff(channel_counters.last_seen, current_datetime)
+                        .unwrap_or_default()
+                ));
+
+                egui::Grid::new(ui.id().with(("decrypt_failures", u32::from(channel))))
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.label("From");
+                        ui.label("Not found");
+                        ui.label("Decrypt failed");
+                        ui.label("Construct failed");
+                        ui.label("PKI");
+                        ui.label("Last seen");
+                        ui.end_row();
+
+                        for (node_id, counters) in senders {
+                            let name = nodes
+                                .get(node_id)
+                                .and_then(|v| v.extended_info_history.last())
+                                .map(|v| format!("{} {}", node_id, v.short_name))
+                                .unwrap_or(node_id.to_string());
+                            ui.label(name);
+                            ui.label(counters.not_found.to_string());
+                            ui.label(counters.decrypt_failed.to_string());
+                            ui.label(counters.construct_failed.to_string());
+                            ui.label(counters.pki.to_string());
+                            ui.label(
+                                format_timediff(counters.last_seen, current_datetime)
+                                    .unwrap_or_default(),
+                            );
+                            ui.end_row();
+                        }
+                    });
+                ui.separator();
+            }
+        });
+    }
+}
@@ -0,0 +1,216 @@
+use egui::{Color32, RichText};
+use egui_plot::{Line, Plot, Points, VLine};
+use geo::{Distance, Haversine};
+
+use crate::app::{data::NodeInfo, map::fix_or_position, node_book::NodeBook};
+
+// Effective Earth radius factor for radio propagation (standard atmosphere)
+const EARTH_RADIUS_FACTOR: f64 = 4.0 / 3.0;
+const EARTH_RADIUS_METERS: f64 = 6_371_000.0;
+const SPEED_OF_LIGHT: f64 = 299_792_458.0;
+const PROFILE_SAMPLES: usize = 64;
+
+// Elevation profile between two nodes: line of sight,
+// first Fresnel zone and Earth's bulge
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct ElevationProfile {
+    frequency_mhz: f64,
+}
+
+impl Default for ElevationProfile {
+    fn default() -> Self {
+        Self {
+            frequency_mhz: 868.0,
+        }
+    }
+}
+
+fn node_title(node_info: &NodeInfo) -> String {
+    if let Some(extended) = node_info.extended_info_history.last() {
+        format!("{} {}", node_info.node_id, extended.short_name)
+    } else {
+        node_info.node_id.to_string()
+    }
+}
+
+fn node_position(node_info: &NodeInfo, nodebook: &NodeBook) -> Option<walkers::Position> {
+    fix_or_position(nodebook, node_info.node_id, &node_info.position)
+        .or(node_info.assumed_position.map(|v| v.position))
+}
+
+// Altitude is unknown if position has no altitude
+// or position is set manually in the nodebook
+fn node_altitude(node_info: &NodeInfo, nodebook: &NodeBook) -> Option<f64> {
+    if nodebook
+        .node_get(&node_info.node_id)
+        .map(|v| v.position.is_some())
+        .unwrap_or(false)
+    {
+        return None;
+    }
+    node_info
+        .position
+        .last()
+        .and_then(|v| v.altitude)
+        .map(|v| v as f64)
+}
+
+impl ElevationProfile {
+    pub fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        source: &NodeInfo,
+        destination: &NodeInfo,
+        nodebook: &NodeBook,
+    ) {
+        let source_title = node_title(source);
+        let destination_title = node_title(destination);
+        ui.heading(format!("{} ➡ {}", source_title, destination_title));
+
+        let (Some(source_position), Some(destination_position)) = (
+            node_position(source, nodebook),
+            node_position(destination, nodebook),
+        ) else {
+            ui.label("Position of one of the nodes is unknown");
+            return;
+        };
+
+        ui.horizontal(|ui| {
+            ui.label("Frequency");
+            ui.add(
+                egui::DragValue::new(&mut self.frequency_mhz)
+                    .range(100.0..=6000.0)
+                    .suffix(" MHz"),
+            );
+        });
+
+        let distance = Haversine.distance(source_position, destination_position);
+        let distance_km = distance / 1000.0;
+        let source_altitude = node_altitude(source, nodebook);
+        let destination_altitude = node_altitude(destination, nodebook);
+        let wavelength = SPEED_OF_LIGHT / (self.frequency_mhz * 1_000_000.0);
+
+        ui.label(format!("Distance: {:.3} km", distance_km));
+
+        let samples = (0..=PROFILE_SAMPLES)
+            .map(|i| distance * i as f64 / PROFILE_SAMPLES as f64)
+            .collect::<Vec<_>>();
+        // Earth's bulge over the straight path
+        let bulge = |d1: f64| {
+            let d2 = distance - d1;
+            d1 * d2 / (2.0 * EARTH_RADIUS_FACTOR * EARTH_RADIUS_METERS)
+        };
+        let fresnel_radius = |d1: f64| {
+            let d2 = distance - d1;
+            if distance > 0.0 {
+                (wavelength * d1 * d2 / distance).sqrt()
+            } else {
+                0.0
+            }
+        };
+        let line_of_sight = source_altitude.zip(destination_altitude).map(
+            |(source_altitude, destination_altitude)| {
+                move |d1: f64| {
+                    if distance > 0.0 {
+                        source_altitude + (destination_altitude - source_altitude) * d1 / distance
+                    } else {
+                        source_altitude
+                    }
+                }
+            },
+        );
+
+        if let Some(line_of_sight) = line_of_sight {
+            // Clearance of 60% of the first Fresnel zone over the curved Earth
+            let clearance = samples
+                .iter()
+                .map(|d1| line_of_sight(*d1) - 0.6 * fresnel_radius(*d1) - bulge(*d1))
+                .fold(f64::INFINITY, f64::min);
+            ui.label(format!(
+                "First Fresnel zone radius at midpoint: {:.1} m",
+                fresnel_radius(distance / 2.0)
+            ));
+            ui.label(format!(
+                "Earth bulge at midpoint: {:.1} m",
+                bulge(distance / 2.0)
+            ));
+            let text = format!(
+                "Minimal 60% Fresnel clearance without terrain: {:.1} m",
+                clearance
+            );
+            if clearance < 0.0 {
+                ui.label(RichText::new(text).color(Color32::LIGHT_RED));
+            } else {
+                ui.label(text);
+            }
+        } else {
+            ui.label("Altitude of one of the nodes is unknown: line of sight is not calculated");
+        }
+
+        let plot = Plot::new("elevation_profile")
+            .legend(egui_plot::Legend::default())
+            .x_axis_label("km")
+            .y_axis_label("m");
+
+        plot.show(ui, |plot_ui| {
+            plot_ui.line(
+                Line::new(
+                    "Earth bulge",
+                    samples
+                        .iter()
+                        .map(|d1| [d1 / 1000.0, bulge(*d1)])
+                        .collect::<Vec<_>>(),
+                )
+                .color(Color32::BROWN),
+            );
+
+            for (title, x, altitude) in [
+                (&source_title, 0.0, source_altitude),
+                (&destination_title, distance_km, destination_altitude),
+            ] {
+                if let Some(altitude) = altitude {
+                    plot_ui.points(
+                        Points::new(title.as_str(), vec![[x, altitude]])
+                            .radius(5.0)
+                            .color(Color32::LIGHT_BLUE),
+                    );
+                } else {
+                    plot_ui.vline(
+                        VLine::new(format!("{}: altitude unknown", title), x)
+                            .color(Color32::LIGHT_RED),
+                    );
+                }
+            }
+
+            let Some(line_of_sight) = line_of_sight else {
+                return;
+            };
+
+            plot_ui.line(
+                Line::new(
+                    "Line of sight",
+                    vec![
+                        [0.0, line_of_sight(0.0)],
+                        [distance_km, line_of_sight(distance)],
+                    ],
+                )
+                .color(Color32::LIGHT_GREEN)
+                .width(2.0),
+            );
+            for sign in [-1.0, 1.0] {
+                plot_ui.line(
+                    Line::new(
+                        "Fresnel zone",
+                        samples
+                            .iter()
+                            .map(|d1| {
+                                [d1 / 1000.0, line_of_sight(*d1) + sign * fresnel_radius(*d1)]
+                            })
+                            .collect::<Vec<_>>(),
+                    )
+                    .color(Color32::YELLOW),
+                );
+            }
+        });
+    }
+}
@@ -1,17 +1,25 @@
-use egui::{Align2, Area, Frame, Label, RichText, ScrollArea, TextWrapMode};
-use meshtastic_connect::keyring::node_id::NodeId;
+use std::collections::{BTreeSet, HashMap};
+
+use chrono::{DateTime, Duration, Utc};
+use egui::{
+    Align2, Area, Color32, Frame, Label, RichText, ScrollArea, TextFormat, TextWrapMode,
+    text::LayoutJob,
+};
+use meshtastic_connect::{keyring::node_id::NodeId, portnum::portnum_info_raw};
 
 use crate::app::{
     byte_node_id::ByteNodeId,
     data::NodeInfo,
     node_book::NodeBook,
     node_filter::{FilterVariant, NodeFilter},
-    roster,
+    packet_inspector, roster,
 };
 
 use super::data::JournalData;
 
 const SHOW_LIMIT_BASE: usize = 150;
+// Journal follows new entries while scrolled no further from the latest
+const STICKY_OFFSET: f32 = 5.0;
 
 #[derive(Default, serde::Deserialize, serde::Serialize)]
 pub struct ScrollInfo {
@@ -19,13 +27,233 @@ pub struct ScrollInfo {
     journal_length: usize,
 }
 
+// Periods for the time filter, in minutes
+const PERIODS: [(u32, &str); 4] = [
+    (15, "15 min"),
+    (60, "1 hour"),
+    (360, "6 hours"),
+    (1440, "24 hours"),
+];
+
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+pub struct JournalFilter {
+    // Node id or name in roster's syntax, or text of the message
+    search: String,
+    portnum: Option<i32>,
+    // Only entries of the last minutes
+    period_minutes: Option<u32>,
+    // Only packets sent by nodes shown in the roster
+    by_roster_filter: bool,
+    // `search` parsed as roster's filter, so syntax is the same
+    #[serde(skip)]
+    node_search: NodeFilter,
+}
+
+// Parts of the entry matched by search
+#[derive(Default)]
+struct Highlight {
+    text: String,
+    from: bool,
+    to: bool,
+}
+
+// Search's matches of the entry and filters changed by clicks on it
+struct SearchState<'a> {
+    highlight: &'a Highlight,
+    node_filter: &'a mut NodeFilter,
+    nodebook: &'a NodeBook,
+}
+
+impl JournalFilter {
+    fn is_active(&self) -> bool {
+        !self.search.is_empty()
+            || self.portnum.is_some()
+            || self.period_minutes.is_some()
+            || self.by_roster_filter
+    }
+
+    fn highlight(
+        &self,
+        entry: &JournalData,
+        nodes: &HashMap<NodeId, NodeInfo>,
+        nodebook: &NodeBook,
+    ) -> Highlight {
+        if self.search.is_empty() {
+            return Highlight::default();
+        }
+        let node_matches = |node_id: NodeId| {
+            nodes
+                .get(&node_id)
+                .is_some_and(|node_info| self.node_search.matches(node_info, nodes, nodebook))
+        };
+        Highlight {
+            text: self.search.clone(),
+            from: node_matches(entry.from),
+            to: entry.to != NodeId::broadcast() && node_matches(entry.to),
+        }
+    }
+
+    fn matches(
+        &self,
+        entry: &JournalData,
+        highlight: &Highlight,
+        nodes: &HashMap<NodeId, NodeInfo>,
+        nodebook: &NodeBook,
+        node_filter: &NodeFilter,
+        now: DateTime<Utc>,
+    ) -> bool {
+        if self.portnum.is_some() && entry.portnum != self.portnum {
+            return false;
+        }
+        if let Some(minutes) = self.period_minutes
+            && now - entry.timestamp > Duration::minutes(minutes as i64)
+        {
+            return false;
+        }
+        if self.by_roster_filter
+            && !nodes
+                .get(&entry.from)
+                .is_some_and(|node_info| node_filter.matches(node_info, nodes, nodebook))
+        {
+            return false;
+        }
+        if !self.search.is_empty() {
+            let search = self.search.to_lowercase();
+            return highlight.from
+                || highlight.to
+                || entry.message_hint.to_lowercase().contains(&search)
+                || entry.message_type.to_lowercase().contains(&search);
+        }
+        true
+    }
+
+    // True if filter is changed
+    fn ui(&mut self, ui: &mut egui::Ui, journal: &[JournalData]) -> bool {
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            changed |= ui
+                .add(
+                    egui::TextEdit::singleline(&mut self.search)
+                        .desired_width(200.0)
+                        .hint_text("Search by node or text"),
+                )
+                .on_hover_text(
+                    "Node id, name or alias with roster's syntax,\nor text of the message",
+                )
+                .changed();
+
+            let selected_text = self
+                .portnum
+                .map(|portnum| portnum_info_raw(portnum).name)
+                .unwrap_or("All ports");
+            egui::ComboBox::from_id_salt("journal_portnum")
+                .selected_text(selected_text)
+                .show_ui(ui, |ui| {
+                    changed |= ui
+                        .selectable_value(&mut self.portnum, None, "All ports")
+                        .changed();
+                    // Only ports present in the journal
+                    let portnums = journal
+                        .iter()
+                        .filter_map(|entry| entry.portnum)
+                        .collect::<BTreeSet<_>>();
+                    for portnum in portnums {
+                        let text = format!("{} ({})", portnum_info_raw(portnum).name, portnum);
+                        changed |= ui
+                            .selectable_value(&mut self.portnum, Some(portnum), text)
+                            .changed();
+                    }
+                });
+
+            let selected_text = PERIODS
+                .iter()
+                .find(|(minutes, _)| Some(*minutes) == self.period_minutes)
+                .map(|(_, name)| *name)
+                .unwrap_or("All time");
+            egui::ComboBox::from_id_salt("journal_period")
+                .selected_text(format!("🕒 {}", selected_text))
+                .show_ui(ui, |ui| {
+                    changed |= ui
+                        .selectable_value(&mut self.period_minutes, None, "All time")
+                        .changed();
+                    for (minutes, name) in PERIODS {
+                        changed |= ui
+                            .selectable_value(&mut self.period_minutes, Some(minutes), name)
+                            .changed();
+                    }
+                });
+
+            if ui
+                .selectable_label(self.by_roster_filter, "🎭")
+                .on_hover_text("Only packets from nodes shown in the roster")
+                .clicked()
+            {
+                self.by_roster_filter = !self.by_roster_filter;
+                changed = true;
+            }
+
+            if self.is_active() && ui.button("✖").on_hover_text("Reset filter").clicked() {
+                *self = Default::default();
+                changed = true;
+            }
+        });
+        self.node_search.update_filter(self.search.as_str());
+        changed
+    }
+}
+
+// Case insensitive matches of `needle` are highlighted
+fn highlighted(text: &str, needle: &str, color: Color32, font_id: egui::FontId) -> LayoutJob {
+    let mut job = LayoutJob::default();
+    let lowercase = text.to_lowercase();
+    // Lowercase could change lengths of non-ASCII text
+    let (haystack, needle) = if lowercase.len() == text.len() {
+        (lowercase.as_str(), needle.to_lowercase())
+    } else {
+        (text, needle.to_string())
+    };
+    let regular = TextFormat {
+        font_id: font_id.clone(),
+        ..Default::default()
+    };
+    let marked = TextFormat {
+        font_id,
+        background: color,
+        ..Default::default()
+    };
+
+    let mut position = 0;
+    if !needle.is_empty() {
+        for (start, _) in haystack.match_indices(needle.as_str()) {
+            if start < position {
+                continue;
+            }
+            job.append(&text[position..start], 0.0, regular.clone());
+            job.append(&text[start..start + needle.len()], 0.0, marked.clone());
+            position = start + needle.len();
+        }
+    }
+    job.append(&text[position..], 0.0, regular);
+    job
+}
+
 #[derive(Default, serde::Deserialize, serde::Serialize)]
 pub struct JournalPanel {
     show_limit: usize,
+    #[serde(default)]
+    filter: JournalFilter,
     #[serde(skip)]
     journal_rows_height: Vec<Option<f32>>,
     #[serde(skip)]
     scroll_info: Option<ScrollInfo>,
+    // Index of the entry shown with the packet inspector
+    #[serde(skip)]
+    inspected: Option<usize>,
+    // Entries arrived while journal is scrolled away from the latest
+    #[serde(skip)]
+    unseen: usize,
+    #[serde(skip)]
+    jump_to_latest: bool,
 }
 
 impl JournalPanel {
@@ -34,34 +262,51 @@ impl JournalPanel {
             show_limit: SHOW_LIMIT_BASE,
             journal_rows_height: Vec::new(),
             scroll_info: None,
+            inspected: None,
+            filter: Default::default(),
+            unseen: 0,
+            jump_to_latest: false,
         }
     }
 
+    // `previous` is the older entry shown before, if any
     fn show_journal_entry(
-        &self,
+        &mut self,
         ui: &mut egui::Ui,
-        journal: &Vec<JournalData>,
+        entry: &JournalData,
         journal_index: usize,
-        node_filter: &mut NodeFilter,
-    ) -> Result<f32, ()> {
-        if journal_index >= journal.len() {
-            return Err(());
-        }
-        let entry = &journal[journal_index];
-        let previous_date = (journal_index > 0)
-            .then(|| {
-                journal
-                    .get(journal_index - 1)
-                    .map(|v| v.timestamp.date_naive())
-            })
-            .flatten()
+        previous: Option<&JournalData>,
+        is_newest: bool,
+        search: SearchState<'_>,
+    ) -> f32 {
+        let SearchState {
+            highlight,
+            node_filter,
+            nodebook,
+        } = search;
+        let previous_date = previous
+            .map(|v| v.timestamp.date_naive())
             .unwrap_or_default();
+        let highlight_color = ui.visuals().selection.bg_fill;
+
+        // Alias from the NodeBook is shown instead of node id
+        let node_label = |node_id: NodeId, matched: bool| {
+            let text = RichText::new(
+                nodebook
+                    .alias(&node_id)
+                    .map(|alias| format!("{} ({})", alias, node_id))
+                    .unwrap_or_else(|| node_id.to_string()),
+            );
+            if matched {
+                text.background_color(highlight_color)
+            } else {
+                text
+            }
+        };
 
         let mut header = |ui: &mut egui::Ui| {
             ui.vertical(|ui| {
-                if journal_index == journal.len() - 1
-                    || previous_date != entry.timestamp.date_naive()
-                {
+                if is_newest || previous_date != entry.timestamp.date_naive() {
                     ui.heading(entry.timestamp.format("%Y-%m-%d").to_string());
                 }
                 ui.horizontal(|ui| {
@@ -95,7 +340,7 @@ impl JournalPanel {
 
                     if entry.to != NodeId::broadcast() {
                         if ui
-                            .label(entry.from.to_string())
+                            .label(node_label(entry.from, highlight.from))
                             .on_hover_text("Sender Node ID\nclick to filter by sender")
                             .clicked()
                         {
@@ -103,7 +348,7 @@ impl JournalPanel {
                         };
                         ui.label("➡");
                         if ui
-                            .label(entry.to.to_string())
+                            .label(node_label(entry.to, highlight.to))
                             .on_hover_text("Recipient Node ID\nclick to filter by recipient")
                             .clicked()
                         {
@@ -111,7 +356,7 @@ impl JournalPanel {
                         }
                     } else {
                         if ui
-                            .label(entry.from.to_string())
+                            .label(node_label(entry.from, highlight.from))
                             .on_hover_text(
                                 "Sender Node ID for broadcast message\nclick to filter by sender",
                             )
@@ -181,7 +426,11 @@ impl JournalPanel {
             })
         };
 
-        let body = |ui: &mut egui::Ui| {
+        // Filter is borrowed by header
+        let mut category_clicked = None;
+        let is_inspected = self.inspected == Some(journal_index);
+        let mut inspect_clicked = false;
+        let mut body = |ui: &mut egui::Ui| {
             ui.horizontal(|ui| {
                 if entry.is_encrypted {
                     ui.add_sized([10.0, 10.0], Label::new("🔒"))
@@ -195,26 +444,62 @@ impl JournalPanel {
                 } else {
                     ui.add_sized([10.0, 10.0], Label::new(""));
                 }
+                if ui
+                    .selectable_label(is_inspected, "🔍")
+                    .on_hover_text("Inspect packet's fields")
+                    .clicked()
+                {
+                    inspect_clicked = true;
+                }
 
                 if entry.message_type != "TEXT_MESSAGE_APP" {
-                    ui.label(entry.message_type.as_str());
+                    let label = ui.label(entry.message_type.as_str());
+                    if let Some(category) = entry.port_category
+                        && label
+                            .on_hover_text(format!(
+                                "Category: {}\nclick to filter nodes by category",
+                                category
+                            ))
+                            .clicked()
+                    {
+                        category_clicked = Some(category);
+                    }
                 }
 
-                let text = entry.message_hint.as_str();
-                ui.add(Label::new(RichText::new(text).monospace()).wrap_mode(TextWrapMode::Wrap));
+                let text = highlighted(
+                    entry.message_hint.as_str(),
+                    highlight.text.as_str(),
+                    highlight_color,
+                    egui::TextStyle::Monospace.resolve(ui.style()),
+                );
+                ui.add(Label::new(text).wrap_mode(TextWrapMode::Wrap));
             })
         };
-        Ok(Frame::default()
+        let height = Frame::default()
             .show(ui, |ui| {
                 ui.set_width(ui.available_width());
                 ui.vertical(|ui| {
                     header(ui);
                     body(ui);
+                    if is_inspected {
+                        egui::Frame::group(ui.style()).show(ui, |ui| {
+                            ui.push_id(journal_index, |ui| {
+                                packet_inspector::ui(ui, &entry.raw, entry.is_decrypted);
+                            });
+                        });
+                    }
                 })
             })
             .response
             .rect
-            .height())
+            .height();
+        if let Some(category) = category_clicked {
+            node_filter.set_port_category(category);
+        }
+        if inspect_clicked {
+            self.inspected = (!is_inspected).then_some(journal_index);
+        }
+        height
     }
 
     pub fn ui(
@@ -222,6 +507,8 @@ impl JournalPanel {
         ui: &mut egui::Ui,
         journal: &Vec<JournalData>,
         node_filter: &mut NodeFilter,
+        nodebook: &NodeBook,
+        nodes: &HashMap<NodeId, NodeInfo>,
     ) {
         let mut scroll_area = ScrollArea::both().auto_shrink([false; 2]);
         let default_message_height = 20.0;
@@ -230,27 +517,69 @@ impl JournalPanel {
             self.journal_rows_height.resize(journal_length, None);
         }
 
-        if let Some(scroll_info) = self.scroll_info.take() {
+        let filter_changed = self.filter.ui(ui, journal);
+        let now = node_filter.before().unwrap_or_else(Utc::now);
+        // Indexes of shown entries, from the oldest
+        let visible = (0..journal_length)
+            .filter(|journal_index| {
+                let entry = &journal[*journal_index];
+                !self.filter.is_active()
+                    || self.filter.matches(
+                        entry,
+                        &self.filter.highlight(entry, nodes, nodebook),
+                        nodes,
+                        nodebook,
+                        node_filter,
+                        now,
+                    )
+            })
+            .collect::<Vec<_>>();
+        if self.filter.is_active() {
+            ui.label(format!("shown: {} of {}", visible.len(), journal_length));
+        }
+
+        // Offset is meaningless for other entries when filter is changed
+        if std::mem::take(&mut self.jump_to_latest) || filter_changed {
+            self.scroll_info = None;
+            self.unseen = 0;
+            scroll_area = scroll_area.vertical_scroll_offset(0.0);
+        } else if let Some(scroll_info) = self.scroll_info.take() {
             if journal_length == 0 || scroll_info.journal_length > journal_length {
                 return;
             }
 
-            let y_offset = scroll_info.y_offset
-                + self.journal_rows_height[scroll_info.journal_length..journal_length]
-                    .iter()
-                    .map(|h| h.unwrap_or(default_message_height))
-                    .sum::<f32>();
+            let appeared = visible
+                .iter()
+                .filter(|journal_index| **journal_index >= scroll_info.journal_length);
+            if scroll_info.y_offset <= STICKY_OFFSET {
+                // Follow the latest entries
+                if appeared.count() > 0 {
+                    scroll_area = scroll_area.vertical_scroll_offset(0.0);
+                }
+            } else {
+                // Keep in place entries user is looking at
+                let mut appeared_count = 0;
+                let y_offset = scroll_info.y_offset
+                    + appeared
+                        .map(|journal_index| {
+                            appeared_count += 1;
+                            self.journal_rows_height[*journal_index]
+                                .unwrap_or(default_message_height)
+                        })
+                        .sum::<f32>();
+                self.unseen += appeared_count;
 
-            scroll_area = scroll_area.vertical_scroll_offset(y_offset);
+                scroll_area = scroll_area.vertical_scroll_offset(y_offset);
+            }
         }
 
         let y_offset = scroll_area
             .show_viewport(ui, |ui, viewport| {
                 let mut y_offset = 0.0;
 
-                for journal_index in (0..journal_length).rev() {
+                for (position, journal_index) in visible.iter().copied().enumerate().rev() {
                     if y_offset > viewport.bottom() {
-                        ui.add_space(default_message_height * journal_index as f32);
+                        ui.add_space(default_message_height * (position + 1) as f32);
                         break;
                     }
 
@@ -259,17 +588,27 @@ impl JournalPanel {
                         let hypothetical_height = height_entry.unwrap_or(default_message_height);
 
                         if y_offset + hypothetical_height > viewport.top() {
-                            if let Ok(height) =
-                                self.show_journal_entry(ui, journal, journal_index, node_filter)
-                            {
-                                if self.journal_rows_height[journal_index] != Some(height) {
-                                    self.journal_rows_height[journal_index] = Some(height);
-                                }
-                                y_offset += height;
-                            } else {
-                                log::error!("Failed to show journal entry");
-                                break;
+                            let entry = &journal[journal_index];
+                            let previous = position
+                                .checked_sub(1)
+                                .map(|position| &journal[visible[position]]);
+                            let highlight = self.filter.highlight(entry, nodes, nodebook);
+                            let height = self.show_journal_entry(
+                                ui,
+                                entry,
+                                journal_index,
+                                previous,
+                                position + 1 == visible.len(),
+                                SearchState {
+                                    highlight: &highlight,
+                                    node_filter: &mut *node_filter,
+                                    nodebook,
+                                },
+                            );
+                            if self.journal_rows_height[journal_index] != Some(height) {
+                                self.journal_rows_height[journal_index] = Some(height);
                             }
+                            y_offset += height;
                         } else {
                             y_offset += hypothetical_height;
                             ui.add_space(hypothetical_height);
@@ -284,19 +623,27 @@ impl JournalPanel {
             .offset
             .y;
 
-        if y_offset != 0.0 {
-            self.scroll_info = Some(ScrollInfo {
-                y_offset,
-                journal_length,
-            });
+        self.scroll_info = Some(ScrollInfo {
+            y_offset,
+            journal_length,
+        });
+        if y_offset <= STICKY_OFFSET {
+            self.unseen = 0;
+        } else {
             Area::new(ui.id())
                 .anchor(Align2::RIGHT_TOP, [-15.0, 35.0])
                 .show(ui.ctx(), |ui| {
-                    if ui.button("⬆").clicked() {
-                        self.scroll_info = Some(ScrollInfo {
-                            y_offset: 0.0,
-                            journal_length,
-                        });
+                    let text = if self.unseen > 0 {
+                        format!("⬆ Latest ({} new)", self.unseen)
+                    } else {
+                        "⬆".to_string()
+                    };
+                    if ui
+                        .button(text)
+                        .on_hover_text("Jump to the latest entries")
+                        .clicked()
+                    {
+                        self.jump_to_latest = true;
                     }
                 });
         }
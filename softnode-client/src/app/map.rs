@@ -1,32 +1,234 @@
-use std::{collections::HashMap, fmt::Display};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
 
 use chrono::{DateTime, Utc};
 use egui::{Align2, Button, Color32, Context, FontId, Pos2, Rect, Vec2};
-use geo::{Distance, Haversine};
+use geo::{Bearing, BoundingRect, Distance, Haversine};
 use meshtastic_connect::keyring::node_id::NodeId;
 use walkers::{
-    HttpTiles, MapMemory,
+    HeaderValue, HttpOptions, HttpTiles, MapMemory, TileId, Tiles,
     extras::{LabeledSymbol, LabeledSymbolStyle, Place, Symbol},
     lon_lat,
-    sources::OpenStreetMap,
+    sources::{Attribution, OpenStreetMap},
 };
 
+use crate::app::telemetry_formatter::TelemetryFormatter;
+use crate::app::tile_cache::{self, TileCacheConfig};
 use crate::app::time_format::format_timediff;
 use crate::app::{
-    Panel, color_generator,
+    Panel,
+    color_generator::NodeColors,
     data::{GatewayInfo, NodeInfo, Position, TelemetryVariant},
     node_book::{IgnoreZone, NodeAnnotation, NodeBook, ZoneId},
 };
 use crate::app::{node_filter::NodeFilter, roster};
 
+#[derive(Debug, Default, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct CustomTiles {
+    // Url with `{z}`, `{x}` and `{y}` placeholders
+    url_template: String,
+    attribution: String,
+}
+
+impl CustomTiles {
+    fn is_valid(&self) -> bool {
+        ["{z}", "{x}", "{y}"]
+            .iter()
+            .all(|placeholder| self.url_template.contains(placeholder))
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum TileSource {
+    // OpenStreetMap in light theme, CARTO Dark in dark theme
+    #[default]
+    Themed,
+    OpenStreetMap,
+    // CARTO's dark basemap, based on OpenStreetMap data
+    CartoDark,
+    Custom(CustomTiles),
+}
+
+impl Display for TileSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TileSource::Themed => write!(f, "By theme"),
+            TileSource::OpenStreetMap => write!(f, "OpenStreetMap"),
+            TileSource::CartoDark => write!(f, "CARTO Dark"),
+            TileSource::Custom(_) => write!(f, "Custom"),
+        }
+    }
+}
+
+// Tile source with `{z}/{x}/{y}` url template
+struct TemplateTiles {
+    url_template: String,
+    attribution: &'static str,
+}
+
+impl walkers::sources::TileSource for TemplateTiles {
+    fn tile_url(&self, tile_id: TileId) -> String {
+        self.url_template
+            .replace("{z}", &tile_id.zoom.to_string())
+            .replace("{x}", &tile_id.x.to_string())
+            .replace("{y}", &tile_id.y.to_string())
+    }
+
+    fn attribution(&self) -> Attribution {
+        Attribution {
+            text: self.attribution,
+            url: "",
+            logo_light: None,
+            logo_dark: None,
+        }
+    }
+}
+
+// walkers wants static attribution: each distinct text is leaked once
+fn static_attribution(attributions: &mut HashSet<&'static str>, text: &str) -> &'static str {
+    if let Some(attribution) = attributions.get(text) {
+        return attribution;
+    }
+    let attribution: &'static str = Box::leak(text.into());
+    attributions.insert(attribution);
+    attribution
+}
+
+fn build_tiles(
+    tile_source: &TileSource,
+    dark_mode: bool,
+    user_agent: &str,
+    tile_cache: &TileCacheConfig,
+    attributions: &mut HashSet<&'static str>,
+    egui_ctx: Context,
+) -> HttpTiles {
+    let options = HttpOptions {
+        user_agent: (!user_agent.is_empty())
+            .then(|| HeaderValue::from_str(user_agent).ok())
+            .flatten(),
+        ..Default::default()
+    };
+    #[cfg(not(target_arch = "wasm32"))]
+    let options = HttpOptions {
+        cache: tile_cache.path(),
+        ..options
+    };
+    #[cfg(target_arch = "wasm32")]
+    let _ = tile_cache;
+
+    let tile_source = match tile_source {
+        TileSource::Themed if dark_mode => &TileSource::CartoDark,
+        TileSource::Themed => &TileSource::OpenStreetMap,
+        tile_source => tile_source,
+    };
+    match tile_source {
+        TileSource::CartoDark => HttpTiles::with_options(
+            TemplateTiles {
+                url_template: "https://basemaps.cartocdn.com/dark_all/{z}/{x}/{y}.png".into(),
+                attribution: "© OpenStreetMap contributors © CARTO",
+            },
+            options,
+            egui_ctx,
+        ),
+        TileSource::Custom(custom) if custom.is_valid() => HttpTiles::with_options(
+            TemplateTiles {
+                url_template: custom.url_template.clone(),
+                attribution: static_attribution(attributions, &custom.attribution),
+            },
+            options,
+            egui_ctx,
+        ),
+        // Not filled custom template falls back to default tiles
+        TileSource::Themed | TileSource::OpenStreetMap | TileSource::Custom(_) => {
+            HttpTiles::with_options(OpenStreetMap, options, egui_ctx)
+        }
+    }
+}
+
+// Upper limit of tiles requested by one prefetch
+const PREFETCH_TILES_LIMIT: usize = 256;
+
 pub struct MapContext {
     tiles: HttpTiles,
+    tile_source: TileSource,
+    dark_mode: bool,
+    user_agent: String,
+    tile_cache: TileCacheConfig,
+    // Attributions of custom tiles, leaked for walkers
+    attributions: HashSet<&'static str>,
+    egui_ctx: Context,
 }
 
 impl MapContext {
-    pub fn new(egui_ctx: Context) -> Self {
+    pub fn new(egui_ctx: Context, tile_cache: &TileCacheConfig) -> Self {
+        #[cfg(not(target_arch = "wasm32"))]
+        tile_cache::evict(tile_cache);
+        let dark_mode = egui_ctx.style().visuals.dark_mode;
+        let mut attributions = HashSet::new();
         Self {
-            tiles: HttpTiles::new(OpenStreetMap, egui_ctx),
+            tiles: build_tiles(
+                &TileSource::default(),
+                dark_mode,
+                "",
+                tile_cache,
+                &mut attributions,
+                egui_ctx.clone(),
+            ),
+            tile_source: TileSource::default(),
+            dark_mode,
+            user_agent: String::new(),
+            tile_cache: tile_cache.clone(),
+            attributions,
+            egui_ctx,
+        }
+    }
+
+    // Recreate tiles when source, theme, user-agent or cache settings are changed
+    fn update_source(
+        &mut self,
+        tile_source: &TileSource,
+        dark_mode: bool,
+        user_agent: &str,
+        tile_cache: &TileCacheConfig,
+    ) {
+        if self.tile_source != *tile_source
+            || (*tile_source == TileSource::Themed && self.dark_mode != dark_mode)
+            || self.user_agent != user_agent
+            || self.tile_cache != *tile_cache
+        {
+            #[cfg(not(target_arch = "wasm32"))]
+            if self.tile_cache != *tile_cache {
+                tile_cache::evict(tile_cache);
+            }
+            self.tiles = build_tiles(
+                tile_source,
+                dark_mode,
+                user_agent,
+                tile_cache,
+                &mut self.attributions,
+                self.egui_ctx.clone(),
+            );
+            self.tile_source = tile_source.clone();
+            self.dark_mode = dark_mode;
+            self.user_agent = user_agent.to_string();
+            self.tile_cache = tile_cache.clone();
+        }
+    }
+
+    // Request tiles of the visible area for current and next zoom levels,
+    // so they are stored in the cache (disk or browser's one)
+    fn prefetch(&mut self, bbox: [walkers::Position; 2], zoom: f64) {
+        let zoom = zoom.round().clamp(0.0, 19.0) as u8;
+        let tiles = (zoom..=(zoom + 1).min(19))
+            .flat_map(|zoom| tile_cache::tiles_in_bbox(bbox, zoom))
+            .take(PREFETCH_TILES_LIMIT)
+            .collect::<Vec<_>>();
+
+        log::info!("Prefetch {} tiles", tiles.len());
+        for tile_id in tiles {
+            let _ = self.tiles.at(tile_id);
         }
     }
 }
@@ -46,11 +248,13 @@ impl NewZoneInfo {
     }
 }
 
-#[derive(Debug, serde::Deserialize, serde::Serialize, PartialEq, Clone, Copy)]
+#[derive(Debug, serde::Deserialize, serde::Serialize, PartialEq, Clone)]
 enum MemorySelection {
     Node(NodeId),
     Zone(ZoneId),
     NewZone(NewZoneInfo),
+    // Ruler: points clicked on the map
+    Measure(Vec<walkers::Position>),
 }
 
 #[derive(Debug, serde::Deserialize, serde::Serialize, PartialEq, Clone)]
@@ -68,6 +272,25 @@ impl Default for TracksConfig {
     }
 }
 
+// Segments to positions reached too fast are GPS glitches
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+struct TrackGlitches {
+    enabled: bool,
+    max_speed_kmh: f64,
+    // Glitch segments are not drawn instead of drawing dashed
+    hide: bool,
+}
+
+impl Default for TrackGlitches {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_speed_kmh: 300.0,
+            hide: false,
+        }
+    }
+}
+
 #[derive(Debug, Default, serde::Deserialize, serde::Serialize)]
 pub struct Memory {
     gateway_connections: GatewayConnections,
@@ -76,13 +299,95 @@ pub struct Memory {
     display_tracks: DisplayTracks,
     hide_labels: bool,
     selected_tracks: HashMap<NodeId, TracksConfig>,
+    #[serde(default)]
+    track_glitches: TrackGlitches,
     bounding_box: Option<[walkers::Position; 2]>,
+    // Applied to `MapMemory` after the map is drawn
+    #[serde(skip)]
+    zoom_request: Option<ZoomRequest>,
+    // Last fit to nodes is failed: no node with known position
+    #[serde(skip)]
+    fit_nodes_failed: bool,
 }
 
+#[derive(Debug, Clone, Copy)]
+enum ZoomRequest {
+    // Show area between two points
+    Fit([walkers::Position; 2]),
+    // Show all filtered nodes with known positions
+    FitNodes,
+}
+
+// Zoom for the case when only one node is fitted
+const SINGLE_NODE_ZOOM: f64 = 15.0;
+
 #[derive(Default, serde::Deserialize, serde::Serialize)]
 pub struct MapPanel {
     map_memory: MapMemory,
     memory: Memory,
+    #[serde(default)]
+    tile_source: TileSource,
+    // Custom tiles settings, applied to `tile_source` by user
+    #[serde(default)]
+    custom_tiles: CustomTiles,
+    // User-Agent for tile requests, to respect tile servers' usage policies
+    #[serde(default)]
+    tile_user_agent: String,
+    #[serde(skip)]
+    tile_user_agent_edit: Option<String>,
+    #[serde(skip)]
+    replay: Replay,
+    #[serde(default)]
+    node_colors: NodeColors,
+    #[serde(default)]
+    home: MapHome,
+    // Home view is applied once per app start
+    #[serde(skip)]
+    home_applied: bool,
+}
+
+// Initial view of the map
+#[derive(serde::Deserialize, serde::Serialize)]
+struct MapHome {
+    center: walkers::Position,
+    zoom: f64,
+    // Fit the visible nodes instead of the center on start
+    fit_nodes: bool,
+}
+
+impl Default for MapHome {
+    fn default() -> Self {
+        Self {
+            center: lon_lat(17.03664, 51.09916),
+            zoom: 12.0,
+            fit_nodes: false,
+        }
+    }
+}
+
+// Bounding box of filtered nodes with known positions
+fn nodes_bounds(
+    node_filter: &NodeFilter,
+    nodes: &HashMap<NodeId, NodeInfo>,
+    nodebook: &NodeBook,
+) -> Option<[walkers::Position; 2]> {
+    let positions = node_filter
+        .seeker_for(nodes, nodebook)
+        .filter_map(|node_info| fix_or_position(nodebook, node_info.node_id, &node_info.position))
+        .collect::<Vec<_>>();
+
+    geo::MultiPoint::from(positions)
+        .bounding_rect()
+        .map(|bounds| [bounds.min().into(), bounds.max().into()])
+}
+
+// Replay of the mesh's history: time is selected by `NodeFilter::before`
+#[derive(Default)]
+struct Replay {
+    enabled: bool,
+    playing: bool,
+    // Nodes' data received before the timestamp
+    snapshot: Option<(DateTime<Utc>, HashMap<NodeId, NodeInfo>)>,
 }
 
 pub struct MapPointsPlugin<'a> {
@@ -90,7 +395,8 @@ pub struct MapPointsPlugin<'a> {
     nodes: &'a HashMap<NodeId, NodeInfo>,
     memory: &'a mut Memory,
     nodebook: &'a mut NodeBook,
-    color_generator: color_generator::ColorGenerator,
+    node_colors: &'a NodeColors,
+    telemetry_formatter: &'a TelemetryFormatter,
 }
 
 impl<'a> MapPointsPlugin<'a> {
@@ -99,18 +405,34 @@ impl<'a> MapPointsPlugin<'a> {
         nodes: &'a HashMap<NodeId, NodeInfo>,
         memory: &'a mut Memory,
         nodebook: &'a mut NodeBook,
+        node_colors: &'a NodeColors,
+        telemetry_formatter: &'a TelemetryFormatter,
     ) -> Self {
         Self {
             node_filter,
             nodes,
             memory,
             nodebook,
-            color_generator: Default::default(),
+            node_colors,
+            telemetry_formatter,
         }
     }
 }
 
-fn fix_or_position(
+// Alias from the NodeBook or announced short name
+fn short_name(nodebook: &NodeBook, node_info: &NodeInfo) -> Option<String> {
+    nodebook
+        .alias(&node_info.node_id)
+        .map(|alias| alias.to_string())
+        .or_else(|| {
+            node_info
+                .extended_info_history
+                .last()
+                .map(|extended_info| extended_info.short_name.clone())
+        })
+}
+
+pub fn fix_or_position(
     nodebook: &NodeBook,
     node_id: NodeId,
     positions: &Vec<Position>,
@@ -126,7 +448,7 @@ fn fix_or_position(
         })
 }
 
-fn get_telemetry_label(node_info: &NodeInfo) -> String {
+fn get_telemetry_label(node_info: &NodeInfo, telemetry_formatter: &TelemetryFormatter) -> String {
     [
         TelemetryVariant::EnvironmentTemperature,
         TelemetryVariant::Humidity,
@@ -143,19 +465,7 @@ fn get_telemetry_label(node_info: &NodeInfo) -> String {
                 list_or_none
                     .values
                     .last()
-                    .map(|value| match variant {
-                        TelemetryVariant::EnvironmentTemperature => {
-                            Some(format!("{:.2} °C", value.value))
-                        }
-                        TelemetryVariant::Humidity => Some(format!("{:.2}%", value.value)),
-                        TelemetryVariant::Lux => Some(format!("{:.2} lx", value.value)),
-                        TelemetryVariant::BarometricPressure => {
-                            Some(format!("{:.2} hPa", value.value))
-                        }
-                        TelemetryVariant::Radiation => Some(format!("{:.2} μSv/h", value.value)),
-                        _ => None,
-                    })
-                    .flatten()
+                    .map(|value| telemetry_formatter.format(value.value, *variant))
             })
             .flatten()
     })
@@ -192,7 +502,7 @@ impl<'a> MapPointsPlugin<'a> {
             .flatten()
         {
             let assumed_position = if self.memory.display_assumed_positions {
-                gateway_node_info.assumed_position
+                gateway_node_info.assumed_position.map(|v| v.position)
             } else {
                 None
             };
@@ -204,7 +514,7 @@ impl<'a> MapPointsPlugin<'a> {
                     projector.project(other_position).to_pos2(),
                     current_datetime,
                     gateway_info,
-                    self.color_generator.next_color(),
+                    self.node_colors.get(gateway_node_info.node_id),
                 );
             } else {
                 not_on_map_nodes.push(node_id);
@@ -223,13 +533,12 @@ impl<'a> MapPointsPlugin<'a> {
     ) -> Vec<NodeId> {
         let mut not_on_map_nodes = Vec::new();
         for (node_id, gateway_info) in gateway_node_info.gateway_for.iter() {
-            let connection_color = self.color_generator.next_color();
+            let connection_color = self.node_colors.get(*node_id);
             if let Some(node_info) = self.nodes.get(node_id) {
-                if !self.node_filter.matches(
-                    &node_info,
-                    self.nodes,
-                    self.nodebook.node_get(&node_info.node_id),
-                ) {
+                if !self
+                    .node_filter
+                    .matches(node_info, self.nodes, self.nodebook)
+                {
                     not_on_map_nodes.push(*node_id);
                     continue;
                 }
@@ -237,7 +546,7 @@ impl<'a> MapPointsPlugin<'a> {
                     fix_or_position(&self.nodebook, node_info.node_id, &node_info.position);
 
                 let assumed_position = if self.memory.display_assumed_positions {
-                    node_info.assumed_position
+                    node_info.assumed_position.map(|v| v.position)
                 } else {
                     None
                 };
@@ -279,7 +588,7 @@ impl<'a> MapPointsPlugin<'a> {
                 &other_node_info.position,
             );
             let assumed_position = if self.memory.display_assumed_positions {
-                other_node_info.assumed_position
+                other_node_info.assumed_position.map(|v| v.position)
             } else {
                 None
             };
@@ -337,6 +646,12 @@ impl<'a> MapPointsPlugin<'a> {
                         .map(|v| format!("{}\n{}", label, v))
                         .unwrap_or(label);
 
+                    let label = other_node_info
+                        .position_fixed_at()
+                        .and_then(|fixed_at| format_timediff(fixed_at, current_datetime))
+                        .map(|v| format!("{}\nFixed {} ago", label, v))
+                        .unwrap_or(label);
+
                     let label = if let Some(some_mesh_position) = selected_node_position {
                         let title = if mesh_position.is_none() {
                             "Distance?"
@@ -353,14 +668,9 @@ impl<'a> MapPointsPlugin<'a> {
                         label
                     };
 
-                    let label = other_node_info
-                        .extended_info_history
-                        .last()
-                        .map(|extended_info| {
-                            format!(
-                                "{}\n{}\n{}",
-                                label, extended_info.short_name, other_node_info.node_id
-                            )
+                    let label = short_name(self.nodebook, other_node_info)
+                        .map(|short_name| {
+                            format!("{}\n{}\n{}", label, short_name, other_node_info.node_id)
                         })
                         .unwrap_or(format!("{}\n{}", label, other_node_info.node_id));
 
@@ -411,12 +721,12 @@ impl<'a> MapPointsPlugin<'a> {
         let is_gateway = !node_info.gateway_for.is_empty();
         let display_gatewayed_connections =
             is_gateway && self.memory.gateway_connections == GatewayConnections::Incoming;
-        let current_datetime = chrono::Utc::now();
+        let current_datetime = self.node_filter.before().unwrap_or_else(chrono::Utc::now);
         let mesh_position = fix_or_position(&self.nodebook, node_info.node_id, &node_info.position);
         let assumed_position = self
             .memory
             .display_assumed_positions
-            .then(|| node_info.assumed_position)
+            .then(|| node_info.assumed_position.map(|v| v.position))
             .flatten();
         let position = mesh_position.unwrap_or(
             assumed_position
@@ -462,11 +772,16 @@ impl<'a> MapPointsPlugin<'a> {
         let label = if self.memory.hide_labels {
             String::new()
         } else {
-            let label = if let Some(extended_info) = node_info.extended_info_history.last() {
-                format!("{}\n{}", extended_info.short_name, node_info.node_id)
+            let label = if let Some(short_name) = short_name(self.nodebook, node_info) {
+                format!("{}\n{}", short_name, node_info.node_id)
             } else {
                 node_info.node_id.to_string()
             };
+            let label = if self.nodebook.is_favorite(&node_info.node_id) {
+                format!("★ {}", label)
+            } else {
+                label
+            };
 
             let label = if display_gatewayed_connections {
                 let timestamp = node_info
@@ -495,6 +810,12 @@ impl<'a> MapPointsPlugin<'a> {
                     .unwrap_or(label)
             };
 
+            let label = node_info
+                .position_fixed_at()
+                .and_then(|fixed_at| format_timediff(fixed_at, current_datetime))
+                .map(|v| format!("Fixed {} ago\n{}", v, label))
+                .unwrap_or(label);
+
             let label = if display_gatewayed_connections {
                 if !not_landed_nodes.is_empty() {
                     format!(
@@ -535,9 +856,11 @@ impl<'a> MapPointsPlugin<'a> {
                     .node(node_info.node_id)
                     .and_modify(|v| {
                         v.position = Some(position);
+                        v.updated = Some(Utc::now());
                     })
                     .or_insert(NodeAnnotation {
                         position: Some(position),
+                        updated: Some(Utc::now()),
                         ..Default::default()
                     });
             };
@@ -565,81 +888,189 @@ impl<'a> MapPointsPlugin<'a> {
         projector: &walkers::Projector,
         clicked_pos: Option<Pos2>,
     ) {
+        let mut visible_nodes = Vec::new();
         for node_info in self.node_filter.seeker_for(self.nodes, self.nodebook) {
-            let is_gateway = !node_info.gateway_for.is_empty();
             let mesh_position =
                 fix_or_position(&self.nodebook, node_info.node_id, &node_info.position);
             let assumed_position = if self.memory.display_assumed_positions {
-                node_info.assumed_position
+                node_info.assumed_position.map(|v| v.position)
             } else {
                 None
             };
 
             if let Some(position) = mesh_position.or(assumed_position) {
-                let symbol_size = circle_radius(node_info.gateway_for.len());
-                let onscreen_position = projector.project(position).to_pos2();
-                if let Some(clicked_pos) = clicked_pos {
-                    if clicked_pos.distance(onscreen_position)
-                        < symbol_size * Self::SYMBOL_SIZE_SELECT_FACTOR
-                    {
-                        self.memory.selection = Some(MemorySelection::Node(node_info.node_id));
-                        ui.ctx().request_repaint();
-                        return;
-                    }
+                visible_nodes.push((node_info.node_id, position, mesh_position.is_none()));
+            }
+        }
+        // Seeker borrows the NodeBook, nodes are taken again to draw them mutably
+        let nodes = self.nodes;
+        let visible_nodes = visible_nodes
+            .into_iter()
+            .filter_map(|(node_id, position, is_assumed)| {
+                nodes
+                    .get(&node_id)
+                    .map(|node_info| (node_info, position, is_assumed))
+            })
+            .collect::<Vec<_>>();
+
+        if zoom >= CLUSTER_ZOOM_THRESHOLD {
+            for node in visible_nodes {
+                if self.draw_regular_node(ui, zoom, projector, clicked_pos, node) {
+                    return;
                 }
+            }
+            return;
+        }
 
-                let label = if self.memory.hide_labels {
-                    String::new()
-                } else {
-                    let label = if is_gateway && zoom > 5.0 || zoom > 10.0 {
-                        if let Some(extended_info) = node_info.extended_info_history.last() {
-                            format!("{}\n{}", extended_info.short_name, node_info.node_id)
-                        } else {
-                            node_info.node_id.to_string()
-                        }
-                    } else {
-                        String::new()
-                    };
+        // Group nodes, which are close on the screen
+        let mut clusters: Vec<(Pos2, Vec<VisibleNode<'_>>)> = Vec::new();
+        for (node_info, position, is_assumed) in visible_nodes {
+            let onscreen_position = projector.project(position).to_pos2();
+            if let Some((_, members)) = clusters
+                .iter_mut()
+                .find(|(center, _)| center.distance(onscreen_position) < CLUSTER_DISTANCE)
+            {
+                members.push((node_info, position, is_assumed));
+            } else {
+                clusters.push((onscreen_position, vec![(node_info, position, is_assumed)]));
+            }
+        }
 
-                    let label = if zoom > 12.0 {
-                        let telemetry_label = get_telemetry_label(&node_info);
-                        let label = if telemetry_label.is_empty() {
-                            label
-                        } else {
-                            format!("{}\n{}", telemetry_label, label)
-                        };
-                        label
-                    } else {
-                        label
-                    };
-                    label
-                };
+        for (_, members) in clusters {
+            if members.len() == 1 {
+                if self.draw_regular_node(ui, zoom, projector, clicked_pos, members[0]) {
+                    return;
+                }
+                continue;
+            }
 
-                let symbol_background = if mesh_position.is_none() {
-                    Color32::LIGHT_BLUE.gamma_multiply(0.6)
+            let bounds = geo::MultiPoint::from(
+                members
+                    .iter()
+                    .map(|(_, position, _)| *position)
+                    .collect::<Vec<_>>(),
+            )
+            .bounding_rect();
+            let Some(bounds) = bounds else {
+                continue;
+            };
+            let position = bounds.center().into();
+            let symbol_size = circle_radius(members.len());
+            let onscreen_position = projector.project(position).to_pos2();
+
+            if let Some(clicked_pos) = clicked_pos
+                && clicked_pos.distance(onscreen_position)
+                    < symbol_size * Self::SYMBOL_SIZE_SELECT_FACTOR
+            {
+                self.memory.zoom_request =
+                    Some(ZoomRequest::Fit([bounds.min().into(), bounds.max().into()]));
+                ui.ctx().request_repaint();
+                return;
+            }
+
+            LabeledSymbol {
+                position,
+                label: String::new(),
+                symbol: Some(Symbol::Circle(members.len().to_string())),
+                style: LabeledSymbolStyle {
+                    label_corner_radius: 10.0,
+                    symbol_size,
+                    symbol_background: Color32::YELLOW.gamma_multiply(0.6),
+                    ..Default::default()
+                },
+            }
+            .draw(ui, projector);
+        }
+    }
+
+    // Draw one node, returns true if node is selected by click
+    fn draw_regular_node(
+        self: &mut Box<Self>,
+        ui: &mut egui::Ui,
+        zoom: f64,
+        projector: &walkers::Projector,
+        clicked_pos: Option<Pos2>,
+        (node_info, position, is_assumed): VisibleNode<'_>,
+    ) -> bool {
+        let is_gateway = !node_info.gateway_for.is_empty();
+        let symbol_size = circle_radius(node_info.gateway_for.len());
+        let onscreen_position = projector.project(position).to_pos2();
+        if let Some(clicked_pos) = clicked_pos
+            && clicked_pos.distance(onscreen_position)
+                < symbol_size * Self::SYMBOL_SIZE_SELECT_FACTOR
+        {
+            self.memory.selection = Some(MemorySelection::Node(node_info.node_id));
+            ui.ctx().request_repaint();
+            return true;
+        }
+
+        let is_favorite = self.nodebook.is_favorite(&node_info.node_id);
+        let label = if self.memory.hide_labels {
+            String::new()
+        } else {
+            // Favorites are labeled at any zoom
+            let label = if is_favorite || is_gateway && zoom > 5.0 || zoom > 10.0 {
+                let label = if let Some(short_name) = short_name(self.nodebook, node_info) {
+                    format!("{}\n{}", short_name, node_info.node_id)
                 } else {
-                    Color32::WHITE.gamma_multiply(0.6)
+                    node_info.node_id.to_string()
                 };
-                let symbol = if node_info.gateway_for.is_empty() {
-                    Some(Symbol::TwoCorners("👤".into()))
+                if is_favorite {
+                    format!("★ {}", label)
                 } else {
-                    Some(Symbol::Circle("👤".into()))
-                };
+                    label
+                }
+            } else {
+                String::new()
+            };
 
-                LabeledSymbol {
-                    position,
-                    label,
-                    symbol,
-                    style: LabeledSymbolStyle {
-                        label_corner_radius: 10.0,
-                        symbol_size,
-                        symbol_background,
-                        ..Default::default()
-                    },
+            if zoom > 12.0 {
+                let telemetry_label = get_telemetry_label(node_info, self.telemetry_formatter);
+                if telemetry_label.is_empty() {
+                    label
+                } else {
+                    format!("{}\n{}", telemetry_label, label)
                 }
-                .draw(ui, projector);
+            } else {
+                label
             }
+        };
+
+        if is_assumed && let Some(assumed) = &node_info.assumed_position {
+            // Area where the node could be
+            let radius = assumed.radius_meters as f32 * projector.scale_pixel_per_meter(position);
+            ui.painter().circle(
+                onscreen_position,
+                radius,
+                Color32::LIGHT_BLUE.gamma_multiply(0.1),
+                egui::Stroke::new(1.0, Color32::LIGHT_BLUE.gamma_multiply(0.4)),
+            );
+        }
+
+        let symbol_background = if is_assumed {
+            Color32::LIGHT_BLUE.gamma_multiply(0.6)
+        } else {
+            Color32::WHITE.gamma_multiply(0.6)
+        };
+        let symbol = if node_info.gateway_for.is_empty() {
+            Some(Symbol::TwoCorners("👤".into()))
+        } else {
+            Some(Symbol::Circle("👤".into()))
+        };
+
+        LabeledSymbol {
+            position,
+            label,
+            symbol,
+            style: LabeledSymbolStyle {
+                label_corner_radius: 10.0,
+                symbol_size,
+                symbol_background,
+                ..Default::default()
+            },
         }
+        .draw(ui, projector);
+        false
     }
 
     fn draw_tracks(self: &mut Box<Self>, ui: &mut egui::Ui, projector: &walkers::Projector) {
@@ -681,10 +1112,10 @@ impl<'a> MapPointsPlugin<'a> {
             let stroke = match self.memory.display_tracks {
                 DisplayTracks::All => {
                     drop_unprecise = true;
-                    if let Some(selection) = self.memory.selection {
-                        if MemorySelection::Node(node_info.node_id) == selection {
-                            drop_unprecise = false;
-                        }
+                    if let Some(selection) = &self.memory.selection
+                        && MemorySelection::Node(node_info.node_id) == *selection
+                    {
+                        drop_unprecise = false;
                     }
                     tracks_config.stroke
                 }
@@ -698,6 +1129,11 @@ impl<'a> MapPointsPlugin<'a> {
             };
 
             let total_segments = node_info.position.len() - 1;
+            let glitches = if self.memory.track_glitches.enabled {
+                node_info.position_glitches(self.memory.track_glitches.max_speed_kmh)
+            } else {
+                vec![false; node_info.position.len()]
+            };
 
             for i in 0..total_segments {
                 if drop_unprecise {
@@ -719,7 +1155,15 @@ impl<'a> MapPointsPlugin<'a> {
                     ))
                     .to_pos2();
 
-                ui.painter().line_segment([p1, p2], stroke);
+                if glitches[i] || glitches[i + 1] {
+                    if !self.memory.track_glitches.hide {
+                        let stroke = egui::Stroke::new(stroke.width, Color32::GRAY);
+                        ui.painter()
+                            .extend(egui::Shape::dashed_line(&[p1, p2], stroke, 6.0, 6.0));
+                    }
+                } else {
+                    ui.painter().line_segment([p1, p2], stroke);
+                }
             }
         }
     }
@@ -788,23 +1232,107 @@ impl<'a> MapPointsPlugin<'a> {
                         Align2::CENTER_CENTER,
                         zone.name.clone(),
                         FontId::proportional(16.0),
-                        Color32::WHITE,
+                        // Tiles follow the theme, so does text over them
+                        ui.visuals().strong_text_color(),
                     );
                 }
             }
         }
     }
 
+    fn draw_measure(self: &mut Box<Self>, ui: &mut egui::Ui, projector: &walkers::Projector) {
+        let Some(MemorySelection::Measure(points)) = &self.memory.selection else {
+            return;
+        };
+        let stroke = egui::Stroke::new(2.0, Color32::ORANGE);
+        let onscreen_points = points
+            .iter()
+            .map(|point| projector.project(*point).to_pos2())
+            .collect::<Vec<_>>();
+
+        ui.painter()
+            .add(egui::Shape::line(onscreen_points.clone(), stroke));
+        for onscreen_point in &onscreen_points {
+            ui.painter()
+                .circle(*onscreen_point, 4.0, Color32::WHITE, stroke);
+        }
+
+        let mut total_distance = 0.0;
+        for (segment, onscreen_segment) in points.windows(2).zip(onscreen_points.windows(2)) {
+            let distance = Haversine.distance(segment[0], segment[1]);
+            let bearing = Haversine.bearing(segment[0], segment[1]);
+            total_distance += distance;
+            draw_text_label(
+                ui,
+                onscreen_segment[0] + (onscreen_segment[1] - onscreen_segment[0]) / 2.0,
+                format!("{}\n{:.1}°", format_distance(distance), bearing),
+            );
+        }
+
+        if let Some(last) = onscreen_points.last()
+            && points.len() > 1
+        {
+            draw_text_label(
+                ui,
+                *last + Vec2::new(0.0, -20.0),
+                format!("Total: {}", format_distance(total_distance)),
+            );
+        }
+    }
+
     fn buttons(
         self: &mut Box<Self>,
-        _ui: &mut egui::Ui,
-        _response: &egui::Response,
+        ui: &mut egui::Ui,
+        response: &egui::Response,
         _projector: &walkers::Projector,
     ) {
+        let measuring = matches!(self.memory.selection, Some(MemorySelection::Measure(_)));
+        let button_position = response.rect.right_top() + Vec2::new(-25.0, 25.0);
+        if ui
+            .put(
+                Rect::from_center_size(button_position, Vec2::new(30., 30.)),
+                Button::selectable(measuring, "📏"),
+            )
+            .on_hover_text("Measure distance\nclick points on the map, Escape to clear")
+            .clicked()
+        {
+            self.memory.selection = if measuring {
+                None
+            } else {
+                Some(MemorySelection::Measure(Vec::new()))
+            };
+        }
+    }
+}
+
+fn format_distance(distance: f64) -> String {
+    if distance > 1000.0 {
+        format!("{:.3} km", distance / 1000.0)
+    } else {
+        format!("{:.2} m", distance)
     }
 }
 
+fn draw_text_label(ui: &mut egui::Ui, position: Pos2, text: String) {
+    let galley = ui
+        .painter()
+        .layout_no_wrap(text, FontId::proportional(14.0), Color32::WHITE);
+    let rect = Align2::CENTER_CENTER.anchor_size(position, galley.size());
+    ui.painter()
+        .rect_filled(rect.expand(4.0), 4.0, Color32::BLACK.gamma_multiply(0.6));
+    ui.painter().galley(rect.min, galley, Color32::WHITE);
+}
+
 const ZONE_RADIUS_THRESHOLD: f32 = 100.0;
+// Below this zoom level close nodes are grouped into clusters
+const CLUSTER_ZOOM_THRESHOLD: f64 = 11.0;
+// Distance in pixels to join node into cluster
+const CLUSTER_DISTANCE: f32 = 40.0;
+// Duration of replaying the whole history
+const REPLAY_DURATION_SECONDS: f64 = 60.0;
+
+// Node to draw, its position and whether the position is assumed
+type VisibleNode<'a> = (&'a NodeInfo, walkers::Position, bool);
 
 impl<'a> walkers::Plugin for MapPointsPlugin<'a> {
     fn run(
@@ -819,6 +1347,16 @@ impl<'a> walkers::Plugin for MapPointsPlugin<'a> {
             projector.unproject(response.rect.min.to_vec2()),
         ]);
         let clicked_pos = response.clicked().then(|| response.hover_pos()).flatten();
+        let clicked_pos = if let Some(MemorySelection::Measure(points)) = &mut self.memory.selection
+        {
+            // Clicks are measure points, not node selection
+            if let Some(clicked_pos) = clicked_pos {
+                points.push(projector.unproject(clicked_pos.to_vec2()));
+            }
+            None
+        } else {
+            clicked_pos
+        };
         if clicked_pos.is_some() {
             self.memory.selection = None;
         } else {
@@ -829,19 +1367,12 @@ impl<'a> walkers::Plugin for MapPointsPlugin<'a> {
             });
         }
 
-        let selection = self
-            .memory
-            .selection
-            .map(|selection| {
-                if let MemorySelection::Node(selected_node_id) = selection {
-                    self.nodes
-                        .get(&selected_node_id)
-                        .map(|selected_node_info| selected_node_info)
-                } else {
-                    None
-                }
-            })
-            .flatten();
+        let selection = if let Some(MemorySelection::Node(selected_node_id)) = self.memory.selection
+        {
+            self.nodes.get(&selected_node_id)
+        } else {
+            None
+        };
 
         self.draw_tracks(ui, projector);
 
@@ -853,11 +1384,128 @@ impl<'a> walkers::Plugin for MapPointsPlugin<'a> {
             self.draw_regular(ui, map_memory.zoom(), projector, clicked_pos);
         }
 
+        self.draw_measure(ui, projector);
         self.buttons(ui, response, projector);
     }
 }
 
 impl MapPanel {
+    // Center and zoom the map so the bounds are visible
+    // Takes the map's memory only: the shown nodes could be borrowed from `self`
+    fn fit_bounds(map_memory: &mut MapMemory, rect: Rect, bounds: [walkers::Position; 2]) {
+        const TILE_SIZE: f64 = 256.0;
+        const MAX_ZOOM: f64 = 19.0;
+
+        let mercator_y = |position: walkers::Position| {
+            let lat = position.y().clamp(-85.0511, 85.0511).to_radians();
+            (1.0 - lat.tan().asinh() / std::f64::consts::PI) / 2.0
+        };
+        let width = ((bounds[0].x() - bounds[1].x()).abs() / 360.0).max(f64::EPSILON);
+        let height = (mercator_y(bounds[0]) - mercator_y(bounds[1]))
+            .abs()
+            .max(f64::EPSILON);
+        let zoom_x = (rect.width() as f64 / TILE_SIZE / width).log2();
+        let zoom_y = (rect.height() as f64 / TILE_SIZE / height).log2();
+        // Keep a small margin around the bounds
+        let zoom = (zoom_x.min(zoom_y) - 0.5).clamp(0.0, MAX_ZOOM);
+
+        let center = geo::Rect::new(bounds[0], bounds[1]).center().into();
+        map_memory.center_at(center);
+        if let Err(e) = map_memory.set_zoom(zoom) {
+            log::error!("Unable to set map zoom {}: {:?}", zoom, e);
+        }
+    }
+
+    fn replay_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        node_filter: &mut NodeFilter,
+        nodes: &HashMap<NodeId, NodeInfo>,
+    ) {
+        let time_range = nodes
+            .values()
+            .filter_map(|v| v.packet_statistics.first().zip(v.packet_statistics.last()))
+            .map(|(first, last)| (first.timestamp, last.timestamp))
+            .reduce(|(min, max), (first, last)| (min.min(first), max.max(last)));
+
+        egui::Area::new(ui.id().with("map_replay"))
+            .anchor(Align2::LEFT_BOTTOM, [10.0, -25.0])
+            .show(ui.ctx(), |ui| {
+                ui.horizontal(|ui| {
+                    if ui
+                        .selectable_label(self.replay.enabled, "⏱")
+                        .on_hover_text("Replay mesh's history")
+                        .clicked()
+                    {
+                        self.replay.enabled = !self.replay.enabled;
+                    }
+
+                    let Some((first, last)) = time_range.filter(|_| self.replay.enabled) else {
+                        self.replay.enabled = false;
+                        self.replay.playing = false;
+                        node_filter.set_before(None);
+                        return;
+                    };
+
+                    let mut before = node_filter.before().unwrap_or(first).clamp(first, last);
+                    if self.replay.playing {
+                        let range = (last - first).num_milliseconds() as f64;
+                        let dt = ui.input(|i| i.stable_dt) as f64;
+                        let step = (range * dt / REPLAY_DURATION_SECONDS).max(1.0);
+                        before = (before + chrono::Duration::milliseconds(step as i64)).min(last);
+                        if before == last {
+                            self.replay.playing = false;
+                        }
+                        ui.ctx()
+                            .request_repaint_after(std::time::Duration::from_millis(50));
+                    }
+
+                    let play_label = if self.replay.playing { "⏸" } else { "▶" };
+                    if ui.button(play_label).clicked() {
+                        if !self.replay.playing && before == last {
+                            before = first;
+                        }
+                        self.replay.playing = !self.replay.playing;
+                    }
+
+                    let mut seconds = before.timestamp();
+                    let slider =
+                        egui::Slider::new(&mut seconds, first.timestamp()..=last.timestamp())
+                            .show_value(false);
+                    if ui.add(slider).changed() {
+                        self.replay.playing = false;
+                        before = DateTime::from_timestamp(seconds, 0).unwrap_or(before);
+                    }
+                    ui.label(before.format("%d/%m/%Y %H:%M:%S").to_string());
+
+                    node_filter.set_before(Some(before));
+                });
+            });
+
+        if let Some(before) = node_filter.before() {
+            if self
+                .replay
+                .snapshot
+                .as_ref()
+                .is_none_or(|(timestamp, _)| *timestamp != before)
+            {
+                let snapshot = nodes
+                    .iter()
+                    .filter_map(|(node_id, node_info)| {
+                        node_info.until(before).map(|v| (*node_id, v))
+                    })
+                    .collect();
+                self.replay.snapshot = Some((before, snapshot));
+            }
+        } else {
+            self.replay.snapshot = None;
+        }
+    }
+
+    pub fn node_colors(&self) -> &NodeColors {
+        &self.node_colors
+    }
+
     pub fn ui<'a>(
         &mut self,
         ui: &mut egui::Ui,
@@ -865,22 +1513,95 @@ impl MapPanel {
         node_filter: &mut NodeFilter,
         nodes: &HashMap<NodeId, NodeInfo>,
         nodebook: &mut NodeBook,
+        tile_cache: &TileCacheConfig,
+        telemetry_formatter: &TelemetryFormatter,
     ) {
-        {
+        map_context.update_source(
+            &self.tile_source,
+            ui.visuals().dark_mode,
+            &self.tile_user_agent,
+            tile_cache,
+        );
+        self.replay_ui(ui, node_filter, nodes);
+        let nodes = self
+            .replay
+            .snapshot
+            .as_ref()
+            .map(|(_, snapshot)| snapshot)
+            .unwrap_or(nodes);
+        let map_rect = {
             // let excess_nodebook_clone = nodebook.clone();
             // let node_seeker = node_filter.seeker_for(nodes, &excess_nodebook_clone);
-            let map_nodes = MapPointsPlugin::new(node_filter, nodes, &mut self.memory, nodebook);
+            let map_nodes = MapPointsPlugin::new(
+                node_filter,
+                nodes,
+                &mut self.memory,
+                nodebook,
+                &self.node_colors,
+                telemetry_formatter,
+            );
             let map = walkers::Map::new(
                 Some(&mut map_context.tiles),
                 &mut self.map_memory,
-                lon_lat(17.03664, 51.09916),
+                self.home.center,
             )
             .with_plugin(map_nodes);
-            ui.add(map);
+            ui.add(map).rect
+        };
+
+        // Returning users keep their view, which is stored in `map_memory`
+        if !self.home_applied && (!nodes.is_empty() || !self.home.fit_nodes) {
+            self.home_applied = true;
+            if self.map_memory.detached().is_none() {
+                let bounds = if self.home.fit_nodes {
+                    nodes_bounds(node_filter, nodes, nodebook)
+                } else {
+                    None
+                };
+                if let Some(bounds) = bounds {
+                    Self::fit_bounds(&mut self.map_memory, map_rect, bounds);
+                } else if let Err(e) = self.map_memory.set_zoom(self.home.zoom) {
+                    log::error!("Unable to set map zoom {}: {:?}", self.home.zoom, e);
+                }
+            }
+        }
+
+        if let Some(zoom_request) = self.memory.zoom_request.take() {
+            match zoom_request {
+                ZoomRequest::Fit(bounds) => {
+                    Self::fit_bounds(&mut self.map_memory, map_rect, bounds)
+                }
+                ZoomRequest::FitNodes => match nodes_bounds(node_filter, nodes, nodebook) {
+                    Some(bounds) if bounds[0] == bounds[1] => {
+                        self.memory.fit_nodes_failed = false;
+                        self.map_memory.center_at(bounds[0]);
+                        if let Err(e) = self.map_memory.set_zoom(SINGLE_NODE_ZOOM) {
+                            log::error!("Unable to set map zoom {}: {:?}", SINGLE_NODE_ZOOM, e);
+                        }
+                    }
+                    Some(bounds) => {
+                        self.memory.fit_nodes_failed = false;
+                        Self::fit_bounds(&mut self.map_memory, map_rect, bounds);
+                    }
+                    None => self.memory.fit_nodes_failed = true,
+                },
+            }
         }
 
         if let Some(bbox) = self.memory.bounding_box {
             node_filter.set_bbox(bbox);
+
+            egui::Area::new(ui.id().with("map_prefetch"))
+                .anchor(Align2::RIGHT_BOTTOM, [-10.0, -25.0])
+                .show(ui.ctx(), |ui| {
+                    if ui
+                        .button("⬇")
+                        .on_hover_text("Prefetch tiles of visible area")
+                        .clicked()
+                    {
+                        map_context.prefetch(bbox, self.map_memory.zoom());
+                    }
+                });
         }
     }
 }
@@ -933,6 +1654,104 @@ impl Display for DisplayTracks {
     }
 }
 
+impl<'a> MapRosterPlugin<'a> {
+    fn home_ui(&mut self, ui: &mut egui::Ui) {
+        let home = &mut self.map.home;
+        ui.horizontal(|ui| {
+            if ui.button("🏠 Go home").clicked() {
+                self.map.map_memory.center_at(home.center);
+                if let Err(e) = self.map.map_memory.set_zoom(home.zoom) {
+                    log::error!("Unable to set map zoom {}: {:?}", home.zoom, e);
+                }
+            }
+            if ui
+                .button("Set current view as home")
+                .on_hover_text(format!(
+                    "Home: {:.6} {:.6}, zoom {:.1}",
+                    home.center.y(),
+                    home.center.x(),
+                    home.zoom
+                ))
+                .clicked()
+            {
+                home.center = self.map.map_memory.detached().unwrap_or(home.center);
+                home.zoom = self.map.map_memory.zoom();
+            }
+        });
+        ui.checkbox(&mut home.fit_nodes, "Fit visible nodes on start");
+    }
+
+    fn tiles_ui(&mut self, ui: &mut egui::Ui) {
+        let is_custom = matches!(self.map.tile_source, TileSource::Custom(_));
+        egui::ComboBox::from_label("tiles")
+            .selected_text(self.map.tile_source.to_string())
+            .show_ui(ui, |ui| {
+                ui.selectable_value(
+                    &mut self.map.tile_source,
+                    TileSource::Themed,
+                    TileSource::Themed.to_string(),
+                );
+                ui.selectable_value(
+                    &mut self.map.tile_source,
+                    TileSource::OpenStreetMap,
+                    TileSource::OpenStreetMap.to_string(),
+                );
+                ui.selectable_value(
+                    &mut self.map.tile_source,
+                    TileSource::CartoDark,
+                    TileSource::CartoDark.to_string(),
+                );
+                if ui.selectable_label(is_custom, "Custom").clicked() && !is_custom {
+                    self.map.tile_source = TileSource::Custom(self.map.custom_tiles.clone());
+                }
+            });
+
+        if is_custom {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.map.custom_tiles.url_template)
+                    .hint_text("https://tile.example.org/{z}/{x}/{y}.png"),
+            );
+            ui.add(
+                egui::TextEdit::singleline(&mut self.map.custom_tiles.attribution)
+                    .hint_text("Attribution"),
+            );
+            let applied = self.map.tile_source == TileSource::Custom(self.map.custom_tiles.clone());
+            if ui
+                .add_enabled(
+                    self.map.custom_tiles.is_valid() && !applied,
+                    Button::new("Apply tiles"),
+                )
+                .on_disabled_hover_text("Template must contain {z}, {x} and {y}")
+                .clicked()
+            {
+                self.map.tile_source = TileSource::Custom(self.map.custom_tiles.clone());
+            }
+        }
+
+        let user_agent = self
+            .map
+            .tile_user_agent_edit
+            .get_or_insert_with(|| self.map.tile_user_agent.clone());
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(user_agent)
+                    .desired_width(150.0)
+                    .hint_text("User-Agent"),
+            )
+            .on_hover_text("User-Agent for tile requests\nsee tile server's usage policy");
+            if ui
+                .add_enabled(
+                    *user_agent != self.map.tile_user_agent,
+                    Button::new("Apply"),
+                )
+                .clicked()
+            {
+                self.map.tile_user_agent = user_agent.clone();
+            }
+        });
+    }
+}
+
 impl<'a> roster::Plugin for MapRosterPlugin<'a> {
     fn node_is_selected(&self, node_info: &NodeInfo) -> roster::Selection {
         if let Some(MemorySelection::Node(node_id)) = self.map.memory.selection {
@@ -945,6 +1764,21 @@ impl<'a> roster::Plugin for MapRosterPlugin<'a> {
         roster::Selection::None
     }
 
+    fn node_activated(
+        self: &mut Self,
+        node_info: &NodeInfo,
+        nodebook: &mut NodeBook,
+    ) -> roster::PanelCommand {
+        if let Some(position) = fix_or_position(nodebook, node_info.node_id, &node_info.position)
+            .or(node_info.assumed_position.map(|v| v.position))
+        {
+            self.map.memory.selection = Some(MemorySelection::Node(node_info.node_id));
+            self.map.map_memory.center_at(position);
+            return roster::PanelCommand::NextPanel(Panel::Map);
+        }
+        roster::PanelCommand::Nothing
+    }
+
     fn node_is_dropped(&self, node_info: &NodeInfo) -> bool {
         if let Some(MemorySelection::Node(node_id)) = self.map.memory.selection {
             if node_id == node_info.node_id {
@@ -963,6 +1797,22 @@ impl<'a> roster::Plugin for MapRosterPlugin<'a> {
         ui: &mut egui::Ui,
         nodebook: &mut NodeBook,
     ) -> roster::PanelCommand {
+        ui.horizontal(|ui| {
+            if ui
+                .button("⛶ Fit to visible nodes")
+                .on_hover_text("Show all filtered nodes with known positions")
+                .clicked()
+            {
+                self.map.memory.zoom_request = Some(ZoomRequest::FitNodes);
+            }
+            if self.map.memory.fit_nodes_failed {
+                ui.label(
+                    egui::RichText::new("No visible nodes with position")
+                        .small()
+                        .color(Color32::YELLOW),
+                );
+            }
+        });
         ui.collapsing("Map settings", |ui| {
             egui::ComboBox::from_label("gateway connections")
                 .selected_text(self.map.memory.gateway_connections.to_string())
@@ -997,13 +1847,31 @@ impl<'a> roster::Plugin for MapRosterPlugin<'a> {
                         "Only selected",
                     );
                 });
+            let track_glitches = &mut self.map.memory.track_glitches;
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut track_glitches.enabled, "Mark GPS glitches faster than");
+                ui.add_enabled(
+                    track_glitches.enabled,
+                    egui::DragValue::new(&mut track_glitches.max_speed_kmh)
+                        .range(1.0..=2000.0)
+                        .suffix(" km/h"),
+                );
+            });
+            ui.add_enabled(
+                track_glitches.enabled,
+                egui::Checkbox::new(&mut track_glitches.hide, "Hide glitches in tracks"),
+            );
+            self.tiles_ui(ui);
+            self.home_ui(ui);
         });
         ui.collapsing("GNSS Spoofing Zones", |ui| {
             if let Some(MemorySelection::NewZone(zone)) = self.map.memory.selection {
-                let label =
-                    egui::RichText::new(format!(
-                        "{:.6} {:.6} {:.2} m",
-                        zone.center.x(),  zone.center.y(), zone.radius_meters));
+                let label = egui::RichText::new(format!(
+                    "{:.6} {:.6} {:.2} m",
+                    zone.center.x(),
+                    zone.center.y(),
+                    zone.radius_meters
+                ));
                 ui.label(label);
 
                 if ui.button("CANCEL").clicked() {
@@ -1011,25 +1879,26 @@ impl<'a> roster::Plugin for MapRosterPlugin<'a> {
                 }
             } else {
                 if ui.button("ADD").clicked() {
-                    self.map.memory.selection = Some(MemorySelection::NewZone(NewZoneInfo::new(geo::Point::default(), 0.0)));
+                    self.map.memory.selection = Some(MemorySelection::NewZone(NewZoneInfo::new(
+                        geo::Point::default(),
+                        0.0,
+                    )));
                 }
             }
             let mut delete = None;
             for (zone_id, zone) in nodebook.zones_list_mut() {
-                let selected = matches!(self.map.memory.selection, Some(selection) if selection == MemorySelection::Zone(zone_id));
+                let selected = self.map.memory.selection == Some(MemorySelection::Zone(zone_id));
 
-                let label = egui::RichText::new(zone.name.clone());
-                let label = if selected {
-                    label.strong()
+                let label = if zone.name.is_empty() {
+                    egui::RichText::new("<unnamed>").italics()
                 } else {
-                    label
+                    egui::RichText::new(zone.name.clone())
                 };
+                let label = if selected { label.strong() } else { label };
                 ui.label(label);
                 ui.horizontal(|ui| {
                     if ui.button("EDIT").clicked() {
-                        self.map
-                            .map_memory
-                            .center_at(zone.center);
+                        self.map.map_memory.center_at(zone.center);
                         self.map.memory.selection = Some(MemorySelection::Zone(zone_id));
                     }
                     if ui.button("DEL").clicked() {
@@ -1037,14 +1906,57 @@ impl<'a> roster::Plugin for MapRosterPlugin<'a> {
                     }
                 });
 
-                let label =
-                    egui::RichText::new(format!(
-                        "{:.6} {:.6} {:.2} m",
-                        zone.center.x(), zone.center.y(), zone.radius_meters));
+                let label = egui::RichText::new(format!(
+                    "{:.6} {:.6} {:.2} m",
+                    zone.center.x(),
+                    zone.center.y(),
+                    zone.radius_meters
+                ));
                 if ui.label(label).clicked() {
-                    self.map
-                        .map_memory
-                        .center_at(zone.center);
+                    self.map.map_memory.center_at(zone.center);
+                }
+
+                if selected {
+                    egui::Grid::new(("zone_editor", zone_id)).show(ui, |ui| {
+                        ui.label("Name");
+                        ui.text_edit_singleline(&mut zone.name);
+                        ui.end_row();
+
+                        let mut latitude = zone.center.y();
+                        let mut longitude = zone.center.x();
+                        ui.label("Latitude");
+                        let latitude_changed = ui
+                            .add(
+                                egui::DragValue::new(&mut latitude)
+                                    .range(-90.0..=90.0)
+                                    .speed(0.0001)
+                                    .max_decimals(6),
+                            )
+                            .changed();
+                        ui.end_row();
+                        ui.label("Longitude");
+                        let longitude_changed = ui
+                            .add(
+                                egui::DragValue::new(&mut longitude)
+                                    .range(-180.0..=180.0)
+                                    .speed(0.0001)
+                                    .max_decimals(6),
+                            )
+                            .changed();
+                        ui.end_row();
+                        if latitude_changed || longitude_changed {
+                            zone.center = geo::Point::new(longitude, latitude);
+                        }
+
+                        ui.label("Radius");
+                        // Zero radius zone can't be selected on the map anymore
+                        ui.add(
+                            egui::DragValue::new(&mut zone.radius_meters)
+                                .range(1.0..=1_000_000.0)
+                                .suffix(" m"),
+                        );
+                        ui.end_row();
+                    });
                 }
             }
             if let Some(zone_id) = delete {
@@ -1055,12 +1967,51 @@ impl<'a> roster::Plugin for MapRosterPlugin<'a> {
         roster::PanelCommand::Nothing
     }
 
+    fn bulk_ui(&mut self, ui: &mut egui::Ui, picked: &HashSet<NodeId>, _nodebook: &mut NodeBook) {
+        let selected_tracks = &mut self.map.memory.selected_tracks;
+        let mut enabled = picked.iter().all(|node_id| {
+            selected_tracks
+                .get(node_id)
+                .is_some_and(|tracks_config| tracks_config.enabled)
+        });
+        if ui
+            .checkbox(&mut enabled, "Tracks")
+            .on_hover_text("Show tracks of picked nodes on map")
+            .changed()
+        {
+            for node_id in picked {
+                selected_tracks.entry(*node_id).or_default().enabled = enabled;
+            }
+        }
+    }
+
     fn panel_node_ui(
         self: &mut Self,
         ui: &mut egui::Ui,
         node_info: &NodeInfo,
         nodebook: &mut NodeBook,
     ) -> roster::PanelCommand {
+        if !node_info.ignored_positions.is_empty() {
+            let total: usize = node_info.ignored_positions.values().sum();
+            let zones = node_info
+                .ignored_positions
+                .iter()
+                .map(|(zone_id, count)| {
+                    let name = nodebook
+                        .zone_get(zone_id)
+                        .map(|zone| zone.name.clone())
+                        .filter(|name| !name.is_empty())
+                        .unwrap_or("<unnamed>".into());
+                    format!("{}: {}", name, count)
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            ui.label(
+                egui::RichText::new(format!("⚠ {} positions suppressed by GNSS zones", total))
+                    .color(Color32::YELLOW),
+            )
+            .on_hover_text(zones);
+        }
         if node_info.position.len() > 1 {
             ui.push_id(node_info.node_id, |ui| {
                 ui.collapsing(
@@ -1087,11 +2038,82 @@ impl<'a> roster::Plugin for MapRosterPlugin<'a> {
             });
         }
 
+        if let Some(MemorySelection::Node(selected_node_id)) = self.map.memory.selection
+            && selected_node_id != node_info.node_id
+        {
+            if ui
+                .button("⛰ Elevation profile")
+                .on_hover_text(format!(
+                    "Line of sight from {} to {}",
+                    selected_node_id, node_info.node_id
+                ))
+                .clicked()
+            {
+                return roster::PanelCommand::NextPanel(Panel::Elevation(
+                    selected_node_id,
+                    node_info.node_id,
+                    Default::default(),
+                ));
+            }
+            if ui
+                .button("⚖ Compare")
+                .on_hover_text(format!(
+                    "Compare identities of {} and {}",
+                    selected_node_id, node_info.node_id
+                ))
+                .clicked()
+            {
+                return roster::PanelCommand::NextPanel(Panel::Compare(
+                    selected_node_id,
+                    node_info.node_id,
+                ));
+            }
+        }
+
+        ui.horizontal(|ui| {
+            let mut color = self.map.node_colors.get(node_info.node_id);
+            if egui::color_picker::color_edit_button_srgba(
+                ui,
+                &mut color,
+                egui::color_picker::Alpha::Opaque,
+            )
+            .on_hover_text("Node's color on the map and plots")
+            .changed()
+            {
+                self.map.node_colors.set(node_info.node_id, color);
+            }
+            if self.map.node_colors.is_overridden(node_info.node_id)
+                && ui
+                    .button("⟲")
+                    .on_hover_text("Reset to default color")
+                    .clicked()
+            {
+                self.map.node_colors.reset(node_info.node_id);
+            }
+            ui.label("Color");
+        });
+
+        if let Some(assumed) = &node_info.assumed_position {
+            ui.label(format!(
+                "Assumed position: {}, {} gateways, ±{:.0} m",
+                assumed.method, assumed.gateways, assumed.radius_meters
+            ));
+        }
         if let Some(position) = fix_or_position(nodebook, node_info.node_id, &node_info.position)
-            .or(node_info.assumed_position)
+            .or(node_info.assumed_position.map(|v| v.position))
         {
-            if nodebook.node_get(&node_info.node_id).is_some() {
-                if ui.button("Move").clicked() {
+            if nodebook
+                .node_get(&node_info.node_id)
+                .is_some_and(|v| v.position.is_some())
+                && ui.button("Move").clicked()
+            {
+                // Alias is kept
+                if nodebook.alias(&node_info.node_id).is_some() {
+                    nodebook.node(node_info.node_id).and_modify(|v| {
+                        v.position = None;
+                        v.updated = Some(Utc::now());
+                    });
+                } else {
                     nodebook.node_remove(&node_info.node_id);
                 }
             }
@@ -1151,7 +2173,7 @@ fn width_by_rssi(rssi: i32) -> f32 {
     }
 }
 
-fn opaque_by_timedelta(current_datetime: DateTime<Utc>, remote_datetime: DateTime<Utc>) -> f32 {
+pub fn opaque_by_timedelta(current_datetime: DateTime<Utc>, remote_datetime: DateTime<Utc>) -> f32 {
     const RANGE: [f32; 2] = [0.0, 1.0];
     const TIME_HOURS_LIMIT: i64 = 24;
 
@@ -1171,7 +2193,7 @@ fn opaque_by_timedelta(current_datetime: DateTime<Utc>, remote_datetime: DateTim
     }
 }
 
-fn opaque_width(
+pub fn opaque_width(
     current_datetime: DateTime<Utc>,
     gateway_info: Option<&GatewayInfo>,
     base_color: Color32,
@@ -1198,7 +2220,14 @@ fn draw_connection(
     gateway_info: Option<&GatewayInfo>,
     color: Color32,
 ) {
-    let stroke = opaque_width(current_datetime, gateway_info, color);
+    let stroke: egui::Stroke = opaque_width(current_datetime, gateway_info, color).into();
+    // Underlay keeps dark and light colors visible on any tiles
+    let underlay = egui::Stroke::new(
+        stroke.width + 2.0,
+        ui.visuals()
+            .extreme_bg_color
+            .gamma_multiply(0.5 * stroke.color.a() as f32 / 255.0),
+    );
     let distance = onscreen_position.distance(other_onscreen_position);
     let dash_count = gateway_info
         .map(|gateway_info| {
@@ -1211,12 +2240,13 @@ fn draw_connection(
     let gap_length = 15.0;
     let dash_length = (distance / dash_count as f32) - 15.0;
 
-    let shape = egui::Shape::dashed_line(
-        &vec![onscreen_position, other_onscreen_position],
-        stroke,
-        dash_length,
-        gap_length,
-    );
-
-    ui.painter().add(shape);
+    for stroke in [underlay, stroke] {
+        let shape = egui::Shape::dashed_line(
+            &vec![onscreen_position, other_onscreen_position],
+            stroke,
+            dash_length,
+            gap_length,
+        );
+        ui.painter().add(shape);
+    }
 }
@@ -1,14 +1,38 @@
+mod activity;
+mod alert_rules;
+mod alerts;
+pub mod api_auth;
 pub mod byte_node_id;
+mod command_palette;
+mod compare;
+mod coverage_export;
+mod dashboard;
 pub mod data;
+mod decrypt_failures;
+mod elevation;
 mod journal;
 mod map;
 mod node_filter;
+mod packet_inspector;
+mod qr;
 mod radio_telemetry;
+mod session;
 pub mod settings;
 mod telemetry;
 mod telemetry_formatter;
+mod tile_cache;
+mod topology;
 use std::collections::HashSet;
-use std::{collections::HashMap, f32, ops::ControlFlow, sync::Arc};
+use std::io::Write;
+use std::{
+    collections::HashMap,
+    f32,
+    ops::ControlFlow,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+};
 pub mod color_generator;
 pub mod node_book;
 mod node_dump;
@@ -17,9 +41,10 @@ mod roster;
 mod time_format;
 
 use chrono::{DateTime, Utc};
-use data::{JournalData, NodeInfo, StoredMeshPacket};
+use data::{JournalData, NodeInfo, SeenPackets, StoredMeshPacket};
 use egui::RichText;
 use egui::mutex::Mutex;
+use flate2::write::{GzDecoder, ZlibDecoder};
 use journal::JournalPanel;
 use map::MapPanel;
 use meshtastic_connect::keyring::channel::{Channel, ChannelHash};
@@ -29,13 +54,20 @@ use node_dump::NodeDump;
 use settings::Settings;
 use telemetry::Telemetry;
 
-use crate::app::data::{DataVariant, PublicKey, TelemetryValue};
+use crate::app::alert_rules::{AlertRules, RuleEngine};
+use crate::app::alerts::Alerts;
+use crate::app::api_auth::ApiAuth;
+use crate::app::command_palette::{Command, CommandPalette};
+use crate::app::dashboard::Dashboard;
+use crate::app::data::{DataVariant, PacketTime, PublicKey, RadioThresholds, TelemetryValue};
+use crate::app::decrypt_failures::DecryptFailures;
 use crate::app::journal::JournalRosterPlugin;
 use crate::app::map::{MapContext, MapRosterPlugin};
-use crate::app::node_filter::NodeFilter;
-use crate::app::radio_center::assume_position;
-use crate::app::roster::{Panel, Roster};
+use crate::app::node_filter::{FilterPresets, NodeFilter};
+use crate::app::radio_center::{AssumeConfig, assume_position};
+use crate::app::roster::{Panel, Roster, RosterContext};
 use crate::app::telemetry_formatter::TelemetryFormatter;
+use crate::app::tile_cache::TileCacheConfig;
 
 #[derive(Clone, Copy)]
 pub enum DownloadState {
@@ -48,6 +80,10 @@ pub enum DownloadState {
     Parse,
     // Hold to next download action
     Delay,
+    // Server is unreachable or API URL is wrong, retry after delay
+    ConnectionError,
+    // Server rejected credentials, retry after delay
+    Unauthorized,
 }
 
 impl std::fmt::Display for DownloadState {
@@ -61,10 +97,93 @@ impl std::fmt::Display for DownloadState {
             }
             Self::Parse => write!(f, "Parsing"),
             Self::Delay => write!(f, "Resting"),
+            Self::ConnectionError => write!(f, "Connection error"),
+            Self::Unauthorized => write!(f, "Unauthorized"),
         }
     }
 }
 
+// Outcome of recent syncs, to tell "server is down" from "no new packets"
+#[derive(Default)]
+pub struct SyncHealth {
+    consecutive_failures: u32,
+    last_success: Option<DateTime<Utc>>,
+    last_error: Option<String>,
+    // Delay before retry after the last failure
+    backoff: Option<std::time::Duration>,
+}
+
+// Failures in a row to consider the server down
+const SYNC_FAILURES_PERSISTENT: u32 = 3;
+// Upper limit of the retry delay
+const SYNC_BACKOFF_MAX: std::time::Duration = std::time::Duration::from_secs(300);
+
+impl SyncHealth {
+    // Returns delay before retry: doubled on each failure in a row, with jitter
+    fn failed(&mut self, base: std::time::Duration, error: String) -> std::time::Duration {
+        self.consecutive_failures += 1;
+        self.last_error = Some(error);
+
+        let exponent = (self.consecutive_failures - 1).min(16);
+        let backoff = base.saturating_mul(1 << exponent).min(SYNC_BACKOFF_MAX);
+        // ±20% to spread retries of many clients, no need for a good randomness
+        let jitter = Utc::now().timestamp_subsec_nanos() as f32 / 1e9 * 0.4 + 0.8;
+        let backoff = backoff.mul_f32(jitter);
+        self.backoff = Some(backoff);
+        backoff
+    }
+
+    fn succeeded(&mut self) {
+        self.consecutive_failures = 0;
+        self.last_error = None;
+        self.last_success = Some(Utc::now());
+        self.backoff = None;
+    }
+
+    fn ui(&self, ui: &mut egui::Ui) {
+        let color = match self.consecutive_failures {
+            0 if self.last_success.is_some() => egui::Color32::GREEN,
+            0 => egui::Color32::GRAY,
+            failures if failures < SYNC_FAILURES_PERSISTENT => egui::Color32::YELLOW,
+            _ => egui::Color32::RED,
+        };
+        let (rect, response) = ui.allocate_exact_size(egui::vec2(10.0, 10.0), egui::Sense::hover());
+        ui.painter().circle_filled(rect.center(), 5.0, color);
+        if self.consecutive_failures >= SYNC_FAILURES_PERSISTENT {
+            ui.label(RichText::new(self.consecutive_failures.to_string()).color(color));
+        }
+
+        response.on_hover_ui(|ui| {
+            match self.last_success {
+                Some(last_success) => ui.label(format!(
+                    "Last success: {} ago",
+                    time_format::format_timediff(last_success, Utc::now()).unwrap_or("0 s".into())
+                )),
+                None => ui.label("No successful sync yet"),
+            };
+            if self.consecutive_failures > 0 {
+                ui.label(format!("Failures in a row: {}", self.consecutive_failures));
+            }
+            if let Some(backoff) = self.backoff {
+                ui.label(format!("Retry after {:.0} s", backoff.as_secs_f32()));
+            }
+            if let Some(error) = &self.last_error {
+                ui.label(RichText::new(error).color(egui::Color32::LIGHT_RED));
+            }
+        });
+    }
+}
+
+impl DownloadState {
+    // Waiting for the next download
+    pub fn is_resting(&self) -> bool {
+        matches!(
+            self,
+            Self::Delay | Self::ConnectionError | Self::Unauthorized
+        )
+    }
+}
+
 impl Default for DownloadState {
     fn default() -> Self {
         DownloadState::Idle
@@ -81,6 +200,54 @@ pub struct PersistentData {
     pub map: MapPanel,
     pub node_dump: NodeDump,
     pub update_interval_secs: std::time::Duration,
+    #[serde(default)]
+    pub tile_cache: TileCacheConfig,
+    #[serde(default)]
+    pub filter_presets: FilterPresets,
+    // Brute-force keys for unknown channels' hashes
+    #[serde(default)]
+    pub try_all_channel_keys: bool,
+    // Limits for gateways' RSSI/SNR samples
+    #[serde(default)]
+    pub radio_thresholds: RadioThresholds,
+    #[serde(default)]
+    pub assume_config: AssumeConfig,
+    #[serde(default)]
+    pub packet_time: PacketTime,
+    // Base URL of softnode's API, `/sync` is appended
+    #[serde(default = "default_api_url")]
+    pub api_url: String,
+    // System, dark or light, applied on start
+    #[serde(default)]
+    pub theme: egui::ThemePreference,
+    #[serde(default)]
+    pub alert_rules: AlertRules,
+}
+
+fn default_api_url() -> String {
+    env!("SOFTNODE_API_URL_BASE").into()
+}
+
+// Relative URL is allowed for the web client, served by softnode itself
+pub fn validate_api_url(api_url: &str) -> Result<(), String> {
+    let api_url = api_url.trim();
+    if api_url.is_empty() {
+        return Err("URL is empty".into());
+    }
+    if api_url.starts_with('/') {
+        return Ok(());
+    }
+    let Some((scheme, rest)) = api_url.split_once("://") else {
+        return Err("URL should start with http:// or https://".into());
+    };
+    if scheme != "http" && scheme != "https" {
+        return Err(format!("Unsupported scheme: {}", scheme));
+    }
+    let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    if host.is_empty() || host.contains(char::is_whitespace) {
+        return Err("Invalid host".into());
+    }
+    Ok(())
 }
 
 #[derive(Debug, PartialEq, Eq, Hash)]
@@ -109,6 +276,12 @@ pub struct SoftNodeApp {
 
     /// Channel statistics, indexed by channel hash
     channel_stats: HashMap<ChannelPublicy, ChannelStats>,
+    /// Packets which are not decrypted
+    decrypt_failures: DecryptFailures,
+    alerts: Alerts,
+    rule_engine: RuleEngine,
+    /// Mesh-wide counters
+    dashboard: Dashboard,
 
     // Keyring data. Similar to persistent,
     // but saved separately, to avoid keyring drop
@@ -116,11 +289,20 @@ pub struct SoftNodeApp {
     keyring: Keyring,
     // GNSS fixes. Persistent as keyring data
     nodebook: NodeBook,
+    // API credentials. Persistent as keyring data
+    api_auth: ApiAuth,
     // Persistent data
     persistent: PersistentData,
     bootstrap_done: bool,
-    download_state: Arc<Mutex<DownloadState>>,
-    download_data: Arc<Mutex<Vec<StoredMeshPacket>>>,
+    sync: SyncHandles,
+    // Processed and total packets of the backlog, which takes several frames
+    ingest_progress: Option<(usize, usize)>,
+    seen_packets: SeenPackets,
+    // Nodes whose announced key differs from the key verified in the NodeBook
+    key_changes: HashSet<NodeId>,
+    // Session is loaded from bundle, sync with server is stopped
+    offline: bool,
+    command_palette: CommandPalette,
 }
 
 impl Default for PersistentData {
@@ -134,6 +316,15 @@ impl Default for PersistentData {
             map: Default::default(),
             node_dump: NodeDump::new(),
             update_interval_secs: std::time::Duration::from_secs(5),
+            tile_cache: Default::default(),
+            filter_presets: Default::default(),
+            try_all_channel_keys: false,
+            radio_thresholds: Default::default(),
+            assume_config: Default::default(),
+            packet_time: Default::default(),
+            api_url: default_api_url(),
+            theme: Default::default(),
+            alert_rules: Default::default(),
         }
     }
 }
@@ -196,28 +387,43 @@ impl SoftNodeApp {
             .flatten()
             .unwrap_or_else(|| Default::default());
 
+        let api_auth = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, PERSISTENT_API_AUTH_KEY))
+            .unwrap_or_default();
+
         let persistent = PersistentData::new(cc);
-        let download_state: Arc<Mutex<DownloadState>> = Default::default();
-        let download_data: Arc<Mutex<Vec<StoredMeshPacket>>> = Default::default();
+        cc.egui_ctx.set_theme(persistent.theme);
+        let sync = SyncHandles::default();
         go_download(
+            &persistent.api_url,
+            &api_auth,
             persistent.update_interval_secs,
             Default::default(),
-            download_state.clone(),
-            download_data.clone(),
+            sync.clone(),
             cc.egui_ctx.clone(),
         );
         Self {
             journal: Default::default(),
             nodes: Default::default(),
             last_sync_point: Default::default(),
-            map_context: MapContext::new(cc.egui_ctx.clone()),
-            download_state,
-            download_data,
+            map_context: MapContext::new(cc.egui_ctx.clone(), &persistent.tile_cache),
+            sync,
+            ingest_progress: None,
+            seen_packets: Default::default(),
+            key_changes: Default::default(),
             keyring,
             nodebook,
             persistent,
             bootstrap_done: false,
             channel_stats: Default::default(),
+            decrypt_failures: Default::default(),
+            alerts: Default::default(),
+            rule_engine: Default::default(),
+            dashboard: Default::default(),
+            api_auth,
+            offline: false,
+            command_palette: Default::default(),
         }
     }
 }
@@ -238,24 +444,215 @@ fn run_after(delay: std::time::Duration, f: impl FnOnce() + Send + 'static) {
     });
 }
 
+// Newline-delimited JSON, one `StoredMeshPacket` per line
+pub const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+
+enum SyncDecoder {
+    Gzip(GzDecoder<Vec<u8>>),
+    Deflate(ZlibDecoder<Vec<u8>>),
+}
+
+impl SyncDecoder {
+    // Transport could decompress data itself and keep the header,
+    // so the first chunk is checked too
+    fn detect(encoding: Option<&str>, first_chunk: &[u8]) -> Option<Self> {
+        match encoding? {
+            "gzip" | "x-gzip" if first_chunk.starts_with(&[0x1f, 0x8b]) => {
+                Some(Self::Gzip(GzDecoder::new(Vec::new())))
+            }
+            "deflate" if first_chunk.first().is_some_and(|v| v & 0x0f == 8) => {
+                Some(Self::Deflate(ZlibDecoder::new(Vec::new())))
+            }
+            _ => None,
+        }
+    }
+
+    // Returns decompressed data
+    fn write(&mut self, chunk: &[u8]) -> Result<Vec<u8>, String> {
+        let output = match self {
+            Self::Gzip(decoder) => {
+                decoder.write_all(chunk).map_err(|e| e.to_string())?;
+                decoder.get_mut()
+            }
+            Self::Deflate(decoder) => {
+                decoder.write_all(chunk).map_err(|e| e.to_string())?;
+                decoder.get_mut()
+            }
+        };
+        Ok(std::mem::take(output))
+    }
+
+    // Fails on truncated stream
+    fn finish(&mut self) -> Result<Vec<u8>, String> {
+        let output = match self {
+            Self::Gzip(decoder) => {
+                decoder.try_finish().map_err(|e| e.to_string())?;
+                decoder.get_mut()
+            }
+            Self::Deflate(decoder) => {
+                decoder.try_finish().map_err(|e| e.to_string())?;
+                decoder.get_mut()
+            }
+        };
+        Ok(std::mem::take(output))
+    }
+}
+
+#[derive(Default)]
+struct SyncBody {
+    // Received but not parsed yet data
+    buffer: Vec<u8>,
+    downloaded: usize,
+    // Parsed records
+    records: usize,
+    // `Content-Encoding` of the response
+    encoding: Option<String>,
+    decoder: Option<SyncDecoder>,
+}
+
+impl SyncBody {
+    fn push_chunk(&mut self, chunk: &[u8]) -> Result<(), String> {
+        if self.downloaded == 0 {
+            self.decoder = SyncDecoder::detect(self.encoding.as_deref(), chunk);
+        }
+        self.downloaded += chunk.len();
+        if let Some(decoder) = &mut self.decoder {
+            let decoded = decoder.write(chunk)?;
+            self.buffer.extend_from_slice(&decoded);
+        } else {
+            self.buffer.extend_from_slice(chunk);
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), String> {
+        if let Some(decoder) = &mut self.decoder {
+            let decoded = decoder.finish()?;
+            self.buffer.extend_from_slice(&decoded);
+        }
+        Ok(())
+    }
+
+    // Old servers return JSON array. None if format is unknown yet
+    fn is_ndjson(&self) -> Option<bool> {
+        self.buffer
+            .iter()
+            .find(|v| !v.is_ascii_whitespace())
+            .map(|v| *v != b'[')
+    }
+
+    // Parses the rest of the completely received body
+    fn parse_rest(&mut self, data: &Mutex<Vec<StoredMeshPacket>>) -> Result<usize, String> {
+        match self.is_ndjson() {
+            Some(true) => {
+                // Last record may be not terminated by newline
+                self.buffer.push(b'\n');
+                parse_ndjson_lines(&mut self.buffer, data)
+            }
+            Some(false) => {
+                let mut new_data =
+                    serde_json::from_slice::<Vec<StoredMeshPacket>>(self.buffer.as_slice())
+                        .map_err(|e| e.to_string())?;
+                self.buffer.clear();
+                let count = new_data.len();
+                data.lock().append(&mut new_data);
+                Ok(count)
+            }
+            None => Ok(0),
+        }
+    }
+}
+
+// Parses complete lines, the incomplete tail is kept in the buffer
+fn parse_ndjson_lines(
+    buffer: &mut Vec<u8>,
+    data: &Mutex<Vec<StoredMeshPacket>>,
+) -> Result<usize, String> {
+    let Some(last_newline) = buffer.iter().rposition(|v| *v == b'\n') else {
+        return Ok(0);
+    };
+    let tail = buffer.split_off(last_newline + 1);
+    let lines = std::mem::replace(buffer, tail);
+
+    let mut count = 0;
+    for line in lines.split(|v| *v == b'\n') {
+        if line.iter().all(|v| v.is_ascii_whitespace()) {
+            continue;
+        }
+        let record = serde_json::from_slice::<StoredMeshPacket>(line)
+            .map_err(|e| format!("record #{}: {}", count, e))?;
+        data.lock().push(record);
+        count += 1;
+    }
+    Ok(count)
+}
+
+// Shared between the app and download's callbacks
+#[derive(Clone, Default)]
+struct SyncHandles {
+    state: Arc<Mutex<DownloadState>>,
+    data: Arc<Mutex<Vec<StoredMeshPacket>>>,
+    health: Arc<Mutex<SyncHealth>>,
+    // Incremented when rest is cancelled: timers of previous rests are ignored
+    rest_generation: Arc<AtomicU64>,
+}
+
+// Download is restarted after delay, unless it is restarted by "Refresh now"
+fn idle_after(
+    delay: std::time::Duration,
+    state: Arc<Mutex<DownloadState>>,
+    rest_generation: Arc<AtomicU64>,
+    egui_ctx: egui::Context,
+) {
+    let generation = rest_generation.load(Ordering::Relaxed);
+    run_after(delay, move || {
+        let mut state = state.lock();
+        // Rest is cancelled meanwhile: the state is of other rest or download
+        if state.is_resting() && rest_generation.load(Ordering::Relaxed) == generation {
+            *state = DownloadState::Idle;
+            egui_ctx.request_repaint();
+        }
+    });
+}
+
 fn go_download(
+    api_url_base: &str,
+    api_auth: &ApiAuth,
     delay_if_no_data: std::time::Duration,
     last_sync_point: Option<u64>,
-    state: Arc<Mutex<DownloadState>>,
-    data: Arc<Mutex<Vec<StoredMeshPacket>>>,
+    sync: SyncHandles,
     egui_ctx: egui::Context,
 ) {
+    let SyncHandles {
+        state,
+        data,
+        health,
+        rest_generation,
+    } = sync;
+    if let Err(e) = validate_api_url(api_url_base) {
+        log::error!("Invalid API URL {:?}: {}", api_url_base, e);
+        let backoff = health
+            .lock()
+            .failed(delay_if_no_data, format!("Invalid API URL: {}", e));
+        *state.lock() = DownloadState::ConnectionError;
+        idle_after(backoff, state, rest_generation, egui_ctx);
+        return;
+    }
+
     *state.lock() = DownloadState::WaitHeader;
-    let api_url = format!("{}{}", env!("SOFTNODE_API_URL_BASE"), "/sync");
-    let request = if let Some(sync_point) = last_sync_point {
+    let api_url = format!("{}{}", api_url_base.trim().trim_end_matches('/'), "/sync");
+    let mut request = if let Some(sync_point) = last_sync_point {
         ehttp::Request::get(format!("{}?start={}", api_url, sync_point))
     } else {
         ehttp::Request::get(&api_url)
     };
+    request.headers.insert("Accept", NDJSON_CONTENT_TYPE);
+    if let Some(authorization) = api_auth.header() {
+        request.headers.insert("Authorization", authorization);
+    }
 
     let inner_state = state.clone();
-    let body = Arc::new(Mutex::new(Vec::new()));
-    let inner_body = body.clone();
+    let inner_body = Arc::new(Mutex::new(SyncBody::default()));
     log::info!("Fetching data: {} ...", api_url);
     ehttp::streaming::fetch(
         request,
@@ -263,13 +660,14 @@ fn go_download(
             let part = match part {
                 Err(err) => {
                     log::error!("Fetching error: {}", err);
-                    *state.lock() = DownloadState::Delay;
-                    let state = state.clone();
-                    let egui_ctx = egui_ctx.clone();
-                    run_after(delay_if_no_data, move || {
-                        *state.lock() = DownloadState::Idle;
-                        egui_ctx.request_repaint();
-                    });
+                    let backoff = health.lock().failed(delay_if_no_data, err);
+                    *state.lock() = DownloadState::ConnectionError;
+                    idle_after(
+                        backoff,
+                        state.clone(),
+                        rest_generation.clone(),
+                        egui_ctx.clone(),
+                    );
                     return ControlFlow::Break(());
                 }
                 Ok(part) => part,
@@ -278,6 +676,10 @@ fn go_download(
             match part {
                 ehttp::streaming::Part::Response(response) => match response.status {
                     200 => {
+                        inner_body.lock().encoding = response
+                            .headers
+                            .get("Content-Encoding")
+                            .map(|v| v.trim().to_lowercase());
                         match response
                             .headers
                             .get("Content-Length")
@@ -302,21 +704,72 @@ fn go_download(
                         }
                         ControlFlow::Continue(())
                     }
+                    401 | 403 => {
+                        log::error!("Fetching error: unauthorized: {}", response.status_text);
+                        let backoff = health.lock().failed(
+                            delay_if_no_data,
+                            format!("Unauthorized: {}", response.status_text),
+                        );
+                        *state.lock() = DownloadState::Unauthorized;
+                        idle_after(
+                            backoff,
+                            state.clone(),
+                            rest_generation.clone(),
+                            egui_ctx.clone(),
+                        );
+                        ControlFlow::Break(())
+                    }
                     _ => {
                         log::error!(
                             "Fetching error: status code={}: {}",
                             response.status,
                             response.status_text
                         );
-                        *state.lock() = DownloadState::Idle;
-                        egui_ctx.request_repaint();
+                        let backoff = health.lock().failed(
+                            delay_if_no_data,
+                            format!("Status {}: {}", response.status, response.status_text),
+                        );
+                        *state.lock() = DownloadState::ConnectionError;
+                        idle_after(
+                            backoff,
+                            state.clone(),
+                            rest_generation.clone(),
+                            egui_ctx.clone(),
+                        );
                         ControlFlow::Break(())
                     }
                 },
                 ehttp::streaming::Part::Chunk(chunk) => {
                     let mut body = inner_body.lock();
                     if !chunk.is_empty() {
-                        body.extend_from_slice(&chunk);
+                        // Complete records are committed at once, so the dropped
+                        // connection is resumed from the last received record
+                        let parsed = body.push_chunk(&chunk).and_then(|()| {
+                            if body.is_ndjson() == Some(true) {
+                                parse_ndjson_lines(&mut body.buffer, &data)
+                            } else {
+                                Ok(0)
+                            }
+                        });
+                        match parsed {
+                            Ok(0) => {}
+                            Ok(count) => {
+                                body.records += count;
+                                egui_ctx.request_repaint();
+                            }
+                            Err(e) => {
+                                log::error!("Fetching data error: {}", e);
+                                let backoff = health.lock().failed(delay_if_no_data, e);
+                                *inner_state.lock() = DownloadState::Delay;
+                                idle_after(
+                                    backoff,
+                                    state.clone(),
+                                    rest_generation.clone(),
+                                    egui_ctx.clone(),
+                                );
+                                return ControlFlow::Break(());
+                            }
+                        }
 
                         let next_state = match *inner_state.lock() {
                             DownloadState::Idle
@@ -324,53 +777,52 @@ fn go_download(
                             | DownloadState::Download => DownloadState::Download,
                             DownloadState::DownloadWithSize(_, full_size) => {
                                 DownloadState::DownloadWithSize(
-                                    body.len() as f32 / full_size as f32 * 100.0,
+                                    body.downloaded as f32 / full_size as f32 * 100.0,
                                     full_size,
                                 )
                             }
-                            DownloadState::Delay | DownloadState::Parse => unreachable!(),
+                            DownloadState::Delay
+                            | DownloadState::Parse
+                            | DownloadState::ConnectionError
+                            | DownloadState::Unauthorized => unreachable!(),
                         };
                         *inner_state.lock() = next_state;
                         ControlFlow::Continue(())
                     } else {
-                        if body.len() != 0 {
+                        if let Err(e) = body.finish() {
+                            log::error!("Fetching data error: {}", e);
+                            let backoff = health.lock().failed(delay_if_no_data, e);
+                            *inner_state.lock() = DownloadState::Delay;
+                            idle_after(
+                                backoff,
+                                state.clone(),
+                                rest_generation.clone(),
+                                egui_ctx.clone(),
+                            );
+                            return ControlFlow::Break(());
+                        }
+
+                        if body.is_ndjson() == Some(false) {
                             *inner_state.lock() = DownloadState::Parse;
-                            match serde_json::from_slice::<Vec<StoredMeshPacket>>(body.as_slice()) {
-                                Ok(mut new_data) => {
-                                    log::info!("Fetched {} packets", new_data.len());
-                                    if new_data.is_empty() {
-                                        *state.lock() = DownloadState::Delay;
-                                        let state = state.clone();
-                                        let egui_ctx = egui_ctx.clone();
-                                        run_after(delay_if_no_data, move || {
-                                            *state.lock() = DownloadState::Idle;
-                                            egui_ctx.request_repaint();
-                                        });
-                                    } else {
-                                        data.lock().append(&mut new_data);
-                                        *state.lock() = DownloadState::Idle;
-                                        egui_ctx.request_repaint();
-                                    }
-                                }
-                                Err(e) => {
-                                    log::error!("Fetching json error: {}", e);
-                                    *inner_state.lock() = DownloadState::Delay;
-                                    let state = state.clone();
-                                    let egui_ctx = egui_ctx.clone();
-                                    run_after(delay_if_no_data, move || {
-                                        *state.lock() = DownloadState::Idle;
-                                        egui_ctx.request_repaint();
-                                    });
-                                }
-                            }
-                        } else {
+                        }
+                        match body.parse_rest(&data) {
+                            Ok(count) => body.records += count,
+                            Err(e) => log::error!("Fetching json error: {}", e),
+                        }
+
+                        log::info!("Fetched {} packets", body.records);
+                        health.lock().succeeded();
+                        if body.records == 0 {
                             *inner_state.lock() = DownloadState::Delay;
-                            let state = state.clone();
-                            let egui_ctx = egui_ctx.clone();
-                            run_after(delay_if_no_data, move || {
-                                *state.lock() = DownloadState::Idle;
-                                egui_ctx.request_repaint();
-                            });
+                            idle_after(
+                                delay_if_no_data,
+                                state.clone(),
+                                rest_generation.clone(),
+                                egui_ctx.clone(),
+                            );
+                        } else {
+                            *state.lock() = DownloadState::Idle;
+                            egui_ctx.request_repaint();
                         }
                         ControlFlow::Break(())
                     }
@@ -423,22 +875,59 @@ fn find_compromised_pkeys(node_id: NodeId, nodes: &mut HashMap<NodeId, NodeInfo>
     }
 }
 
+// Node is verified in the NodeBook, but announces another key or none
+fn is_verified_key_changed(nodebook: &NodeBook, node_info: &NodeInfo) -> bool {
+    match (
+        nodebook.verified_key(&node_info.node_id),
+        node_info.extended_info_history.last(),
+    ) {
+        (Some(verified_key), Some(extended)) => extended.pkey.key() != Some(verified_key),
+        _ => false,
+    }
+}
+
+// Time for processing of downloaded packets per frame. Web build decrypts
+// on the main thread, and the whole backlog at once freezes UI
+#[cfg(target_arch = "wasm32")]
+const DECRYPT_BUDGET_MS: Option<i64> = Some(8);
+#[cfg(not(target_arch = "wasm32"))]
+const DECRYPT_BUDGET_MS: Option<i64> = None;
+
 impl SoftNodeApp {
     fn update_data(&mut self, ctx: &egui::Context) -> bool {
-        let download_state = *self.download_state.lock();
-        if matches!(download_state, DownloadState::Delay)
-            || matches!(download_state, DownloadState::Idle)
-        {
-            let mut data: Vec<StoredMeshPacket> = self.download_data.lock().drain(..).collect();
+        if self.offline {
+            return false;
+        }
+        let download_state = *self.sync.state.lock();
+        // Streamed records are applied while download is still in progress
+        if matches!(
+            download_state,
+            DownloadState::Delay
+                | DownloadState::ConnectionError
+                | DownloadState::Unauthorized
+                | DownloadState::Idle
+                | DownloadState::Download
+                | DownloadState::DownloadWithSize(_, _)
+        ) {
+            let data: Vec<StoredMeshPacket> = self.sync.data.lock().drain(..).collect();
             if let Some(last_record) = data.last() {
                 self.last_sync_point = Some(last_record.sequence_number);
             }
             let mut affected_nodes = Vec::new();
             let mut node_info_changed = Vec::new();
-
-            for stored_mesh_packet in data.drain(..) {
+            let started = Utc::now();
+            let mut processed = 0;
+            let mut data = data.into_iter();
+
+            for stored_mesh_packet in data.by_ref() {
+                processed += 1;
+                // Server could send overlapped ranges after reconnect
+                if !self.seen_packets.insert(&stored_mesh_packet) {
+                    continue;
+                }
                 let node_id = stored_mesh_packet.header.from;
-                let stored_mesh_packet = stored_mesh_packet.decrypt(&self.keyring);
+                let stored_mesh_packet =
+                    stored_mesh_packet.decrypt(&self.keyring, self.persistent.try_all_channel_keys);
 
                 if let Some(gateway_id) = stored_mesh_packet.gateway {
                     let gateway_entry =
@@ -449,7 +938,8 @@ impl SoftNodeApp {
                                 ..Default::default()
                             });
 
-                    gateway_entry.update_as_gateway(&stored_mesh_packet);
+                    gateway_entry
+                        .update_as_gateway(&stored_mesh_packet, &self.persistent.radio_thresholds);
                 }
 
                 let entry = self.nodes.entry(node_id).or_insert_with(|| data::NodeInfo {
@@ -457,12 +947,37 @@ impl SoftNodeApp {
                     ..Default::default()
                 });
 
-                entry.update(&stored_mesh_packet, &self.nodebook);
+                entry.update(
+                    &stored_mesh_packet,
+                    &self.nodebook,
+                    &self.persistent.radio_thresholds,
+                    &self.persistent.packet_time,
+                );
+                self.decrypt_failures.push(&stored_mesh_packet);
+                self.dashboard.push(&stored_mesh_packet);
                 self.journal.push(stored_mesh_packet.clone().into());
                 if is_node_info(&stored_mesh_packet) {
                     node_info_changed.push(node_id);
                 }
                 affected_nodes.push(node_id);
+
+                if let Some(budget) = DECRYPT_BUDGET_MS
+                    && (Utc::now() - started).num_milliseconds() >= budget
+                {
+                    break;
+                }
+            }
+
+            // Rest of the backlog is processed in the next frames
+            let rest = data.collect::<Vec<_>>();
+            if rest.is_empty() {
+                self.ingest_progress = None;
+            } else {
+                let (done, _) = self.ingest_progress.unwrap_or_default();
+                let done = done + processed;
+                self.ingest_progress = Some((done, done + rest.len()));
+                self.sync.data.lock().splice(0..0, rest);
+                ctx.request_repaint();
             }
 
             for node_id in affected_nodes {
@@ -470,7 +985,12 @@ impl SoftNodeApp {
                     if node_info.position.is_empty()
                         && (!node_info.gateway_for.is_empty() || !node_info.gatewayed_by.is_empty())
                     {
-                        assume_position(node_info, &self.nodes, &self.nodebook)
+                        assume_position(
+                            node_info,
+                            &self.nodes,
+                            &self.nodebook,
+                            &self.persistent.assume_config,
+                        )
                     } else {
                         None
                     }
@@ -482,40 +1002,39 @@ impl SoftNodeApp {
                     .and_modify(|v| v.assumed_position = assumed_position);
             }
 
-            self.channel_stats.clear();
-            for journal_item in self.journal.iter() {
-                let stats_key = if let Some(channel) = self
-                    .keyring
-                    .info_for_channel(journal_item.from, journal_item.channel)
-                {
-                    ChannelPublicy::Famous(channel.clone())
-                } else {
-                    ChannelPublicy::Underground(journal_item.channel)
-                };
-
-                let channel_entry = self.channel_stats.entry(stats_key).or_default();
-                channel_entry.messages += 1;
-                channel_entry.participants.insert(journal_item.from);
-                if journal_item.is_encrypted {
-                    channel_entry.encrypted += 1;
-                } else {
-                    channel_entry.decrypted += 1;
-                }
-                if journal_item.is_pki {
-                    channel_entry.pki_messages += 1;
+            self.update_channel_stats();
+
+            if !node_info_changed.is_empty() {
+                for node_id in node_info_changed {
+                    find_compromised_pkeys(node_id, &mut self.nodes);
+                    if let Some(node_info) = self.nodes.get(&node_id) {
+                        self.alerts
+                            .check_key_change(node_info, self.nodebook.verified_key(&node_id));
+                        if is_verified_key_changed(&self.nodebook, node_info) {
+                            log::warn!("Verified key of {} is changed", node_id);
+                            self.key_changes.insert(node_id);
+                        }
+                    }
                 }
+                self.dashboard.update_keys(&self.nodes);
             }
 
-            for node_id in node_info_changed {
-                find_compromised_pkeys(node_id, &mut self.nodes);
+            // Received backlog is history, rules are armed after it
+            if self.ingest_progress.is_none() && download_state.is_resting() {
+                self.rule_engine.evaluate(
+                    &self.persistent.alert_rules,
+                    &self.nodes,
+                    &mut self.alerts,
+                );
             }
 
             if matches!(download_state, DownloadState::Idle) {
                 go_download(
+                    &self.persistent.api_url,
+                    &self.api_auth,
                     self.persistent.update_interval_secs,
                     self.last_sync_point,
-                    self.download_state.clone(),
-                    self.download_data.clone(),
+                    self.sync.clone(),
                     ctx.clone(),
                 );
             }
@@ -523,16 +1042,183 @@ impl SoftNodeApp {
 
         false
     }
+
+    // Cancels rest between syncs, download is started by `update_data`
+    fn refresh_now(&mut self) {
+        let mut state = self.sync.state.lock();
+        if state.is_resting() {
+            self.sync.rest_generation.fetch_add(1, Ordering::Relaxed);
+            *state = DownloadState::Idle;
+        }
+    }
+
+    fn update_channel_stats(&mut self) {
+        self.channel_stats.clear();
+        for journal_item in self.journal.iter() {
+            let stats_key = if let Some(channel) = self
+                .keyring
+                .info_for_channel(journal_item.from, journal_item.channel)
+            {
+                ChannelPublicy::Famous(channel.clone())
+            } else {
+                ChannelPublicy::Underground(journal_item.channel)
+            };
+
+            let channel_entry = self.channel_stats.entry(stats_key).or_default();
+            channel_entry.messages += 1;
+            channel_entry.participants.insert(journal_item.from);
+            if journal_item.is_encrypted {
+                channel_entry.encrypted += 1;
+            } else {
+                channel_entry.decrypted += 1;
+            }
+            if journal_item.is_pki {
+                channel_entry.pki_messages += 1;
+            }
+        }
+    }
+
+    fn run_command(&mut self, command: Command) {
+        let roster = &mut self.persistent.roster;
+        let next_panel = match command {
+            Command::Journal => Panel::Journal,
+            Command::Telemetry => Panel::Telemetry(Box::new(Telemetry {})),
+            Command::Map => Panel::Map,
+            Command::Topology => Panel::Topology(Default::default()),
+            Command::Dashboard => Panel::Dashboard,
+            Command::DecryptFailures => Panel::DecryptFailures,
+            Command::Alerts => Panel::Alerts,
+            Command::Session => Panel::Session(Default::default()),
+            Command::Settings => Panel::Settings(Box::new(Settings::new(&self.keyring))),
+            Command::ToggleRoster => {
+                roster.show = !roster.show;
+                return;
+            }
+            Command::FocusSearch => {
+                roster.show = true;
+                roster.focus_filter();
+                return;
+            }
+            Command::GoToNode(node_id) => {
+                roster.show = true;
+                roster.focus(node_id);
+                return;
+            }
+            // Handled by the palette
+            Command::Help => return,
+        };
+        self.persistent.active_panel = next_panel;
+        roster.show = false;
+    }
+
+    // Drops received data and downloads it again, decrypting with the current keyring
+    fn resync(&mut self, ctx: &egui::Context) {
+        self.offline = false;
+        self.last_sync_point = None;
+        self.sync.state = Default::default();
+        self.sync.data = Default::default();
+        self.sync.health = Default::default();
+        self.ingest_progress = None;
+        self.seen_packets.clear();
+        self.key_changes.clear();
+        go_download(
+            &self.persistent.api_url,
+            &self.api_auth,
+            self.persistent.update_interval_secs,
+            self.last_sync_point,
+            self.sync.clone(),
+            ctx.clone(),
+        );
+        self.bootstrap_done = false;
+        self.nodes.clear();
+        self.journal.clear();
+        self.decrypt_failures.clear();
+        self.alerts.clear();
+        self.rule_engine.clear();
+        self.dashboard.clear();
+        ctx.request_repaint();
+    }
+
+    // Warning stays until the key is verified again, or verification is dropped, or dismissed
+    fn key_changes_ui(&mut self, ctx: &egui::Context) {
+        self.key_changes.retain(|node_id| {
+            self.nodes
+                .get(node_id)
+                .is_some_and(|node_info| is_verified_key_changed(&self.nodebook, node_info))
+        });
+        if self.key_changes.is_empty() {
+            return;
+        }
+
+        let mut key_changes = self.key_changes.iter().copied().collect::<Vec<_>>();
+        key_changes.sort();
+        egui::Window::new(RichText::new("⚠ Verified key changed").color(egui::Color32::RED))
+            .id(egui::Id::new("key_changes"))
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label("Nodes announce keys which differ from the keys you verified");
+                egui::Grid::new("key_changes_grid").show(ui, |ui| {
+                    for node_id in key_changes {
+                        ui.label(RichText::new(node_id.to_string()).strong());
+                        if let Some(extended) = self
+                            .nodes
+                            .get(&node_id)
+                            .and_then(|node_info| node_info.extended_info_history.last())
+                        {
+                            ui.label(format!("{} {}", extended.short_name, extended.long_name));
+                        } else {
+                            ui.label("");
+                        }
+                        if ui.button("Show").clicked() {
+                            self.persistent.roster.show = true;
+                            self.persistent.roster.focus(node_id);
+                        }
+                        if ui.button("Dismiss").clicked() {
+                            self.key_changes.remove(&node_id);
+                        }
+                        ui.end_row();
+                    }
+                });
+            });
+    }
+
+    // Replaces the live session with the bundle's one
+    fn load_session(&mut self, bundle: session::Bundle) {
+        self.offline = true;
+        // Running download is left with its own buffers
+        self.sync.state = Default::default();
+        self.sync.data = Default::default();
+        self.ingest_progress = None;
+        self.seen_packets.clear();
+        self.last_sync_point = None;
+        self.journal = bundle.journal;
+        self.nodes = bundle.nodes;
+        self.nodebook
+            .merge(bundle.nodebook, node_book::MergeStrategy::KeepNewest);
+        // Built from packets, which are not bundled
+        self.decrypt_failures = Default::default();
+        self.alerts = Default::default();
+        self.rule_engine.clear();
+        self.dashboard = Default::default();
+        self.dashboard.update_keys(&self.nodes);
+        self.update_channel_stats();
+    }
 }
 
 impl SoftNodeApp {
     fn update_central_panel(&mut self, ctx: &egui::Context) {
+        let mut imported_session = None;
         match &mut self.persistent.active_panel {
             Panel::Journal => {
                 egui::CentralPanel::default().show(ctx, |ui| {
-                    self.persistent
-                        .journal
-                        .ui(ui, &self.journal, &mut self.persistent.node_filter)
+                    self.persistent.journal.ui(
+                        ui,
+                        &self.journal,
+                        &mut self.persistent.node_filter,
+                        &self.nodebook,
+                        &self.nodes,
+                    )
                 });
             }
             Panel::Telemetry(telemetry) => {
@@ -566,6 +1252,7 @@ impl SoftNodeApp {
                                             title,
                                             *telemetry_variant,
                                             telemetry_store,
+                                            Some(*node_id),
                                         ));
                                     }
                                 }
@@ -573,6 +1260,20 @@ impl SoftNodeApp {
                         }
                     }
 
+                    // Node's color is used only if the node has single plot,
+                    // otherwise plots of the node would be indistinguishable
+                    let telemetry_list = telemetry_list
+                        .iter()
+                        .map(|(title, telemetry_variant, telemetry_store, node_id)| {
+                            let plots_of_node =
+                                telemetry_list.iter().filter(|v| v.3 == *node_id).count();
+                            let color = node_id
+                                .filter(|_| plots_of_node == 1)
+                                .map(|node_id| self.persistent.map.node_colors().get(node_id));
+                            (title.clone(), *telemetry_variant, *telemetry_store, color)
+                        })
+                        .collect::<Vec<_>>();
+
                     if telemetry_list.len() != 0 {
                         telemetry.ui(
                             ui,
@@ -586,28 +1287,24 @@ impl SoftNodeApp {
                     }
                 });
             }
-            Panel::Settings(settings) => {
+            Panel::Settings(_) => {
+                // Panel is taken out, settings edit the rest of persistent data
+                let Panel::Settings(mut settings) =
+                    std::mem::replace(&mut self.persistent.active_panel, Panel::Journal)
+                else {
+                    unreachable!()
+                };
                 if settings.ui(
                     ctx,
+                    &mut self.persistent,
                     &mut self.keyring,
-                    &mut self.persistent.telemetry_formatter,
+                    &mut self.nodebook,
+                    &mut self.api_auth,
                     &self.channel_stats,
                 ) {
-                    self.last_sync_point = None;
-                    self.download_state = Default::default();
-                    self.download_data = Default::default();
-                    go_download(
-                        self.persistent.update_interval_secs,
-                        self.last_sync_point,
-                        self.download_state.clone(),
-                        self.download_data.clone(),
-                        ctx.clone(),
-                    );
-                    self.bootstrap_done = false;
-                    self.nodes.clear();
-                    self.journal.clear();
-                    self.persistent.active_panel = Panel::Journal;
-                    ctx.request_repaint();
+                    self.resync(ctx);
+                } else {
+                    self.persistent.active_panel = Panel::Settings(settings);
                 }
             }
             Panel::Rssi(node_id, telemetry) => {
@@ -651,6 +1348,7 @@ impl SoftNodeApp {
                             telemetry.ui(
                                 ui,
                                 &self.nodes,
+                                self.persistent.map.node_colors(),
                                 start_datetime,
                                 rssi_per_gw_sorted,
                                 None,
@@ -713,6 +1411,7 @@ impl SoftNodeApp {
                             telemetry.ui(
                                 ui,
                                 &self.nodes,
+                                self.persistent.map.node_colors(),
                                 start_datetime,
                                 hops_per_gw_sorted,
                                 Some(dup_packets),
@@ -758,10 +1457,35 @@ impl SoftNodeApp {
                     sorted.sort_by_key(|(k, _)| *k);
 
                     egui::CentralPanel::default().show(ctx, |ui| {
+                        ui.horizontal(|ui| {
+                            if ui
+                                .button("📋 KML")
+                                .on_hover_text("Copy heard nodes as KML placemarks")
+                                .clicked()
+                            {
+                                ui.ctx().copy_text(coverage_export::export_kml(
+                                    gateway_info,
+                                    &self.nodes,
+                                    &self.nodebook,
+                                ));
+                            }
+                            if ui
+                                .button("📋 CSV")
+                                .on_hover_text("Copy heard nodes as CSV for heatmap tools")
+                                .clicked()
+                            {
+                                ui.ctx().copy_text(coverage_export::export_csv(
+                                    gateway_info,
+                                    &self.nodes,
+                                    &self.nodebook,
+                                ));
+                            }
+                        });
                         if sorted.len() != 0 {
                             telemetry.ui(
                                 ui,
                                 &self.nodes,
+                                self.persistent.map.node_colors(),
                                 start_datetime,
                                 sorted,
                                 None,
@@ -784,6 +1508,8 @@ impl SoftNodeApp {
                         &mut self.persistent.node_filter,
                         &self.nodes,
                         &mut self.nodebook,
+                        &self.persistent.tile_cache,
+                        &self.persistent.telemetry_formatter,
                     )
                 });
             }
@@ -827,6 +1553,7 @@ impl SoftNodeApp {
                             telemetry.ui(
                                 ui,
                                 &self.nodes,
+                                self.persistent.map.node_colors(),
                                 start_datetime,
                                 sorted,
                                 Some(dup_packets),
@@ -840,6 +1567,74 @@ impl SoftNodeApp {
                     });
                 }
             }
+            Panel::Elevation(source_id, destination_id, profile) => {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    if let (Some(source), Some(destination)) =
+                        (self.nodes.get(source_id), self.nodes.get(destination_id))
+                    {
+                        profile.ui(ui, source, destination, &self.nodebook);
+                    } else {
+                        ui.label("No data");
+                    }
+                });
+            }
+            Panel::Compare(first_id, second_id) => {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    if let (Some(first), Some(second)) =
+                        (self.nodes.get(first_id), self.nodes.get(second_id))
+                    {
+                        compare::ui(ui, first, second, &self.nodes);
+                    } else {
+                        ui.label("No data");
+                    }
+                });
+            }
+            Panel::DecryptFailures => {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    self.decrypt_failures.ui(ui, &self.nodes);
+                });
+            }
+            Panel::Alerts => {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    ui.collapsing("Rules", |ui| {
+                        if self.persistent.alert_rules.ui(ui) {
+                            self.rule_engine.reset_states();
+                        }
+                    });
+                    ui.separator();
+                    if let Some(node_id) = self.alerts.ui(ui, &self.nodes) {
+                        self.persistent.roster.show = true;
+                        self.persistent.roster.focus(node_id);
+                    }
+                });
+            }
+            Panel::Dashboard => {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    self.dashboard.ui(ui);
+                });
+            }
+            Panel::Activity(node_id, activity) => {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    if let Some(node_info) = self.nodes.get(node_id) {
+                        activity.ui(ui, node_info);
+                    } else {
+                        ui.label("No data");
+                    }
+                });
+            }
+            Panel::Topology(topology) => {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    if let Some(node_id) = topology.ui(
+                        ui,
+                        &self.nodes,
+                        self.persistent.map.node_colors(),
+                        self.persistent.roster.focused(),
+                    ) {
+                        self.persistent.roster.focus(node_id);
+                        self.persistent.roster.show = true;
+                    }
+                });
+            }
             Panel::NodeDump => {
                 egui::CentralPanel::default().show(ctx, |ui| {
                     self.persistent.node_dump.ui(
@@ -851,18 +1646,30 @@ impl SoftNodeApp {
                     )
                 });
             }
+            Panel::Session(session) => {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    imported_session =
+                        session.ui(ui, &self.journal, &self.nodes, &self.nodebook, self.offline);
+                });
+            }
         };
+
+        if let Some(bundle) = imported_session {
+            self.load_session(bundle);
+        }
     }
 }
 
 const PERSISTENT_KEYRING_KEY: &str = "keyring";
 const PERSISTENT_NODEBOOK_KEY: &str = "node_book";
+const PERSISTENT_API_AUTH_KEY: &str = "api_auth";
 
 impl eframe::App for SoftNodeApp {
     /// Called by the framework to save state before shutdown.
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
         eframe::set_value(storage, PERSISTENT_KEYRING_KEY, &self.keyring);
         eframe::set_value(storage, PERSISTENT_NODEBOOK_KEY, &self.nodebook);
+        eframe::set_value(storage, PERSISTENT_API_AUTH_KEY, &self.api_auth);
         self.persistent.save(storage);
     }
 
@@ -874,6 +1681,9 @@ impl eframe::App for SoftNodeApp {
             });
             return;
         }
+        if let Some(command) = self.command_palette.ui(ctx, &self.nodes) {
+            self.run_command(command);
+        }
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             // The top panel is often a good place for a menu bar:
 
@@ -907,6 +1717,20 @@ impl eframe::App for SoftNodeApp {
                                     format!("Income hops ({})", node_id)
                                 }
                                 Panel::NodeDump => format!("Text"),
+                                Panel::Elevation(source_id, destination_id, _) => {
+                                    format!("Elevation {} ➡ {}", source_id, destination_id)
+                                }
+                                Panel::Compare(first_id, second_id) => {
+                                    format!("Compare {} ⚖ {}", first_id, second_id)
+                                }
+                                Panel::DecryptFailures => "Decrypt failures".into(),
+                                Panel::Alerts => "Alerts".into(),
+                                Panel::Dashboard => "Dashboard".into(),
+                                Panel::Topology(_) => "Topology".into(),
+                                Panel::Activity(node_id, _) => {
+                                    format!("Activity {}", node_id)
+                                }
+                                Panel::Session(_) => "Session".into(),
                             };
 
                             ui.menu_button(menu_text, |ui| {
@@ -916,7 +1740,8 @@ impl eframe::App for SoftNodeApp {
                                 }
 
                                 if ui.button("Telemetry").clicked() {
-                                    self.persistent.active_panel = Panel::Telemetry(Telemetry {});
+                                    self.persistent.active_panel =
+                                        Panel::Telemetry(Box::new(Telemetry {}));
                                     self.persistent.roster.show = false;
                                 }
 
@@ -924,10 +1749,83 @@ impl eframe::App for SoftNodeApp {
                                     self.persistent.active_panel = Panel::Map;
                                     self.persistent.roster.show = false;
                                 }
+
+                                if ui.button("Topology").clicked() {
+                                    self.persistent.active_panel =
+                                        Panel::Topology(Default::default());
+                                    self.persistent.roster.show = false;
+                                }
+
+                                if ui.button("Dashboard").clicked() {
+                                    self.persistent.active_panel = Panel::Dashboard;
+                                    self.persistent.roster.show = false;
+                                }
+
+                                if ui.button("Decrypt failures").clicked() {
+                                    self.persistent.active_panel = Panel::DecryptFailures;
+                                    self.persistent.roster.show = false;
+                                }
+
+                                if ui.button("Alerts").clicked() {
+                                    self.persistent.active_panel = Panel::Alerts;
+                                    self.persistent.roster.show = false;
+                                }
+
+                                if ui.button("Session").clicked() {
+                                    self.persistent.active_panel =
+                                        Panel::Session(Default::default());
+                                    self.persistent.roster.show = false;
+                                }
                             });
 
-                            let state = *self.download_state.lock();
-                            if !matches!(state, DownloadState::Delay) {
+                            let state = *self.sync.state.lock();
+                            if !self.offline
+                                && ui
+                                    .add_enabled(state.is_resting(), egui::Button::new("🔄"))
+                                    .on_hover_text("Refresh now")
+                                    .on_disabled_hover_text("Sync is in progress")
+                                    .clicked()
+                            {
+                                self.refresh_now();
+                            }
+                            if !self.offline {
+                                self.sync.health.lock().ui(ui);
+                            }
+                            if let Some((done, total)) = self.ingest_progress {
+                                ui.add(
+                                    egui::ProgressBar::new(done as f32 / total as f32)
+                                        .desired_width(150.0)
+                                        .text(format!("Ingesting {}/{}", done, total)),
+                                );
+                            }
+                            let unseen_alerts = self.alerts.unseen();
+                            if unseen_alerts > 0
+                                && ui
+                                    .button(
+                                        RichText::new(format!("🔔 {}", unseen_alerts))
+                                            .color(egui::Color32::ORANGE),
+                                    )
+                                    .on_hover_text("New alerts")
+                                    .clicked()
+                            {
+                                self.persistent.active_panel = Panel::Alerts;
+                            }
+                            if self.offline {
+                                ui.label("Offline session")
+                                    .on_hover_text("Session is loaded from bundle");
+                            } else if matches!(
+                                state,
+                                DownloadState::ConnectionError | DownloadState::Unauthorized
+                            ) {
+                                ui.add(
+                                    egui::Label::new(
+                                        RichText::new(format!("{}", state))
+                                            .color(egui::Color32::LIGHT_RED),
+                                    )
+                                    .wrap_mode(egui::TextWrapMode::Extend),
+                                )
+                                .on_hover_text(&self.persistent.api_url);
+                            } else if !matches!(state, DownloadState::Delay) {
                                 ui.add(
                                     egui::Label::new(format!("{}", state))
                                         .wrap_mode(egui::TextWrapMode::Extend),
@@ -943,10 +1841,22 @@ impl eframe::App for SoftNodeApp {
                                     .clicked()
                                 {
                                     self.persistent.active_panel =
-                                        Panel::Settings(Settings::new(&self.keyring));
+                                        Panel::Settings(Box::new(Settings::new(&self.keyring)));
                                     self.persistent.roster.show = false;
                                 }
 
+                                if ui
+                                    .button("⌨")
+                                    .on_hover_text(format!(
+                                        "Keyboard shortcuts: ?\nCommand palette: {}",
+                                        ui.ctx()
+                                            .format_shortcut(&command_palette::PALETTE_SHORTCUT)
+                                    ))
+                                    .clicked()
+                                {
+                                    self.command_palette.toggle_help();
+                                }
+
                                 let fps = (1.0 / ui.ctx().input(|i| i.stable_dt)).round();
                                 ui.label(RichText::new(fps.to_string()).small());
                             });
@@ -955,6 +1865,11 @@ impl eframe::App for SoftNodeApp {
             });
         });
 
+        self.key_changes_ui(ctx);
+        if self.alerts.toasts_ui(ctx) {
+            self.persistent.active_panel = Panel::Alerts;
+        }
+
         let roster = &mut self.persistent.roster;
         let hide_on_action = ctx.content_rect().width() < 400.0;
 
@@ -964,19 +1879,36 @@ impl eframe::App for SoftNodeApp {
             let mut map_plugin = MapRosterPlugin::new(&mut self.persistent.map);
             let mut journal_plugin = JournalRosterPlugin::new(&mut self.persistent.journal);
             egui::SidePanel::left("Roster").show(ctx, |ui| {
+                let context = RosterContext {
+                    telemetry_formatter: &self.persistent.telemetry_formatter,
+                    nodebook: &mut self.nodebook,
+                    nodes: &self.nodes,
+                    node_filter: &mut self.persistent.node_filter,
+                    keyring: &self.keyring,
+                };
                 if let Some(next_panel) = roster.ui(
                     ui,
-                    &self.persistent.telemetry_formatter,
+                    context,
                     vec![&mut map_plugin, &mut journal_plugin],
-                    &mut self.persistent.node_filter,
-                    &mut self.nodebook,
-                    &self.nodes,
+                    &mut self.persistent.filter_presets,
                     hide_on_action,
                 ) {
                     self.persistent.active_panel = next_panel;
                 }
             });
         }
+        if let Some((node_id, public_key)) = self.persistent.roster.take_trust_request() {
+            match self.keyring.add_remote_peer(node_id, public_key) {
+                Ok(()) => {
+                    log::info!("Trusted key of {}: {}", node_id, public_key);
+                    if let Panel::Settings(settings) = &mut self.persistent.active_panel {
+                        **settings = Settings::new(&self.keyring);
+                    }
+                    self.resync(ctx);
+                }
+                Err(error) => log::error!("Key of {} is not trusted: {}", node_id, error),
+            }
+        }
         self.update_central_panel(ctx);
         // } else {
         //     if list_panel.show {
@@ -1005,3 +1937,71 @@ impl eframe::App for SoftNodeApp {
         // }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use meshtastic_connect::meshtastic;
+
+    fn record(sequence_number: u64) -> String {
+        let packet = StoredMeshPacket::from_mesh_packet(
+            sequence_number,
+            None,
+            "test".into(),
+            None,
+            &meshtastic::MeshPacket::default(),
+        );
+        serde_json::to_string(&packet).unwrap()
+    }
+
+    fn sequence_numbers(data: &Mutex<Vec<StoredMeshPacket>>) -> Vec<u64> {
+        data.lock().iter().map(|v| v.sequence_number).collect()
+    }
+
+    #[test]
+    fn ndjson_detection() {
+        let mut body = SyncBody::default();
+        assert_eq!(body.is_ndjson(), None);
+        body.push_chunk(b" \n").unwrap();
+        assert_eq!(body.is_ndjson(), None);
+        body.push_chunk(b"{").unwrap();
+        assert_eq!(body.is_ndjson(), Some(true));
+
+        let mut body = SyncBody::default();
+        body.push_chunk(b"\n [").unwrap();
+        assert_eq!(body.is_ndjson(), Some(false));
+    }
+
+    #[test]
+    fn ndjson_records_split_across_chunks() {
+        let data = Mutex::new(Vec::new());
+        let stream = format!("{}\n{}\n{}", record(1), record(2), record(3));
+        let (first, second) = stream.as_bytes().split_at(record(1).len() + 10);
+
+        let mut body = SyncBody::default();
+        body.push_chunk(first).unwrap();
+        assert_eq!(parse_ndjson_lines(&mut body.buffer, &data), Ok(1));
+        body.push_chunk(second).unwrap();
+        assert_eq!(parse_ndjson_lines(&mut body.buffer, &data), Ok(1));
+        assert_eq!(sequence_numbers(&data), vec![1, 2]);
+
+        // Tail without trailing newline is parsed on finish
+        assert_eq!(body.parse_rest(&data), Ok(1));
+        assert_eq!(sequence_numbers(&data), vec![1, 2, 3]);
+        assert!(body.buffer.is_empty());
+    }
+
+    #[test]
+    fn array_format_fallback() {
+        let data = Mutex::new(Vec::new());
+        let stream = format!("[{},{}]", record(1), record(2));
+        let (first, second) = stream.as_bytes().split_at(5);
+
+        let mut body = SyncBody::default();
+        body.push_chunk(first).unwrap();
+        body.push_chunk(second).unwrap();
+        assert_eq!(body.is_ndjson(), Some(false));
+        assert_eq!(body.parse_rest(&data), Ok(2));
+        assert_eq!(sequence_numbers(&data), vec![1, 2]);
+    }
+}
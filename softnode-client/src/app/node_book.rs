@@ -1,12 +1,38 @@
-use std::collections::{HashMap, hash_map::Entry};
+use std::collections::{HashMap, HashSet, hash_map::Entry};
 
+use chrono::{DateTime, Utc};
 use geo::{Distance, Haversine, Point};
-use meshtastic_connect::keyring::node_id::NodeId;
+use meshtastic_connect::keyring::{key::Key, node_id::NodeId};
 
 // Custom annotation for a node: manually set position, comment, manual name
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, Copy, PartialEq, Default)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, Default)]
 pub struct NodeAnnotation {
     pub position: Option<Point<f64>>,
+    // Own name of the node, shown instead of the announced one. Empty if unset
+    #[serde(default)]
+    pub alias: String,
+    // Time of the last change, used to merge imported annotations
+    #[serde(default)]
+    pub updated: Option<DateTime<Utc>>,
+}
+
+// How to resolve annotations which exist locally and in the import
+#[derive(Debug, Clone, Copy, PartialEq, Default, serde::Serialize, serde::Deserialize)]
+pub enum MergeStrategy {
+    #[default]
+    KeepNewest,
+    KeepLocal,
+    Overwrite,
+}
+
+impl std::fmt::Display for MergeStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MergeStrategy::KeepNewest => write!(f, "Keep newest"),
+            MergeStrategy::KeepLocal => write!(f, "Keep local"),
+            MergeStrategy::Overwrite => write!(f, "Overwrite"),
+        }
+    }
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -48,6 +74,13 @@ pub struct NodeBook {
     zone_id_generator: ZoneId,
     ignore_zones: HashMap<ZoneId, IgnoreZone>,
     annotation: HashMap<NodeId, NodeAnnotation>,
+    // Observer's own favorites, not the mesh's `is_favorite` flag
+    #[serde(default)]
+    favorites: HashSet<NodeId>,
+    // Public keys verified out-of-band by the observer,
+    // not the firmware's `is_key_manually_verified` flag
+    #[serde(default)]
+    verified_keys: HashMap<NodeId, Key>,
 }
 
 impl Default for NodeBook {
@@ -56,6 +89,8 @@ impl Default for NodeBook {
             zone_id_generator: ZoneId(0),
             ignore_zones: HashMap::new(),
             annotation: HashMap::new(),
+            favorites: HashSet::new(),
+            verified_keys: HashMap::new(),
         };
         new.zone_add(IgnoreZone {
             name: "Null Island".into(),
@@ -68,7 +103,8 @@ impl Default for NodeBook {
 }
 
 impl NodeBook {
-    pub fn point_in_zone(&self, point: Point<f64>) -> Option<ZoneId> {
+    // Zone which suppresses GNSS fixes at the point
+    pub fn is_ignored(&self, point: Point<f64>) -> Option<ZoneId> {
         self.ignore_zones
             .iter()
             .find(|(_, zone)| zone.contains(point))
@@ -82,6 +118,10 @@ impl NodeBook {
             .collect()
     }
 
+    pub fn zone_get(&self, key: &ZoneId) -> Option<&IgnoreZone> {
+        self.ignore_zones.get(key)
+    }
+
     pub fn zones_list(&self) -> Vec<(ZoneId, &IgnoreZone)> {
         self.ignore_zones
             .iter()
@@ -118,4 +158,100 @@ impl NodeBook {
     pub fn node_remove(&mut self, key: &NodeId) {
         self.annotation.remove(key);
     }
+
+    pub fn alias(&self, key: &NodeId) -> Option<&str> {
+        self.annotation
+            .get(key)
+            .map(|annotation| annotation.alias.as_str())
+            .filter(|alias| !alias.is_empty())
+    }
+
+    // Empty alias resets it, annotation without data is removed
+    pub fn set_alias(&mut self, key: NodeId, alias: String) {
+        let annotation = self.annotation.entry(key).or_default();
+        annotation.alias = alias;
+        annotation.updated = Some(Utc::now());
+        if annotation.alias.is_empty() && annotation.position.is_none() {
+            self.annotation.remove(&key);
+        }
+    }
+
+    pub fn is_favorite(&self, key: &NodeId) -> bool {
+        self.favorites.contains(key)
+    }
+
+    pub fn set_favorite(&mut self, key: NodeId, favorite: bool) {
+        if favorite {
+            self.favorites.insert(key);
+        } else {
+            self.favorites.remove(&key);
+        }
+    }
+
+    pub fn verified_key(&self, key: &NodeId) -> Option<Key> {
+        self.verified_keys.get(key).copied()
+    }
+
+    // None drops the verification
+    pub fn set_verified_key(&mut self, key: NodeId, pkey: Option<Key>) {
+        match pkey {
+            Some(pkey) => self.verified_keys.insert(key, pkey),
+            None => self.verified_keys.remove(&key),
+        };
+    }
+
+    pub fn export_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(&self.annotation).map_err(|e| e.to_string())
+    }
+
+    // Returns number of added or replaced annotations
+    pub fn import_json(&mut self, json: &str, strategy: MergeStrategy) -> Result<usize, String> {
+        let imported: HashMap<NodeId, NodeAnnotation> =
+            serde_json::from_str(json).map_err(|e| e.to_string())?;
+
+        Ok(self.merge_annotations(imported, strategy))
+    }
+
+    // Zones and annotations of other NodeBook, favorites and verified keys are observer's own
+    pub fn merge(&mut self, other: NodeBook, strategy: MergeStrategy) -> usize {
+        for zone in other.ignore_zones.into_values() {
+            let exists = self.ignore_zones.values().any(|v| {
+                v.name == zone.name
+                    && v.center == zone.center
+                    && v.radius_meters == zone.radius_meters
+            });
+            if !exists {
+                self.zone_add(zone);
+            }
+        }
+        self.merge_annotations(other.annotation, strategy)
+    }
+
+    fn merge_annotations(
+        &mut self,
+        imported: HashMap<NodeId, NodeAnnotation>,
+        strategy: MergeStrategy,
+    ) -> usize {
+        let mut changed = 0;
+        for (node_id, annotation) in imported {
+            match self.annotation.entry(node_id) {
+                Entry::Occupied(mut entry) => {
+                    let replace = match strategy {
+                        MergeStrategy::KeepNewest => annotation.updated > entry.get().updated,
+                        MergeStrategy::KeepLocal => false,
+                        MergeStrategy::Overwrite => *entry.get() != annotation,
+                    };
+                    if replace {
+                        entry.insert(annotation);
+                        changed += 1;
+                    }
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(annotation);
+                    changed += 1;
+                }
+            }
+        }
+        changed
+    }
 }
@@ -35,7 +35,7 @@ impl NodeDump {
                 {
                     (fix_position, "!")
                 } else if let Some(assumed) = node_info.assumed_position {
-                    (assumed, "?")
+                    (assumed.position, "?")
                 } else if let Some(received) = node_info.position.last() {
                     (lon_lat(received.longitude, received.latitude), " ")
                 } else {
@@ -7,7 +7,11 @@ use std::{
 use base64::{Engine, engine::general_purpose};
 use chrono::{DateTime, Duration, Utc};
 use egui::{Color32, RichText};
-use meshtastic_connect::keyring::{channel::ChannelHash, key::Key, node_id::NodeId};
+use meshtastic_connect::{
+    keyring::{channel::ChannelHash, key::Key, node_id::NodeId},
+    portnum::{PortCategory, portnum_info_raw},
+};
+use regex::Regex;
 use walkers::lon_lat;
 
 use crate::app::{
@@ -16,9 +20,40 @@ use crate::app::{
     node_book::{NodeAnnotation, NodeBook},
 };
 
+// Compiled regex, comparable and serializable by its pattern
+#[derive(Debug, Clone)]
+pub struct FilterRegex(Regex);
+
+impl PartialEq for FilterRegex {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+impl Eq for FilterRegex {}
+
+impl serde::Serialize for FilterRegex {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0.as_str())
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for FilterRegex {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let pattern = String::deserialize(deserializer)?;
+        Regex::new(&pattern)
+            .map(FilterRegex)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
 pub enum FilterVariant {
     Generic(String, String),
+    /// `/pattern/`: regex over node id, short and long names
+    Regex(FilterRegex),
+    /// `"text"`: exact match of node id, short or long name
+    Exact(String),
     PublicPkey(Key),
     ByteNodeId(ByteNodeId),
     NodeId(NodeId),
@@ -71,6 +106,18 @@ enum StaticFilterVariant {
     BoundingBox,
     IsGateway,
     LastSeen(Duration),
+    /// Node is heard by any gateway within the hops distance.
+    /// Unknown distance (hop limit only) is not matched
+    MaxHops(u32),
+    /// Node sent any packet of portnum's category
+    HasPortCategory(PortCategory),
+    /// Newest position is fixed within the duration.
+    /// Position without fix time is stale
+    StalePosition(Duration),
+    /// Node is in observer's favorites of the NodeBook
+    IsFavorite,
+    /// Node announced different public keys over time
+    KeyChanged,
 }
 
 impl StaticFilterVariant {
@@ -79,7 +126,9 @@ impl StaticFilterVariant {
         bbox: &Option<[walkers::Position; 2]>,
         node_info: &NodeInfo,
         node_annotation: Option<&NodeAnnotation>,
+        is_favorite: bool,
         ignore_extended: bool,
+        now: DateTime<Utc>,
     ) -> bool {
         let device_telemetry = [
             TelemetryVariant::UptimeSeconds,
@@ -126,6 +175,7 @@ impl StaticFilterVariant {
                     if let Some(position) =
                         node_annotation.map(|a| a.position).flatten().or(node_info
                             .assumed_position
+                            .map(|v| v.position)
                             .or(node_info
                                 .position
                                 .last()
@@ -157,7 +207,6 @@ impl StaticFilterVariant {
                 return false;
             }
             StaticFilterVariant::LastSeen(duration) => {
-                let now = Utc::now();
                 if let Some(last) = node_info.packet_statistics.last() {
                     return now - last.timestamp < *duration;
                 }
@@ -166,6 +215,29 @@ impl StaticFilterVariant {
             StaticFilterVariant::IsGateway => {
                 return node_info.gateway_for.len() != 0;
             }
+            StaticFilterVariant::MaxHops(max_hops) => {
+                return node_info
+                    .gateway_for
+                    .values()
+                    .flatten()
+                    .chain(node_info.gatewayed_by.values())
+                    .filter_map(|v| v.hop_distance)
+                    .min()
+                    .is_some_and(|hop_distance| hop_distance <= *max_hops);
+            }
+            StaticFilterVariant::HasPortCategory(category) => {
+                return node_info
+                    .seen_portnums
+                    .keys()
+                    .any(|portnum| portnum_info_raw(*portnum).category == *category);
+            }
+            StaticFilterVariant::StalePosition(duration) => {
+                return node_info
+                    .position_fixed_at()
+                    .is_some_and(|fixed_at| now - fixed_at < *duration);
+            }
+            StaticFilterVariant::IsFavorite => return is_favorite,
+            StaticFilterVariant::KeyChanged => return node_info.has_key_changed(),
             StaticFilterVariant::HasHealthTelemetry => {
                 for (variant, telemetry) in node_info.telemetry.iter() {
                     if !health_telemetry.contains(variant) {
@@ -204,6 +276,11 @@ impl StaticFilterVariant {
                 StaticFilterVariant::HasDeviceTelemetry => {}
                 StaticFilterVariant::LastSeen(_) => {}
                 StaticFilterVariant::IsGateway => {}
+                StaticFilterVariant::MaxHops(_) => {}
+                StaticFilterVariant::HasPortCategory(_) => {}
+                StaticFilterVariant::StalePosition(_) => {}
+                StaticFilterVariant::IsFavorite => {}
+                StaticFilterVariant::KeyChanged => {}
                 StaticFilterVariant::HasHealthTelemetry => {}
             }
         }
@@ -213,10 +290,12 @@ impl StaticFilterVariant {
 }
 
 impl FilterVariant {
+    // `alias` is node's name from the NodeBook, searched as node's names
     pub fn matches(
         &self,
         node_info: &NodeInfo,
         nodes: &HashMap<NodeId, NodeInfo>,
+        alias: Option<&str>,
         seen_window: Option<(DateTime<Utc>, DateTime<Utc>)>,
     ) -> bool {
         match self {
@@ -226,11 +305,26 @@ impl FilterVariant {
                     .to_string()
                     .to_lowercase()
                     .contains(normalized_string)
+                    || alias.is_some_and(|v| v.to_lowercase().contains(normalized_string))
                 {
                     return true;
                 }
                 /* drop down to check extended info */
             }
+            FilterVariant::Regex(regex) => {
+                if regex.0.is_match(&node_info.node_id.to_string())
+                    || alias.is_some_and(|v| regex.0.is_match(v))
+                {
+                    return true;
+                }
+                /* drop down to check extended info */
+            }
+            FilterVariant::Exact(text) => {
+                if node_info.node_id.to_string() == *text || alias == Some(text.as_str()) {
+                    return true;
+                }
+                /* drop down to check extended info */
+            }
             FilterVariant::PublicPkey(_key) => {}
             FilterVariant::ByteNodeId(byte_node_id) => return *byte_node_id == node_info.node_id,
             FilterVariant::NodeId(node_id) => return *node_id == node_info.node_id,
@@ -299,6 +393,13 @@ impl FilterVariant {
                             .to_lowercase()
                             .contains(normalized_string);
                 }
+                FilterVariant::Regex(regex) => {
+                    return regex.0.is_match(&extended.short_name)
+                        || regex.0.is_match(&extended.long_name);
+                }
+                FilterVariant::Exact(text) => {
+                    return extended.short_name == *text || extended.long_name == *text;
+                }
                 FilterVariant::PublicPkey(key) => match extended.pkey {
                     PublicKey::None => return false,
                     PublicKey::Key(node_key) => return *key == node_key,
@@ -317,12 +418,11 @@ impl FilterVariant {
     }
 }
 
-#[derive(serde::Deserialize, serde::Serialize)]
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
 enum KnownNodesFilter {
     Unspecified,
     Known,
     Unknown,
-    // Favorite
 }
 
 #[derive(serde::Deserialize, serde::Serialize)]
@@ -333,6 +433,117 @@ pub struct NodeFilter {
     filter_origin: Option<String>,
     // Bounding box for filtering nodes based on their positions
     bbox: Option<[walkers::Position; 2]>,
+    // Error of the last `update_filter`, e.g. invalid regex
+    #[serde(skip)]
+    filter_error: Option<String>,
+    // Show nodes as they were at the moment: used by replay on the map
+    #[serde(skip)]
+    before: Option<DateTime<Utc>>,
+}
+
+// Named filter's state, could be shared as JSON
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+pub struct FilterPreset {
+    name: String,
+    known_nodes_filter: KnownNodesFilter,
+    filter_parts: Vec<(FilterVariant, bool)>,
+    static_filter: HashSet<StaticFilterVariant>,
+}
+
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+pub struct FilterPresets {
+    presets: Vec<FilterPreset>,
+    #[serde(skip)]
+    name_edit: String,
+    #[serde(skip)]
+    import_edit: String,
+    #[serde(skip)]
+    import_error: Option<String>,
+}
+
+impl FilterPresets {
+    fn save(&mut self, preset: FilterPreset) {
+        if let Some(existing) = self.presets.iter_mut().find(|v| v.name == preset.name) {
+            *existing = preset;
+        } else {
+            self.presets.push(preset);
+        }
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, node_filter: &mut NodeFilter) {
+        let mut remove = None;
+        for (index, preset) in self.presets.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.button(&preset.name).on_hover_text("Apply").clicked() {
+                    node_filter.apply_preset(preset);
+                }
+                if ui
+                    .small_button("📋")
+                    .on_hover_text("Copy as JSON")
+                    .clicked()
+                {
+                    match serde_json::to_string(preset) {
+                        Ok(json) => ui.ctx().copy_text(json),
+                        Err(e) => log::error!("Unable to export filter preset: {}", e),
+                    }
+                }
+                if ui.small_button("🗑").on_hover_text("Delete").clicked() {
+                    remove = Some(index);
+                }
+            });
+        }
+        if let Some(index) = remove {
+            self.presets.remove(index);
+        }
+        if !self.presets.is_empty() {
+            ui.separator();
+        }
+
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.name_edit)
+                    .desired_width(120.0)
+                    .hint_text("Preset name"),
+            );
+            if ui
+                .add_enabled(!self.name_edit.trim().is_empty(), egui::Button::new("💾"))
+                .on_hover_text("Save current filter")
+                .clicked()
+            {
+                let preset = node_filter.preset(self.name_edit.trim());
+                self.save(preset);
+                self.name_edit.clear();
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.import_edit)
+                    .desired_width(120.0)
+                    .hint_text("Preset's JSON"),
+            );
+            if ui
+                .add_enabled(!self.import_edit.is_empty(), egui::Button::new("📥"))
+                .on_hover_text("Import preset")
+                .clicked()
+            {
+                match serde_json::from_str::<FilterPreset>(&self.import_edit) {
+                    Ok(preset) => {
+                        self.import_error = None;
+                        self.import_edit.clear();
+                        self.save(preset);
+                    }
+                    Err(e) => self.import_error = Some(e.to_string()),
+                }
+            }
+        });
+        if let Some(import_error) = &self.import_error {
+            ui.label(
+                RichText::new(import_error)
+                    .small()
+                    .color(Color32::LIGHT_RED),
+            );
+        }
+    }
 }
 
 impl Default for NodeFilter {
@@ -343,6 +554,8 @@ impl Default for NodeFilter {
             static_filter: HashSet::new(),
             filter_origin: None,
             bbox: None,
+            filter_error: None,
+            before: None,
         }
     }
 }
@@ -351,8 +564,10 @@ impl Display for FilterVariant {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             FilterVariant::Generic(orig, _) => write!(f, "{}", orig),
+            FilterVariant::Regex(regex) => write!(f, "/{}/", regex.0.as_str()),
+            FilterVariant::Exact(text) => write!(f, "\"{}\"", text),
             FilterVariant::PublicPkey(key) => write!(f, "{}", key),
-            FilterVariant::ByteNodeId(byte_node_id) => write!(f, "!{}", byte_node_id),
+            FilterVariant::ByteNodeId(byte_node_id) => write!(f, "{}", byte_node_id),
             FilterVariant::NodeId(node_id) => write!(f, "{}", node_id),
             FilterVariant::HopDistance(node_id, min, max) => {
                 if min == max {
@@ -384,6 +599,24 @@ impl NodeFilter {
         Default::default()
     }
 
+    pub fn preset(&self, name: &str) -> FilterPreset {
+        FilterPreset {
+            name: name.to_string(),
+            known_nodes_filter: self.known_nodes_filter.clone(),
+            filter_parts: self.filter_parts.clone(),
+            static_filter: self.static_filter.clone(),
+        }
+    }
+
+    pub fn apply_preset(&mut self, preset: &FilterPreset) {
+        self.known_nodes_filter = preset.known_nodes_filter.clone();
+        self.filter_parts = preset.filter_parts.clone();
+        self.static_filter = preset.static_filter.clone();
+        // Force `update_filter` to parse the next string
+        self.filter_origin = None;
+        self.filter_error = None;
+    }
+
     pub fn set_filters(&mut self, new_filters: &mut Vec<FilterVariant>) {
         self.filter_parts.clear();
         for filter in new_filters.drain(..) {
@@ -395,7 +628,7 @@ impl NodeFilter {
         &self,
         node_info: &NodeInfo,
         nodes: &HashMap<NodeId, NodeInfo>,
-        node_annotation: Option<&NodeAnnotation>,
+        nodebook: &NodeBook,
     ) -> bool {
         let ignore_extended = match self.known_nodes_filter {
             KnownNodesFilter::Unspecified => false,
@@ -413,6 +646,16 @@ impl NodeFilter {
             }
         };
 
+        if let Some(before) = self.before
+            && node_info
+                .packet_statistics
+                .first()
+                .is_none_or(|v| v.timestamp > before)
+        {
+            return false;
+        }
+
+        let now = self.before.unwrap_or_else(Utc::now);
         let seen_window = self
             .static_filter
             .iter()
@@ -420,27 +663,36 @@ impl NodeFilter {
             .last()
             .map(|v| {
                 if let StaticFilterVariant::LastSeen(duration) = v {
-                    let now = Utc::now();
                     (now - *duration, now)
                 } else {
                     unreachable!()
                 }
             });
 
+        let alias = nodebook.alias(&node_info.node_id);
         for (filter_part, enabled) in &self.filter_parts {
             if *enabled {
-                if !filter_part.matches(node_info, nodes, seen_window) {
+                if !filter_part.matches(node_info, nodes, alias, seen_window) {
                     return false;
                 }
             }
         }
 
+        let node_annotation = nodebook.node_get(&node_info.node_id);
+        let is_favorite = nodebook.is_favorite(&node_info.node_id);
         for static_filter in &self.static_filter {
             let position_is_available = !self
                 .static_filter
                 .contains(&StaticFilterVariant::Position(PositionVariant::None));
             let bbox = &position_is_available.then(|| self.bbox).flatten();
-            if !static_filter.matches(bbox, node_info, node_annotation, ignore_extended) {
+            if !static_filter.matches(
+                bbox,
+                node_info,
+                node_annotation,
+                is_favorite,
+                ignore_extended,
+                now,
+            ) {
                 return false;
             }
         }
@@ -457,6 +709,7 @@ impl NodeFilter {
         }
         self.filter_origin = Some(filter.to_string());
         self.filter_parts.clear();
+        self.filter_error = None;
         for unparsed_part in filter.split_whitespace() {
             if let Ok(base64_decoded) = general_purpose::STANDARD.decode(unparsed_part) {
                 if base64_decoded.len() == 32 || base64_decoded.len() == 16 {
@@ -468,6 +721,41 @@ impl NodeFilter {
                 }
             }
 
+            if unparsed_part.len() > 2
+                && unparsed_part.starts_with("/")
+                && unparsed_part.ends_with("/")
+            {
+                let pattern = &unparsed_part[1..unparsed_part.len() - 1];
+                match Regex::new(pattern) {
+                    Ok(regex) => self
+                        .filter_parts
+                        .push((FilterVariant::Regex(FilterRegex(regex)), true)),
+                    Err(e) => {
+                        // Degrade to substring search
+                        self.filter_error = Some(format!("{}: {}", unparsed_part, e));
+                        self.filter_parts.push((
+                            FilterVariant::Generic(
+                                unparsed_part.to_string(),
+                                pattern.to_lowercase(),
+                            ),
+                            true,
+                        ));
+                    }
+                }
+                continue;
+            }
+
+            if unparsed_part.len() > 2
+                && unparsed_part.starts_with("\"")
+                && unparsed_part.ends_with("\"")
+            {
+                self.filter_parts.push((
+                    FilterVariant::Exact(unparsed_part[1..unparsed_part.len() - 1].to_string()),
+                    true,
+                ));
+                continue;
+            }
+
             if unparsed_part.starts_with("%")
                 && unparsed_part.ends_with("h")
                 && let Ok(channel) = ChannelHash::try_from(&unparsed_part[1..])
@@ -475,9 +763,12 @@ impl NodeFilter {
                 self.filter_parts
                     .push((FilterVariant::Channel(channel), true));
                 continue;
-            } else if unparsed_part.starts_with("!*")
-                && unparsed_part.len() <= 2 + 2 /* means: '!*' + '<2 bytes of node id's hex>' */
-                && let Ok(byte_node_id) = ByteNodeId::try_from(&unparsed_part[2..])
+            } else if let Some(byte_part) = unparsed_part
+                .strip_prefix("!*")
+                // `*ef` as shown in labels, `!*ef` is kept for old filters
+                .or_else(|| unparsed_part.strip_prefix("*"))
+                && byte_part.len() <= 2
+                && let Ok(byte_node_id) = ByteNodeId::try_from(byte_part)
             {
                 self.filter_parts
                     .push((FilterVariant::ByteNodeId(byte_node_id), true));
@@ -529,8 +820,11 @@ impl NodeFilter {
         }
     }
 
-    pub fn ui(&mut self, ui: &mut egui::Ui) {
+    pub fn ui(&mut self, ui: &mut egui::Ui, filter_presets: &mut FilterPresets) {
         ui.horizontal_wrapped(|ui| {
+            ui.menu_button("☰", |ui| filter_presets.ui(ui, self))
+                .response
+                .on_hover_text("Filter presets");
             for (filter_part, enabled) in self.filter_parts.iter_mut() {
                 match filter_part {
                     FilterVariant::PublicPkey(pkey) => ui
@@ -540,11 +834,17 @@ impl NodeFilter {
                         .selectable_label(*enabled, format!("📡 {}", node_id))
                         .on_hover_text("Node ID"),
                     FilterVariant::ByteNodeId(byte_node_id) => ui
-                        .selectable_label(*enabled, format!("📡 !{}", byte_node_id))
+                        .selectable_label(*enabled, format!("📡 {}", byte_node_id))
                         .on_hover_text("NodeID's last byte"),
                     FilterVariant::Generic(origin_string, _normalized_string) => {
                         ui.selectable_label(*enabled, format!("{}", origin_string))
                     }
+                    FilterVariant::Regex(regex) => ui
+                        .selectable_label(*enabled, format!("/{}/", regex.0.as_str()))
+                        .on_hover_text("Regular expression"),
+                    FilterVariant::Exact(text) => ui
+                        .selectable_label(*enabled, format!("\"{}\"", text))
+                        .on_hover_text("Exact match"),
                     FilterVariant::HopDistance(node_id, distance_min, distance_max) => {
                         if distance_min == distance_max {
                             ui.selectable_label(
@@ -575,6 +875,14 @@ impl NodeFilter {
                 });
             }
         });
+        if let Some(filter_error) = &self.filter_error {
+            ui.label(
+                RichText::new(filter_error)
+                    .small()
+                    .color(Color32::LIGHT_RED),
+            )
+            .on_hover_text("Invalid regex, substring search is used");
+        }
         ui.horizontal_wrapped(|ui| {
             let position_is_available = !self
                 .static_filter
@@ -640,6 +948,28 @@ impl NodeFilter {
                         "Filter by last seen time: 15 minutes".to_string(),
                     ),
                 ],
+                vec![
+                    (
+                        None,
+                        Arc::new(RichText::new("🐰")),
+                        "Switch on filter by hops distance".to_string(),
+                    ),
+                    (
+                        Some(StaticFilterVariant::MaxHops(0)),
+                        Arc::new(RichText::new("🐰 0")),
+                        "Show nodes heard directly by any gateway".to_string(),
+                    ),
+                    (
+                        Some(StaticFilterVariant::MaxHops(1)),
+                        Arc::new(RichText::new("🐰 ≤1")),
+                        "Show nodes heard by any gateway within 1 hop".to_string(),
+                    ),
+                    (
+                        Some(StaticFilterVariant::MaxHops(3)),
+                        Arc::new(RichText::new("🐰 ≤3")),
+                        "Show nodes heard by any gateway within 3 hops".to_string(),
+                    ),
+                ],
                 vec![
                     (
                         None,
@@ -672,6 +1002,54 @@ impl NodeFilter {
                         "Show nodes without any position".to_string(),
                     ),
                 ],
+                vec![
+                    (
+                        None,
+                        Arc::new(RichText::new("★")),
+                        "Switch on filter by favorites".to_string(),
+                    ),
+                    (
+                        Some(StaticFilterVariant::IsFavorite),
+                        Arc::new(RichText::new("★").color(Color32::GOLD)),
+                        "Show only favorite nodes".to_string(),
+                    ),
+                ],
+                vec![
+                    (
+                        None,
+                        Arc::new(RichText::new("🛰")),
+                        "Switch on filter by position's fix time".to_string(),
+                    ),
+                    (
+                        Some(StaticFilterVariant::StalePosition(Duration::hours(24))),
+                        Arc::new(RichText::new("🛰 24h")),
+                        "Hide nodes with position fixed more than 24 hours ago".to_string(),
+                    ),
+                    (
+                        Some(StaticFilterVariant::StalePosition(Duration::hours(6))),
+                        Arc::new(RichText::new("🛰 6h")),
+                        "Hide nodes with position fixed more than 6 hours ago".to_string(),
+                    ),
+                    (
+                        Some(StaticFilterVariant::StalePosition(Duration::hours(1))),
+                        Arc::new(RichText::new("🛰 1h")),
+                        "Hide nodes with position fixed more than 1 hour ago".to_string(),
+                    ),
+                ],
+                [(
+                    None,
+                    Arc::new(RichText::new("🔌")),
+                    "Switch on filter by sent packets".to_string(),
+                )]
+                .into_iter()
+                .chain(PortCategory::KNOWN.into_iter().map(|category| {
+                    (
+                        Some(StaticFilterVariant::HasPortCategory(category)),
+                        Arc::new(RichText::new(format!("🔌 {}", category))),
+                        format!("Node sent packets of category: {}", category),
+                    )
+                }))
+                .collect(),
             ];
 
             if show_extended {
@@ -700,6 +1078,18 @@ impl NodeFilter {
                             "Show nodes with no public key".to_string(),
                         ),
                     ],
+                    vec![
+                        (
+                            None,
+                            Arc::new(RichText::new("🔁")),
+                            "Switch on filter by public key's change".to_string(),
+                        ),
+                        (
+                            Some(StaticFilterVariant::KeyChanged),
+                            Arc::new(RichText::new("🔁").color(Color32::ORANGE)),
+                            "Show nodes which changed public key".to_string(),
+                        ),
+                    ],
                     vec![
                         (
                             None,
@@ -855,10 +1245,27 @@ impl NodeFilter {
         }
     }
 
+    // Show only nodes which sent packets of the category
+    pub fn set_port_category(&mut self, category: PortCategory) {
+        self.static_filter
+            .retain(|v| !matches!(v, StaticFilterVariant::HasPortCategory(_)));
+        self.static_filter
+            .insert(StaticFilterVariant::HasPortCategory(category));
+    }
+
     // Set bounding box for filtering nodes based on position
     pub fn set_bbox(&mut self, bbox: [walkers::Position; 2]) {
         self.bbox = Some(bbox);
     }
+
+    // Hide nodes and data received after the timestamp
+    pub fn set_before(&mut self, before: Option<DateTime<Utc>>) {
+        self.before = before;
+    }
+
+    pub fn before(&self) -> Option<DateTime<Utc>> {
+        self.before
+    }
 }
 
 #[derive(Clone)]
@@ -872,8 +1279,8 @@ pub struct NodeSeeker<'a> {
 }
 
 impl<'a> NodeSeeker<'a> {
-    pub fn matches(&self, node_info: &NodeInfo, node_annotation: Option<&NodeAnnotation>) -> bool {
-        self.filter.matches(node_info, self.nodes, node_annotation)
+    pub fn matches(&self, node_info: &NodeInfo) -> bool {
+        self.filter.matches(node_info, self.nodes, self.nodebook)
     }
 }
 
@@ -882,9 +1289,7 @@ impl<'a> Iterator for NodeSeeker<'a> {
 
     fn next(&mut self) -> Option<Self::Item> {
         while let Some(node_info) = self.iterator.next() {
-            let node_annotation = self.nodebook.node_get(&node_info.node_id);
-
-            if self.matches(node_info, node_annotation) {
+            if self.matches(node_info) {
                 return Some(node_info);
             }
         }
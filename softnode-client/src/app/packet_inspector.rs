@@ -0,0 +1,570 @@
+// Protobuf field tree of a packet, like protocol tree of Wireshark.
+// Wire format is walked by hand: names and types are taken from schemas of
+// known messages, unknown fields are shown by tag and wire type
+use chrono::DateTime;
+use egui::{CollapsingHeader, RichText};
+use meshtastic_connect::{keyring::node_id::NodeId, meshtastic};
+use prost::Message;
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Uint32,
+    Int32,
+    Sint32,
+    Bool,
+    Sfixed32,
+    Fixed32,
+    Float,
+    // Unix time in seconds, fixed32
+    Time,
+    // Node id, varint or fixed32
+    NodeId,
+    String,
+    Bytes,
+    Enum(fn(i32) -> Option<&'static str>),
+    Message(&'static Schema),
+}
+
+struct Field {
+    tag: u32,
+    name: &'static str,
+    kind: Kind,
+}
+
+struct Schema {
+    name: &'static str,
+    fields: &'static [Field],
+}
+
+const fn field(tag: u32, name: &'static str, kind: Kind) -> Field {
+    Field { tag, name, kind }
+}
+
+fn port_num_name(value: i32) -> Option<&'static str> {
+    meshtastic::PortNum::try_from(value)
+        .ok()
+        .map(|v| v.as_str_name())
+}
+
+fn hardware_model_name(value: i32) -> Option<&'static str> {
+    meshtastic::HardwareModel::try_from(value)
+        .ok()
+        .map(|v| v.as_str_name())
+}
+
+fn role_name(value: i32) -> Option<&'static str> {
+    meshtastic::config::device_config::Role::try_from(value)
+        .ok()
+        .map(|v| v.as_str_name())
+}
+
+fn routing_error_name(value: i32) -> Option<&'static str> {
+    meshtastic::routing::Error::try_from(value)
+        .ok()
+        .map(|v| v.as_str_name())
+}
+
+static DATA: Schema = Schema {
+    name: "Data",
+    fields: &[
+        field(1, "portnum", Kind::Enum(port_num_name)),
+        field(2, "payload", Kind::Bytes),
+        field(3, "want_response", Kind::Bool),
+        field(4, "dest", Kind::NodeId),
+        field(5, "source", Kind::NodeId),
+        field(6, "request_id", Kind::Fixed32),
+        field(7, "reply_id", Kind::Fixed32),
+        field(8, "emoji", Kind::Fixed32),
+        field(9, "bitfield", Kind::Uint32),
+    ],
+};
+
+static POSITION: Schema = Schema {
+    name: "Position",
+    fields: &[
+        field(1, "latitude_i", Kind::Sfixed32),
+        field(2, "longitude_i", Kind::Sfixed32),
+        field(3, "altitude", Kind::Int32),
+        field(4, "time", Kind::Time),
+        field(5, "location_source", Kind::Uint32),
+        field(6, "altitude_source", Kind::Uint32),
+        field(7, "timestamp", Kind::Time),
+        field(8, "timestamp_millis_adjust", Kind::Int32),
+        field(9, "altitude_hae", Kind::Sint32),
+        field(10, "altitude_geoidal_separation", Kind::Sint32),
+        field(11, "pdop", Kind::Uint32),
+        field(12, "hdop", Kind::Uint32),
+        field(13, "vdop", Kind::Uint32),
+        field(14, "gps_accuracy", Kind::Uint32),
+        field(15, "ground_speed", Kind::Uint32),
+        field(16, "ground_track", Kind::Uint32),
+        field(17, "fix_quality", Kind::Uint32),
+        field(18, "fix_type", Kind::Uint32),
+        field(19, "sats_in_view", Kind::Uint32),
+        field(20, "sensor_id", Kind::Uint32),
+        field(21, "next_update", Kind::Uint32),
+        field(22, "seq_number", Kind::Uint32),
+        field(23, "precision_bits", Kind::Uint32),
+    ],
+};
+
+static USER: Schema = Schema {
+    name: "User",
+    fields: &[
+        field(1, "id", Kind::String),
+        field(2, "long_name", Kind::String),
+        field(3, "short_name", Kind::String),
+        field(4, "macaddr", Kind::Bytes),
+        field(5, "hw_model", Kind::Enum(hardware_model_name)),
+        field(6, "is_licensed", Kind::Bool),
+        field(7, "role", Kind::Enum(role_name)),
+        field(8, "public_key", Kind::Bytes),
+        field(9, "is_unmessagable", Kind::Bool),
+    ],
+};
+
+static ROUTE_DISCOVERY: Schema = Schema {
+    name: "RouteDiscovery",
+    fields: &[
+        field(1, "route", Kind::NodeId),
+        field(2, "snr_towards", Kind::Int32),
+        field(3, "route_back", Kind::NodeId),
+        field(4, "snr_back", Kind::Int32),
+    ],
+};
+
+static ROUTING: Schema = Schema {
+    name: "Routing",
+    fields: &[
+        field(1, "route_request", Kind::Message(&ROUTE_DISCOVERY)),
+        field(2, "route_reply", Kind::Message(&ROUTE_DISCOVERY)),
+        field(3, "error_reason", Kind::Enum(routing_error_name)),
+    ],
+};
+
+static NEIGHBOR: Schema = Schema {
+    name: "Neighbor",
+    fields: &[
+        field(1, "node_id", Kind::NodeId),
+        field(2, "snr", Kind::Float),
+        field(3, "last_rx_time", Kind::Time),
+        field(4, "node_broadcast_interval_secs", Kind::Uint32),
+    ],
+};
+
+static NEIGHBOR_INFO: Schema = Schema {
+    name: "NeighborInfo",
+    fields: &[
+        field(1, "node_id", Kind::NodeId),
+        field(2, "last_sent_by_id", Kind::NodeId),
+        field(3, "node_broadcast_interval_secs", Kind::Uint32),
+        field(4, "neighbors", Kind::Message(&NEIGHBOR)),
+    ],
+};
+
+static DEVICE_METRICS: Schema = Schema {
+    name: "DeviceMetrics",
+    fields: &[
+        field(1, "battery_level", Kind::Uint32),
+        field(2, "voltage", Kind::Float),
+        field(3, "channel_utilization", Kind::Float),
+        field(4, "air_util_tx", Kind::Float),
+        field(5, "uptime_seconds", Kind::Uint32),
+    ],
+};
+
+// Most used fields only, the rest is shown by tag
+static ENVIRONMENT_METRICS: Schema = Schema {
+    name: "EnvironmentMetrics",
+    fields: &[
+        field(1, "temperature", Kind::Float),
+        field(2, "relative_humidity", Kind::Float),
+        field(3, "barometric_pressure", Kind::Float),
+        field(4, "gas_resistance", Kind::Float),
+        field(5, "voltage", Kind::Float),
+        field(6, "current", Kind::Float),
+        field(7, "iaq", Kind::Uint32),
+        field(8, "distance", Kind::Float),
+        field(9, "lux", Kind::Float),
+        field(13, "wind_direction", Kind::Uint32),
+        field(14, "wind_speed", Kind::Float),
+    ],
+};
+
+static TELEMETRY: Schema = Schema {
+    name: "Telemetry",
+    fields: &[
+        field(1, "time", Kind::Time),
+        field(2, "device_metrics", Kind::Message(&DEVICE_METRICS)),
+        field(
+            3,
+            "environment_metrics",
+            Kind::Message(&ENVIRONMENT_METRICS),
+        ),
+    ],
+};
+
+static WAYPOINT: Schema = Schema {
+    name: "Waypoint",
+    fields: &[
+        field(1, "id", Kind::Uint32),
+        field(2, "latitude_i", Kind::Sfixed32),
+        field(3, "longitude_i", Kind::Sfixed32),
+        field(4, "expire", Kind::Time),
+        field(5, "locked_to", Kind::NodeId),
+        field(6, "name", Kind::String),
+        field(7, "description", Kind::String),
+        field(8, "icon", Kind::Fixed32),
+    ],
+};
+
+// Schema of `Data.payload`, None if the port's message is unknown
+fn payload_kind(portnum: meshtastic::PortNum) -> Option<Kind> {
+    match portnum {
+        meshtastic::PortNum::TextMessageApp => Some(Kind::String),
+        meshtastic::PortNum::PositionApp => Some(Kind::Message(&POSITION)),
+        meshtastic::PortNum::NodeinfoApp => Some(Kind::Message(&USER)),
+        meshtastic::PortNum::RoutingApp => Some(Kind::Message(&ROUTING)),
+        meshtastic::PortNum::TracerouteApp => Some(Kind::Message(&ROUTE_DISCOVERY)),
+        meshtastic::PortNum::NeighborinfoApp => Some(Kind::Message(&NEIGHBOR_INFO)),
+        meshtastic::PortNum::TelemetryApp => Some(Kind::Message(&TELEMETRY)),
+        meshtastic::PortNum::WaypointApp => Some(Kind::Message(&WAYPOINT)),
+        _ => None,
+    }
+}
+
+pub struct FieldNode {
+    pub name: String,
+    // Protobuf type or wire type if field is unknown
+    pub kind: String,
+    pub value: String,
+    pub children: Vec<FieldNode>,
+}
+
+impl FieldNode {
+    fn new(name: String, kind: impl Into<String>, value: String) -> Self {
+        Self {
+            name,
+            kind: kind.into(),
+            value,
+            children: Vec::new(),
+        }
+    }
+}
+
+fn read_varint(bytes: &[u8], offset: &mut usize) -> Result<u64, String> {
+    let mut value: u64 = 0;
+    for shift in (0..64).step_by(7) {
+        let byte = *bytes
+            .get(*offset)
+            .ok_or_else(|| format!("Truncated varint at byte {}", *offset))?;
+        *offset += 1;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(format!("Too long varint at byte {}", *offset))
+}
+
+fn read_bytes<'a>(bytes: &'a [u8], offset: &mut usize, len: usize) -> Result<&'a [u8], String> {
+    let value = offset
+        .checked_add(len)
+        .and_then(|end| bytes.get(*offset..end))
+        .ok_or_else(|| format!("Truncated field at byte {}: {} bytes needed", *offset, len))?;
+    *offset += len;
+    Ok(value)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn format_time(value: u32) -> String {
+    DateTime::from_timestamp(value as i64, 0)
+        .map(|v| format!("{} ({})", v.format("%Y-%m-%d %H:%M:%S"), value))
+        .unwrap_or_else(|| value.to_string())
+}
+
+// Value of a varint or fixed field, interpreted by known kind
+fn scalar(name: String, kind: Option<Kind>, wire_value: u64, fixed32: bool) -> FieldNode {
+    let (kind, value) = match kind {
+        Some(Kind::Uint32) => ("uint32", (wire_value as u32).to_string()),
+        Some(Kind::Int32) => ("int32", (wire_value as i64 as i32).to_string()),
+        Some(Kind::Sint32) => {
+            let value = ((wire_value >> 1) as i64) ^ -((wire_value & 1) as i64);
+            ("sint32", (value as i32).to_string())
+        }
+        Some(Kind::Bool) => ("bool", (wire_value != 0).to_string()),
+        Some(Kind::Sfixed32) => ("sfixed32", (wire_value as u32 as i32).to_string()),
+        Some(Kind::Fixed32) => ("fixed32", format!("{:#010x}", wire_value as u32)),
+        Some(Kind::Float) => ("float", f32::from_bits(wire_value as u32).to_string()),
+        Some(Kind::Time) => ("fixed32", format_time(wire_value as u32)),
+        Some(Kind::NodeId) => (
+            if fixed32 { "fixed32" } else { "uint32" },
+            NodeId::from(wire_value as u32).to_string(),
+        ),
+        Some(Kind::Enum(name_of)) => (
+            "enum",
+            format!(
+                "{} ({})",
+                name_of(wire_value as i32).unwrap_or("<unknown>"),
+                wire_value as i32
+            ),
+        ),
+        // Wire type doesn't match schema: raw value
+        Some(Kind::String) | Some(Kind::Bytes) | Some(Kind::Message(_)) | None => {
+            if fixed32 {
+                ("fixed32", format!("{:#010x}", wire_value as u32))
+            } else {
+                ("varint", wire_value.to_string())
+            }
+        }
+    };
+    FieldNode::new(name, kind, value)
+}
+
+// Length delimited field: string, bytes, message or packed repeated scalars
+fn length_delimited(name: String, kind: Option<Kind>, value: &[u8]) -> FieldNode {
+    match kind {
+        Some(Kind::String) => FieldNode::new(
+            name,
+            "string",
+            format!("{:?}", String::from_utf8_lossy(value)),
+        ),
+        Some(Kind::Bytes) => FieldNode::new(name, "bytes", hex(value)),
+        Some(Kind::Message(schema)) => match walk(value, Some(schema)) {
+            Ok(children) => FieldNode {
+                children,
+                ..FieldNode::new(name, schema.name, format!("{} bytes", value.len()))
+            },
+            Err(e) => FieldNode::new(name, schema.name, format!("<{}> {}", e, hex(value))),
+        },
+        Some(
+            scalar_kind
+            @ (Kind::Sfixed32 | Kind::Fixed32 | Kind::Float | Kind::Time | Kind::NodeId),
+        ) => {
+            let children = value
+                .chunks(4)
+                .enumerate()
+                .map(|(index, chunk)| {
+                    let mut fixed = [0u8; 4];
+                    fixed[..chunk.len()].copy_from_slice(chunk);
+                    let wire_value = u32::from_le_bytes(fixed) as u64;
+                    scalar(format!("[{}]", index), Some(scalar_kind), wire_value, true)
+                })
+                .collect::<Vec<_>>();
+            FieldNode {
+                children,
+                ..FieldNode::new(name, "packed", format!("{} bytes", value.len()))
+            }
+        }
+        Some(
+            scalar_kind @ (Kind::Uint32 | Kind::Int32 | Kind::Sint32 | Kind::Bool | Kind::Enum(_)),
+        ) => {
+            let mut offset = 0;
+            let mut children = Vec::new();
+            while offset < value.len() {
+                match read_varint(value, &mut offset) {
+                    Ok(wire_value) => children.push(scalar(
+                        format!("[{}]", children.len()),
+                        Some(scalar_kind),
+                        wire_value,
+                        false,
+                    )),
+                    Err(e) => {
+                        children.push(FieldNode::new(
+                            format!("[{}]", children.len()),
+                            "varint",
+                            format!("<{}>", e),
+                        ));
+                        break;
+                    }
+                }
+            }
+            FieldNode {
+                children,
+                ..FieldNode::new(name, "packed", format!("{} bytes", value.len()))
+            }
+        }
+        // Unknown field: message if it is parsed, otherwise text or bytes
+        None => {
+            if let Ok(children) = walk(value, None)
+                && !children.is_empty()
+            {
+                FieldNode {
+                    children,
+                    ..FieldNode::new(name, "message?", format!("{} bytes", value.len()))
+                }
+            } else if let Ok(text) = std::str::from_utf8(value)
+                && !text.contains(char::is_control)
+            {
+                FieldNode::new(name, "string?", format!("{:?}", text))
+            } else {
+                FieldNode::new(name, "bytes", hex(value))
+            }
+        }
+    }
+}
+
+// Fields of a message. Fails on the first malformed field
+fn walk(bytes: &[u8], schema: Option<&Schema>) -> Result<Vec<FieldNode>, String> {
+    let mut fields = Vec::new();
+    let mut offset = 0;
+
+    while offset < bytes.len() {
+        let start = offset;
+        let key = read_varint(bytes, &mut offset)?;
+        let tag = (key >> 3) as u32;
+        if tag == 0 {
+            return Err(format!("Field number 0 at byte {}", start));
+        }
+        let field = schema.and_then(|schema| schema.fields.iter().find(|v| v.tag == tag));
+        let name = field
+            .map(|v| v.name.to_string())
+            .unwrap_or_else(|| format!("#{}", tag));
+        let kind = field.map(|v| v.kind);
+
+        let node = match key & 0x7 {
+            0 => scalar(name, kind, read_varint(bytes, &mut offset)?, false),
+            1 => {
+                let value = read_bytes(bytes, &mut offset, 8)?;
+                let value = u64::from_le_bytes(value.try_into().unwrap());
+                FieldNode::new(name, "fixed64", format!("{:#018x}", value))
+            }
+            2 => {
+                let len = read_varint(bytes, &mut offset)? as usize;
+                length_delimited(name, kind, read_bytes(bytes, &mut offset, len)?)
+            }
+            5 => {
+                let value = read_bytes(bytes, &mut offset, 4)?;
+                let value = u32::from_le_bytes(value.try_into().unwrap());
+                scalar(name, kind, value as u64, true)
+            }
+            wire_type => {
+                return Err(format!("Unknown wire type {} at byte {}", wire_type, start));
+            }
+        };
+        fields.push(node);
+    }
+    Ok(fields)
+}
+
+// Field tree of decrypted `meshtastic::Data`, payload is decoded by portnum
+pub fn inspect_data(bytes: &[u8]) -> Result<Vec<FieldNode>, String> {
+    let mut fields = walk(bytes, Some(&DATA))?;
+    let data = meshtastic::Data::decode(bytes).map_err(|e| e.to_string())?;
+    if let Some(kind) = payload_kind(data.portnum())
+        && let Some(payload) = fields.iter_mut().find(|v| v.name == "payload")
+    {
+        *payload = length_delimited("payload".into(), Some(kind), &data.payload);
+    }
+    Ok(fields)
+}
+
+// Hex and ASCII dump with offsets
+pub fn hexdump(bytes: &[u8]) -> String {
+    bytes
+        .chunks(16)
+        .enumerate()
+        .map(|(index, chunk)| {
+            let ascii = chunk
+                .iter()
+                .map(|b| {
+                    if b.is_ascii_graphic() || *b == b' ' {
+                        *b as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect::<String>();
+            format!("{:04x}  {:<47}  |{}|", index * 16, hex(chunk), ascii)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn tree_ui(ui: &mut egui::Ui, fields: &[FieldNode]) {
+    for (index, field) in fields.iter().enumerate() {
+        let text =
+            RichText::new(format!("{}: {} = {}", field.name, field.kind, field.value)).monospace();
+        if field.children.is_empty() {
+            ui.label(text);
+        } else {
+            CollapsingHeader::new(text)
+                .id_salt(index)
+                .default_open(true)
+                .show(ui, |ui| tree_ui(ui, &field.children));
+        }
+    }
+}
+
+// Field tree of decrypted data, undecrypted payload is shown as hex only
+pub fn ui(ui: &mut egui::Ui, raw: &[u8], is_decrypted: bool) {
+    if is_decrypted {
+        match inspect_data(raw) {
+            Ok(fields) => tree_ui(ui, &fields),
+            Err(e) => {
+                ui.label(RichText::new(format!("<malformed: {}>", e)).monospace());
+            }
+        }
+    } else {
+        ui.label("Payload is not decrypted");
+    }
+    ui.collapsing(format!("Raw: {} bytes", raw.len()), |ui| {
+        ui.label(RichText::new(hexdump(raw)).monospace());
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn position_payload_tree() {
+        let position = meshtastic::Position {
+            latitude_i: Some(553_000_000),
+            longitude_i: Some(-1_225_000_000),
+            altitude_hae: Some(-12),
+            ..Default::default()
+        };
+        let data = meshtastic::Data {
+            portnum: meshtastic::PortNum::PositionApp.into(),
+            payload: position.encode_to_vec(),
+            dest: 0x12345678,
+            ..Default::default()
+        };
+
+        let fields = inspect_data(&data.encode_to_vec()).unwrap();
+        let value_of = |fields: &[FieldNode], name: &str| {
+            fields
+                .iter()
+                .find(|v| v.name == name)
+                .map(|v| v.value.clone())
+        };
+        assert_eq!(
+            value_of(&fields, "portnum").as_deref(),
+            Some("POSITION_APP (3)")
+        );
+        assert_eq!(value_of(&fields, "dest").as_deref(), Some("!12345678"));
+
+        let payload = fields.iter().find(|v| v.name == "payload").unwrap();
+        assert_eq!(payload.kind, "Position");
+        assert_eq!(
+            value_of(&payload.children, "longitude_i").as_deref(),
+            Some("-1225000000")
+        );
+        assert_eq!(
+            value_of(&payload.children, "altitude_hae").as_deref(),
+            Some("-12")
+        );
+
+        // Truncated data is reported, not panicked on
+        let bytes = data.encode_to_vec();
+        assert!(walk(&bytes[..bytes.len() - 1], Some(&DATA)).is_err());
+    }
+}
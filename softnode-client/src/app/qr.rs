@@ -0,0 +1,55 @@
+// QR codes of Meshtastic's share URLs: rendering, and scanning of screenshots on native
+use egui::{Color32, RichText, Sense, Vec2};
+use qrcode::{Color, QrCode};
+
+// Light modules around the code, scanners need them to find the code
+const QUIET_ZONE: usize = 4;
+
+// Draws `text` as QR code, `size` is the side in points
+pub fn qr_ui(ui: &mut egui::Ui, text: &str, size: f32) -> egui::Response {
+    let code = match QrCode::new(text.as_bytes()) {
+        Ok(code) => code,
+        Err(error) => {
+            return ui.label(RichText::new(error.to_string()).small().color(Color32::RED));
+        }
+    };
+    let width = code.width();
+    let module = size / (width + QUIET_ZONE * 2) as f32;
+    let (response, painter) = ui.allocate_painter(Vec2::splat(size), Sense::hover());
+    let origin = response.rect.min;
+
+    // Dark on light regardless of theme: inverted codes are not read by every scanner
+    painter.rect_filled(response.rect, 0.0, Color32::WHITE);
+    for (index, color) in code.to_colors().into_iter().enumerate() {
+        if color == Color::Dark {
+            let x = (index % width + QUIET_ZONE) as f32;
+            let y = (index / width + QUIET_ZONE) as f32;
+            painter.rect_filled(
+                egui::Rect::from_min_size(origin + Vec2::new(x, y) * module, Vec2::splat(module)),
+                0.0,
+                Color32::BLACK,
+            );
+        }
+    }
+    response
+}
+
+// Text of the first readable QR code on image
+#[cfg(not(target_arch = "wasm32"))]
+pub fn scan_file(path: &std::path::Path) -> Result<String, String> {
+    let image = image::open(path)
+        .map_err(|e| format!("{}: {}", path.display(), e))?
+        .to_luma8();
+    let mut prepared = rqrr::PreparedImage::prepare_from_greyscale(
+        image.width() as usize,
+        image.height() as usize,
+        |x, y| image.get_pixel(x as u32, y as u32).0[0],
+    );
+
+    prepared
+        .detect_grids()
+        .iter()
+        .find_map(|grid| grid.decode().ok())
+        .map(|(_, text)| text)
+        .ok_or_else(|| format!("{}: no QR code found", path.display()))
+}
@@ -1,7 +1,7 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt::Display};
 
-use geo::Point;
 use geo::algorithm::centroid::Centroid;
+use geo::{Distance, Haversine, Point};
 use meshtastic_connect::keyring::node_id::NodeId;
 
 use crate::app::{
@@ -9,6 +9,129 @@ use crate::app::{
     node_book::NodeBook,
 };
 
+// How the assumed position is calculated
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum AssumeMethod {
+    // Node is heard by only one gateway with known position
+    SingleGateway,
+    // Centroid of gateways, shifted by RSSI
+    RssiWeighted,
+    // Distances to gateways are estimated with log-distance path-loss model
+    Trilateration,
+}
+
+impl Display for AssumeMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AssumeMethod::SingleGateway => write!(f, "single gateway"),
+            AssumeMethod::RssiWeighted => write!(f, "RSSI-weighted centroid"),
+            AssumeMethod::Trilateration => write!(f, "trilateration"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct AssumedPosition {
+    pub position: walkers::Position,
+    pub method: AssumeMethod,
+    // Number of gateways with known position
+    pub gateways: usize,
+    // Rough radius of uncertainty
+    pub radius_meters: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct AssumeConfig {
+    // Use trilateration when three or more gateways have known positions
+    pub trilateration: bool,
+    // 2.0 for free space, up to 4.0 for dense urban areas
+    pub path_loss_exponent: f64,
+}
+
+impl Default for AssumeConfig {
+    fn default() -> Self {
+        Self {
+            trilateration: false,
+            path_loss_exponent: 2.7,
+        }
+    }
+}
+
+// Typical LoRa's range, when only one gateway heard the node
+const SINGLE_GATEWAY_RADIUS_METERS: f64 = 5000.0;
+// RSSI at 1 meter from the transmitter, dBm
+const REFERENCE_RSSI: f64 = -40.0;
+const METERS_PER_DEGREE: f64 = 111_320.0;
+
+// Log-distance path-loss model
+pub fn rssi_to_meters(rssi: i32, path_loss_exponent: f64) -> f64 {
+    10_f64.powf((REFERENCE_RSSI - rssi as f64) / (10.0 * path_loss_exponent))
+}
+
+// Least squares solution for circles around gateways.
+// Returns position and RMS error of distances.
+// None if there are less than three gateways or gateways are on one line
+pub fn trilaterate(
+    samples: &[(i32, walkers::Position)],
+    path_loss_exponent: f64,
+) -> Option<(walkers::Position, f64)> {
+    if samples.len() < 3 {
+        return None;
+    }
+
+    // Local plane in meters around the gateways
+    let origin =
+        geo::MultiPoint::from(samples.iter().map(|(_, p)| *p).collect::<Vec<_>>()).centroid()?;
+    let meters_per_lon = origin.y().to_radians().cos() * METERS_PER_DEGREE;
+    let circles = samples
+        .iter()
+        .map(|(rssi, p)| {
+            (
+                (p.x() - origin.x()) * meters_per_lon,
+                (p.y() - origin.y()) * METERS_PER_DEGREE,
+                rssi_to_meters(*rssi, path_loss_exponent),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    // Subtract the first circle's equation from others to get linear system,
+    // then solve its normal equations
+    let (x1, y1, d1) = circles[0];
+    let (mut a11, mut a12, mut a22, mut b1, mut b2) = (0.0, 0.0, 0.0, 0.0, 0.0);
+    for (xi, yi, di) in &circles[1..] {
+        let ax = 2.0 * (xi - x1);
+        let ay = 2.0 * (yi - y1);
+        let b = d1 * d1 - di * di + xi * xi - x1 * x1 + yi * yi - y1 * y1;
+        a11 += ax * ax;
+        a12 += ax * ay;
+        a22 += ay * ay;
+        b1 += ax * b;
+        b2 += ay * b;
+    }
+    let det = a11 * a22 - a12 * a12;
+    if det.abs() <= 1e-9 * a11 * a22 {
+        return None;
+    }
+    let x = (b1 * a22 - b2 * a12) / det;
+    let y = (a11 * b2 - a12 * b1) / det;
+
+    let error = (circles
+        .iter()
+        .map(|(xi, yi, di)| (((x - xi).powi(2) + (y - yi).powi(2)).sqrt() - di).powi(2))
+        .sum::<f64>()
+        / circles.len() as f64)
+        .sqrt();
+
+    Some((
+        Point::new(
+            origin.x() + x / meters_per_lon,
+            origin.y() + y / METERS_PER_DEGREE,
+        ),
+        error,
+    ))
+}
+
 fn rssi_to_distance(rssi: i32) -> f64 {
     let clamped = rssi.clamp(-130, 20);
     let normalized = (20 - clamped) as f64 / 150.0;
@@ -59,7 +182,8 @@ pub fn assume_position(
     node_info: &NodeInfo,
     nodes: &HashMap<NodeId, NodeInfo>,
     nodebook: &NodeBook,
-) -> Option<walkers::Position> {
+    config: &AssumeConfig,
+) -> Option<AssumedPosition> {
     let to_pos_info =
         |node_id, gateway_info: Option<&GatewayInfo>| -> Option<(i32, walkers::Position)> {
             if let Some(gateway_info) = gateway_info {
@@ -74,6 +198,8 @@ pub fn assume_position(
                                     .position
                                     .last()
                                     .map(|pos| walkers::Position::new(pos.longitude, pos.latitude))
+                                    // Position could be received before the zone is added
+                                    .filter(|position| nodebook.is_ignored(*position).is_none())
                                     .or_else(|| {
                                         nodebook
                                             .node_get(node_id)
@@ -111,5 +237,71 @@ pub fn assume_position(
             .collect::<Vec<_>>(),
     );
 
-    compute_weighted_center(positions)
+    let gateways = positions.len();
+    if config.trilateration
+        && let Some((position, radius_meters)) = trilaterate(&positions, config.path_loss_exponent)
+    {
+        return Some(AssumedPosition {
+            position,
+            method: AssumeMethod::Trilateration,
+            gateways,
+            radius_meters,
+        });
+    }
+
+    let position = compute_weighted_center(positions.clone())?;
+    let (method, radius_meters) = if gateways == 1 {
+        (AssumeMethod::SingleGateway, SINGLE_GATEWAY_RADIUS_METERS)
+    } else {
+        // Node is somewhere between gateways
+        let radius_meters = positions
+            .iter()
+            .map(|(_, gateway_position)| Haversine.distance(position, *gateway_position))
+            .fold(0.0, f64::max);
+        (AssumeMethod::RssiWeighted, radius_meters)
+    };
+
+    Some(AssumedPosition {
+        position,
+        method,
+        gateways,
+        radius_meters,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{METERS_PER_DEGREE, REFERENCE_RSSI, trilaterate};
+    use geo::{Distance, Haversine, Point};
+
+    #[test]
+    fn trilaterate_synthetic_gateways() {
+        let path_loss_exponent = 2.7;
+        let node = Point::new(17.03664_f64, 51.09916);
+        let meters_per_lon = node.y().to_radians().cos() * METERS_PER_DEGREE;
+
+        let samples = [
+            (1000.0, 0.0),
+            (-500.0, 800.0),
+            (-300.0, -900.0),
+            (600.0, 1200.0),
+        ]
+        .iter()
+        .map(|(east, north): &(f64, f64)| {
+            let gateway = Point::new(
+                node.x() + east / meters_per_lon,
+                node.y() + north / METERS_PER_DEGREE,
+            );
+            let distance = east.hypot(*north);
+            let rssi = REFERENCE_RSSI - 10.0 * path_loss_exponent * distance.log10();
+            (rssi.round() as i32, gateway)
+        })
+        .collect::<Vec<_>>();
+
+        let (position, _) = trilaterate(&samples, path_loss_exponent).unwrap();
+        let error = Haversine.distance(position, node);
+        assert!(error < 100.0, "error is {} m", error);
+
+        assert!(trilaterate(&samples[..2], path_loss_exponent).is_none());
+    }
 }
@@ -4,7 +4,10 @@ use egui_plot::{HLine, Line, PlotItem, Text};
 use meshtastic_connect::keyring::node_id::NodeId;
 use std::{collections::HashMap, time::Duration};
 
-use crate::app::data::{NodeInfo, TelemetryValue};
+use crate::app::{
+    color_generator::NodeColors,
+    data::{NodeInfo, TelemetryValue},
+};
 
 #[derive(Default, serde::Deserialize, serde::Serialize)]
 pub struct RadioTelemetry {}
@@ -74,6 +77,7 @@ impl RadioTelemetry {
         &mut self,
         ui: &mut egui::Ui,
         nodes: &HashMap<NodeId, NodeInfo>,
+        node_colors: &NodeColors,
         start_time: DateTime<Utc>,
         telemetry: Vec<(&Option<NodeId>, &Vec<TelemetryValue>)>,
         link_telemetry: Option<HashMap<(Option<NodeId>, u32), Vec<TelemetryValue>>>,
@@ -132,6 +136,11 @@ impl RadioTelemetry {
 
         legend_plot.show(ui, |plot_ui| {
             let mut colors: HashMap<Option<NodeId>, Color32> = Default::default();
+            // Known nodes have stable colors, unknown gateways get generated
+            let mut next_color = |node_id: Option<NodeId>| match node_id {
+                Some(node_id) => node_colors.get(node_id),
+                None => color_generator.next_color(),
+            };
             // if let Some((title, node_telemetry)) = telemetry.first() {
             for (gateway_id, node_telemetry) in telemetry.iter() {
                 let points: Vec<[f64; 2]> = node_telemetry
@@ -156,7 +165,7 @@ impl RadioTelemetry {
 
                 let color = *colors
                     .entry(**gateway_id)
-                    .or_insert_with(|| color_generator.next_color());
+                    .or_insert_with(|| next_color(**gateway_id));
                 let title = build_title(gateway_id);
 
                 let mut plot_points = egui_plot::Points::new(title.as_str(), points.clone())
@@ -200,7 +209,7 @@ impl RadioTelemetry {
                     let title = build_title(&gateway_id);
                     let color = *colors
                         .entry(gateway_id)
-                        .or_insert_with(|| color_generator.next_color());
+                        .or_insert_with(|| next_color(gateway_id));
                     let line = Line::new(
                         title,
                         values
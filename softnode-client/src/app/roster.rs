@@ -1,28 +1,48 @@
 use crate::app::{
+    activity::Activity,
     data::{NodeInfo, NodeInfoExtended, PublicKey, TelemetryValue, TelemetryVariant},
+    elevation::ElevationProfile,
     node_book::NodeBook,
-    node_filter::{FilterVariant, NodeFilter},
+    node_filter::{FilterPresets, FilterVariant, NodeFilter},
     radio_telemetry::RadioTelemetry,
+    session::SessionPanel,
     settings::Settings,
     telemetry::Telemetry,
     telemetry_formatter::TelemetryFormatter,
     time_format::format_timediff,
+    topology::Topology,
 };
 use egui::{Align, Button, Color32, Frame, Layout, RichText, Stroke, Vec2};
-use meshtastic_connect::keyring::node_id::NodeId;
-use std::collections::HashMap;
+use meshtastic_connect::keyring::{
+    Keyring,
+    key::{K256, Key},
+    node_id::NodeId,
+};
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
 
 #[derive(serde::Deserialize, serde::Serialize)]
 pub enum Panel {
     Journal,
-    Telemetry(Telemetry),
-    Settings(Settings),
+    Telemetry(Box<Telemetry>),
+    Settings(Box<Settings>),
     Rssi(NodeId, RadioTelemetry),
     Hops(NodeId, RadioTelemetry),
     GatewayByRSSI(NodeId, RadioTelemetry),
     GatewayByHops(NodeId, RadioTelemetry),
     Map,
     NodeDump,
+    Elevation(NodeId, NodeId, ElevationProfile),
+    Compare(NodeId, NodeId),
+    DecryptFailures,
+    Alerts,
+    Dashboard,
+    Activity(NodeId, Activity),
+    Topology(Topology),
+    Session(SessionPanel),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -32,6 +52,15 @@ pub enum Selection {
     None = 30,
 }
 
+// Data shared by roster's nodes
+pub struct RosterContext<'a> {
+    pub telemetry_formatter: &'a TelemetryFormatter,
+    pub nodebook: &'a mut NodeBook,
+    pub nodes: &'a HashMap<NodeId, NodeInfo>,
+    pub node_filter: &'a mut NodeFilter,
+    pub keyring: &'a Keyring,
+}
+
 pub trait Plugin {
     fn node_is_selected(&self, _node_info: &NodeInfo) -> Selection {
         Selection::None
@@ -40,6 +69,15 @@ pub trait Plugin {
     fn node_is_dropped(&self, _node_info: &NodeInfo) -> bool {
         false
     }
+
+    // Node is chosen from the roster by keyboard
+    fn node_activated(
+        self: &mut Self,
+        _node_info: &NodeInfo,
+        _nodebook: &mut NodeBook,
+    ) -> PanelCommand {
+        PanelCommand::Nothing
+    }
     fn panel_header_ui(self: &mut Self, ui: &mut egui::Ui, nodebook: &mut NodeBook)
     -> PanelCommand;
     fn panel_node_ui(
@@ -48,6 +86,85 @@ pub trait Plugin {
         node_info: &NodeInfo,
         nodebook: &mut NodeBook,
     ) -> PanelCommand;
+    // Actions for all nodes picked in the roster
+    fn bulk_ui(&mut self, _ui: &mut egui::Ui, _picked: &HashSet<NodeId>, _nodebook: &mut NodeBook) {
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum RosterDensity {
+    #[default]
+    Comfortable,
+    // Single line per node, details are expanded by click
+    Compact,
+}
+
+#[derive(Default, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum RosterSort {
+    #[default]
+    NodeId,
+    // Recently heard first
+    LastSeen,
+    // Nearest by hops first
+    Hops,
+    Name,
+    // Weakest battery first
+    Battery,
+}
+
+impl Display for RosterSort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RosterSort::NodeId => write!(f, "Node id"),
+            RosterSort::LastSeen => write!(f, "Last seen"),
+            RosterSort::Hops => write!(f, "Hops"),
+            RosterSort::Name => write!(f, "Name"),
+            RosterSort::Battery => write!(f, "Battery"),
+        }
+    }
+}
+
+impl RosterSort {
+    const ALL: [RosterSort; 5] = [
+        RosterSort::NodeId,
+        RosterSort::LastSeen,
+        RosterSort::Hops,
+        RosterSort::Name,
+        RosterSort::Battery,
+    ];
+
+    // Nodes without the value are placed last
+    fn compare(&self, a: &NodeInfo, b: &NodeInfo) -> Ordering {
+        fn none_last<T: PartialOrd>(a: Option<T>, b: Option<T>) -> Ordering {
+            match (a, b) {
+                (Some(a), Some(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            }
+        }
+        let last_seen = |v: &NodeInfo| v.packet_statistics.last().map(|v| Reverse(v.timestamp));
+        let hops = |v: &NodeInfo| v.gatewayed_by.values().filter_map(|v| v.hop_distance).min();
+        let name = |v: &NodeInfo| {
+            v.extended_info_history
+                .last()
+                .map(|v| v.long_name.to_lowercase())
+        };
+        let battery = |v: &NodeInfo| {
+            v.telemetry
+                .get(&TelemetryVariant::BatteryLevel)
+                .and_then(|v| v.values.last())
+                .map(|v| v.value)
+        };
+
+        match self {
+            RosterSort::NodeId => Ordering::Equal,
+            RosterSort::LastSeen => none_last(last_seen(a), last_seen(b)),
+            RosterSort::Hops => none_last(hops(a), hops(b)),
+            RosterSort::Name => none_last(name(a), name(b)),
+            RosterSort::Battery => none_last(battery(a), battery(b)),
+        }
+    }
 }
 
 #[derive(Default, serde::Deserialize, serde::Serialize)]
@@ -58,6 +175,38 @@ pub struct Roster {
     pub offset: Vec2,
     #[serde(skip)]
     pub roster_heights: HashMap<NodeId, f32>,
+    // Node selected by keyboard
+    #[serde(skip)]
+    focused: Option<NodeId>,
+    // Focus is moved and should be scrolled into view
+    #[serde(skip)]
+    scroll_to_focused: bool,
+    // `offset` is changed by roster and should be applied to the scroll area
+    #[serde(skip)]
+    apply_offset: bool,
+    #[serde(default)]
+    pub density: RosterDensity,
+    // Nodes expanded in the compact mode
+    #[serde(skip)]
+    expanded: HashSet<NodeId>,
+    #[serde(default)]
+    pub sort: RosterSort,
+    // Nodes picked by Ctrl/Shift-click for bulk actions, independent of
+    // plugins' selection
+    #[serde(skip)]
+    picked: HashSet<NodeId>,
+    // Last node picked by Ctrl-click, start of Shift-click range
+    #[serde(skip)]
+    pick_anchor: Option<NodeId>,
+    // Node clicked with modifiers in this frame, true if it is Shift-click
+    #[serde(skip)]
+    pick_request: Option<(NodeId, bool)>,
+    // Search box should take keyboard focus
+    #[serde(skip)]
+    focus_filter: bool,
+    // Node's announced key to add to the keyring
+    #[serde(skip)]
+    trust_request: Option<(NodeId, K256)>,
 }
 
 #[derive(Default)]
@@ -69,47 +218,93 @@ pub enum PanelCommand {
 }
 
 impl Roster {
+    // Moves keyboard focus to the node, as if it is chosen in the roster
+    pub fn focus(&mut self, node_id: NodeId) {
+        self.focused = Some(node_id);
+        self.scroll_to_focused = true;
+    }
+
+    pub fn focused(&self) -> Option<NodeId> {
+        self.focused
+    }
+
+    pub fn focus_filter(&mut self) {
+        self.focus_filter = true;
+    }
+
+    pub fn take_trust_request(&mut self) -> Option<(NodeId, K256)> {
+        self.trust_request.take()
+    }
+
     pub fn ui<'a>(
         &mut self,
         ui: &mut egui::Ui,
-        telemetry_formatter: &TelemetryFormatter,
+        mut context: RosterContext<'_>,
         mut roster_plugins: Vec<&'a mut dyn Plugin>,
-        node_filter: &mut NodeFilter,
-        nodebook: &mut NodeBook,
-        nodes: &HashMap<NodeId, NodeInfo>,
+        filter_presets: &mut FilterPresets,
         hide_on_action: bool,
     ) -> Option<Panel> {
-        ui.horizontal(|ui| {
+        let nodes = context.nodes;
+        let keyboard_navigation = ui.horizontal(|ui| {
             let edit = egui::TextEdit::singleline(&mut self.filter)
                 .desired_width(f32::INFINITY)
                 .hint_text("Search node by id or name")
                 .show(ui);
+            if std::mem::take(&mut self.focus_filter) {
+                edit.response.request_focus();
+            }
             if edit.response.changed() {
-                node_filter.update_filter(self.filter.as_str());
+                context.node_filter.update_filter(self.filter.as_str());
             } else if !edit.response.has_focus() {
-                self.filter = node_filter.to_string();
+                self.filter = context.node_filter.to_string();
             }
+            // Search box uses only left and right arrows, so up and down move the focus
+            edit.response.has_focus()
+                || edit.response.lost_focus()
+                || ui.memory(|memory| memory.focused().is_none())
         });
+        let keyboard_navigation = keyboard_navigation.inner;
+        let (focus_step, activate) = if keyboard_navigation {
+            ui.input_mut(|input| {
+                let mut step = 0_i32;
+                if input.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown) {
+                    step += 1;
+                }
+                if input.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp) {
+                    step -= 1;
+                }
+                (step, input.key_pressed(egui::Key::Enter))
+            })
+        } else {
+            (0, false)
+        };
 
         for roster_plugin in roster_plugins.iter_mut() {
-            roster_plugin.panel_header_ui(ui, nodebook);
+            roster_plugin.panel_header_ui(ui, context.nodebook);
+        }
+
+        self.picked.retain(|node_id| nodes.contains_key(node_id));
+        if !self.picked.is_empty() {
+            self.bulk_ui(ui, &mut roster_plugins, context.nodebook, nodes);
         }
 
         let scroll_area = egui::ScrollArea::vertical().auto_shrink(false);
-        let scroll_area = if self.filter.is_empty() {
+        let scroll_area = if self.filter.is_empty() || self.apply_offset {
+            self.apply_offset = false;
             scroll_area.scroll_offset(self.offset)
         } else {
             scroll_area
         };
 
         let mut next_page = None;
+        let mut scroll_request = None;
         let mut y_offset = 0.0;
         let scroll_area_output = scroll_area.show_viewport(ui, |ui, viewport| {
             const DEFAULT_HEIGHT: f32 = 20.0;
 
             y_offset += Frame::new()
                 .show(ui, |ui| {
-                    node_filter.ui(ui);
+                    context.node_filter.ui(ui, filter_presets);
                 })
                 .response
                 .rect
@@ -118,7 +313,7 @@ impl Roster {
             let mut filtered_nodes: Vec<(&NodeInfo, Selection)> = {
                 nodes
                     .values()
-                    .filter(|v| node_filter.matches(*v, &nodes, nodebook.node_get(&v.node_id)))
+                    .filter(|v| context.node_filter.matches(v, nodes, context.nodebook))
                     .map(|node_info| {
                         let mut selection = Selection::None;
                         for roster_plugin in roster_plugins.iter_mut() {
@@ -141,6 +336,28 @@ impl Roster {
                 .show(ui, |ui| {
                     ui.horizontal(|ui| {
                         ui.label(format!("nodes: {}", filtered_nodes.len()));
+                        let compact = self.density == RosterDensity::Compact;
+                        if ui
+                            .selectable_label(compact, "☰")
+                            .on_hover_text("Compact layout")
+                            .clicked()
+                        {
+                            self.density = if compact {
+                                RosterDensity::Comfortable
+                            } else {
+                                RosterDensity::Compact
+                            };
+                            self.expanded.clear();
+                            // Heights are different in other layout
+                            self.roster_heights.clear();
+                        }
+                        egui::ComboBox::from_id_salt("roster_sort")
+                            .selected_text(format!("⇅ {}", self.sort))
+                            .show_ui(ui, |ui| {
+                                for sort in RosterSort::ALL {
+                                    ui.selectable_value(&mut self.sort, sort, sort.to_string());
+                                }
+                            });
                         if ui.button("as text").clicked() {
                             next_page = Some(Panel::NodeDump);
                             if hide_on_action {
@@ -154,8 +371,77 @@ impl Roster {
                 .rect
                 .height();
 
-            filtered_nodes.sort_by_key(|(node_info, _)| node_info.node_id);
-            filtered_nodes.sort_by_key(|(_, selection)| *selection);
+            // Selected nodes are pinned to the top, then favorites,
+            // ties are resolved by node id
+            filtered_nodes.sort_by(|(a, a_selection), (b, b_selection)| {
+                a_selection
+                    .cmp(b_selection)
+                    .then_with(|| {
+                        Reverse(context.nodebook.is_favorite(&a.node_id))
+                            .cmp(&Reverse(context.nodebook.is_favorite(&b.node_id)))
+                    })
+                    .then_with(|| self.sort.compare(a, b))
+                    .then_with(|| a.node_id.cmp(&b.node_id))
+            });
+
+            let mut focused_index = self.focused.and_then(|focused| {
+                filtered_nodes
+                    .iter()
+                    .position(|(node_info, _)| node_info.node_id == focused)
+            });
+            if focus_step != 0 && !filtered_nodes.is_empty() {
+                let last = filtered_nodes.len() as i32 - 1;
+                let index = focused_index
+                    .map(|v| (v as i32 + focus_step).clamp(0, last))
+                    .unwrap_or(if focus_step > 0 { 0 } else { last });
+                focused_index = Some(index as usize);
+                self.focused = Some(filtered_nodes[index as usize].0.node_id);
+                self.scroll_to_focused = true;
+            }
+
+            if self.scroll_to_focused {
+                self.scroll_to_focused = false;
+                if let Some(index) = focused_index {
+                    let height_of = |node_info: &NodeInfo| {
+                        *self
+                            .roster_heights
+                            .get(&node_info.node_id)
+                            .unwrap_or(&DEFAULT_HEIGHT)
+                    };
+                    let top = y_offset
+                        + filtered_nodes[..index]
+                            .iter()
+                            .map(|(node_info, _)| height_of(node_info))
+                            .sum::<f32>();
+                    let height = height_of(filtered_nodes[index].0);
+                    if top < viewport.top() || top + height > viewport.bottom() {
+                        let y = (top - (viewport.height() - height) / 2.0).max(0.0);
+                        scroll_request = Some(Vec2::new(viewport.left(), y));
+                    }
+                }
+            }
+
+            if activate && let Some(index) = focused_index {
+                let node_info = filtered_nodes[index].0;
+                for roster_plugin in roster_plugins.iter_mut() {
+                    if let PanelCommand::NextPanel(panel) =
+                        roster_plugin.node_activated(node_info, context.nodebook)
+                    {
+                        next_page = Some(panel);
+                        break;
+                    }
+                }
+                if next_page.is_none() && !node_info.packet_statistics.is_empty() {
+                    next_page = Some(Panel::Rssi(node_info.node_id, Default::default()));
+                }
+                if next_page.is_some() {
+                    if hide_on_action {
+                        self.show = false;
+                    }
+                    ui.ctx().request_repaint();
+                    return;
+                }
+            }
 
             for (index, (node_info, selection)) in filtered_nodes.iter().enumerate() {
                 let probably_height = *self
@@ -175,13 +461,11 @@ impl Roster {
                 }
                 let (panel_command, height) = self.node_ui(
                     ui,
-                    nodebook,
+                    &mut context,
                     node_info,
                     &mut roster_plugins,
-                    telemetry_formatter,
                     *selection,
-                    nodes,
-                    node_filter,
+                    focused_index == Some(index),
                 );
                 match panel_command {
                     PanelCommand::Nothing => {
@@ -201,9 +485,17 @@ impl Roster {
                     }
                 }
             }
+
+            if let Some((node_id, range)) = self.pick_request.take() {
+                self.pick(node_id, range, &filtered_nodes);
+            }
         });
 
-        if self.filter.is_empty() {
+        if let Some(offset) = scroll_request {
+            self.offset = offset;
+            self.apply_offset = true;
+            ui.ctx().request_repaint();
+        } else if self.filter.is_empty() {
             self.offset = scroll_area_output.state.offset;
         }
         next_page
@@ -212,15 +504,39 @@ impl Roster {
     fn node_ui<'a>(
         &mut self,
         ui: &mut egui::Ui,
-        nodebook: &mut NodeBook,
+        context: &mut RosterContext<'_>,
         node_info: &NodeInfo,
         roster_plugins: &mut Vec<&'a mut dyn Plugin>,
-        telemetry_formatter: &TelemetryFormatter,
         selection: Selection,
-        nodes: &HashMap<NodeId, NodeInfo>,
-        node_filter: &mut NodeFilter,
+        focused: bool,
     ) -> (PanelCommand, f32) {
+        let RosterContext {
+            telemetry_formatter,
+            nodebook,
+            nodes,
+            node_filter,
+            keyring,
+        } = context;
         let current_datetime = chrono::Utc::now();
+        let is_compact = self.density == RosterDensity::Compact;
+        if is_compact && !self.expanded.contains(&node_info.node_id) {
+            return self.compact_node_ui(
+                ui,
+                node_info,
+                selection,
+                focused,
+                nodebook,
+                current_datetime,
+            );
+        }
+        let mut is_favorite = nodebook.is_favorite(&node_info.node_id);
+        let was_favorite = is_favorite;
+        let mut alias = nodebook
+            .alias(&node_info.node_id)
+            .unwrap_or_default()
+            .to_string();
+        let previous_alias = alias.clone();
+
         let label_last_seen = |ui: &mut egui::Ui| {
             if let Some(label) = node_info
                 .packet_statistics
@@ -232,9 +548,15 @@ impl Roster {
             }
         };
 
-        let show_extended = |ui: &mut egui::Ui, extended: &NodeInfoExtended, is_via_mqtt: bool| {
+        let show_extended = |ui: &mut egui::Ui,
+                             extended: &NodeInfoExtended,
+                             is_via_mqtt: bool,
+                             is_favorite: &mut bool,
+                             alias: &mut String| {
             let node_id_str = node_info.node_id.to_string();
             ui.horizontal(|ui| {
+                Self::favorite_ui(ui, is_favorite);
+                Self::alias_ui(ui, alias);
                 if ui
                     .selectable_label(false, extended.short_name.clone())
                     .on_hover_text("Node's short name\nclick to copy")
@@ -244,7 +566,20 @@ impl Roster {
                         .copy_text(format!("{} {}", node_id_str, extended.short_name));
                 }
 
-                if extended.long_name.len() > 0 {
+                if !alias.is_empty() {
+                    let label = Button::selectable(false, RichText::new(alias.as_str()).strong())
+                        .wrap_mode(egui::TextWrapMode::Wrap);
+                    if ui
+                        .add(label)
+                        .on_hover_text(format!(
+                            "Alias, announced long name: {}\nclick to copy",
+                            extended.long_name
+                        ))
+                        .clicked()
+                    {
+                        ui.ctx().copy_text(format!("{} {}", node_id_str, alias));
+                    };
+                } else if !extended.long_name.is_empty() {
                     let long_name = RichText::new(extended.long_name.clone()).strong();
                     let label =
                         Button::selectable(false, long_name).wrap_mode(egui::TextWrapMode::Wrap);
@@ -283,6 +618,10 @@ impl Roster {
                             .on_hover_text("No key is announced");
                     }
                 };
+                if node_info.has_key_changed() {
+                    ui.label(RichText::new("🔁").color(Color32::ORANGE))
+                        .on_hover_text("Node announced different public keys:\ndevice reset or impersonation");
+                }
 
                 if extended.is_licensed {
                     ui.label(RichText::new("🖹").color(Color32::LIGHT_BLUE))
@@ -308,6 +647,12 @@ impl Roster {
             });
         };
 
+        let trusted_key = keyring.peer_public_key(node_info.node_id);
+        let mut trust_request = None;
+        let verified_key = nodebook.verified_key(&node_info.node_id);
+        // Some(None) drops the verification
+        let mut verify_request = None;
+
         let mut show_node_info = |ui: &mut egui::Ui| -> PanelCommand {
             let mut panel_command = PanelCommand::Nothing;
             let via_mqtt = node_info
@@ -316,13 +661,15 @@ impl Roster {
                 .any(|node_packet| node_packet.via_mqtt);
             ui.vertical(|ui| {
                 if let Some(extended) = node_info.extended_info_history.last() {
-                    show_extended(ui, extended, via_mqtt);
+                    show_extended(ui, extended, via_mqtt, &mut is_favorite, &mut alias);
                 } else {
                     if via_mqtt {
                         ui.label(RichText::new("").color(Color32::LIGHT_GRAY))
                             .on_hover_text("Some packets hearrd via MQTT");
                     }
                     ui.horizontal(|ui| {
+                        Self::favorite_ui(ui, &mut is_favorite);
+                        Self::alias_ui(ui, &mut alias);
                         if ui
                             .selectable_label(false, node_info.node_id.to_string())
                             .on_hover_text("No NodeInfo announced")
@@ -330,6 +677,10 @@ impl Roster {
                         {
                             ui.ctx().copy_text(node_info.node_id.into());
                         };
+                        if !alias.is_empty() {
+                            ui.label(RichText::new(alias.as_str()).strong())
+                                .on_hover_text("Alias, no NodeInfo announced");
+                        }
                         ui.with_layout(Layout::right_to_left(Align::TOP), |ui| {
                             label_last_seen(ui);
                         });
@@ -438,8 +789,85 @@ impl Roster {
                             ));
                             return;
                         }
+                        if ui.button("Activity").clicked() {
+                            panel_command = PanelCommand::NextPanel(Panel::Activity(
+                                node_info.node_id,
+                                Default::default(),
+                            ));
+                            return;
+                        }
                     });
                 }
+                if let Some(extended) = node_info.extended_info_history.last()
+                    && let Some(pkey) = extended.pkey.key()
+                {
+                    match verified_key {
+                        Some(verified_key) if verified_key == pkey => {
+                            if ui
+                                .selectable_label(true, RichText::new("☑ Verified").color(Color32::LIGHT_BLUE))
+                                .on_hover_text("Key is verified by you out-of-band\nclick to drop the verification")
+                                .clicked()
+                            {
+                                verify_request = Some(None);
+                            }
+                        }
+                        Some(verified_key) => {
+                            ui.menu_button(RichText::new("⚠ Key changed").color(Color32::RED).strong(), |ui| {
+                                ui.label(format!(
+                                    "Announced key differs from the verified one\nverified: {}\nannounced: {}",
+                                    verified_key, pkey
+                                ));
+                                if ui.button("Verify the new key").clicked() {
+                                    verify_request = Some(Some(pkey));
+                                }
+                                if ui.button("Drop verification").clicked() {
+                                    verify_request = Some(None);
+                                }
+                            });
+                        }
+                        None => {
+                            if ui
+                                .button("☐ Mark verified")
+                                .on_hover_text("Key is checked out-of-band, e.g. in person:\nits change will be warned")
+                                .clicked()
+                            {
+                                verify_request = Some(Some(pkey));
+                            }
+                        }
+                    }
+                }
+                if let Some(extended) = node_info.extended_info_history.last()
+                    && let PublicKey::Key(Key::K256(pkey)) | PublicKey::Compromised(Key::K256(pkey)) =
+                        extended.pkey
+                {
+                    match trusted_key {
+                        Some(trusted_key) if trusted_key == pkey => {
+                            ui.label(RichText::new("🔑").color(Color32::LIGHT_GREEN))
+                                .on_hover_text("Key is in the keyring: direct messages are decrypted");
+                        }
+                        Some(_) => {
+                            ui.label(RichText::new("🔑").color(Color32::LIGHT_RED))
+                                .on_hover_text("Keyring has another key for this node,\nedit it in Settings");
+                        }
+                        None if matches!(extended.pkey, PublicKey::Compromised(_)) => {
+                            ui.menu_button(RichText::new("⚠ Trust this key").color(Color32::YELLOW), |ui| {
+                                ui.label("Key is used by another node:\nthat node could read direct messages too");
+                                if ui.button("Trust anyway").clicked() {
+                                    trust_request = Some((node_info.node_id, pkey));
+                                }
+                            });
+                        }
+                        None => {
+                            if ui
+                                .button("🔑 Trust this key")
+                                .on_hover_text("Add key to the keyring to decrypt direct messages")
+                                .clicked()
+                            {
+                                trust_request = Some((node_info.node_id, pkey));
+                            }
+                        }
+                    }
+                }
             });
             if !node_info.gateway_for.is_empty() {
                 let timediff = node_info
@@ -577,6 +1005,230 @@ impl Roster {
         };
 
         let mut panel_command = PanelCommand::Nothing;
+        let picked = self.picked.contains(&node_info.node_id);
+        let frame = Self::node_frame(ui, selection, focused, picked);
+        let response = frame
+            .show(ui, |ui| {
+                ui.set_width(ui.available_width());
+                if is_compact
+                    && ui
+                        .small_button("⏶")
+                        .on_hover_text("Collapse to one line")
+                        .clicked()
+                {
+                    self.expanded.remove(&node_info.node_id);
+                }
+                panel_command = show_node_info(ui);
+                if matches!(panel_command, PanelCommand::Nothing) {
+                    panel_command = show_plugins(ui);
+                }
+            })
+            .response;
+        if let Some(range) = Self::pick_clicked(ui, &response) {
+            self.pick_request = Some((node_info.node_id, range));
+        }
+        if trust_request.is_some() {
+            self.trust_request = trust_request;
+        }
+        if let Some(pkey) = verify_request {
+            nodebook.set_verified_key(node_info.node_id, pkey);
+        }
+        if is_favorite != was_favorite {
+            nodebook.set_favorite(node_info.node_id, is_favorite);
+        }
+        if alias != previous_alias {
+            nodebook.set_alias(node_info.node_id, alias);
+        }
+        (panel_command, response.rect.height())
+    }
+
+    // Own name of the node, kept in the NodeBook
+    fn alias_ui(ui: &mut egui::Ui, alias: &mut String) {
+        ui.menu_button("✏", |ui| {
+            ui.add(
+                egui::TextEdit::singleline(alias)
+                    .hint_text("Alias")
+                    .desired_width(150.0),
+            );
+            if !alias.is_empty() && ui.button("Reset").clicked() {
+                alias.clear();
+            }
+        })
+        .response
+        .on_hover_text("Set own name for the node");
+    }
+
+    // Star to add or remove the node from observer's favorites
+    fn favorite_ui(ui: &mut egui::Ui, is_favorite: &mut bool) {
+        let (text, hover_text) = if *is_favorite {
+            (
+                RichText::new("★").color(Color32::GOLD),
+                "Remove from favorites",
+            )
+        } else {
+            (RichText::new("☆"), "Add to favorites")
+        };
+        if ui
+            .selectable_label(false, text)
+            .on_hover_text(hover_text)
+            .clicked()
+        {
+            *is_favorite = !*is_favorite;
+        }
+    }
+
+    // Ctrl-click or Shift-click anywhere on the node's frame. Widgets inside
+    // the frame still get the click. Some(true) for Shift-click
+    fn pick_clicked(ui: &egui::Ui, response: &egui::Response) -> Option<bool> {
+        if !response.contains_pointer() {
+            return None;
+        }
+        ui.input(|input| {
+            let modifiers = input.modifiers;
+            (input.pointer.primary_clicked() && (modifiers.command || modifiers.shift))
+                .then_some(modifiers.shift)
+        })
+    }
+
+    // Shift-click picks all nodes between the anchor and the clicked node in
+    // the current order, otherwise the clicked node is toggled
+    fn pick(&mut self, node_id: NodeId, range: bool, order: &[(&NodeInfo, Selection)]) {
+        let position = |node_id: NodeId| {
+            order
+                .iter()
+                .position(|(node_info, _)| node_info.node_id == node_id)
+        };
+        if range
+            && let Some(anchor) = self.pick_anchor.and_then(position)
+            && let Some(index) = position(node_id)
+        {
+            let range = anchor.min(index)..=anchor.max(index);
+            self.picked
+                .extend(order[range].iter().map(|(node_info, _)| node_info.node_id));
+        } else if self.picked.remove(&node_id) {
+            self.pick_anchor = None;
+        } else {
+            self.picked.insert(node_id);
+            self.pick_anchor = Some(node_id);
+        }
+    }
+
+    fn bulk_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        roster_plugins: &mut Vec<&mut dyn Plugin>,
+        nodebook: &mut NodeBook,
+        nodes: &HashMap<NodeId, NodeInfo>,
+    ) {
+        let mut picked_nodes = self
+            .picked
+            .iter()
+            .filter_map(|node_id| nodes.get(node_id))
+            .collect::<Vec<_>>();
+        picked_nodes.sort_by_key(|node_info| node_info.node_id);
+
+        Frame::group(ui.style()).show(ui, |ui| {
+            ui.set_width(ui.available_width());
+            ui.horizontal_wrapped(|ui| {
+                ui.label(format!("picked: {}", picked_nodes.len()))
+                    .on_hover_text("Ctrl-click to pick a node\nShift-click to pick a range");
+
+                ui.menu_button("Telemetry", |ui| {
+                    let mut telemetry_variants = picked_nodes
+                        .iter()
+                        .flat_map(|node_info| node_info.telemetry.keys().copied())
+                        .collect::<Vec<_>>();
+                    telemetry_variants.sort();
+                    telemetry_variants.dedup();
+
+                    for telemetry_variant in telemetry_variants {
+                        let reporting = picked_nodes
+                            .iter()
+                            .filter(|node_info| {
+                                node_info.telemetry.contains_key(&telemetry_variant)
+                            })
+                            .map(|node_info| node_info.node_id)
+                            .collect::<Vec<_>>();
+                        let enabled_for = self
+                            .telemetry_enabled_for
+                            .entry(telemetry_variant)
+                            .or_default();
+                        let mut enabled = reporting
+                            .iter()
+                            .all(|node_id| enabled_for.contains(node_id));
+                        if ui
+                            .checkbox(
+                                &mut enabled,
+                                format!("{} ({})", telemetry_variant, reporting.len()),
+                            )
+                            .changed()
+                        {
+                            if enabled {
+                                for node_id in reporting {
+                                    if !enabled_for.contains(&node_id) {
+                                        enabled_for.push(node_id);
+                                    }
+                                }
+                            } else {
+                                enabled_for.retain(|node_id| !reporting.contains(node_id));
+                            }
+                        }
+                    }
+                });
+
+                if ui
+                    .button("📌 To NodeBook")
+                    .on_hover_text("Save last known positions as manual positions")
+                    .clicked()
+                {
+                    for node_info in picked_nodes.iter() {
+                        if let Some(last_position) = node_info.position.last() {
+                            let position =
+                                geo::Point::new(last_position.longitude, last_position.latitude);
+                            let annotation = nodebook.node(node_info.node_id).or_default();
+                            if annotation.position.is_none() {
+                                annotation.position = Some(position);
+                                annotation.updated = Some(chrono::Utc::now());
+                            }
+                        }
+                    }
+                }
+
+                if ui
+                    .button("Copy")
+                    .on_hover_text("Copy node ids and names as text")
+                    .clicked()
+                {
+                    let text = picked_nodes
+                        .iter()
+                        .map(|node_info| {
+                            if let Some(extended) = node_info.extended_info_history.last() {
+                                format!(
+                                    "{} {} {}",
+                                    node_info.node_id, extended.short_name, extended.long_name
+                                )
+                            } else {
+                                node_info.node_id.to_string()
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    ui.ctx().copy_text(text);
+                }
+
+                for roster_plugin in roster_plugins.iter_mut() {
+                    roster_plugin.bulk_ui(ui, &self.picked, nodebook);
+                }
+
+                if ui.button("✖").on_hover_text("Clear picked nodes").clicked() {
+                    self.picked.clear();
+                    self.pick_anchor = None;
+                }
+            });
+        });
+    }
+
+    fn node_frame(ui: &egui::Ui, selection: Selection, focused: bool, picked: bool) -> Frame {
         let mut frame = Frame::group(ui.style());
         match selection {
             Selection::None => {}
@@ -587,17 +1239,96 @@ impl Roster {
                 frame = frame.stroke(Stroke::new(0.5, Color32::LIGHT_BLUE));
             }
         }
-        let height = frame
+        if picked {
+            frame = frame.stroke(Stroke::new(1.5, Color32::GOLD));
+        }
+        if focused {
+            frame = frame.fill(ui.visuals().selection.bg_fill.gamma_multiply(0.3));
+        }
+        frame
+    }
+
+    // Short name, node id, key badge and last seen in one line
+    fn compact_node_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        node_info: &NodeInfo,
+        selection: Selection,
+        focused: bool,
+        nodebook: &NodeBook,
+        current_datetime: chrono::DateTime<chrono::Utc>,
+    ) -> (PanelCommand, f32) {
+        let is_favorite = nodebook.is_favorite(&node_info.node_id);
+        let alias = nodebook.alias(&node_info.node_id);
+        let verified_key = nodebook.verified_key(&node_info.node_id);
+        let extended = node_info.extended_info_history.last();
+        let picked = self.picked.contains(&node_info.node_id);
+        let response = Self::node_frame(ui, selection, focused, picked)
             .show(ui, |ui| {
                 ui.set_width(ui.available_width());
-                panel_command = show_node_info(ui);
-                if matches!(panel_command, PanelCommand::Nothing) {
-                    panel_command = show_plugins(ui);
-                }
+                ui.horizontal(|ui| {
+                    if is_favorite {
+                        ui.label(RichText::new("★").color(Color32::GOLD));
+                    }
+                    match extended.map(|v| &v.pkey) {
+                        Some(PublicKey::Key(_)) => {
+                            ui.label(RichText::new("🔒").color(Color32::LIGHT_GREEN));
+                        }
+                        Some(PublicKey::Compromised(_)) => {
+                            ui.label(RichText::new("🔒").color(Color32::YELLOW));
+                        }
+                        Some(PublicKey::None) => {
+                            ui.label(RichText::new("🔓").color(Color32::LIGHT_RED));
+                        }
+                        None => {}
+                    }
+                    if let Some(verified_key) = verified_key
+                        && let Some(extended) = extended
+                    {
+                        if extended.pkey.key() == Some(verified_key) {
+                            ui.label(RichText::new("☑").color(Color32::LIGHT_BLUE))
+                                .on_hover_text("Key is verified by you");
+                        } else {
+                            ui.label(RichText::new("⚠").color(Color32::RED))
+                                .on_hover_text("Announced key differs from the verified one");
+                        }
+                    }
+                    if node_info.has_key_changed() {
+                        ui.label(RichText::new("🔁").color(Color32::ORANGE))
+                            .on_hover_text("Node announced different public keys");
+                    }
+                    if let Some(alias) = alias {
+                        let label = ui.label(RichText::new(alias).strong());
+                        if let Some(extended) = extended {
+                            label.on_hover_text(format!(
+                                "Alias, announced short name: {}",
+                                extended.short_name
+                            ));
+                        }
+                    } else if let Some(extended) = extended {
+                        ui.label(RichText::new(&extended.short_name).strong());
+                    }
+                    ui.label(node_info.node_id.to_string());
+                    ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                        if let Some(label) = node_info
+                            .packet_statistics
+                            .last()
+                            .and_then(|v| format_timediff(v.timestamp, current_datetime))
+                        {
+                            ui.label(format!("🕒 {}", label));
+                        }
+                    });
+                });
             })
             .response
-            .rect
-            .height();
-        (panel_command, height)
+            .interact(egui::Sense::click())
+            .on_hover_text("Click to expand");
+
+        if let Some(range) = Self::pick_clicked(ui, &response) {
+            self.pick_request = Some((node_info.node_id, range));
+        } else if response.clicked() {
+            self.expanded.insert(node_info.node_id);
+        }
+        (PanelCommand::Nothing, response.rect.height())
     }
 }
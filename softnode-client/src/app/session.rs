@@ -0,0 +1,254 @@
+use std::collections::HashMap;
+#[cfg(target_arch = "wasm32")]
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use egui::RichText;
+#[cfg(target_arch = "wasm32")]
+use egui::mutex::Mutex;
+use meshtastic_connect::keyring::node_id::NodeId;
+
+use crate::app::{
+    data::{JournalData, NodeInfo},
+    node_book::NodeBook,
+};
+
+// Bumped on incompatible changes of the bundled types
+pub const SCHEMA_VERSION: u32 = 1;
+
+// Read first, to reject bundles of other versions with a clear message
+#[derive(serde::Deserialize)]
+struct BundleHeader {
+    schema_version: u32,
+}
+
+#[derive(serde::Serialize)]
+struct BundleRef<'a> {
+    schema_version: u32,
+    exported: DateTime<Utc>,
+    journal: &'a Vec<JournalData>,
+    nodes: &'a HashMap<NodeId, NodeInfo>,
+    nodebook: &'a NodeBook,
+}
+
+// Captured session: journal, nodes and NodeBook's zones and annotations
+#[derive(serde::Deserialize)]
+pub struct Bundle {
+    pub exported: DateTime<Utc>,
+    pub journal: Vec<JournalData>,
+    pub nodes: HashMap<NodeId, NodeInfo>,
+    pub nodebook: NodeBook,
+}
+
+pub fn export_json(
+    journal: &Vec<JournalData>,
+    nodes: &HashMap<NodeId, NodeInfo>,
+    nodebook: &NodeBook,
+) -> Result<String, String> {
+    serde_json::to_string(&BundleRef {
+        schema_version: SCHEMA_VERSION,
+        exported: Utc::now(),
+        journal,
+        nodes,
+        nodebook,
+    })
+    .map_err(|e| e.to_string())
+}
+
+pub fn import_json(json: &str) -> Result<Bundle, String> {
+    let header: BundleHeader =
+        serde_json::from_str(json).map_err(|e| format!("Not a session bundle: {}", e))?;
+    if header.schema_version != SCHEMA_VERSION {
+        return Err(format!(
+            "Unsupported bundle version: {} (expected {})",
+            header.schema_version, SCHEMA_VERSION
+        ));
+    }
+    serde_json::from_str(json).map_err(|e| e.to_string())
+}
+
+// Bundle is saved to file picked by dialog
+#[cfg(not(target_arch = "wasm32"))]
+fn save_file(file_name: &str, json: &str) -> Result<bool, String> {
+    let Some(path) = rfd::FileDialog::new()
+        .set_file_name(file_name)
+        .add_filter("JSON", &["json"])
+        .save_file()
+    else {
+        return Ok(false);
+    };
+    std::fs::write(&path, json).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(true)
+}
+
+// Bundle is downloaded by browser
+#[cfg(target_arch = "wasm32")]
+fn save_file(file_name: &str, json: &str) -> Result<bool, String> {
+    use eframe::wasm_bindgen::{JsCast, JsValue};
+    use web_sys::js_sys;
+
+    let js_error = |e: JsValue| format!("{:?}", e);
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type("application/json");
+    let blob = web_sys::Blob::new_with_str_sequence_and_options(
+        &js_sys::Array::of1(&JsValue::from_str(json)),
+        &options,
+    )
+    .map_err(js_error)?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob).map_err(js_error)?;
+    let anchor = web_sys::window()
+        .and_then(|window| window.document())
+        .ok_or("No document")?
+        .create_element("a")
+        .map_err(js_error)?
+        .dyn_into::<web_sys::HtmlAnchorElement>()
+        .map_err(|_| "Not an anchor element")?;
+    anchor.set_href(&url);
+    anchor.set_download(file_name);
+    anchor.click();
+    web_sys::Url::revoke_object_url(&url).map_err(js_error)?;
+    Ok(true)
+}
+
+// None if dialog is cancelled
+#[cfg(not(target_arch = "wasm32"))]
+fn pick_file() -> Option<Result<String, String>> {
+    let path = rfd::FileDialog::new()
+        .add_filter("JSON", &["json"])
+        .pick_file()?;
+    Some(std::fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e)))
+}
+
+// Browser's file input is async: content is put to `picked` when it's read
+#[cfg(target_arch = "wasm32")]
+fn pick_file(picked: Arc<Mutex<Option<Result<String, String>>>>, egui_ctx: egui::Context) {
+    wasm_bindgen_futures::spawn_local(async move {
+        let Some(file) = rfd::AsyncFileDialog::new()
+            .add_filter("JSON", &["json"])
+            .pick_file()
+            .await
+        else {
+            return;
+        };
+        let json = String::from_utf8(file.read().await)
+            .map_err(|e| format!("{}: {}", file.file_name(), e));
+        *picked.lock() = Some(json);
+        egui_ctx.request_repaint();
+    });
+}
+
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+pub struct SessionPanel {
+    #[cfg(target_arch = "wasm32")]
+    #[serde(skip)]
+    picked: Arc<Mutex<Option<Result<String, String>>>>,
+    #[serde(skip)]
+    result: Option<Result<String, String>>,
+}
+
+impl SessionPanel {
+    // Returns bundle to load instead of the current session
+    pub fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        journal: &Vec<JournalData>,
+        nodes: &HashMap<NodeId, NodeInfo>,
+        nodebook: &NodeBook,
+        offline: bool,
+    ) -> Option<Bundle> {
+        #[cfg(not(target_arch = "wasm32"))]
+        let mut picked = None;
+        #[cfg(target_arch = "wasm32")]
+        let picked = self.picked.lock().take();
+
+        ui.heading("Session");
+        if offline {
+            ui.label("Session is loaded from bundle, sync with server is stopped");
+        }
+        ui.label(format!(
+            "Journal entries: {}, nodes: {}",
+            journal.len(),
+            nodes.len()
+        ));
+        ui.horizontal(|ui| {
+            if ui
+                .button("💾 Export session")
+                .on_hover_text("Save journal, nodes and NodeBook to JSON file")
+                .clicked()
+            {
+                let file_name = format!("session-{}.json", Utc::now().format("%Y%m%d-%H%M%S"));
+                match export_json(journal, nodes, nodebook)
+                    .and_then(|json| save_file(&file_name, &json).map(|saved| (saved, json)))
+                {
+                    Ok((true, json)) => {
+                        self.result = Some(Ok(format!("Exported: {} bytes", json.len())))
+                    }
+                    Ok((false, _)) => {}
+                    Err(error) => self.result = Some(Err(error)),
+                }
+            }
+            if ui
+                .button("📥 Import session")
+                .on_hover_text(
+                    "Load JSON file: replace journal and nodes with the bundle's ones,\n\
+                     NodeBook's zones and annotations are merged",
+                )
+                .clicked()
+            {
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    picked = pick_file();
+                }
+                #[cfg(target_arch = "wasm32")]
+                pick_file(self.picked.clone(), ui.ctx().clone());
+            }
+        });
+
+        let imported = match picked.map(|json| json.and_then(|json| import_json(&json))) {
+            Some(Ok(bundle)) => {
+                self.result = Some(Ok(format!(
+                    "Imported session exported at {}",
+                    bundle.exported.format("%Y-%m-%d %H:%M:%S")
+                )));
+                Some(bundle)
+            }
+            Some(Err(error)) => {
+                self.result = Some(Err(error));
+                None
+            }
+            None => None,
+        };
+        match &self.result {
+            Some(Ok(message)) => {
+                ui.label(message);
+            }
+            Some(Err(error)) => {
+                ui.label(RichText::new(error).small().color(egui::Color32::RED));
+            }
+            None => {}
+        }
+
+        imported
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mismatched_version() {
+        let json = export_json(&Vec::new(), &HashMap::new(), &NodeBook::default()).unwrap();
+        assert!(import_json(&json).is_ok());
+
+        let json = json.replace(
+            &format!("\"schema_version\":{}", SCHEMA_VERSION),
+            &format!("\"schema_version\":{}", SCHEMA_VERSION + 1),
+        );
+        assert!(
+            import_json(&json)
+                .err()
+                .is_some_and(|error| error.starts_with("Unsupported bundle version"))
+        );
+    }
+}
@@ -1,23 +1,64 @@
 use crate::app::{
-    ChannelPublicy, ChannelStats,
-    telemetry_formatter::{BarometricUnit, TelemetryFormatter, TemperatureUnit},
+    ChannelPublicy, ChannelStats, PersistentData,
+    api_auth::ApiAuth,
+    data::RadioThresholds,
+    node_book::{MergeStrategy, NodeBook},
+    qr,
+    telemetry_formatter::{
+        BarometricUnit, DEFAULT_CPM_PER_MICROSIEVERT, RadiationUnit, TemperatureUnit,
+    },
+    tile_cache::TileCacheConfig,
+    validate_api_url,
 };
 use egui::RichText;
-use meshtastic_connect::keyring::{Keyring, key::Key, node_id::NodeId};
+use meshtastic_connect::keyring::{ChannelImport, Keyring, key::Key, node_id::NodeId};
 use std::{collections::HashMap, sync::LazyLock};
 
 #[derive(Default, serde::Deserialize, serde::Serialize)]
 pub struct Settings {
     pub keyring_edit: String,
     pub encoder_error: Option<String>,
+    // Edited copy of the thresholds, applied by the button
+    #[serde(skip)]
+    pub thresholds_edit: Option<RadioThresholds>,
+    // NodeBook's annotations to import, in JSON
+    #[serde(skip)]
+    pub annotations_import: String,
+    #[serde(skip)]
+    pub annotations_merge: MergeStrategy,
+    #[serde(skip)]
+    pub annotations_result: Option<Result<usize, String>>,
+    // Edited API URL, applied by the button
+    #[serde(skip)]
+    pub api_url_edit: Option<String>,
+    #[serde(skip)]
+    pub api_auth_edit: Option<ApiAuth>,
+    // Meshtastic channel-share URL to import
+    #[serde(skip)]
+    pub share_url_import: String,
+    #[serde(skip)]
+    pub share_url_result: Option<Result<String, String>>,
+    // Share URL shown as QR code
+    #[serde(skip)]
+    pub share_qr: Option<String>,
 }
 
 const SPACE_SIZE: f32 = 13.0;
+const QR_SIZE: f32 = 240.0;
 
 impl Settings {
     pub fn new(keyring: &Keyring) -> Self {
         Self {
             encoder_error: None,
+            thresholds_edit: None,
+            annotations_import: String::new(),
+            annotations_merge: Default::default(),
+            annotations_result: None,
+            api_url_edit: None,
+            api_auth_edit: None,
+            share_url_import: String::new(),
+            share_url_result: None,
+            share_qr: None,
             keyring_edit: serde_yaml_ng::to_string(keyring).unwrap(),
         }
     }
@@ -25,10 +66,24 @@ impl Settings {
     pub fn ui(
         &mut self,
         ctx: &egui::Context,
+        persistent: &mut PersistentData,
         keyring: &mut Keyring,
-        telemetry_formatter: &mut TelemetryFormatter,
+        nodebook: &mut NodeBook,
+        api_auth: &mut ApiAuth,
         channel_stats: &HashMap<ChannelPublicy, ChannelStats>,
     ) -> bool {
+        let PersistentData {
+            telemetry_formatter,
+            tile_cache,
+            try_all_channel_keys,
+            radio_thresholds,
+            packet_time,
+            assume_config,
+            api_url,
+            theme,
+            update_interval_secs: update_interval,
+            ..
+        } = persistent;
         let mut need_update = false;
 
         egui::CentralPanel::default().show(ctx, |ui| {
@@ -41,6 +96,75 @@ impl Settings {
             ui.label("При обновлении версии приложения, настройки могут быть сброшены на значения по умолчанию");
             ui.add_space(SPACE_SIZE);
 
+            ui.heading("Server");
+            let api_url_edit = self.api_url_edit.get_or_insert_with(|| api_url.clone());
+            let validation = validate_api_url(api_url_edit);
+            ui.horizontal(|ui| {
+                ui.label("API URL");
+                ui.add(egui::TextEdit::singleline(api_url_edit).hint_text(env!("SOFTNODE_API_URL_BASE")));
+                if ui.add_enabled(validation.is_ok() && api_url_edit != api_url, egui::Button::new("Apply"))
+                    .on_hover_text("Reload all data from the new server")
+                    .clicked() {
+                    *api_url = api_url_edit.trim().to_string();
+                    need_update = true;
+                }
+                if ui.button("Default").clicked() {
+                    *api_url_edit = env!("SOFTNODE_API_URL_BASE").to_string();
+                }
+            });
+            if let Err(error) = validation {
+                ui.label(RichText::new(error).color(egui::Color32::LIGHT_RED));
+            }
+            let api_auth_edit = self.api_auth_edit.get_or_insert_with(|| api_auth.clone());
+            ui.horizontal(|ui| {
+                ui.label("Authorization");
+                if ui.selectable_label(matches!(api_auth_edit, ApiAuth::None), "None").clicked() {
+                    *api_auth_edit = ApiAuth::None;
+                }
+                if ui.selectable_label(matches!(api_auth_edit, ApiAuth::Bearer(_)), "Bearer token").clicked()
+                    && !matches!(api_auth_edit, ApiAuth::Bearer(_)) {
+                    *api_auth_edit = ApiAuth::Bearer(String::new());
+                }
+                if ui.selectable_label(matches!(api_auth_edit, ApiAuth::Basic { .. }), "Basic").clicked()
+                    && !matches!(api_auth_edit, ApiAuth::Basic { .. }) {
+                    *api_auth_edit = ApiAuth::Basic { username: String::new(), password: String::new() };
+                }
+            });
+            match api_auth_edit {
+                ApiAuth::None => {}
+                ApiAuth::Bearer(token) => {
+                    ui.horizontal(|ui| {
+                        ui.label("Token");
+                        ui.add(egui::TextEdit::singleline(token).password(true));
+                    });
+                }
+                ApiAuth::Basic { username, password } => {
+                    ui.horizontal(|ui| {
+                        ui.label("Username");
+                        ui.add(egui::TextEdit::singleline(username));
+                        ui.label("Password");
+                        ui.add(egui::TextEdit::singleline(password).password(true));
+                    });
+                }
+            }
+            if ui.add_enabled(api_auth_edit != api_auth, egui::Button::new("Apply credentials"))
+                .on_hover_text("Stored separately from other settings")
+                .clicked() {
+                *api_auth = api_auth_edit.clone();
+                need_update = true;
+            }
+            // Used by the next sync, no reload is needed
+            ui.horizontal(|ui| {
+                ui.label("Update interval");
+                let mut seconds = update_interval.as_secs();
+                if ui.add(egui::Slider::new(&mut seconds, 1..=600).logarithmic(true).suffix(" s"))
+                    .on_hover_text("Delay between syncs when server has no new data")
+                    .changed() {
+                    *update_interval = std::time::Duration::from_secs(seconds);
+                }
+            });
+            ui.add_space(SPACE_SIZE);
+
             let text = telemetry_formatter.temperature_units.to_string();
             egui::ComboBox::from_label("Temperature Units").selected_text(text).show_ui(ui, |ui| {
                 ui.selectable_value(&mut telemetry_formatter.temperature_units,
@@ -58,14 +182,173 @@ impl Settings {
                 ui.selectable_value(&mut telemetry_formatter.barometric_units,
                     BarometricUnit::MillimetersOfMercury,
                     BarometricUnit::MillimetersOfMercury.to_string());
+                ui.selectable_value(&mut telemetry_formatter.barometric_units,
+                    BarometricUnit::InchesOfMercury,
+                    BarometricUnit::InchesOfMercury.to_string());
+
+            });
+
+            let text = telemetry_formatter.radiation_units.to_string();
+            egui::ComboBox::from_label("Radiation Units").selected_text(text).show_ui(ui, |ui|  {
+                ui.selectable_value(&mut telemetry_formatter.radiation_units,
+                    RadiationUnit::MicrosievertsPerHour,
+                    RadiationUnit::MicrosievertsPerHour.to_string());
+                ui.selectable_value(&mut telemetry_formatter.radiation_units,
+                    RadiationUnit::CountsPerMinute,
+                    RadiationUnit::CountsPerMinute.to_string());
+            });
+            if telemetry_formatter.radiation_units == RadiationUnit::CountsPerMinute {
+                ui.horizontal(|ui| {
+                    ui.label("CPM per 1 μSv/h");
+                    ui.add(egui::DragValue::new(&mut telemetry_formatter.cpm_per_microsievert).range(1.0..=10000.0))
+                        .on_hover_text("Depends on the Geiger tube");
+                    if ui.small_button("⟲").on_hover_text("SBM-20/J305 tube").clicked() {
+                        telemetry_formatter.cpm_per_microsievert = DEFAULT_CPM_PER_MICROSIEVERT;
+                    }
+                });
+            }
+
+            ui.add_space(SPACE_SIZE);
+
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                ui.heading("Tile cache");
+                ui.checkbox(&mut tile_cache.enabled, "Store map tiles on disk");
+                ui.add_enabled_ui(tile_cache.enabled, |ui| {
+                    let default_path = TileCacheConfig::default()
+                        .path()
+                        .map(|v| v.display().to_string())
+                        .unwrap_or_default();
+                    ui.horizontal(|ui| {
+                        ui.label("Directory");
+                        ui.add(egui::TextEdit::singleline(&mut tile_cache.directory).hint_text(default_path));
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Max size");
+                        ui.add(egui::DragValue::new(&mut tile_cache.max_size_mb).range(10..=10240).suffix(" MiB"));
+                    });
+                });
+                ui.add_space(SPACE_SIZE);
+            }
+            #[cfg(target_arch = "wasm32")]
+            let _ = tile_cache;
+
+            ui.heading("Radio values filter");
+            let thresholds = self.thresholds_edit.get_or_insert_with(|| radio_thresholds.clone());
+            ui.checkbox(&mut thresholds.enabled, "Discard bogus RSSI/SNR values reported by gateways");
+            ui.add_enabled_ui(thresholds.enabled, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("RSSI");
+                    ui.add(egui::DragValue::new(&mut thresholds.rssi_lower).range(-1000..=thresholds.rssi_upper).suffix(" dBm"))
+                        .on_hover_text("Lower values are discarded");
+                    ui.label("..");
+                    ui.add(egui::DragValue::new(&mut thresholds.rssi_upper).range(thresholds.rssi_lower..=1000).suffix(" dBm"))
+                        .on_hover_text("Higher values are discarded");
+                });
+                ui.horizontal(|ui| {
+                    ui.label("SNR");
+                    ui.add(egui::DragValue::new(&mut thresholds.snr_lower).range(-1000.0..=thresholds.snr_upper).suffix(" dB"))
+                        .on_hover_text("Lower values are discarded");
+                    ui.label("..");
+                    ui.add(egui::DragValue::new(&mut thresholds.snr_upper).range(thresholds.snr_lower..=1000.0).suffix(" dB"))
+                        .on_hover_text("Higher values are discarded");
+                });
+            });
+            ui.horizontal(|ui| {
+                if ui.add_enabled(thresholds != radio_thresholds, egui::Button::new("Apply")).clicked() {
+                    *radio_thresholds = thresholds.clone();
+                    need_update = true;
+                }
+                if ui.button("Default").clicked() {
+                    *thresholds = RadioThresholds::default();
+                }
+            });
+            ui.add_space(SPACE_SIZE);
+
+            ui.heading("Packet time");
+            if ui.checkbox(&mut packet_time.position, "Use position time sent by nodes")
+                .on_hover_text("Otherwise time of receiving is used")
+                .changed() {
+                need_update = true;
+            }
+            if ui.checkbox(&mut packet_time.telemetry, "Use telemetry time sent by nodes")
+                .on_hover_text("Otherwise time of receiving is used")
+                .changed() {
+                need_update = true;
+            }
+            ui.horizontal(|ui| {
+                ui.label("Accepted difference");
+                let response = ui.add(egui::DragValue::new(&mut packet_time.window_hours).range(1..=720).suffix(" h"))
+                    .on_hover_text("Position and telemetry time too far from time of receiving is ignored");
+                if response.drag_stopped() || (response.changed() && !response.dragged()) {
+                    need_update = true;
+                }
+            });
+            ui.add_space(SPACE_SIZE);
 
+            ui.heading("Assumed positions");
+            if ui.checkbox(&mut assume_config.trilateration, "Trilateration by RSSI")
+                .on_hover_text("Used when three or more gateways with known positions heard the node")
+                .changed() {
+                need_update = true;
+            }
+            ui.add_enabled_ui(assume_config.trilateration, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Path-loss exponent");
+                    let response = ui.add(egui::DragValue::new(&mut assume_config.path_loss_exponent).range(1.5..=6.0).speed(0.05))
+                        .on_hover_text("2.0 for free space, up to 4.0 for dense urban areas");
+                    // Don't resync on every step of dragging
+                    if response.drag_stopped() || (response.changed() && !response.dragged()) {
+                        need_update = true;
+                    }
+                });
             });
+            ui.add_space(SPACE_SIZE);
 
+            ui.heading("Node annotations");
+            ui.horizontal(|ui| {
+                if ui.button("📋 Export").on_hover_text("Copy annotations as JSON").clicked() {
+                    match nodebook.export_json() {
+                        Ok(json) => ui.ctx().copy_text(json),
+                        Err(error) => self.annotations_result = Some(Err(error)),
+                    }
+                }
+                egui::ComboBox::from_id_salt("annotations_merge")
+                    .selected_text(self.annotations_merge.to_string())
+                    .show_ui(ui, |ui| {
+                        for strategy in [MergeStrategy::KeepNewest, MergeStrategy::KeepLocal, MergeStrategy::Overwrite] {
+                            ui.selectable_value(&mut self.annotations_merge, strategy, strategy.to_string());
+                        }
+                    });
+                if ui.add_enabled(!self.annotations_import.is_empty(), egui::Button::new("📥 Import")).clicked() {
+                    let result = nodebook.import_json(&self.annotations_import, self.annotations_merge);
+                    if matches!(result, Ok(changed) if changed > 0) {
+                        self.annotations_import.clear();
+                        // Positions are used by assumed positions, recalculate them
+                        need_update = true;
+                    }
+                    self.annotations_result = Some(result);
+                }
+            });
+            ui.add(egui::TextEdit::multiline(&mut self.annotations_import)
+                .hint_text("Paste exported annotations here")
+                .desired_rows(2));
+            match &self.annotations_result {
+                Some(Ok(changed)) => { ui.label(format!("Annotations imported: {}", changed)); }
+                Some(Err(error)) => { ui.label(RichText::new(error).small().color(egui::Color32::RED)); }
+                None => {}
+            }
             ui.add_space(SPACE_SIZE);
 
             egui::ScrollArea::vertical().show(ui, |ui| {
                 ui.heading("Theme");
-                egui::widgets::global_theme_preference_buttons(ui);
+                ui.horizontal(|ui| {
+                    let previous = *theme;
+                    theme.radio_buttons(ui);
+                    if *theme != previous {
+                        ui.ctx().set_theme(*theme);
+                    }
+                });
 
                 let theme =
                     egui_extras::syntax_highlighting::CodeTheme::from_memory(ui.ctx(), ui.style());
@@ -107,6 +390,13 @@ impl Settings {
                     let theme = egui_extras::syntax_highlighting::CodeTheme::from_memory(ui.ctx(), ui.style());
                     egui_extras::syntax_highlighting::code_view_ui(ui, &theme, KEYRING_YAML.as_str(), "yaml");
                 });
+                if ui
+                    .checkbox(try_all_channel_keys, "Try all channel keys for unknown channels")
+                    .on_hover_text("Slower: every packet of an unknown channel is decrypted with each key")
+                    .changed()
+                {
+                    need_update = true;
+                }
                 if !channel_stats.is_empty() {
                     ui.collapsing(format!("Channel Stats ({} channels)", channel_stats.len()), |ui| {
                         let mut display_chan_info = |k: &ChannelPublicy, v: &ChannelStats| {
@@ -171,10 +461,124 @@ impl Settings {
                     }
                 }
 
+                ui.add_space(SPACE_SIZE);
+                ui.heading("Channel and contact URLs");
+                ui.label("Meshtastic share URLs, as apps show them with QR codes");
+                let shares = keyring
+                    .channel_names()
+                    .filter_map(|name| Some((name.to_string(), keyring.channel_share_url(name)?)))
+                    .chain(keyring.peer_ids().filter_map(|node_id| {
+                        Some((node_id.to_string(), keyring.contact_share_url(node_id)?))
+                    }))
+                    .collect::<Vec<_>>();
+                ui.horizontal_wrapped(|ui| {
+                    for (title, url) in shares {
+                        let shown = self.share_qr.as_ref() == Some(&url);
+                        ui.label(RichText::new(&title).strong());
+                        if ui.small_button("📋").on_hover_text("Copy share URL").clicked() {
+                            ui.ctx().copy_text(url.clone());
+                        }
+                        if ui
+                            .selectable_label(shown, "QR")
+                            .on_hover_text("Show share URL as QR code")
+                            .clicked()
+                        {
+                            self.share_qr = (!shown).then_some(url);
+                        }
+                        ui.separator();
+                    }
+                });
+                if let Some(url) = &self.share_qr {
+                    qr::qr_ui(ui, url, QR_SIZE);
+                }
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.share_url_import)
+                            .hint_text("https://meshtastic.org/e/#..."),
+                    );
+                    if ui
+                        .add_enabled(
+                            !self.share_url_import.is_empty(),
+                            egui::Button::new("📥 Import URL"),
+                        )
+                        .on_hover_text("Channel or contact URL, known channels and contacts are kept")
+                        .clicked()
+                    {
+                        need_update |= self.import_share_url(keyring);
+                    }
+                });
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    ui.label(RichText::new("Drop a screenshot with QR code to import it").weak());
+                    let dropped = ui.ctx().input(|input| {
+                        input
+                            .raw
+                            .dropped_files
+                            .iter()
+                            .filter_map(|file| file.path.clone())
+                            .collect::<Vec<_>>()
+                    });
+                    for path in dropped {
+                        match qr::scan_file(&path) {
+                            Ok(url) => {
+                                self.share_url_import = url;
+                                need_update |= self.import_share_url(keyring);
+                            }
+                            Err(error) => self.share_url_result = Some(Err(error)),
+                        }
+                    }
+                }
+                match &self.share_url_result {
+                    Some(Ok(message)) => {
+                        ui.label(message);
+                    }
+                    Some(Err(error)) => {
+                        ui.label(RichText::new(error).small().color(egui::Color32::RED));
+                    }
+                    None => {}
+                }
+
                 ui.add_space(SPACE_SIZE);
             });
         });
 
         need_update
     }
+
+    // Imports channel or contact of `share_url_import`, true if keyring is changed
+    fn import_share_url(&mut self, keyring: &mut Keyring) -> bool {
+        let url = self.share_url_import.trim();
+        let result = if url.contains("meshtastic.org/v/") {
+            keyring
+                .import_contact_url(url)
+                .map(|node_id| format!("{}: contact added", node_id))
+        } else if url.contains("meshtastic.org/e/") {
+            keyring.import_share_url(url).map(|imports| {
+                imports
+                    .iter()
+                    .map(|import| match import {
+                        ChannelImport::Added(name) => format!("{}: added", name),
+                        ChannelImport::Known(name) => format!("{}: already known", name),
+                        ChannelImport::Conflict(name) => {
+                            format!("{}: known with another key, kept", name)
+                        }
+                        ChannelImport::Skipped(name, reason) => {
+                            format!("{}: skipped, {}", name, reason)
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+        } else {
+            Err(format!("Not a Meshtastic channel or contact URL: {}", url))
+        };
+
+        let imported = result.is_ok();
+        self.share_url_result = Some(result);
+        if imported {
+            self.share_url_import.clear();
+            self.keyring_edit = serde_yaml_ng::to_string(keyring).unwrap();
+        }
+        imported
+    }
 }
@@ -1,7 +1,7 @@
 use chrono::{DateTime, NaiveTime, TimeDelta, Utc};
 use egui::{Align2, Color32, RichText, Style, TextStyle, epaint::Hsva};
 use egui_plot::{HLine, Line, PlotItem, PlotUi, Points, Text};
-use std::{sync::Arc, time::Duration};
+use std::{ops::RangeInclusive, sync::Arc, time::Duration};
 
 use crate::app::{
     data::{NodeTelemetry, TelemetryValue, TelemetryVariant},
@@ -14,15 +14,25 @@ pub struct Telemetry {}
 #[derive(Default)]
 struct ColorGenerator {
     next_auto_color_idx: usize,
+    // Brighter colors to be legible on dark background
+    dark_mode: bool,
 }
 
 impl ColorGenerator {
+    fn new(dark_mode: bool) -> Self {
+        Self {
+            next_auto_color_idx: 0,
+            dark_mode,
+        }
+    }
+
     fn next_color(&mut self) -> Color32 {
         let i = self.next_auto_color_idx;
         self.next_auto_color_idx += 1;
         let golden_ratio = (5.0_f32.sqrt() - 1.0) / 2.0; // 0.61803398875
         let h = i as f32 * golden_ratio;
-        Hsva::new(h, 0.85, 0.5, 1.0).into() // TODO(emilk): OkLab or some other perspective color space
+        let v = if self.dark_mode { 0.85 } else { 0.5 };
+        Hsva::new(h, 0.85, v, 1.0).into() // TODO(emilk): OkLab or some other perspective color space
     }
 }
 
@@ -72,6 +82,105 @@ fn plot_value(
     plot_ui.text(text);
 }
 
+// Summary of the series' values inside the visible range
+struct SeriesStats {
+    title: String,
+    color: Color32,
+    unit: String,
+    count: usize,
+    min: f64,
+    max: f64,
+    mean: f64,
+    stddev: f64,
+}
+
+impl SeriesStats {
+    fn collect(
+        title: &str,
+        color: Color32,
+        unit: String,
+        values: impl Iterator<Item = f64>,
+    ) -> Option<Self> {
+        let values = values.collect::<Vec<_>>();
+        if values.is_empty() {
+            return None;
+        }
+        let count = values.len();
+        let mean = values.iter().sum::<f64>() / count as f64;
+        let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / count as f64;
+
+        Some(Self {
+            title: title.to_string(),
+            color,
+            unit,
+            count,
+            min: values.iter().copied().fold(f64::INFINITY, f64::min),
+            max: values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            mean,
+            stddev: variance.sqrt(),
+        })
+    }
+}
+
+fn stats_ui(ui: &mut egui::Ui, stats: &[SeriesStats]) {
+    egui::Grid::new("telemetry_stats")
+        .striped(true)
+        .show(ui, |ui| {
+            ui.label("");
+            ui.label("Min");
+            ui.label("Max");
+            ui.label("Avg");
+            ui.label("σ");
+            ui.label("Samples");
+            ui.end_row();
+
+            for series in stats {
+                ui.label(RichText::new(&series.title).color(series.color));
+                ui.label(format!("{:.2} {}", series.min, series.unit));
+                ui.label(format!("{:.2} {}", series.max, series.unit));
+                ui.label(format!("{:.2} {}", series.mean, series.unit));
+                ui.label(format!("{:.2}", series.stddev));
+                ui.label(series.count.to_string());
+                ui.end_row();
+            }
+        });
+}
+
+// Reduces points to min and max per pixel's column inside the visible range.
+// Points are expected to be sorted by x.
+fn decimate(points: &[[f64; 2]], range: &RangeInclusive<f64>, columns: usize) -> Vec<[f64; 2]> {
+    // Keep the nearest points outside of range to not break the line
+    let start = points
+        .partition_point(|[x, _]| x < range.start())
+        .saturating_sub(1);
+    let end = (points.partition_point(|[x, _]| x <= range.end()) + 1).min(points.len());
+    let points = &points[start..end];
+
+    let column_width = (range.end() - range.start()) / columns.max(1) as f64;
+    if points.len() <= columns * 2 || column_width <= 0.0 {
+        return points.to_vec();
+    }
+
+    let mut decimated = Vec::with_capacity(columns * 2 + 2);
+    let column_points = points.chunk_by(|[a, _], [b, _]| {
+        ((a - range.start()) / column_width).floor() == ((b - range.start()) / column_width).floor()
+    });
+    for column in column_points {
+        let min = column.iter().min_by(|a, b| a[1].total_cmp(&b[1])).unwrap();
+        let max = column.iter().max_by(|a, b| a[1].total_cmp(&b[1])).unwrap();
+        if min == max {
+            decimated.push(*min);
+        } else if min[0] <= max[0] {
+            decimated.push(*min);
+            decimated.push(*max);
+        } else {
+            decimated.push(*max);
+            decimated.push(*min);
+        }
+    }
+    decimated
+}
+
 impl Telemetry {
     fn base_datetime(&self, start_time: DateTime<Utc>) -> DateTime<Utc> {
         let base_datetime = start_time
@@ -122,10 +231,10 @@ impl Telemetry {
         &mut self,
         ui: &mut egui::Ui,
         start_time: DateTime<Utc>,
-        telemetry: Vec<(String, TelemetryVariant, &NodeTelemetry)>,
+        telemetry: Vec<(String, TelemetryVariant, &NodeTelemetry, Option<Color32>)>,
         telemetry_formatter: &TelemetryFormatter,
     ) {
-        let mut color_generator: ColorGenerator = Default::default();
+        let mut color_generator = ColorGenerator::new(ui.visuals().dark_mode);
         let basetime = self.base_datetime(start_time);
         let tf = TimeFormatter::new(basetime);
         let lf = LabelFormatter::new(basetime);
@@ -141,16 +250,32 @@ impl Telemetry {
             .position(egui_plot::Corner::LeftTop)
             .follow_insertion_order(true);
 
-        let legend_plot = egui_plot::Plot::new("telemetry_plot")
+        // Unit is displayed only when all series have the same one
+        let mut units = telemetry
+            .iter()
+            .map(|(_, telemetry_variant, _, _)| telemetry_formatter.unit(*telemetry_variant));
+        let y_unit = units
+            .next()
+            .filter(|first| !first.is_empty() && units.all(|unit| unit == *first));
+
+        let mut legend_plot = egui_plot::Plot::new("telemetry_plot")
             .legend(legend)
             .custom_x_axes(x_axes)
             .x_grid_spacer(Self::x_grid)
             .label_formatter(|a, b| lf.format(a, b));
+        if let Some(y_unit) = y_unit {
+            legend_plot =
+                legend_plot.custom_y_axes(vec![egui_plot::AxisHints::new_y().label(y_unit)]);
+        }
 
         let style = ui.style().clone();
-        legend_plot.show(ui, |plot_ui| {
+        let plot_response = legend_plot.show(ui, |plot_ui| {
             let text_value_style = plot_value_is_printable(plot_ui);
-            for (title, telemetry_variant, node_telemetry) in telemetry.iter() {
+            let bounds = plot_ui.plot_bounds();
+            let visible_range = bounds.min()[0]..=bounds.max()[0];
+            let columns = plot_ui.transform().frame().width().max(1.0) as usize;
+            let mut stats = Vec::new();
+            for (title, telemetry_variant, node_telemetry, node_color) in telemetry.iter() {
                 let mut min_value: Option<TelemetryValue> = None;
                 let mut max_value: Option<TelemetryValue> = None;
                 let mut point_groups: Vec<Vec<[f64; 2]>> = Vec::new();
@@ -179,7 +304,17 @@ impl Telemetry {
                 }
                 point_groups.push(c);
 
-                let color = color_generator.next_color();
+                let color = node_color.unwrap_or_else(|| color_generator.next_color());
+                stats.extend(SeriesStats::collect(
+                    title,
+                    color,
+                    telemetry_formatter.unit(*telemetry_variant),
+                    point_groups
+                        .iter()
+                        .flatten()
+                        .filter(|[x, _]| visible_range.contains(x))
+                        .map(|[_, y]| *y),
+                ));
                 for points in point_groups {
                     if min_value != max_value {
                         if let Some(min_value) = &min_value {
@@ -228,6 +363,7 @@ impl Telemetry {
                             *telemetry_variant,
                         );
                     }
+                    let points = decimate(&points, &visible_range, columns);
                     let plot_points = Points::new(title, points.clone()).radius(4.0).color(color);
                     let id = PlotItem::id(&plot_points);
                     plot_ui.points(plot_points);
@@ -299,7 +435,18 @@ impl Telemetry {
                     );
                 }
             }
+            stats
         });
+
+        let stats = plot_response.inner;
+        if !stats.is_empty() {
+            egui::Area::new(ui.id().with("telemetry_stats_area"))
+                .fixed_pos(plot_response.response.rect.right_top() + egui::vec2(-8.0, 8.0))
+                .pivot(Align2::RIGHT_TOP)
+                .show(ui.ctx(), |ui| {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| stats_ui(ui, &stats));
+                });
+        }
     }
 }
 
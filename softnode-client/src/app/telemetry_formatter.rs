@@ -23,6 +23,7 @@ impl fmt::Display for TemperatureUnit {
 pub enum BarometricUnit {
     Hectopascals,
     MillimetersOfMercury,
+    InchesOfMercury,
 }
 
 impl fmt::Display for BarometricUnit {
@@ -30,14 +31,45 @@ impl fmt::Display for BarometricUnit {
         match self {
             BarometricUnit::Hectopascals => write!(f, "hPa"),
             BarometricUnit::MillimetersOfMercury => write!(f, "mmHg"),
+            BarometricUnit::InchesOfMercury => write!(f, "inHg"),
         }
     }
 }
 
-#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[derive(
+    serde::Deserialize, serde::Serialize, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord,
+)]
+pub enum RadiationUnit {
+    #[default]
+    MicrosievertsPerHour,
+    // Counts per minute, depends on the Geiger tube: see `cpm_per_microsievert`
+    CountsPerMinute,
+}
+
+impl fmt::Display for RadiationUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RadiationUnit::MicrosievertsPerHour => write!(f, "μSv/h"),
+            RadiationUnit::CountsPerMinute => write!(f, "CPM"),
+        }
+    }
+}
+
+// Conversion factor for SBM-20 and J305 tubes
+pub const DEFAULT_CPM_PER_MICROSIEVERT: f64 = 153.8;
+
+fn default_cpm_per_microsievert() -> f64 {
+    DEFAULT_CPM_PER_MICROSIEVERT
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq)]
 pub struct TelemetryFormatter {
     pub temperature_units: TemperatureUnit,
     pub barometric_units: BarometricUnit,
+    #[serde(default)]
+    pub radiation_units: RadiationUnit,
+    #[serde(default = "default_cpm_per_microsievert")]
+    pub cpm_per_microsievert: f64,
 }
 
 impl Default for TelemetryFormatter {
@@ -45,6 +77,8 @@ impl Default for TelemetryFormatter {
         Self {
             temperature_units: TemperatureUnit::Celsius,
             barometric_units: BarometricUnit::Hectopascals,
+            radiation_units: RadiationUnit::MicrosievertsPerHour,
+            cpm_per_microsievert: DEFAULT_CPM_PER_MICROSIEVERT,
         }
     }
 }
@@ -55,6 +89,7 @@ impl TelemetryFormatter {
             TelemetryVariant::BarometricPressure => match self.barometric_units {
                 BarometricUnit::Hectopascals => value,
                 BarometricUnit::MillimetersOfMercury => value * 0.750063755419211,
+                BarometricUnit::InchesOfMercury => value * 0.0295299830714,
             },
             TelemetryVariant::EnvironmentTemperature => match self.temperature_units {
                 TemperatureUnit::Celsius => value,
@@ -65,7 +100,10 @@ impl TelemetryFormatter {
             TelemetryVariant::Iaq => value,
             TelemetryVariant::Humidity => value,
             TelemetryVariant::GasResistance => value,
-            TelemetryVariant::Radiation => value,
+            TelemetryVariant::Radiation => match self.radiation_units {
+                RadiationUnit::MicrosievertsPerHour => value,
+                RadiationUnit::CountsPerMinute => value * self.cpm_per_microsievert,
+            },
             TelemetryVariant::PowerMetricVoltage(_) => value,
             TelemetryVariant::PowerMetricCurrent(_) => value,
             TelemetryVariant::AirUtilTx => value,
@@ -101,15 +139,51 @@ impl TelemetryFormatter {
             TelemetryVariant::PaxBLE => value,
         }
     }
+
+    // Unit of the converted value, for plot's axes
+    pub fn unit(&self, variant: TelemetryVariant) -> String {
+        match variant {
+            TelemetryVariant::BarometricPressure => self.barometric_units.to_string(),
+            TelemetryVariant::EnvironmentTemperature
+            | TelemetryVariant::HealthTemperature
+            | TelemetryVariant::AirCo2Temperature => self.temperature_units.to_string(),
+            TelemetryVariant::Radiation => self.radiation_units.to_string(),
+            TelemetryVariant::Lux => "lx".into(),
+            TelemetryVariant::UVLux => "uvlx".into(),
+            TelemetryVariant::Iaq => "IAQ".into(),
+            TelemetryVariant::Humidity
+            | TelemetryVariant::BatteryLevel
+            | TelemetryVariant::SpO2
+            | TelemetryVariant::AirCo2Humidity => "%".into(),
+            TelemetryVariant::GasResistance => "kΩ".into(),
+            TelemetryVariant::PowerMetricVoltage(_) | TelemetryVariant::Voltage => "V".into(),
+            TelemetryVariant::PowerMetricCurrent(_) => "A".into(),
+            TelemetryVariant::AirUtilTx | TelemetryVariant::ChannelUtilization => "%/min".into(),
+            TelemetryVariant::HeartRate => "bpm".into(),
+            TelemetryVariant::UptimeSeconds => "s".into(),
+            TelemetryVariant::AirPM10Standard
+            | TelemetryVariant::AirPM25Standard
+            | TelemetryVariant::AirPM100Standard
+            | TelemetryVariant::AirPM10Environmental
+            | TelemetryVariant::AirPM25Environmental
+            | TelemetryVariant::AirPM100Environmental => "μg/m³".into(),
+            TelemetryVariant::AirParticles03um
+            | TelemetryVariant::AirParticles05um
+            | TelemetryVariant::AirParticles10um
+            | TelemetryVariant::AirParticles25um
+            | TelemetryVariant::AirParticles50um
+            | TelemetryVariant::AirParticles100um => "particles/cm³".into(),
+            TelemetryVariant::AirCo2 => "ppm".into(),
+            TelemetryVariant::PaxWifi | TelemetryVariant::PaxBLE => String::new(),
+        }
+    }
+
     pub fn format(&self, value: f64, variant: TelemetryVariant) -> String {
         let value = self.value(value, variant);
         match variant {
-            TelemetryVariant::BarometricPressure => match self.barometric_units {
-                BarometricUnit::Hectopascals => format!("{:.2} hPa", value),
-                BarometricUnit::MillimetersOfMercury => {
-                    format!("{:.2} mmHg", value)
-                }
-            },
+            TelemetryVariant::BarometricPressure => {
+                format!("{:.2} {}", value, self.barometric_units)
+            }
             TelemetryVariant::EnvironmentTemperature => match self.temperature_units {
                 TemperatureUnit::Celsius => {
                     format!("{:.2} °C", value)
@@ -123,7 +197,10 @@ impl TelemetryFormatter {
             TelemetryVariant::Iaq => format!("{:.2} IAQ", value),
             TelemetryVariant::Humidity => format!("{:.2}%", value),
             TelemetryVariant::GasResistance => format!("{:.2} kΩ", value),
-            TelemetryVariant::Radiation => format!("{:.2} μSv/h", value),
+            TelemetryVariant::Radiation => match self.radiation_units {
+                RadiationUnit::MicrosievertsPerHour => format!("{:.2} μSv/h", value),
+                RadiationUnit::CountsPerMinute => format!("{:.0} CPM", value),
+            },
             TelemetryVariant::PowerMetricVoltage(_) => format!("{:.2} V", value),
             TelemetryVariant::PowerMetricCurrent(_) => format!("{:.2} A", value),
             TelemetryVariant::AirUtilTx => format!("{:.2} %/min", value),
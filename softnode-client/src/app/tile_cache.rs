@@ -0,0 +1,99 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+
+// Disk cache for map tiles. Used only in native build,
+// web build relies on the browser's cache
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct TileCacheConfig {
+    pub enabled: bool,
+    // Empty means directory in the app's storage
+    pub directory: String,
+    pub max_size_mb: u64,
+}
+
+impl Default for TileCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            directory: String::new(),
+            max_size_mb: 200,
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl TileCacheConfig {
+    pub fn path(&self) -> Option<PathBuf> {
+        if !self.enabled {
+            None
+        } else if self.directory.is_empty() {
+            eframe::storage_dir("Softnode").map(|v| v.join("tiles"))
+        } else {
+            Some(PathBuf::from(&self.directory))
+        }
+    }
+}
+
+// Drop the oldest tiles until cache fits into `max_size_mb`.
+// Only entries of cacache's index are removed, with their content:
+// other files in the directory are never touched.
+// Removed entries are treated as a cache miss and downloaded again.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn evict(config: &TileCacheConfig) {
+    let Some(directory) = config.path() else {
+        return;
+    };
+    let max_size = config.max_size_mb * 1024 * 1024;
+
+    std::thread::spawn(move || {
+        let mut entries = cacache::index::ls(&directory).flatten().collect::<Vec<_>>();
+
+        let mut total_size: u64 = entries.iter().map(|entry| entry.size as u64).sum();
+        if total_size <= max_size {
+            return;
+        }
+
+        entries.sort_by_key(|entry| entry.time);
+        for entry in entries {
+            if total_size <= max_size {
+                break;
+            }
+            match cacache::RemoveOpts::new()
+                .remove_fully(true)
+                .remove_sync(&directory, &entry.key)
+            {
+                Ok(_) => total_size -= entry.size as u64,
+                Err(e) => log::error!("Tile cache: unable to remove {}: {}", entry.key, e),
+            }
+        }
+        log::info!(
+            "Tile cache: evicted to {} KiB in {}",
+            total_size / 1024,
+            directory.display()
+        );
+    });
+}
+
+// Tiles covering the bounding box on the zoom level
+pub fn tiles_in_bbox(bbox: [walkers::Position; 2], zoom: u8) -> Vec<walkers::TileId> {
+    let to_tile = |position: walkers::Position| -> (u32, u32) {
+        let n = (1u32 << zoom) as f64;
+        let lat = position.y().clamp(-85.0511, 85.0511).to_radians();
+        let x = ((position.x() + 180.0) / 360.0 * n).floor();
+        let y = ((1.0 - lat.tan().asinh() / std::f64::consts::PI) / 2.0 * n).floor();
+        let max = n as u32 - 1;
+        ((x.max(0.0) as u32).min(max), (y.max(0.0) as u32).min(max))
+    };
+
+    let (x1, y1) = to_tile(bbox[0]);
+    let (x2, y2) = to_tile(bbox[1]);
+    let mut tiles = Vec::new();
+
+    for x in x1.min(x2)..=x1.max(x2) {
+        for y in y1.min(y2)..=y1.max(y2) {
+            tiles.push(walkers::TileId { x, y, zoom });
+        }
+    }
+    tiles
+}
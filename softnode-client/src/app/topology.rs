@@ -0,0 +1,238 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::Utc;
+use egui::{Align2, Color32, FontId, Rect, Sense, Stroke, Vec2};
+use meshtastic_connect::keyring::node_id::NodeId;
+
+use crate::app::{
+    color_generator::NodeColors,
+    data::{GatewayInfo, NodeInfo},
+    map::opaque_width,
+};
+
+// Desired distance between connected nodes, in graph units
+const EDGE_LENGTH: f32 = 80.0;
+// Max node's movement per frame
+const MAX_STEP: f32 = 10.0;
+// Layout is treated as settled when nodes move slower
+const SETTLED_STEP: f32 = 0.3;
+const NODE_RADIUS: f32 = 6.0;
+
+// Gateways' relations as force-directed graph, regardless of nodes' positions
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+pub struct Topology {
+    #[serde(skip)]
+    layout: HashMap<NodeId, Vec2>,
+    // Screen offset of the graph's origin from the panel's center
+    #[serde(skip)]
+    pan: Vec2,
+    #[serde(skip)]
+    zoom: Option<f32>,
+}
+
+// Relation between gateway and heard node, last packet only
+struct Edge<'a> {
+    gateway: NodeId,
+    node: NodeId,
+    gateway_info: Option<&'a GatewayInfo>,
+}
+
+fn collect_edges(nodes: &HashMap<NodeId, NodeInfo>) -> Vec<Edge<'_>> {
+    let mut edges = Vec::new();
+    for (gateway_id, gateway) in nodes {
+        for (node_id, gateway_infos) in &gateway.gateway_for {
+            if node_id == gateway_id || !nodes.contains_key(node_id) {
+                continue;
+            }
+            edges.push(Edge {
+                gateway: *gateway_id,
+                node: *node_id,
+                gateway_info: gateway_infos.last(),
+            });
+        }
+    }
+    edges
+}
+
+impl Topology {
+    // Drop removed nodes, place new ones on a circle
+    fn sync_layout(&mut self, graph_nodes: &BTreeMap<NodeId, usize>) {
+        self.layout
+            .retain(|node_id, _| graph_nodes.contains_key(node_id));
+        let count = graph_nodes.len() as f32;
+        let radius = EDGE_LENGTH * count.sqrt();
+        for (index, node_id) in graph_nodes.keys().enumerate() {
+            self.layout.entry(*node_id).or_insert_with(|| {
+                let angle = index as f32 / count * std::f32::consts::TAU;
+                Vec2::angled(angle) * radius
+            });
+        }
+    }
+
+    // One Fruchterman-Reingold iteration, returns max node's movement
+    fn step(&mut self, edges: &[Edge<'_>]) -> f32 {
+        let node_ids = self.layout.keys().copied().collect::<Vec<_>>();
+        let mut forces: HashMap<NodeId, Vec2> = node_ids.iter().map(|v| (*v, Vec2::ZERO)).collect();
+
+        for (index, first) in node_ids.iter().enumerate() {
+            for second in &node_ids[index + 1..] {
+                let delta = self.layout[first] - self.layout[second];
+                let distance = delta.length().max(1.0);
+                let force = delta / distance * (EDGE_LENGTH * EDGE_LENGTH / distance);
+                *forces.get_mut(first).unwrap() += force;
+                *forces.get_mut(second).unwrap() -= force;
+            }
+        }
+
+        for edge in edges {
+            let delta = self.layout[&edge.gateway] - self.layout[&edge.node];
+            let distance = delta.length().max(1.0);
+            let force = delta / distance * (distance * distance / EDGE_LENGTH);
+            *forces.get_mut(&edge.gateway).unwrap() -= force;
+            *forces.get_mut(&edge.node).unwrap() += force;
+        }
+
+        let mut max_step: f32 = 0.0;
+        for (node_id, force) in forces {
+            // Weak gravity keeps unconnected parts of the mesh together
+            let force = force - self.layout[&node_id] * 0.05;
+            let length = force.length();
+            if length > 0.0 {
+                let step = force / length * length.min(MAX_STEP);
+                *self.layout.get_mut(&node_id).unwrap() += step;
+                max_step = max_step.max(step.length());
+            }
+        }
+        max_step
+    }
+
+    // Returns node clicked by user
+    pub fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        nodes: &HashMap<NodeId, NodeInfo>,
+        node_colors: &NodeColors,
+        focused: Option<NodeId>,
+    ) -> Option<NodeId> {
+        let edges = collect_edges(nodes);
+        let mut graph_nodes: BTreeMap<NodeId, usize> = BTreeMap::new();
+        for edge in &edges {
+            *graph_nodes.entry(edge.gateway).or_default() += 1;
+            *graph_nodes.entry(edge.node).or_default() += 1;
+        }
+
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "nodes: {} links: {}",
+                graph_nodes.len(),
+                edges.len()
+            ));
+            if ui.button("Reset view").clicked() {
+                self.pan = Vec2::ZERO;
+                self.zoom = None;
+            }
+            if ui.button("Relayout").clicked() {
+                self.layout.clear();
+            }
+        });
+
+        if graph_nodes.is_empty() {
+            ui.label("No gateways' data");
+            return None;
+        }
+
+        self.sync_layout(&graph_nodes);
+        if self.step(&edges) > SETTLED_STEP {
+            ui.ctx().request_repaint();
+        }
+
+        let (response, painter) = ui.allocate_painter(ui.available_size(), Sense::click_and_drag());
+        let rect = response.rect;
+
+        let zoom = *self.zoom.get_or_insert_with(|| {
+            let points = self
+                .layout
+                .values()
+                .map(|v| v.to_pos2())
+                .collect::<Vec<_>>();
+            let bounds = Rect::from_points(&points);
+            let size = bounds.size().max(Vec2::splat(EDGE_LENGTH));
+            (rect.width() / size.x).min(rect.height() / size.y) * 0.9
+        });
+        if response.dragged() {
+            self.pan += response.drag_delta();
+        }
+        if response.hovered() {
+            let scroll = ui.input(|i| i.smooth_scroll_delta.y);
+            if scroll != 0.0 {
+                self.zoom = Some(zoom * (scroll / 200.0).exp());
+            }
+        }
+
+        let to_screen = |position: Vec2| rect.center() + self.pan + position * zoom;
+        let current_datetime = Utc::now();
+
+        for edge in &edges {
+            let (width, color) = opaque_width(
+                current_datetime,
+                edge.gateway_info,
+                node_colors.get(edge.gateway),
+            );
+            painter.line_segment(
+                [
+                    to_screen(self.layout[&edge.gateway]),
+                    to_screen(self.layout[&edge.node]),
+                ],
+                Stroke::new(width * zoom.min(1.0), color),
+            );
+        }
+
+        let pointer = response.hover_pos();
+        let mut hovered: Option<NodeId> = None;
+        for node_id in graph_nodes.keys() {
+            let position = to_screen(self.layout[node_id]);
+            painter.circle_filled(position, NODE_RADIUS, node_colors.get(*node_id));
+            if focused == Some(*node_id) {
+                painter.circle_stroke(
+                    position,
+                    NODE_RADIUS + 3.0,
+                    Stroke::new(2.0, Color32::LIGHT_BLUE),
+                );
+            }
+            let label = nodes
+                .get(node_id)
+                .and_then(|v| v.extended_info_history.last())
+                .map(|v| v.short_name.clone())
+                .unwrap_or_else(|| node_id.to_string());
+            painter.text(
+                position + Vec2::new(NODE_RADIUS + 2.0, 0.0),
+                Align2::LEFT_CENTER,
+                label,
+                FontId::proportional(12.0),
+                ui.visuals().text_color(),
+            );
+            if let Some(pointer) = pointer
+                && pointer.distance(position) <= NODE_RADIUS + 2.0
+            {
+                hovered = Some(*node_id);
+            }
+        }
+
+        let node_id = hovered?;
+        let hover_text = if let Some(extended) = nodes
+            .get(&node_id)
+            .and_then(|v| v.extended_info_history.last())
+        {
+            format!("{} {}", node_id, extended.long_name)
+        } else {
+            node_id.to_string()
+        };
+        let links = graph_nodes.get(&node_id).copied().unwrap_or_default();
+        let response = response.on_hover_text(format!("{}\nlinks: {}", hover_text, links));
+        if response.clicked() {
+            Some(node_id)
+        } else {
+            None
+        }
+    }
+}
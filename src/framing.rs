@@ -0,0 +1,257 @@
+// Runtime agnostic framing of StreamAPI (serial and TCP): bytes in, frames out.
+// `transport::stream` wraps it into tokio's `Framed`, other transports could
+// feed `FrameDecoder` with received bytes and send encoded `ToRadio`s
+use bytes::{BufMut, Bytes, BytesMut};
+use prost::Message;
+use std::io::ErrorKind;
+use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout, U16};
+
+use crate::meshtastic;
+
+pub const STREAM_PACKET_SIZE_MAX: u16 = 512;
+const STREAM_MAGIC_START1: u8 = 0x94;
+const STREAM_MAGIC_START2: u8 = 0xc3;
+const STREAM_HEADER_MAGIC: [u8; 2] = [STREAM_MAGIC_START1, STREAM_MAGIC_START2];
+pub const STREAM_WAKEUP_MAGIC: [u8; 4] = [
+    STREAM_MAGIC_START1,
+    STREAM_MAGIC_START1,
+    STREAM_MAGIC_START1,
+    STREAM_MAGIC_START1,
+];
+
+pub enum BytesSequence {
+    // Wakeup sequence
+    Wakeup,
+    // Raw bytes to send without `STREAM_HEADER_MAGIC`
+    Unheaded(Bytes),
+    // Raw bytes to send with `STREAM_HEADER_MAGIC` and lengths
+    Headed(Bytes),
+}
+
+#[repr(C)]
+#[derive(Debug, Immutable, FromBytes, KnownLayout, IntoBytes)]
+pub struct MeshtasticStreamHeader {
+    magic: [u8; 2],
+    pub length: U16<zerocopy::byteorder::BE>,
+}
+
+impl Default for MeshtasticStreamHeader {
+    fn default() -> Self {
+        Self {
+            magic: STREAM_HEADER_MAGIC,
+            length: U16::new(0),
+        }
+    }
+}
+
+impl MeshtasticStreamHeader {
+    pub fn new(length: u16) -> Self {
+        Self {
+            magic: STREAM_HEADER_MAGIC,
+            length: U16::new(length),
+        }
+    }
+}
+
+pub type PacketId = u32;
+
+pub enum StreamRecvData {
+    // FromRadio structured data
+    FromRadio(PacketId, meshtastic::from_radio::PayloadVariant),
+    // Raw, journal or other unrecognized data
+    Unstructured(BytesMut),
+}
+
+// Takes one frame or unstructured data from the beginning of `src`.
+// Ok(None) if more bytes are needed. Invalid header is not consumed
+pub fn decode_frame(src: &mut BytesMut) -> Result<Option<StreamRecvData>, std::io::Error> {
+    decode_raw_frame(src).map(|decoded| decoded.map(|(recv_data, _)| recv_data))
+}
+
+// As `decode_frame`, with bytes of `FromRadio`'s frame as received, header included
+pub fn decode_raw_frame(
+    src: &mut BytesMut,
+) -> Result<Option<(StreamRecvData, Option<Bytes>)>, std::io::Error> {
+    static HEADER_LEN: usize = size_of::<MeshtasticStreamHeader>();
+
+    let dropoff_len = if let Some(pos) = src
+        .windows(STREAM_HEADER_MAGIC.len())
+        .position(|window| window == STREAM_HEADER_MAGIC)
+    {
+        pos
+    } else if src.last() == Some(&STREAM_MAGIC_START1) {
+        src.len() - 1
+    } else {
+        src.len()
+    };
+
+    if dropoff_len > 0 {
+        return Ok(Some((
+            StreamRecvData::Unstructured(src.split_to(dropoff_len)),
+            None,
+        )));
+    } else if src.len() < HEADER_LEN {
+        return Ok(None);
+    }
+
+    let header_bytes = &src[..HEADER_LEN];
+    let header: &MeshtasticStreamHeader;
+    match MeshtasticStreamHeader::ref_from_bytes(header_bytes) {
+        Ok(result) => header = result,
+        Err(e) => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                e.to_string(),
+            ));
+        }
+    }
+
+    if header.magic != STREAM_HEADER_MAGIC {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "Invalid magic: {:#x?} (expected {:#x?})",
+                header.magic, STREAM_HEADER_MAGIC
+            ),
+        ));
+    }
+
+    let length = header.length.get();
+    if length >= STREAM_PACKET_SIZE_MAX {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "Invalid packet length: {} (expected less {})",
+                length, STREAM_PACKET_SIZE_MAX
+            ),
+        ));
+    }
+
+    let frame_len = length as usize + HEADER_LEN;
+
+    if src.len() >= frame_len {
+        let pbuf = src.split_to(frame_len);
+        let recv_data = decode_from_radio(&pbuf[HEADER_LEN..])?;
+        Ok(Some((recv_data, Some(pbuf.freeze()))))
+    } else {
+        Ok(None)
+    }
+}
+
+// `FromRadio` of frame without header, or of transport's message
+pub fn decode_from_radio(bytes: &[u8]) -> Result<StreamRecvData, std::io::Error> {
+    match meshtastic::FromRadio::decode(bytes) {
+        Ok(meshtastic::FromRadio {
+            id,
+            payload_variant: Some(payload_variant),
+        }) => Ok(StreamRecvData::FromRadio(id, payload_variant)),
+        Ok(from_radio) => Err(std::io::Error::other(format!(
+            "Radio send no payload: {:?}",
+            from_radio
+        ))),
+        Err(e) => Err(std::io::Error::new(ErrorKind::InvalidData, e.to_string())),
+    }
+}
+
+pub fn encode_to_radio(
+    item: meshtastic::to_radio::PayloadVariant,
+    dst: &mut BytesMut,
+) -> Result<(), std::io::Error> {
+    let to_radio = meshtastic::ToRadio {
+        payload_variant: Some(item),
+    };
+    let header = MeshtasticStreamHeader::new(to_radio.encoded_len() as u16);
+    dst.put_slice(header.as_bytes());
+    to_radio
+        .encode(dst)
+        .map_err(|e| std::io::Error::new(ErrorKind::InvalidInput, e.to_string()))?;
+    Ok(())
+}
+
+pub fn encode_sequence(item: BytesSequence, dst: &mut BytesMut) {
+    match item {
+        BytesSequence::Wakeup => dst.put_slice(STREAM_WAKEUP_MAGIC.as_bytes()),
+        BytesSequence::Unheaded(bytes) => dst.put_slice(bytes.as_bytes()),
+        BytesSequence::Headed(bytes) => {
+            let header = MeshtasticStreamHeader::new(bytes.len() as u16);
+            dst.put_slice(header.as_bytes());
+            dst.put_slice(bytes.as_bytes());
+        }
+    }
+}
+
+// Buffering decoder for transports delivering bytes in arbitrary chunks
+#[derive(Default)]
+pub struct FrameDecoder {
+    buffer: BytesMut,
+}
+
+impl FrameDecoder {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    // Next frame from buffered bytes, None if more bytes are needed.
+    // Invalid header is skipped byte by byte, so decoding always advances
+    pub fn decode(&mut self) -> Option<Result<StreamRecvData, std::io::Error>> {
+        let buffered = self.buffer.len();
+        match decode_frame(&mut self.buffer) {
+            Ok(Some(recv_data)) => Some(Ok(recv_data)),
+            Ok(None) => None,
+            Err(e) => {
+                if self.buffer.len() == buffered {
+                    let _ = self.buffer.split_to(1);
+                }
+                Some(Err(e))
+            }
+        }
+    }
+
+    // Bytes waiting for the rest of frame
+    pub fn buffered(&self) -> &[u8] {
+        &self.buffer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_chunked() {
+        let from_radio = meshtastic::FromRadio {
+            id: 7,
+            payload_variant: Some(meshtastic::from_radio::PayloadVariant::ConfigCompleteId(42)),
+        };
+        let payload = from_radio.encode_to_vec();
+        let mut stream = b"log\n".to_vec();
+        stream.extend([0x94, 0xc3, 0x02, 0x00]);
+        stream.extend(MeshtasticStreamHeader::new(payload.len() as u16).as_bytes());
+        stream.extend(payload);
+
+        let mut decoder = FrameDecoder::new();
+        let mut decoded = Vec::new();
+        for chunk in stream.chunks(3) {
+            decoder.push(chunk);
+            while let Some(recv_data) = decoder.decode() {
+                decoded.push(recv_data);
+            }
+        }
+
+        assert!(matches!(&decoded[0], Ok(StreamRecvData::Unstructured(_))));
+        // Too long frame: header is skipped
+        assert_eq!(decoded.iter().filter(|v| v.is_err()).count(), 1);
+        assert!(matches!(
+            decoded.last(),
+            Some(Ok(StreamRecvData::FromRadio(
+                7,
+                meshtastic::from_radio::PayloadVariant::ConfigCompleteId(42)
+            )))
+        ));
+        assert!(decoder.buffered().is_empty());
+    }
+}
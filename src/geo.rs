@@ -0,0 +1,119 @@
+use crate::meshtastic;
+
+// Degrees are sent as integer in units of 1e-7
+pub const COORD_SCALE: f64 = 1e7;
+
+// Division by 1e7 is exact for the whole range, unlike multiplication
+// by not representable 1e-7
+pub fn decode_coord(value: i32) -> f64 {
+    value as f64 / COORD_SCALE
+}
+
+// Rounds to nearest: cast truncates toward zero, biasing positions by up to 1 cm.
+// Out of range values are saturated, NaN is encoded as zero
+pub fn encode_coord(degrees: f64) -> i32 {
+    (degrees * COORD_SCALE).round() as i32
+}
+
+// Latitude and longitude of position, if both are present
+pub fn position_coords(position: &meshtastic::Position) -> Option<(f64, f64)> {
+    Some((
+        decode_coord(position.latitude_i?),
+        decode_coord(position.longitude_i?),
+    ))
+}
+
+// Latitude is clamped to poles, longitude is wrapped to [-180, 180]
+pub struct PositionBuilder {
+    position: meshtastic::Position,
+}
+
+impl PositionBuilder {
+    pub fn new(latitude: f64, longitude: f64) -> Self {
+        let latitude = latitude.clamp(-90.0, 90.0);
+        let longitude = if (-180.0..=180.0).contains(&longitude) {
+            longitude
+        } else {
+            (longitude + 180.0).rem_euclid(360.0) - 180.0
+        };
+
+        Self {
+            position: meshtastic::Position {
+                latitude_i: Some(encode_coord(latitude)),
+                longitude_i: Some(encode_coord(longitude)),
+                ..Default::default()
+            },
+        }
+    }
+
+    // Meters above mean sea level
+    pub fn altitude(mut self, altitude: i32) -> Self {
+        self.position.altitude = Some(altitude);
+        self
+    }
+
+    // Meters above WGS84 ellipsoid
+    pub fn altitude_hae(mut self, altitude_hae: i32) -> Self {
+        self.position.altitude_hae = Some(altitude_hae);
+        self
+    }
+
+    pub fn precision_bits(mut self, precision_bits: u32) -> Self {
+        self.position.precision_bits = precision_bits;
+        self
+    }
+
+    // Seconds since the Unix epoch
+    pub fn timestamp(mut self, timestamp: u32) -> Self {
+        self.position.timestamp = timestamp;
+        self
+    }
+
+    pub fn build(self) -> meshtastic::Position {
+        self.position
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        for value in [
+            0,
+            1,
+            -1,
+            123_456_789,
+            -987_654_321,
+            900_000_000,
+            -900_000_000,
+            1_800_000_000,
+            -1_800_000_000,
+            i32::MAX,
+            i32::MIN,
+        ] {
+            assert_eq!(encode_coord(decode_coord(value)), value);
+        }
+        // Truncation gives 966_947_981
+        assert_eq!(encode_coord(96.6947982), 966_947_982);
+        assert_eq!(encode_coord(-96.6947982), -966_947_982);
+    }
+
+    #[test]
+    fn poles_and_antimeridian() {
+        let position = PositionBuilder::new(90.0, 180.0).build();
+        assert_eq!(position.latitude_i, Some(900_000_000));
+        assert_eq!(position.longitude_i, Some(1_800_000_000));
+
+        let position = PositionBuilder::new(-91.0, -180.0).build();
+        assert_eq!(position.latitude_i, Some(-900_000_000));
+        assert_eq!(position.longitude_i, Some(-1_800_000_000));
+
+        let position = PositionBuilder::new(0.0, 190.5).build();
+        assert_eq!(position_coords(&position), Some((0.0, -169.5)));
+
+        let position = PositionBuilder::new(0.0, -540.25).build();
+        assert_eq!(position_coords(&position), Some((0.0, 179.75)));
+    }
+}
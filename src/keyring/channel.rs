@@ -1,6 +1,31 @@
 use super::key::Key;
+use crate::meshtastic::config::{LoRaConfig, lo_ra_config::ModemPreset};
 use std::fmt;
 
+// Display names of presets, as firmware's `getModemPresetDisplayName`
+pub fn modem_preset_name(modem_preset: ModemPreset) -> &'static str {
+    match modem_preset {
+        ModemPreset::LongFast => "LongFast",
+        ModemPreset::LongSlow => "LongSlow",
+        ModemPreset::VeryLongSlow => "VLongSlow",
+        ModemPreset::MediumSlow => "MediumSlow",
+        ModemPreset::MediumFast => "MediumFast",
+        ModemPreset::ShortSlow => "ShortSlow",
+        ModemPreset::ShortFast => "ShortFast",
+        ModemPreset::LongModerate => "LongMod",
+        ModemPreset::ShortTurbo => "ShortTurbo",
+    }
+}
+
+// Name of channel without name: firmware names it after modem's preset
+pub fn preset_channel_name(lora: Option<&LoRaConfig>) -> &'static str {
+    match lora {
+        Some(lora) if !lora.use_preset => "Custom",
+        Some(lora) => modem_preset_name(lora.modem_preset()),
+        None => modem_preset_name(ModemPreset::LongFast),
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Hash, Copy)]
 pub struct ChannelHash(u32);
 
@@ -97,7 +122,7 @@ impl serde::Serialize for Channel {
     {
         let (name, channel_hash) = if let Some(ref name) = self.name {
             let chan_hash = Self::generate_hash(name.as_str(), self.key.as_bytes()) as u32;
-            if chan_hash == self.channel_hash.into() {
+            if chan_hash == u32::from(self.channel_hash) {
                 (Some(name.clone()), None)
             } else {
                 (Some(name.clone()), Some(chan_hash.to_string()))
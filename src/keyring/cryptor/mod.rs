@@ -33,6 +33,25 @@ impl Decrypt for Cryptor {
     }
 }
 
+impl Cryptor {
+    // Nonce which is used by `Decrypt` for packet's encrypted data
+    pub fn nonce_for(&self, packet_id: u32, data: &[u8]) -> Option<[u8; 16]> {
+        match self {
+            Cryptor::Symmetric(_, symmetric) => Some(symmetric.nonce_for(packet_id)),
+            Cryptor::PKI(pki) => pki.nonce_for(packet_id, data),
+        }
+    }
+
+    // Bypasses nonce derivation, see `Symmetric::decrypt_with_nonce`
+    // and `PKI::decrypt_with_nonce`
+    pub fn decrypt_with_nonce(&self, nonce: [u8; 16], data: Vec<u8>) -> Result<Vec<u8>, String> {
+        match self {
+            Cryptor::Symmetric(_, symmetric) => symmetric.decrypt_with_nonce(nonce, data),
+            Cryptor::PKI(pki) => pki.decrypt_with_nonce(nonce, data),
+        }
+    }
+}
+
 pub trait Encrypt {
     fn encrypt(&self, packet_id: u32, data: Vec<u8>) -> Result<Vec<u8>, String>;
 }
@@ -58,6 +58,44 @@ impl Decrypt for PKI {
         let (ciphertext_with_auth, tail) = buffer.split_at(buffer.len() - EXTRA_NONCE_LEN);
         let nonce = prepare_nonce(packet_id, self.from, tail.try_into().unwrap());
 
+        self.decrypt_ccm(&nonce, ciphertext_with_auth)
+    }
+}
+
+impl PKI {
+    // AES-CCM nonce used for packet, as `Decrypt` builds it: extra nonce is
+    // taken from the tail of encrypted data. None if data is too short
+    pub fn nonce_for(&self, packet_id: u32, buffer: &[u8]) -> Option<[u8; 16]> {
+        if buffer.len() < AUTH_LEN + EXTRA_NONCE_LEN {
+            return None;
+        }
+        let tail = &buffer[buffer.len() - EXTRA_NONCE_LEN..];
+        Some(prepare_nonce(
+            packet_id,
+            self.from,
+            tail.try_into().unwrap(),
+        ))
+    }
+
+    // Forensics only: decrypts with the given nonce instead of one derived
+    // from packet id, `from` and extra nonce. Only first 13 bytes are used by
+    // AES-CCM, `buffer` is ciphertext with auth tag, without extra nonce.
+    // Normal decoding must go through `Decrypt`
+    pub fn decrypt_with_nonce(&self, nonce: [u8; 16], buffer: Vec<u8>) -> Result<Vec<u8>, String> {
+        if buffer.len() < AUTH_LEN {
+            return Err(format!(
+                "PKI: {} bytes is not enough to decode",
+                buffer.len()
+            ));
+        }
+        self.decrypt_ccm(&nonce, &buffer)
+    }
+
+    fn decrypt_ccm(
+        &self,
+        nonce: &[u8; 16],
+        ciphertext_with_auth: &[u8],
+    ) -> Result<Vec<u8>, String> {
         let cipher = Ccm::<Aes256, ccm::consts::U8, ccm::consts::U13>::new_from_slice(
             self.shared_key.as_bytes(),
         )
@@ -82,6 +82,14 @@ impl Key {
             Key::K256(k256) => k256.as_bytes(),
         }
     }
+
+    // Bytes as stored in channel's settings: indexed key is one byte
+    pub fn psk(&self) -> Vec<u8> {
+        match self {
+            Key::KIndex(kindex) => vec![kindex.0[15]],
+            key => key.as_bytes().to_vec(),
+        }
+    }
 }
 
 impl From<[u8; 16]> for K128 {
@@ -173,6 +181,8 @@ impl TryFrom<Vec<u8>> for KIndex {
     // From Base64 string
     fn try_from(bytes: Vec<u8>) -> Result<Self, Self::Error> {
         match bytes.len() {
+            // Firmware turns encryption off for index 0, there is no key to expand
+            1 if bytes[0] == 0 => Err("Key index 0 means no encryption".into()),
             1 => Ok([bytes[0]]
                 .try_into()
                 .map_err(|e| format!("Unsupported input for indexed key: {:?}", e))?),
@@ -1,3 +1,4 @@
+pub mod byte_node_id;
 pub mod channel;
 pub mod cryptor;
 pub mod key;
@@ -6,15 +7,57 @@ mod peer;
 
 use std::collections::HashMap;
 
+use base64::{Engine, engine::general_purpose};
 use channel::Channel;
-use cryptor::{Cryptor, pki::PKI, symmetric::Symmetric};
+use cryptor::{Cryptor, Decrypt, Encrypt, pki::PKI, symmetric::Symmetric};
 use key::{K256, Key};
 use node_id::NodeId;
 use peer::Peer;
+use prost::Message;
 use serde::{Deserialize, Serialize};
 
+use crate::meshtastic;
+#[cfg(feature = "transport")]
+use crate::transport::stream::RadioConfig;
 use channel::ChannelHash;
 
+const CHANNEL_URL_PATH: &str = "meshtastic.org/e/";
+const CONTACT_URL_PATH: &str = "meshtastic.org/v/";
+
+// Decoded payload of Meshtastic's share URL `https://<path>#<base64url>`
+fn share_url_payload(url: &str, path: &str) -> Result<Vec<u8>, String> {
+    let (_, encoded) = url
+        .trim()
+        .split_once('#')
+        .filter(|(location, _)| {
+            location
+                .to_lowercase()
+                .trim_end_matches('/')
+                .ends_with(path.trim_end_matches('/'))
+        })
+        .ok_or_else(|| format!("Not a Meshtastic URL, expected https://{}#...", path))?;
+    // Apps differ in padding and alphabet
+    let encoded = encoded
+        .trim_end_matches('=')
+        .replace('+', "-")
+        .replace('/', "_");
+    general_purpose::URL_SAFE_NO_PAD
+        .decode(encoded)
+        .map_err(|e| format!("Malformed URL: {}", e))
+}
+
+// Result of importing channel's key from radio, by channel's name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChannelImport {
+    Added(String),
+    // Channel with the same key is already known
+    Known(String),
+    // Channel is known with another key: keyring's key is kept
+    Conflict(String),
+    // Channel is not encrypted or key is malformed: name and reason
+    Skipped(String, String),
+}
+
 fn serialize_peers<S>(peers: &HashMap<NodeId, Peer>, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: serde::Serializer,
@@ -77,6 +120,142 @@ impl Keyring {
         Ok(())
     }
 
+    // Adds channels of radio which are not known yet.
+    // Known channels are never changed, keys' mismatches are reported as `Conflict`
+    #[cfg(feature = "transport")]
+    pub fn import_from_radio(&mut self, radio_config: &RadioConfig) -> Vec<ChannelImport> {
+        radio_config
+            .channels
+            .iter()
+            .map(|radio_channel| {
+                self.import_channel(radio_config.channel_name(radio_channel), &radio_channel.psk)
+            })
+            .collect()
+    }
+
+    pub fn channel_names(&self) -> impl Iterator<Item = &str> {
+        self.channels
+            .iter()
+            .filter_map(|channel| channel.name.as_deref())
+    }
+
+    pub fn peer_public_key(&self, node_id: NodeId) -> Option<K256> {
+        self.peers.get(&node_id).map(|peer| peer.public_key)
+    }
+
+    pub fn peer_ids(&self) -> impl Iterator<Item = NodeId> {
+        self.peers.keys().copied()
+    }
+
+    // Channel-share URL of the named channel, as Meshtastic apps show it
+    pub fn channel_share_url(&self, name: &str) -> Option<String> {
+        let channel = self
+            .channels
+            .iter()
+            .find(|channel| channel.name.as_deref() == Some(name))?;
+        let channel_set = meshtastic::ChannelSet {
+            settings: vec![meshtastic::ChannelSettings {
+                name: name.into(),
+                psk: channel.key.psk(),
+                ..Default::default()
+            }],
+            lora_config: None,
+        };
+
+        Some(format!(
+            "https://{}#{}",
+            CHANNEL_URL_PATH,
+            general_purpose::URL_SAFE_NO_PAD.encode(channel_set.encode_to_vec())
+        ))
+    }
+
+    // Contact-share URL of the peer: node id and public key
+    pub fn contact_share_url(&self, node_id: NodeId) -> Option<String> {
+        let peer = self.peers.get(&node_id)?;
+        let contact = meshtastic::SharedContact {
+            node_num: node_id.into(),
+            user: Some(meshtastic::User {
+                id: node_id.into(),
+                public_key: peer.public_key.as_bytes().to_vec(),
+                ..Default::default()
+            }),
+            should_ignore: false,
+        };
+
+        Some(format!(
+            "https://{}#{}",
+            CONTACT_URL_PATH,
+            general_purpose::URL_SAFE_NO_PAD.encode(contact.encode_to_vec())
+        ))
+    }
+
+    // Adds remote peer of contact-share URL (`https://meshtastic.org/v/#...`).
+    // Known peer is kept as in `add_remote_peer`
+    pub fn import_contact_url(&mut self, url: &str) -> Result<NodeId, String> {
+        let bytes = share_url_payload(url, CONTACT_URL_PATH)?;
+        let contact = meshtastic::SharedContact::decode(bytes.as_slice())
+            .map_err(|e| format!("Malformed contact: {}", e))?;
+        let user = contact
+            .user
+            .ok_or_else(|| "No user in contact".to_string())?;
+        let public_key: [u8; 32] = user.public_key.as_slice().try_into().map_err(|_| {
+            format!(
+                "Expected 32-byte public key, not {} bytes",
+                user.public_key.len()
+            )
+        })?;
+        let node_id = NodeId::from(contact.node_num);
+
+        self.add_remote_peer(node_id, public_key.into())?;
+        Ok(node_id)
+    }
+
+    // Adds channels of channel-share URL (`https://meshtastic.org/e/#...`).
+    // Known channels are kept as in `import_from_radio`
+    pub fn import_share_url(&mut self, url: &str) -> Result<Vec<ChannelImport>, String> {
+        let bytes = share_url_payload(url, CHANNEL_URL_PATH)?;
+        let channel_set = meshtastic::ChannelSet::decode(bytes.as_slice())
+            .map_err(|e| format!("Malformed channel set: {}", e))?;
+
+        let preset_name = channel::preset_channel_name(channel_set.lora_config.as_ref());
+        Ok(channel_set
+            .settings
+            .iter()
+            .map(|settings| {
+                let name = if settings.name.is_empty() {
+                    preset_name.into()
+                } else {
+                    settings.name.clone()
+                };
+                self.import_channel(name, &settings.psk)
+            })
+            .collect())
+    }
+
+    // Known channels are never changed, keys' mismatches are reported as `Conflict`
+    fn import_channel(&mut self, name: String, psk: &[u8]) -> ChannelImport {
+        if psk.is_empty() {
+            return ChannelImport::Skipped(name, "no encryption".into());
+        }
+        let key = match Key::try_from(psk.to_vec()) {
+            Ok(key) => key,
+            Err(e) => return ChannelImport::Skipped(name, e),
+        };
+        let channel = Channel::new_with_name(&name, key);
+
+        match self.channels.iter().find(|known| match &known.name {
+            Some(known_name) => *known_name == name,
+            None => known.channel_hash == channel.channel_hash,
+        }) {
+            Some(known) if known.key == key => ChannelImport::Known(name),
+            Some(_) => ChannelImport::Conflict(name),
+            None => {
+                self.channels.push(channel);
+                ChannelImport::Added(name)
+            }
+        }
+    }
+
     // Get cryptographic API for channel name
     // Returns a tuple containing the cryptographic API and the channel's hash
     pub fn cryptor_for_channel_name(
@@ -154,6 +333,64 @@ impl Keyring {
         }
     }
 
+    // Try to decrypt data with keys of all known channels.
+    // Used as a fallback when channel's hash is unknown: result is accepted
+    // only if decrypted data is a valid `Data` message.
+    // Returns the channel's name (or hash) and decoded data
+    pub fn try_decrypt_any(
+        &self,
+        from: NodeId,
+        id: u32,
+        data: &[u8],
+    ) -> Option<(String, crate::meshtastic::Data)> {
+        self.channels.iter().find_map(|channel| {
+            let symmetric = Symmetric {
+                from,
+                key: channel.key,
+            };
+            let decrypted = symmetric.decrypt(id, data.to_vec()).ok()?;
+            let decoded = crate::meshtastic::Data::decode(decrypted.as_slice()).ok()?;
+            // Random bytes could be decoded as an empty message
+            if decoded.portnum == crate::meshtastic::PortNum::UnknownApp as i32 {
+                return None;
+            }
+            Some((
+                channel
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| channel.channel_hash.to_string()),
+                decoded,
+            ))
+        })
+    }
+
+    // Decrypts data of channel `src_channel` and encrypts it with key of
+    // channel `dst_channel_name`, keeping `from` and `packet_id` of nonce.
+    // Decrypted data must be a valid `Data`, as wrong key gives garbage.
+    // Security: nodes of destination channel can read what was sent to source
+    // channel, and the same nonce is used with both keys. Re-keying must be
+    // done only between channels which are trusted by each other
+    pub fn transcode(
+        &self,
+        from: NodeId,
+        packet_id: u32,
+        data: Vec<u8>,
+        src_channel: ChannelHash,
+        dst_channel_name: &str,
+    ) -> Result<Vec<u8>, String> {
+        let decryptor = self
+            .cryptor_for_channel(from, src_channel)
+            .ok_or_else(|| format!("No key for source channel {}", src_channel))?;
+        let (encryptor, _) = self
+            .cryptor_for_channel_name(from, &dst_channel_name.to_string())
+            .ok_or_else(|| format!("No key for destination channel {:?}", dst_channel_name))?;
+
+        let decrypted = decryptor.decrypt(packet_id, data)?;
+        crate::meshtastic::Data::decode(decrypted.as_slice())
+            .map_err(|e| format!("Data of channel {} is not decrypted: {}", decryptor, e))?;
+        encryptor.encrypt(packet_id, decrypted)
+    }
+
     // Get cryptographic API for `MeshPacket::channel` field
     pub fn cryptor_for(&self, from: NodeId, to: NodeId, channel: ChannelHash) -> Option<Cryptor> {
         if channel == 0x0 {
@@ -166,8 +403,14 @@ impl Keyring {
 
 #[cfg(test)]
 mod tests {
-    use super::{Keyring, key::Key};
+    use super::{
+        Keyring,
+        cryptor::{Decrypt, Encrypt, symmetric::Symmetric},
+        key::Key,
+    };
+    use crate::meshtastic;
     use pretty_assertions::assert_eq;
+    use prost::Message;
 
     fn build_test_keyring() -> Keyring {
         let mut keyring = Keyring::new();
@@ -205,6 +448,239 @@ mod tests {
         assert_eq!(se_keyring, de_keyring);
     }
 
+    #[test]
+    fn try_decrypt_any_finds_channel() {
+        // Default key is random: the same key for both sides
+        let key = Key::K256(Default::default());
+        let mut keyring = Keyring::new();
+        keyring.add_channel_with_name("Channel2", key).unwrap();
+
+        let data = meshtastic::Data {
+            portnum: meshtastic::PortNum::TextMessageApp as i32,
+            payload: b"hello".to_vec(),
+            ..Default::default()
+        };
+        let symmetric = Symmetric {
+            from: 0xdeadbeef.into(),
+            key,
+        };
+        let encrypted = symmetric.encrypt(42, data.encode_to_vec()).unwrap();
+
+        let (channel_name, decrypted) = keyring
+            .try_decrypt_any(0xdeadbeef.into(), 42, &encrypted)
+            .unwrap();
+        assert_eq!(channel_name, "Channel2");
+        assert_eq!(decrypted, data);
+    }
+
+    #[test]
+    fn k128_channel_matches_firmware() {
+        // Key 01..10, hash and ciphertext are computed as firmware does
+        let key = Key::try_from("AQIDBAUGBwgJCgsMDQ4PEA==").unwrap();
+        assert!(matches!(key, Key::K128(_)));
+
+        let mut keyring = Keyring::new();
+        keyring.add_channel_with_name("Private", key).unwrap();
+        assert_eq!(keyring.channels[0].channel_hash, 0x5d);
+
+        let cryptor = keyring
+            .cryptor_for(0xdeadbeef.into(), 0xffffffff.into(), 0x5d.into())
+            .unwrap();
+        let ciphertext = vec![0xe7, 0xd6, 0xc7, 0xf2, 0x74, 0x11, 0xe8, 0x66];
+        assert_eq!(
+            cryptor.decrypt(0x01020304, ciphertext.clone()).unwrap(),
+            b"firmware"
+        );
+
+        // Short key is padded with zeros up to AES-128
+        let short = Key::try_from("AQIDBAUGBwgJCg==").unwrap();
+        let mut padded = [0u8; 16];
+        padded[..10].copy_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+        assert_eq!(short, Key::K128(padded.into()));
+
+        let symmetric = Symmetric {
+            from: 0xdeadbeef.into(),
+            key: Key::K128(padded.into()),
+        };
+        assert_eq!(
+            symmetric.encrypt(7, b"data".to_vec()).unwrap(),
+            Symmetric {
+                from: 0xdeadbeef.into(),
+                key: short,
+            }
+            .encrypt(7, b"data".to_vec())
+            .unwrap()
+        );
+
+        assert!(Key::try_from("AA==").is_err());
+    }
+
+    #[test]
+    fn transcode_between_channels() {
+        let mut keyring = Keyring::new();
+        keyring
+            .add_channel_with_name("LongFast", Key::try_from("AQ==").unwrap())
+            .unwrap();
+        keyring
+            .add_channel_with_name("Private", Key::K256(Default::default()))
+            .unwrap();
+        let (private, _) = keyring
+            .cryptor_for_channel_name(0xdeadbeef.into(), &"Private".to_string())
+            .unwrap();
+
+        let data = meshtastic::Data {
+            portnum: meshtastic::PortNum::TextMessageApp as i32,
+            payload: b"bridged".to_vec(),
+            ..Default::default()
+        };
+        let (public, public_hash) = keyring
+            .cryptor_for_channel_name(0xdeadbeef.into(), &"LongFast".to_string())
+            .unwrap();
+        let encrypted = public.encrypt(42, data.encode_to_vec()).unwrap();
+
+        let transcoded = keyring
+            .transcode(0xdeadbeef.into(), 42, encrypted, public_hash, "Private")
+            .unwrap();
+        let decrypted = private.decrypt(42, transcoded).unwrap();
+        assert_eq!(
+            meshtastic::Data::decode(decrypted.as_slice()).unwrap(),
+            data
+        );
+    }
+
+    #[test]
+    fn share_url_round_trip() {
+        use super::ChannelImport;
+
+        let mut keyring = Keyring::new();
+        keyring
+            .add_channel_with_name("LongFast", Key::try_from("AQ==").unwrap())
+            .unwrap();
+        keyring
+            .add_channel_with_name("Private", Key::K256([7; 32].into()))
+            .unwrap();
+
+        let long_fast = keyring.channel_share_url("LongFast").unwrap();
+        assert!(long_fast.starts_with("https://meshtastic.org/e/#"));
+        let private = keyring.channel_share_url("Private").unwrap();
+        assert_eq!(keyring.channel_share_url("Unknown"), None);
+
+        let mut imported = Keyring::new();
+        assert_eq!(
+            imported.import_share_url(&long_fast),
+            Ok(vec![ChannelImport::Added("LongFast".into())])
+        );
+        assert_eq!(
+            imported.import_share_url(&private),
+            Ok(vec![ChannelImport::Added("Private".into())])
+        );
+        assert_eq!(
+            imported.import_share_url(&private),
+            Ok(vec![ChannelImport::Known("Private".into())])
+        );
+        assert_eq!(imported.channels, keyring.channels);
+        assert!(
+            imported
+                .import_share_url("https://meshtastic.org/e/")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn share_url_unnamed_channel() {
+        use super::{ChannelImport, Engine, general_purpose};
+        use crate::meshtastic::config;
+
+        let channel_set = meshtastic::ChannelSet {
+            settings: vec![meshtastic::ChannelSettings {
+                psk: vec![1],
+                ..Default::default()
+            }],
+            lora_config: Some(config::LoRaConfig {
+                use_preset: true,
+                modem_preset: config::lo_ra_config::ModemPreset::LongModerate.into(),
+                ..Default::default()
+            }),
+        };
+        let url = format!(
+            "https://meshtastic.org/e/#{}",
+            general_purpose::URL_SAFE_NO_PAD.encode(channel_set.encode_to_vec())
+        );
+
+        assert_eq!(
+            Keyring::new().import_share_url(&url),
+            Ok(vec![ChannelImport::Added("LongMod".into())])
+        );
+    }
+
+    #[test]
+    fn contact_url_round_trip() {
+        let mut keyring = Keyring::new();
+        keyring
+            .add_remote_peer(0xdeadbeef.into(), [3; 32].into())
+            .unwrap();
+        let url = keyring.contact_share_url(0xdeadbeef.into()).unwrap();
+        assert!(url.starts_with("https://meshtastic.org/v/#"));
+
+        let mut imported = Keyring::new();
+        assert_eq!(imported.import_contact_url(&url), Ok(0xdeadbeef.into()));
+        assert_eq!(imported.peers, keyring.peers);
+
+        // Channel URL is not a contact
+        keyring
+            .add_channel_with_name("Private", Key::K128(Default::default()))
+            .unwrap();
+        let channel_url = keyring.channel_share_url("Private").unwrap();
+        assert!(imported.import_contact_url(&channel_url).is_err());
+        assert!(imported.import_share_url(&url).is_err());
+        assert!(imported.import_contact_url("WIFI:S:home;;").is_err());
+    }
+
+    #[cfg(feature = "transport")]
+    #[test]
+    fn import_from_radio_keeps_known_keys() {
+        use super::ChannelImport;
+        use crate::{
+            meshtastic::channel::Role,
+            transport::stream::{RadioConfig, radio_config::RadioChannel},
+        };
+
+        let mut keyring = Keyring::new();
+        keyring
+            .add_channel_with_name("Private", Key::K128(Default::default()))
+            .unwrap();
+        keyring
+            .add_channel_with_name("LongFast", Key::try_from("AQ==").unwrap())
+            .unwrap();
+
+        let channel = |index, name: &str, psk: Vec<u8>| RadioChannel {
+            index,
+            role: Role::Secondary,
+            name: name.into(),
+            psk,
+        };
+        let radio_config = RadioConfig {
+            channels: vec![
+                channel(0, "", vec![1]),
+                channel(1, "Private", vec![2; 16]),
+                channel(2, "Open", vec![]),
+                channel(3, "New", vec![3; 32]),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            keyring.import_from_radio(&radio_config),
+            vec![
+                ChannelImport::Known("LongFast".into()),
+                ChannelImport::Conflict("Private".into()),
+                ChannelImport::Skipped("Open".into(), "no encryption".into()),
+                ChannelImport::Added("New".into()),
+            ]
+        );
+        assert_eq!(keyring.channels.len(), 3);
+    }
+
     #[test]
     fn ron_serialize_and_deserialize() {
         let se_keyring = build_test_keyring();
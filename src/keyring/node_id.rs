@@ -1,5 +1,6 @@
+use super::byte_node_id::ByteNodeId;
 use rand::Rng;
-use serde::de::{self, Deserialize, Deserializer};
+use serde::de::{self, Deserialize, Deserializer, Visitor};
 use serde::ser::{Serialize, Serializer};
 use std::fmt::{self, LowerHex, UpperHex};
 use std::num;
@@ -16,6 +17,16 @@ impl NodeId {
     pub fn broadcast() -> Self {
         NodeId(0xffffffff)
     }
+
+    // Value for protobuf's fields
+    pub fn to_u32(self) -> u32 {
+        self.0
+    }
+
+    // Last byte as in `relay_node` and `next_hop`: `*ef` for `!deadbeef`
+    pub fn to_short(self) -> String {
+        ByteNodeId::from(self).to_string()
+    }
 }
 
 impl Default for NodeId {
@@ -47,13 +58,87 @@ impl Serialize for NodeId {
     }
 }
 
+struct NodeIdVisitor;
+
+impl<'de> Visitor<'de> for NodeIdVisitor {
+    type Value = NodeId;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("node id as `!hex`, `0xhex`, `^all` or 32-bit integer")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        v.try_into().map_err(de::Error::custom)
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        u32::try_from(v)
+            .map(NodeId)
+            .map_err(|_| de::Error::invalid_value(de::Unexpected::Unsigned(v), &self))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        u32::try_from(v)
+            .map(NodeId)
+            .map_err(|_| de::Error::invalid_value(de::Unexpected::Signed(v), &self))
+    }
+}
+
+// Always read as text, so YAML's plain `12345678` or `0x10` is hex, as before.
+// Integers are rejected in any format, JSON's `3735928559` too:
+// `NumericNodeId` is the only way to read node id from integer
 impl<'de> Deserialize<'de> for NodeId {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: Deserializer<'de>,
     {
-        let s = String::deserialize(deserializer)?;
-        s.try_into().map_err(de::Error::custom)
+        deserializer.deserialize_str(NodeIdVisitor)
+    }
+}
+
+// Serialized as integer for compact JSON.
+// Deserialized from integer as is, or from string as `NodeId`
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Hash)]
+pub struct NumericNodeId(pub NodeId);
+
+impl Serialize for NumericNodeId {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_u32(self.0.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for NumericNodeId {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer
+            .deserialize_any(NodeIdVisitor)
+            .map(NumericNodeId)
+    }
+}
+
+impl From<NodeId> for NumericNodeId {
+    fn from(value: NodeId) -> Self {
+        NumericNodeId(value)
+    }
+}
+
+impl From<NumericNodeId> for NodeId {
+    fn from(value: NumericNodeId) -> Self {
+        value.0
     }
 }
 
@@ -65,12 +150,11 @@ impl TryFrom<&str> for NodeId {
             return Ok(NodeId::broadcast());
         }
 
-        let hex_part = if s.starts_with("!0x") {
-            s.strip_prefix("!0x")
-        } else {
-            s.strip_prefix("!")
-        }
-        .map_or(s, |v| v);
+        let hex_part = s
+            .strip_prefix("!0x")
+            .or_else(|| s.strip_prefix("0x"))
+            .or_else(|| s.strip_prefix("!"))
+            .unwrap_or(s);
 
         let value = u32::from_str_radix(hex_part, 16)?;
         Ok(NodeId(value))
@@ -112,3 +196,47 @@ impl From<NodeId> for String {
         value.to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_forms() {
+        let node_id = NodeId::from(0x0000beef);
+        assert_eq!(node_id.to_string(), "!0000beef");
+        assert_eq!(node_id.to_short(), "*ef");
+        assert_eq!(ByteNodeId::from(node_id).to_string(), "*ef");
+        assert_eq!(NodeId::try_from(node_id.to_string()), Ok(node_id));
+        assert_eq!(NodeId::broadcast().to_string(), "^all");
+    }
+
+    #[test]
+    fn deserialize_string_or_integer() {
+        let node_id = NodeId::from(0xdeadbeef);
+        for yaml in ["'!deadbeef'", "'0xdeadbeef'", "0xdeadbeef", "deadbeef"] {
+            assert_eq!(serde_yaml_ng::from_str::<NodeId>(yaml).unwrap(), node_id);
+        }
+        // All-digit id without prefix is hex too
+        assert_eq!(
+            serde_yaml_ng::from_str::<NodeId>("12345678").unwrap(),
+            NodeId::from(0x12345678)
+        );
+        // Integer is read by `NumericNodeId` only
+        assert!(serde_json::from_str::<NodeId>("3735928559").is_err());
+        assert!(serde_json::from_str::<NumericNodeId>("4294967296").is_err());
+        assert!(serde_json::from_str::<NumericNodeId>("-1").is_err());
+
+        assert_eq!(serde_json::to_string(&node_id).unwrap(), "\"!deadbeef\"");
+        let numeric = NumericNodeId(node_id);
+        assert_eq!(serde_json::to_string(&numeric).unwrap(), "3735928559");
+        assert_eq!(
+            serde_json::from_str::<NumericNodeId>("3735928559").unwrap(),
+            numeric
+        );
+        assert_eq!(
+            serde_json::from_str::<NumericNodeId>("\"!deadbeef\"").unwrap(),
+            numeric
+        );
+    }
+}
@@ -1,6 +1,12 @@
+pub mod framing;
+pub mod geo;
 pub mod keyring;
 pub mod meshtastic;
 #[cfg(feature = "display")]
 pub mod meshtastic_display;
+pub mod mqtt;
+pub mod portnum;
+#[cfg(feature = "stored")]
+pub mod stored;
 #[cfg(feature = "transport")]
 pub mod transport;
@@ -3,7 +3,11 @@ use std::fmt;
 use base64::{Engine, engine::general_purpose};
 use chrono::{TimeZone, Utc};
 
-use crate::{keyring::key::Key, meshtastic};
+use crate::{
+    geo::decode_coord,
+    keyring::{key::Key, node_id::NodeId},
+    meshtastic,
+};
 
 impl fmt::Display for meshtastic::telemetry::Variant {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -301,7 +305,7 @@ impl fmt::Display for meshtastic::Position {
         }
 
         if let (Some(lat), Some(lon)) = (self.latitude_i, self.longitude_i) {
-            write!(f, "  🌐 {:.7} {:.7}", lat as f64 * 1e-7, lon as f64 * 1e-7)?;
+            write!(f, "  🌐 {:.7} {:.7}", decode_coord(lat), decode_coord(lon))?;
         }
 
         writeln!(f, " 🛰 Satellites in View: {}", self.sats_in_view)?;
@@ -453,7 +457,7 @@ impl fmt::Display for meshtastic::User {
 
 impl fmt::Display for meshtastic::NodeInfo {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        writeln!(f, "🧭 Node #{} [!{:x}]:", self.num, self.num)?;
+        writeln!(f, "🧭 Node #{} [{}]:", self.num, NodeId::from(self.num))?;
 
         if let Some(user) = &self.user {
             writeln!(f, "  {}", user)?; // assumes fmt::Display for User
@@ -516,8 +520,10 @@ impl fmt::Display for meshtastic::NeighborInfo {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(
             f,
-            "🌐 Neighbor Info for Node !{:x}, Last Sent By: !{:x}, Broadcast Interval: {} s",
-            self.node_id, self.last_sent_by_id, self.node_broadcast_interval_secs
+            "🌐 Neighbor Info for Node {}, Last Sent By: {}, Broadcast Interval: {} s",
+            NodeId::from(self.node_id),
+            NodeId::from(self.last_sent_by_id),
+            self.node_broadcast_interval_secs
         )?;
 
         if self.neighbors.is_empty() {
@@ -542,8 +548,11 @@ impl fmt::Display for meshtastic::Neighbor {
 
         writeln!(
             f,
-            "Neighbor !{:x} SNR: {:.1} dB, Last Heard: {}, Broadcast Interval: {}",
-            self.node_id, self.snr, time_display, self.node_broadcast_interval_secs
+            "Neighbor {} SNR: {:.1} dB, Last Heard: {}, Broadcast Interval: {}",
+            NodeId::from(self.node_id),
+            self.snr,
+            time_display,
+            self.node_broadcast_interval_secs
         )?;
         Ok(())
     }
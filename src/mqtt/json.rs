@@ -0,0 +1,360 @@
+use std::fmt;
+
+use prost::Message;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    keyring::node_id::NodeId,
+    meshtastic::{self, PortNum, mesh_packet},
+};
+
+#[derive(Debug)]
+pub enum JsonError {
+    Json(serde_json::Error),
+    // Node id is zero, broadcast or malformed string
+    InvalidNodeId(String),
+    // Message's `type` or port is not supported
+    UnsupportedType(String),
+    InvalidPayload(String),
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonError::Json(e) => write!(f, "JSON error: {}", e),
+            JsonError::InvalidNodeId(node_id) => write!(f, "Invalid node id: {}", node_id),
+            JsonError::UnsupportedType(message_type) => {
+                write!(f, "Unsupported message type: {}", message_type)
+            }
+            JsonError::InvalidPayload(e) => write!(f, "Invalid payload: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+impl From<serde_json::Error> for JsonError {
+    fn from(value: serde_json::Error) -> Self {
+        JsonError::Json(value)
+    }
+}
+
+fn broadcast() -> u32 {
+    NodeId::broadcast().into()
+}
+
+// Message published to `{root}/2/json/{channel}/{gateway}` topics.
+// Node ids are numbers, as firmware sends them
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonMessage {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<u32>,
+    // Channel's index
+    #[serde(default)]
+    pub channel: u32,
+    pub from: u32,
+    #[serde(default = "broadcast")]
+    pub to: u32,
+    // Gateway, like `!da5ad56c`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sender: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hops_away: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rssi: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snr: Option<f32>,
+    // `text`, `position`, `nodeinfo`, or `send*` variants for downlink
+    #[serde(rename = "type")]
+    pub message_type: String,
+    #[serde(default)]
+    pub payload: serde_json::Value,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TextPayload {
+    text: String,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct PositionPayload {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    latitude_i: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    longitude_i: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    altitude: Option<i32>,
+    time: u32,
+    sats_in_view: u32,
+    precision_bits: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ground_speed: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ground_track: Option<u32>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct NodeInfoPayload {
+    id: String,
+    longname: String,
+    shortname: String,
+    hardware: i32,
+    role: i32,
+}
+
+fn payload_from<T: for<'de> Deserialize<'de>>(value: &serde_json::Value) -> Result<T, JsonError> {
+    T::deserialize(value).map_err(|e| JsonError::InvalidPayload(e.to_string()))
+}
+
+impl JsonMessage {
+    pub fn from_node(&self) -> Result<NodeId, JsonError> {
+        if self.from == 0 || self.from == broadcast() {
+            return Err(JsonError::InvalidNodeId(format!("from={:#x}", self.from)));
+        }
+        Ok(self.from.into())
+    }
+
+    pub fn sender_node(&self) -> Result<Option<NodeId>, JsonError> {
+        self.sender
+            .as_deref()
+            .map(|sender| {
+                NodeId::try_from(sender).map_err(|_| JsonError::InvalidNodeId(sender.to_string()))
+            })
+            .transpose()
+    }
+
+    pub fn to_data(&self) -> Result<meshtastic::Data, JsonError> {
+        let (portnum, payload) = match self.message_type.as_str() {
+            "text" | "sendtext" => {
+                // Downlink messages carry the text itself
+                let text = match &self.payload {
+                    serde_json::Value::String(text) => text.clone(),
+                    payload => payload_from::<TextPayload>(payload)?.text,
+                };
+                (PortNum::TextMessageApp, text.into_bytes())
+            }
+            "position" | "sendposition" => {
+                let position = payload_from::<PositionPayload>(&self.payload)?;
+                let position = meshtastic::Position {
+                    latitude_i: position.latitude_i,
+                    longitude_i: position.longitude_i,
+                    altitude: position.altitude,
+                    time: position.time,
+                    sats_in_view: position.sats_in_view,
+                    precision_bits: position.precision_bits,
+                    ground_speed: position.ground_speed,
+                    ground_track: position.ground_track,
+                    ..Default::default()
+                };
+                (PortNum::PositionApp, position.encode_to_vec())
+            }
+            "nodeinfo" => {
+                let node_info = payload_from::<NodeInfoPayload>(&self.payload)?;
+                let user = meshtastic::User {
+                    id: node_info.id,
+                    long_name: node_info.longname,
+                    short_name: node_info.shortname,
+                    hw_model: node_info.hardware,
+                    role: node_info.role,
+                    ..Default::default()
+                };
+                (PortNum::NodeinfoApp, user.encode_to_vec())
+            }
+            other => return Err(JsonError::UnsupportedType(other.to_string())),
+        };
+
+        Ok(meshtastic::Data {
+            portnum: portnum as i32,
+            payload,
+            ..Default::default()
+        })
+    }
+
+    // Decoded packet, as it is received from radio
+    pub fn to_mesh_packet(&self) -> Result<meshtastic::MeshPacket, JsonError> {
+        let from = self.from_node()?;
+        let data = self.to_data()?;
+
+        Ok(meshtastic::MeshPacket {
+            from: from.into(),
+            to: self.to,
+            channel: self.channel,
+            id: self.id.unwrap_or_default(),
+            rx_time: self.timestamp.unwrap_or_default(),
+            rx_snr: self.snr.unwrap_or_default(),
+            rx_rssi: self.rssi.unwrap_or_default(),
+            payload_variant: Some(mesh_packet::PayloadVariant::Decoded(data)),
+            ..Default::default()
+        })
+    }
+
+    pub fn from_data(
+        from: NodeId,
+        to: NodeId,
+        channel: u32,
+        id: u32,
+        data: &meshtastic::Data,
+    ) -> Result<Self, JsonError> {
+        let invalid_payload = |e: prost::DecodeError| JsonError::InvalidPayload(e.to_string());
+        let (message_type, payload) = match data.portnum() {
+            PortNum::TextMessageApp => (
+                "text",
+                serde_json::to_value(TextPayload {
+                    text: String::from_utf8_lossy(&data.payload).into_owned(),
+                })?,
+            ),
+            PortNum::PositionApp => {
+                let position = meshtastic::Position::decode(data.payload.as_slice())
+                    .map_err(invalid_payload)?;
+                (
+                    "position",
+                    serde_json::to_value(PositionPayload {
+                        latitude_i: position.latitude_i,
+                        longitude_i: position.longitude_i,
+                        altitude: position.altitude,
+                        time: position.time,
+                        sats_in_view: position.sats_in_view,
+                        precision_bits: position.precision_bits,
+                        ground_speed: position.ground_speed,
+                        ground_track: position.ground_track,
+                    })?,
+                )
+            }
+            PortNum::NodeinfoApp => {
+                let user =
+                    meshtastic::User::decode(data.payload.as_slice()).map_err(invalid_payload)?;
+                (
+                    "nodeinfo",
+                    serde_json::to_value(NodeInfoPayload {
+                        id: user.id,
+                        longname: user.long_name,
+                        shortname: user.short_name,
+                        hardware: user.hw_model,
+                        role: user.role,
+                    })?,
+                )
+            }
+            other => return Err(JsonError::UnsupportedType(other.as_str_name().to_string())),
+        };
+
+        Ok(Self {
+            id: Some(id),
+            channel,
+            from: from.into(),
+            to: to.into(),
+            sender: None,
+            hops_away: None,
+            timestamp: None,
+            rssi: None,
+            snr: None,
+            message_type: message_type.to_string(),
+            payload,
+        })
+    }
+}
+
+pub fn decode_json_message(payload: &[u8]) -> Result<JsonMessage, JsonError> {
+    let message: JsonMessage = serde_json::from_slice(payload)?;
+    message.from_node()?;
+    message.sender_node()?;
+    Ok(message)
+}
+
+pub fn encode_json_message(message: &JsonMessage) -> Result<Vec<u8>, JsonError> {
+    Ok(serde_json::to_vec(message)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{JsonError, JsonMessage, decode_json_message, encode_json_message};
+    use crate::meshtastic::{self, PortNum, mesh_packet};
+    use pretty_assertions::assert_eq;
+    use prost::Message;
+
+    #[test]
+    fn decode_text_message() {
+        let payload = br#"{"channel":0,"from":3663386988,"hops_away":0,"id":305441741,
+            "payload":{"text":"hello"},"rssi":-90,"sender":"!da5ad56c","snr":6.25,
+            "timestamp":1760000000,"to":4294967295,"type":"text"}"#;
+        let message = decode_json_message(payload).unwrap();
+        assert_eq!(message.sender_node().unwrap(), Some(0xda5ad56c.into()));
+
+        let packet = message.to_mesh_packet().unwrap();
+        assert_eq!(packet.from, 3663386988);
+        assert_eq!(packet.to, 0xffffffff);
+        assert_eq!(packet.id, 305441741);
+        assert_eq!(packet.rx_rssi, -90);
+        assert_eq!(
+            packet.payload_variant,
+            Some(mesh_packet::PayloadVariant::Decoded(meshtastic::Data {
+                portnum: PortNum::TextMessageApp as i32,
+                payload: b"hello".to_vec(),
+                ..Default::default()
+            }))
+        );
+    }
+
+    #[test]
+    fn decode_with_missing_optional_fields() {
+        let message =
+            decode_json_message(br#"{"from":1,"type":"sendtext","payload":"hi"}"#).unwrap();
+        assert_eq!(message.to, 0xffffffff);
+        assert_eq!(message.sender_node().unwrap(), None);
+        assert_eq!(message.to_data().unwrap().payload, b"hi".to_vec());
+
+        let message = decode_json_message(br#"{"from":1,"type":"position","payload":{}}"#).unwrap();
+        let position =
+            meshtastic::Position::decode(message.to_data().unwrap().payload.as_slice()).unwrap();
+        assert_eq!(position.latitude_i, None);
+    }
+
+    #[test]
+    fn reject_invalid_node_ids() {
+        for payload in [
+            br#"{"from":-1,"type":"text","payload":{"text":""}}"#.as_slice(),
+            br#"{"from":4294967296,"type":"text","payload":{"text":""}}"#,
+            br#"{"from":1.5,"type":"text","payload":{"text":""}}"#,
+            br#"{"from":"!da5ad56c","type":"text","payload":{"text":""}}"#,
+        ] {
+            assert!(matches!(
+                decode_json_message(payload),
+                Err(JsonError::Json(_))
+            ));
+        }
+        assert!(matches!(
+            decode_json_message(br#"{"from":0,"type":"text","payload":{"text":""}}"#),
+            Err(JsonError::InvalidNodeId(_))
+        ));
+        assert!(matches!(
+            decode_json_message(br#"{"from":1,"sender":"gw","type":"text","payload":{}}"#),
+            Err(JsonError::InvalidNodeId(_))
+        ));
+    }
+
+    #[test]
+    fn encode_and_decode_position() {
+        let position = meshtastic::Position {
+            latitude_i: Some(511_000_000),
+            longitude_i: Some(170_300_000),
+            altitude: Some(120),
+            time: 1760000000,
+            ..Default::default()
+        };
+        let data = meshtastic::Data {
+            portnum: PortNum::PositionApp as i32,
+            payload: position.encode_to_vec(),
+            ..Default::default()
+        };
+        let message =
+            JsonMessage::from_data(0xda5ad56c.into(), 0xffffffff.into(), 0, 42, &data).unwrap();
+        let encoded = encode_json_message(&message).unwrap();
+        let decoded = decode_json_message(&encoded).unwrap();
+
+        assert_eq!(decoded, message);
+        assert_eq!(decoded.to_data().unwrap(), data);
+    }
+}
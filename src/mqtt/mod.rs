@@ -0,0 +1,188 @@
+#[cfg(feature = "json")]
+pub mod json;
+mod topic;
+
+use std::fmt;
+
+use prost::Message;
+
+use crate::{
+    keyring::node_id::NodeId,
+    meshtastic::{MeshPacket, ServiceEnvelope},
+};
+
+pub use topic::{ParsedTopic, TopicEncoding, parse_topic};
+
+#[derive(Debug)]
+pub enum DecodeError {
+    // Payload is not a `ServiceEnvelope`
+    Protobuf(prost::DecodeError),
+    // `gateway_id` is not a node id, like `!da5ad56c`
+    InvalidGatewayId(String),
+    // Channel or gateway of the envelope differs from the topic's one
+    TopicMismatch { topic: String, envelope: String },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Protobuf(e) => write!(f, "ServiceEnvelope decode error: {}", e),
+            DecodeError::InvalidGatewayId(gateway_id) => {
+                write!(f, "Invalid gateway id: {:?}", gateway_id)
+            }
+            DecodeError::TopicMismatch { topic, envelope } => {
+                write!(f, "Topic has {:?}, envelope has {:?}", topic, envelope)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl From<prost::DecodeError> for DecodeError {
+    fn from(value: prost::DecodeError) -> Self {
+        DecodeError::Protobuf(value)
+    }
+}
+
+// Decodes payload published to `{root}/2/e/{channel}/{gateway}` topics.
+// The packet is moved out of the envelope. With `topic` given, envelope's
+// empty `channel_id` and `gateway_id` are taken from the topic, and ones
+// different from the topic's are rejected.
+// Topics not parsed by `parse_topic` are not checked
+pub fn decode_service_envelope(
+    topic: Option<&str>,
+    payload: &[u8],
+) -> Result<(ServiceEnvelope, Option<MeshPacket>), DecodeError> {
+    let mut service_envelope = ServiceEnvelope::decode(payload)?;
+
+    if !service_envelope.gateway_id.is_empty()
+        && NodeId::try_from(service_envelope.gateway_id.as_str()).is_err()
+    {
+        return Err(DecodeError::InvalidGatewayId(service_envelope.gateway_id));
+    }
+
+    if let Some(topic) = topic.and_then(parse_topic) {
+        if let Some(channel) = topic.channel {
+            if service_envelope.channel_id.is_empty() {
+                service_envelope.channel_id = channel;
+            } else if service_envelope.channel_id != channel {
+                return Err(DecodeError::TopicMismatch {
+                    topic: channel,
+                    envelope: service_envelope.channel_id,
+                });
+            }
+        }
+        if let Some(gateway) = topic.gateway {
+            if service_envelope.gateway_id.is_empty() {
+                service_envelope.gateway_id = gateway.to_string();
+            } else if NodeId::try_from(service_envelope.gateway_id.as_str()) != Ok(gateway) {
+                return Err(DecodeError::TopicMismatch {
+                    topic: gateway.to_string(),
+                    envelope: service_envelope.gateway_id,
+                });
+            }
+        }
+    }
+
+    let packet = service_envelope.packet.take();
+    Ok((service_envelope, packet))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DecodeError, decode_service_envelope};
+    use crate::meshtastic::{ServiceEnvelope, mesh_packet};
+    use pretty_assertions::assert_eq;
+    use prost::Message;
+
+    // Hand-built envelope, not a capture: fields of `ServiceEnvelope` as firmware
+    // fills them for `msh/EU_868/2/e/LongFast/!da5ad56c`. Encrypted bytes are arbitrary
+    const LONGFAST_ENVELOPE: &[u8] = &[
+        0x0a, 0x28, 0x0d, 0x6c, 0xd5, 0x5a, 0xda, 0x15, 0xff, 0xff, 0xff, 0xff, 0x18, 0x08, 0x2a,
+        0x0c, 0x5f, 0x1c, 0x8a, 0x27, 0xe3, 0x0b, 0xd4, 0x6a, 0x99, 0x10, 0xc2, 0xf4, 0x35, 0xcd,
+        0xab, 0x34, 0x12, 0x3d, 0x00, 0x78, 0xe7, 0x68, 0x48, 0x03, 0x78, 0x03, 0x12, 0x08, 0x4c,
+        0x6f, 0x6e, 0x67, 0x46, 0x61, 0x73, 0x74, 0x1a, 0x09, 0x21, 0x64, 0x61, 0x35, 0x61, 0x64,
+        0x35, 0x36, 0x63,
+    ];
+
+    #[test]
+    fn decode_encrypted_envelope() {
+        let (envelope, packet) = decode_service_envelope(None, LONGFAST_ENVELOPE).unwrap();
+        assert_eq!(envelope.channel_id, "LongFast");
+        assert_eq!(envelope.gateway_id, "!da5ad56c");
+        assert_eq!(envelope.packet, None);
+
+        let packet = packet.unwrap();
+        assert_eq!(packet.from, 0xda5ad56c);
+        assert_eq!(packet.to, 0xffffffff);
+        assert_eq!(packet.channel, 0x08);
+        assert_eq!(packet.id, 0x1234abcd);
+        assert_eq!(packet.rx_time, 1760000000);
+        assert_eq!(packet.hop_limit, 3);
+        assert_eq!(packet.hop_start, 3);
+        assert_eq!(
+            packet.payload_variant,
+            Some(mesh_packet::PayloadVariant::Encrypted(vec![
+                0x5f, 0x1c, 0x8a, 0x27, 0xe3, 0x0b, 0xd4, 0x6a, 0x99, 0x10, 0xc2, 0xf4
+            ]))
+        );
+    }
+
+    #[test]
+    fn decode_envelope_without_packet() {
+        let payload = ServiceEnvelope {
+            packet: None,
+            channel_id: "LongFast".into(),
+            gateway_id: String::new(),
+        }
+        .encode_to_vec();
+
+        let (envelope, packet) = decode_service_envelope(None, &payload).unwrap();
+        assert_eq!(envelope.channel_id, "LongFast");
+        assert_eq!(packet, None);
+    }
+
+    #[test]
+    fn decode_rejects_garbage() {
+        assert!(matches!(
+            decode_service_envelope(None, &[0xff, 0xff, 0xff]),
+            Err(DecodeError::Protobuf(_))
+        ));
+
+        let payload = ServiceEnvelope {
+            packet: None,
+            channel_id: "LongFast".into(),
+            gateway_id: "gateway".into(),
+        }
+        .encode_to_vec();
+        assert!(matches!(
+            decode_service_envelope(None, &payload),
+            Err(DecodeError::InvalidGatewayId(_))
+        ));
+    }
+
+    #[test]
+    fn decode_checks_topic() {
+        let topic = "msh/EU_868/2/e/LongFast/!da5ad56c";
+        let (envelope, packet) = decode_service_envelope(Some(topic), LONGFAST_ENVELOPE).unwrap();
+        assert_eq!(envelope.channel_id, "LongFast");
+        assert!(packet.is_some());
+
+        // Empty fields are taken from topic
+        let payload = ServiceEnvelope::default().encode_to_vec();
+        let (envelope, _) = decode_service_envelope(Some(topic), &payload).unwrap();
+        assert_eq!(envelope.channel_id, "LongFast");
+        assert_eq!(envelope.gateway_id, "!da5ad56c");
+
+        for topic in [
+            "msh/EU_868/2/e/MediumFast/!da5ad56c",
+            "msh/EU_868/2/e/LongFast/!12345678",
+        ] {
+            assert!(matches!(
+                decode_service_envelope(Some(topic), LONGFAST_ENVELOPE),
+                Err(DecodeError::TopicMismatch { .. })
+            ));
+        }
+    }
+}
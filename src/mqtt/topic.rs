@@ -0,0 +1,146 @@
+use crate::keyring::node_id::NodeId;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TopicEncoding {
+    // `/2/e/`: protobuf `ServiceEnvelope`, packet is encrypted
+    Encrypted,
+    // `/2/c/`: protobuf `ServiceEnvelope`, packet is decoded
+    Cleartext,
+    // `/2/json/`: JSON message
+    Json,
+    // `/2/map/`: `MapReport`
+    MapReport,
+    // `/2/stat/`: gateway's status
+    Stat,
+}
+
+impl TopicEncoding {
+    fn parse(segment: &str) -> Option<Self> {
+        match segment {
+            "e" => Some(TopicEncoding::Encrypted),
+            "c" => Some(TopicEncoding::Cleartext),
+            "json" => Some(TopicEncoding::Json),
+            "map" => Some(TopicEncoding::MapReport),
+            "stat" => Some(TopicEncoding::Stat),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedTopic {
+    // Everything before the version, e.g. `msh/EU_868`
+    pub root: String,
+    pub version: u32,
+    pub encoding: TopicEncoding,
+    // Channel name, `PKI` for direct messages. None for map reports and stats
+    pub channel: Option<String>,
+    pub gateway: Option<NodeId>,
+}
+
+// Parses `{root}/{version}/{encoding}/{channel}/{gateway}` topics.
+// Map reports have no channel and gateway, stats have gateway only.
+// Gateway is optional for subscriptions' topics like `msh/EU_868/2/e/LongFast/`
+pub fn parse_topic(topic: &str) -> Option<ParsedTopic> {
+    let segments = topic.trim_end_matches('/').split('/').collect::<Vec<_>>();
+
+    // Root could be of any depth, version is followed by known encoding
+    let (index, version, encoding) = segments.windows(2).enumerate().find_map(|(index, v)| {
+        let version = v[0].parse::<u32>().ok()?;
+        let encoding = TopicEncoding::parse(v[1])?;
+        Some((index, version, encoding))
+    })?;
+    let root = segments[..index].join("/");
+    let rest = &segments[index + 2..];
+
+    let parse_gateway = |segment: &str| NodeId::try_from(segment).ok();
+    let (channel, gateway) = match encoding {
+        TopicEncoding::Encrypted | TopicEncoding::Cleartext | TopicEncoding::Json => match rest {
+            [channel] if !channel.is_empty() => (Some(channel.to_string()), None),
+            [channel, gateway] if !channel.is_empty() => {
+                (Some(channel.to_string()), Some(parse_gateway(gateway)?))
+            }
+            _ => return None,
+        },
+        TopicEncoding::MapReport => match rest {
+            [] => (None, None),
+            [gateway] => (None, Some(parse_gateway(gateway)?)),
+            _ => return None,
+        },
+        TopicEncoding::Stat => match rest {
+            [gateway] => (None, Some(parse_gateway(gateway)?)),
+            _ => return None,
+        },
+    };
+
+    Some(ParsedTopic {
+        root,
+        version,
+        encoding,
+        channel,
+        gateway,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ParsedTopic, TopicEncoding, parse_topic};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parse_channel_topics() {
+        assert_eq!(
+            parse_topic("msh/EU_868/2/e/LongFast/!da5ad56c"),
+            Some(ParsedTopic {
+                root: "msh/EU_868".into(),
+                version: 2,
+                encoding: TopicEncoding::Encrypted,
+                channel: Some("LongFast".into()),
+                gateway: Some(0xda5ad56c.into()),
+            })
+        );
+        assert_eq!(
+            parse_topic("msh/RU/2/c/MediumSlow/!0000abcd").map(|v| (v.encoding, v.channel)),
+            Some((TopicEncoding::Cleartext, Some("MediumSlow".into())))
+        );
+        assert_eq!(
+            parse_topic("msh/2/json/PKI/!0000abcd").map(|v| (v.root, v.encoding, v.channel)),
+            Some(("msh".into(), TopicEncoding::Json, Some("PKI".into())))
+        );
+    }
+
+    #[test]
+    fn parse_trailing_slashes() {
+        let topic = parse_topic("msh/EU_868/2/e/LongFast/").unwrap();
+        assert_eq!(topic.channel, Some("LongFast".into()));
+        assert_eq!(topic.gateway, None);
+
+        let topic = parse_topic("msh/EU_868/2/e/LongFast/!da5ad56c/").unwrap();
+        assert_eq!(topic.gateway, Some(0xda5ad56c.into()));
+    }
+
+    #[test]
+    fn parse_map_and_stat_topics() {
+        let topic = parse_topic("msh/EU_868/2/map/").unwrap();
+        assert_eq!(topic.encoding, TopicEncoding::MapReport);
+        assert_eq!(topic.channel, None);
+        assert_eq!(topic.gateway, None);
+
+        let topic = parse_topic("msh/EU_868/2/stat/!da5ad56c").unwrap();
+        assert_eq!(topic.encoding, TopicEncoding::Stat);
+        assert_eq!(topic.channel, None);
+        assert_eq!(topic.gateway, Some(0xda5ad56c.into()));
+
+        assert_eq!(parse_topic("msh/EU_868/2/stat/"), None);
+    }
+
+    #[test]
+    fn parse_invalid_topics() {
+        assert_eq!(parse_topic(""), None);
+        assert_eq!(parse_topic("msh/EU_868"), None);
+        assert_eq!(parse_topic("msh/EU_868/2/x/LongFast/!da5ad56c"), None);
+        assert_eq!(parse_topic("msh/EU_868/2/e/"), None);
+        assert_eq!(parse_topic("msh/EU_868/2/e/LongFast/gateway"), None);
+        assert_eq!(parse_topic("msh/EU_868/2/e/LongFast/!da5ad56c/extra"), None);
+    }
+}
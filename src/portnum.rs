@@ -0,0 +1,122 @@
+use std::fmt;
+
+use crate::meshtastic::PortNum;
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+)]
+pub enum PortCategory {
+    Text,
+    Telemetry,
+    Position,
+    Routing,
+    Admin,
+    // Known application without own category
+    Other,
+    // Unknown, private or reserved port number
+    Unknown(u32),
+}
+
+impl PortCategory {
+    // Categories of known port numbers, for filters and menus
+    pub const KNOWN: [PortCategory; 6] = [
+        PortCategory::Text,
+        PortCategory::Telemetry,
+        PortCategory::Position,
+        PortCategory::Routing,
+        PortCategory::Admin,
+        PortCategory::Other,
+    ];
+}
+
+impl fmt::Display for PortCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PortCategory::Unknown(portnum) => write!(f, "Unknown ({})", portnum),
+            category => write!(f, "{:?}", category),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PortInfo {
+    pub name: &'static str,
+    pub category: PortCategory,
+    // Sent encrypted with channel's or PKI key as a rule
+    pub encrypted: bool,
+}
+
+pub fn portnum_info(portnum: PortNum) -> PortInfo {
+    let (name, category, encrypted) = match portnum {
+        PortNum::TextMessageApp => ("Text", PortCategory::Text, true),
+        PortNum::TextMessageCompressedApp => ("Compressed text", PortCategory::Text, true),
+        PortNum::ReplyApp => ("Reply", PortCategory::Text, true),
+        PortNum::AlertApp => ("Alert", PortCategory::Text, true),
+        PortNum::PositionApp => ("Position", PortCategory::Position, true),
+        PortNum::WaypointApp => ("Waypoint", PortCategory::Position, true),
+        // Map reports are published to MQTT without encryption
+        PortNum::MapReportApp => ("Map report", PortCategory::Position, false),
+        PortNum::TelemetryApp => ("Telemetry", PortCategory::Telemetry, true),
+        PortNum::PaxcounterApp => ("Paxcounter", PortCategory::Telemetry, true),
+        PortNum::DetectionSensorApp => ("Detection sensor", PortCategory::Telemetry, true),
+        PortNum::PowerstressApp => ("Power stress", PortCategory::Telemetry, true),
+        PortNum::RoutingApp => ("Routing", PortCategory::Routing, true),
+        PortNum::TracerouteApp => ("Traceroute", PortCategory::Routing, true),
+        PortNum::NeighborinfoApp => ("Neighbor info", PortCategory::Routing, true),
+        PortNum::StoreForwardApp => ("Store & forward", PortCategory::Routing, true),
+        PortNum::AdminApp => ("Admin", PortCategory::Admin, true),
+        PortNum::RemoteHardwareApp => ("Remote hardware", PortCategory::Admin, true),
+        PortNum::KeyVerificationApp => ("Key verification", PortCategory::Admin, true),
+        PortNum::NodeinfoApp => ("Node info", PortCategory::Other, true),
+        PortNum::AudioApp => ("Audio", PortCategory::Other, true),
+        PortNum::IpTunnelApp => ("IP tunnel", PortCategory::Other, true),
+        PortNum::SerialApp => ("Serial", PortCategory::Other, true),
+        PortNum::RangeTestApp => ("Range test", PortCategory::Other, true),
+        PortNum::ZpsApp => ("ZPS", PortCategory::Other, true),
+        PortNum::SimulatorApp => ("Simulator", PortCategory::Other, true),
+        PortNum::AtakPlugin => ("ATAK plugin", PortCategory::Other, true),
+        PortNum::AtakForwarder => ("ATAK forwarder", PortCategory::Other, true),
+        PortNum::ReticulumTunnelApp => ("Reticulum tunnel", PortCategory::Other, true),
+        PortNum::CayenneApp => ("Cayenne", PortCategory::Other, true),
+        PortNum::UnknownApp => ("Unknown", PortCategory::Unknown(portnum as u32), true),
+        PortNum::PrivateApp => ("Private", PortCategory::Unknown(portnum as u32), true),
+        PortNum::Max => ("Reserved", PortCategory::Unknown(portnum as u32), true),
+    };
+
+    PortInfo {
+        name,
+        category,
+        encrypted,
+    }
+}
+
+// For raw `Data::portnum`, including values not known by protobuf
+pub fn portnum_info_raw(portnum: i32) -> PortInfo {
+    match PortNum::try_from(portnum) {
+        Ok(portnum) => portnum_info(portnum),
+        Err(_) => PortInfo {
+            name: "Unknown",
+            category: PortCategory::Unknown(portnum as u32),
+            encrypted: true,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn categories() {
+        assert_eq!(
+            portnum_info(PortNum::TracerouteApp).category,
+            PortCategory::Routing
+        );
+        assert_eq!(
+            portnum_info(PortNum::PrivateApp).category,
+            PortCategory::Unknown(256)
+        );
+        assert_eq!(portnum_info_raw(300).category, PortCategory::Unknown(300));
+        assert!(!portnum_info(PortNum::MapReportApp).encrypted);
+    }
+}
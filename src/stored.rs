@@ -0,0 +1,259 @@
+// Packet in the form softnode journals it and serves to clients as JSON.
+// Shared by softnode, its client and monitor's `--format json`
+use chrono::{DateTime, Utc};
+use prost::Message;
+
+use crate::{
+    keyring::{Keyring, byte_node_id::ByteNodeId, cryptor::Decrypt, node_id::NodeId},
+    meshtastic,
+};
+
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+pub enum DecryptTarget {
+    Direct(u32),
+    PKI,
+    Channel(String),
+    // Channel's hash is unknown, key is found by trying all channels
+    Guessed(String),
+}
+
+#[derive(Clone)]
+pub enum DataVariant {
+    Encrypted(Vec<u8>),
+    Decrypted(DecryptTarget, meshtastic::Data),
+    DecryptError(DecryptError, Vec<u8>),
+}
+
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+pub enum DecryptError {
+    DecryptorNotFound,
+    DecryptFailed,
+    ConstructFailed,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+enum DataVariantSerdeHelper {
+    Encrypted(Vec<u8>),
+    Decrypted(DecryptTarget, Vec<u8>),
+    DecryptError(DecryptError, Vec<u8>),
+}
+
+impl serde::Serialize for DataVariant {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            DataVariant::Encrypted(data) => DataVariantSerdeHelper::Encrypted(data.clone()),
+            DataVariant::Decrypted(target, data) => {
+                DataVariantSerdeHelper::Decrypted(target.clone(), data.encode_to_vec())
+            }
+            DataVariant::DecryptError(reason, data) => {
+                DataVariantSerdeHelper::DecryptError(reason.clone(), data.clone())
+            }
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for DataVariant {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let helper = DataVariantSerdeHelper::deserialize(deserializer)?;
+
+        match helper {
+            DataVariantSerdeHelper::Encrypted(items) => Ok(DataVariant::Encrypted(items)),
+            DataVariantSerdeHelper::Decrypted(target, items) => {
+                let data =
+                    meshtastic::Data::decode(items.as_slice()).map_err(serde::de::Error::custom)?;
+
+                Ok(DataVariant::Decrypted(target, data))
+            }
+            DataVariantSerdeHelper::DecryptError(reason, data) => {
+                Ok(DataVariant::DecryptError(reason, data))
+            }
+        }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, PartialOrd)]
+pub struct StoreMeshRxInfo {
+    pub rx_time: DateTime<Utc>,
+    pub rx_snr: f32,
+    pub rx_rssi: i32,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+pub struct StoredMeshHeader {
+    pub from: NodeId,
+    pub to: NodeId,
+    pub channel: u32,
+    pub id: u32,
+    pub priority: String,
+    pub via_mqtt: bool,
+    pub rx: Option<StoreMeshRxInfo>,
+    pub hop_limit: u32,
+    pub hop_start: u32,
+    pub pki_encrypted: bool,
+    pub next_hop: ByteNodeId,
+    pub relay_node: ByteNodeId,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+pub struct StoredMeshPacket {
+    pub sequence_number: u64,
+    pub store_timestamp: DateTime<chrono::Utc>,
+    pub gateway: Option<NodeId>,
+    pub connection_name: String,
+    pub connection_hint: Option<String>,
+    pub header: StoredMeshHeader,
+    pub data: Option<DataVariant>,
+}
+
+impl StoredMeshPacket {
+    // Packet as softnode stores it, without decryption
+    pub fn from_mesh_packet(
+        sequence_number: u64,
+        gateway: Option<NodeId>,
+        connection_name: String,
+        connection_hint: Option<String>,
+        mesh_packet: &meshtastic::MeshPacket,
+    ) -> Self {
+        let rx_time = DateTime::from_timestamp(mesh_packet.rx_time as i64, 0).unwrap_or_default();
+        let rx = if mesh_packet.rx_time != 0
+            || (mesh_packet.rx_snr != 0.0 && mesh_packet.rx_rssi != 0)
+        {
+            Some(StoreMeshRxInfo {
+                rx_time,
+                rx_snr: mesh_packet.rx_snr,
+                rx_rssi: mesh_packet.rx_rssi,
+            })
+        } else {
+            None
+        };
+        let priority = match meshtastic::mesh_packet::Priority::try_from(mesh_packet.priority) {
+            Ok(priority) => priority.as_str_name().to_string(),
+            Err(_) => mesh_packet.priority.to_string(),
+        };
+        let data = match &mesh_packet.payload_variant {
+            Some(meshtastic::mesh_packet::PayloadVariant::Decoded(data)) => Some(
+                DataVariant::Decrypted(DecryptTarget::Direct(mesh_packet.channel), data.clone()),
+            ),
+            Some(meshtastic::mesh_packet::PayloadVariant::Encrypted(items)) => {
+                Some(DataVariant::Encrypted(items.clone()))
+            }
+            None => None,
+        };
+
+        Self {
+            sequence_number,
+            store_timestamp: Utc::now(),
+            gateway,
+            connection_name,
+            connection_hint,
+            header: StoredMeshHeader {
+                from: mesh_packet.from.into(),
+                to: mesh_packet.to.into(),
+                channel: mesh_packet.channel,
+                id: mesh_packet.id,
+                priority,
+                via_mqtt: mesh_packet.via_mqtt,
+                rx,
+                hop_limit: mesh_packet.hop_limit,
+                hop_start: mesh_packet.hop_start,
+                pki_encrypted: mesh_packet.pki_encrypted,
+                next_hop: ByteNodeId::from(mesh_packet.next_hop),
+                relay_node: ByteNodeId::from(mesh_packet.relay_node),
+            },
+            data,
+        }
+    }
+
+    // Reverse of `from_mesh_packet`. Decrypted data is returned as decoded:
+    // original encrypted payload is not stored
+    pub fn to_mesh_packet(&self) -> meshtastic::MeshPacket {
+        let header = &self.header;
+        let priority = meshtastic::mesh_packet::Priority::from_str_name(&header.priority)
+            .map(|priority| priority as i32)
+            .or_else(|| header.priority.parse().ok())
+            .unwrap_or_default();
+        let payload_variant = self.data.as_ref().map(|data| match data {
+            DataVariant::Decrypted(_, data) => {
+                meshtastic::mesh_packet::PayloadVariant::Decoded(data.clone())
+            }
+            DataVariant::Encrypted(items) | DataVariant::DecryptError(_, items) => {
+                meshtastic::mesh_packet::PayloadVariant::Encrypted(items.clone())
+            }
+        });
+
+        meshtastic::MeshPacket {
+            from: header.from.into(),
+            to: header.to.into(),
+            channel: header.channel,
+            id: header.id,
+            rx_time: header
+                .rx
+                .as_ref()
+                .map(|rx| rx.rx_time.timestamp() as u32)
+                .unwrap_or_default(),
+            rx_snr: header.rx.as_ref().map(|rx| rx.rx_snr).unwrap_or_default(),
+            rx_rssi: header.rx.as_ref().map(|rx| rx.rx_rssi).unwrap_or_default(),
+            hop_limit: header.hop_limit,
+            priority,
+            via_mqtt: header.via_mqtt,
+            hop_start: header.hop_start,
+            pki_encrypted: header.pki_encrypted,
+            next_hop: header.next_hop.into(),
+            relay_node: header.relay_node.into(),
+            payload_variant,
+            ..Default::default()
+        }
+    }
+
+    // Decrypt data if possible or return error.
+    // `try_all_channel_keys` enables fallback for unknown channels
+    pub fn decrypt(mut self, keyring: &Keyring, try_all_channel_keys: bool) -> Self {
+        if let Some(data) = self.data {
+            let data = match data {
+                DataVariant::Encrypted(items) | DataVariant::DecryptError(_, items) => {
+                    if let Some(cryptor) = keyring.cryptor_for(
+                        self.header.from,
+                        self.header.to,
+                        self.header.channel.into(),
+                    ) {
+                        if let Ok(decrypted) = cryptor.decrypt(self.header.id, items.clone()) {
+                            if let Ok(data) = meshtastic::Data::decode(decrypted.as_slice()) {
+                                match cryptor {
+                                    crate::keyring::cryptor::Cryptor::Symmetric(name, _) => {
+                                        DataVariant::Decrypted(DecryptTarget::Channel(name), data)
+                                    }
+                                    crate::keyring::cryptor::Cryptor::PKI(_) => {
+                                        DataVariant::Decrypted(DecryptTarget::PKI, data)
+                                    }
+                                }
+                            } else {
+                                DataVariant::DecryptError(DecryptError::ConstructFailed, items)
+                            }
+                        } else {
+                            DataVariant::DecryptError(DecryptError::DecryptFailed, items)
+                        }
+                    } else if try_all_channel_keys
+                        && self.header.channel != 0
+                        && let Some((name, data)) =
+                            keyring.try_decrypt_any(self.header.from, self.header.id, &items)
+                    {
+                        DataVariant::Decrypted(DecryptTarget::Guessed(name), data)
+                    } else {
+                        DataVariant::DecryptError(DecryptError::DecryptorNotFound, items)
+                    }
+                }
+                DataVariant::Decrypted(target, items) => DataVariant::Decrypted(target, items),
+            };
+
+            self.data = Some(data);
+        }
+        self
+    }
+}
@@ -0,0 +1,396 @@
+use std::{
+    fmt,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use btleplug::{
+    api::{Central, Characteristic, Manager as _, Peripheral as _, ScanFilter, WriteType},
+    platform::{Adapter, Manager, Peripheral},
+};
+use futures::{SinkExt, StreamExt, channel::mpsc};
+use prost::Message;
+use tokio::{task::JoinHandle, time::Instant};
+use uuid::Uuid;
+
+use crate::{
+    framing::{self, BytesSequence, StreamRecvData},
+    meshtastic,
+    transport::stream::Stream,
+};
+
+// Meshtastic's GATT service
+pub const SERVICE_UUID: Uuid = Uuid::from_u128(0x6ba1b218_15a8_461f_9fa8_5dcae273eafd);
+// Write: one `ToRadio` per write
+pub const TO_RADIO_UUID: Uuid = Uuid::from_u128(0xf75c76d2_129e_4dad_a1dd_7866124401e7);
+// Read: one `FromRadio` per read, empty value when radio's queue is empty
+pub const FROM_RADIO_UUID: Uuid = Uuid::from_u128(0x2c55e69e_4993_11ed_b878_0242ac120002);
+// Notify: counter increased when `FromRadio` is queued
+pub const FROM_NUM_UUID: Uuid = Uuid::from_u128(0xed9da18c_a800_4f66_a670_aa7547e34453);
+
+// Firmware doesn't accept bigger `ToRadio`
+const TO_RADIO_SIZE_MAX: usize = 512;
+
+fn ble_error(e: btleplug::Error) -> std::io::Error {
+    std::io::Error::other(e.to_string())
+}
+
+fn closed_error() -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::BrokenPipe, "BLE connection is closed")
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BleDevice {
+    pub name: Option<String>,
+    pub address: String,
+}
+
+impl fmt::Display for BleDevice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "{} ({})", name, self.address),
+            None => write!(f, "{}", self.address),
+        }
+    }
+}
+
+async fn first_adapter() -> Result<Adapter, std::io::Error> {
+    let manager = Manager::new().await.map_err(ble_error)?;
+    manager
+        .adapters()
+        .await
+        .map_err(ble_error)?
+        .into_iter()
+        .next()
+        .ok_or(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "Bluetooth adapter is not found",
+        ))
+}
+
+async fn meshtastic_peripherals(
+    adapter: &Adapter,
+) -> Result<Vec<(BleDevice, Peripheral)>, std::io::Error> {
+    let mut found = Vec::new();
+    for peripheral in adapter.peripherals().await.map_err(ble_error)? {
+        let Some(properties) = peripheral.properties().await.map_err(ble_error)? else {
+            continue;
+        };
+        if !properties.services.contains(&SERVICE_UUID) {
+            continue;
+        }
+        let device = BleDevice {
+            name: properties.local_name,
+            address: peripheral.address().to_string(),
+        };
+        found.push((device, peripheral));
+    }
+    Ok(found)
+}
+
+// Devices advertising Meshtastic's service during `duration`
+pub async fn scan(duration: Duration) -> Result<Vec<BleDevice>, std::io::Error> {
+    let adapter = first_adapter().await?;
+    adapter
+        .start_scan(ScanFilter {
+            services: vec![SERVICE_UUID],
+        })
+        .await
+        .map_err(ble_error)?;
+    tokio::time::sleep(duration).await;
+    let found = meshtastic_peripherals(&adapter).await;
+    let _ = adapter.stop_scan().await;
+    Ok(found?.into_iter().map(|(device, _)| device).collect())
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BleBuilder {
+    // Advertised name or address like `AA:BB:CC:DD:EE:FF`
+    pub device: String,
+    pub scan_timeout: Duration,
+}
+
+impl BleBuilder {
+    pub fn new(device: String) -> Self {
+        Self {
+            device,
+            scan_timeout: Duration::from_secs(10),
+        }
+    }
+
+    pub fn scan_timeout(mut self, scan_timeout: Duration) -> Self {
+        self.scan_timeout = scan_timeout;
+        self
+    }
+
+    fn matches(&self, device: &BleDevice) -> bool {
+        device.address.eq_ignore_ascii_case(&self.device)
+            || device.name.as_deref() == Some(self.device.as_str())
+    }
+
+    async fn find(&self, adapter: &Adapter) -> Result<Peripheral, std::io::Error> {
+        let deadline = Instant::now() + self.scan_timeout;
+        loop {
+            if let Some((_, peripheral)) = meshtastic_peripherals(adapter)
+                .await?
+                .into_iter()
+                .find(|(device, _)| self.matches(device))
+            {
+                return Ok(peripheral);
+            }
+            if Instant::now() >= deadline {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("BLE device {} is not found", self.device),
+                ));
+            }
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+    }
+
+    pub async fn connect(&self) -> Result<Stream, std::io::Error> {
+        let adapter = first_adapter().await?;
+        adapter
+            .start_scan(ScanFilter {
+                services: vec![SERVICE_UUID],
+            })
+            .await
+            .map_err(ble_error)?;
+        let peripheral = self.find(&adapter).await;
+        let _ = adapter.stop_scan().await;
+        let peripheral = peripheral?;
+
+        peripheral.connect().await.map_err(ble_error)?;
+        peripheral.discover_services().await.map_err(ble_error)?;
+        let characteristics = Characteristics::find(&peripheral)?;
+
+        let (to_radio_sender, to_radio_receiver) = mpsc::channel(16);
+        let (from_radio_sender, from_radio_receiver) = mpsc::channel(64);
+        let task = tokio::spawn(run(
+            peripheral,
+            characteristics,
+            to_radio_receiver,
+            from_radio_sender,
+        ));
+
+        Ok(Stream::Ble(BleStream {
+            to_radio: to_radio_sender,
+            from_radio: from_radio_receiver,
+            task,
+        }))
+    }
+}
+
+struct Characteristics {
+    to_radio: Characteristic,
+    from_radio: Characteristic,
+    from_num: Characteristic,
+}
+
+impl Characteristics {
+    fn find(peripheral: &Peripheral) -> Result<Self, std::io::Error> {
+        let characteristics = peripheral.characteristics();
+        let find = |uuid: Uuid| {
+            characteristics
+                .iter()
+                .find(|characteristic| characteristic.uuid == uuid)
+                .cloned()
+                .ok_or(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("Characteristic {} is not found", uuid),
+                ))
+        };
+        Ok(Self {
+            to_radio: find(TO_RADIO_UUID)?,
+            from_radio: find(FROM_RADIO_UUID)?,
+            from_num: find(FROM_NUM_UUID)?,
+        })
+    }
+}
+
+// Reads `FromRadio` until radio's queue is empty
+async fn read_queue(
+    peripheral: &Peripheral,
+    characteristics: &Characteristics,
+    from_radio: &mut mpsc::Sender<Result<StreamRecvData, std::io::Error>>,
+) -> Result<(), std::io::Error> {
+    loop {
+        // Value longer than MTU is read by OS' stack with long read
+        let value = peripheral
+            .read(&characteristics.from_radio)
+            .await
+            .map_err(ble_error)?;
+        if value.is_empty() {
+            return Ok(());
+        }
+        let recv_data = framing::decode_from_radio(&value);
+        from_radio
+            .send(recv_data)
+            .await
+            .map_err(|_| closed_error())?;
+    }
+}
+
+async fn exchange(
+    peripheral: &Peripheral,
+    characteristics: &Characteristics,
+    to_radio: &mut mpsc::Receiver<Vec<u8>>,
+    from_radio: &mut mpsc::Sender<Result<StreamRecvData, std::io::Error>>,
+) -> Result<(), std::io::Error> {
+    let mut notifications = peripheral.notifications().await.map_err(ble_error)?;
+    peripheral
+        .subscribe(&characteristics.from_num)
+        .await
+        .map_err(ble_error)?;
+
+    loop {
+        read_queue(peripheral, characteristics, from_radio).await?;
+        tokio::select! {
+            bytes = to_radio.next() => match bytes {
+                // Long write is fragmented by OS' stack when response is requested
+                Some(bytes) => peripheral
+                    .write(&characteristics.to_radio, &bytes, WriteType::WithResponse)
+                    .await
+                    .map_err(ble_error)?,
+                // `BleStream` is dropped
+                None => return Ok(()),
+            },
+            notification = notifications.next() => if notification.is_none() {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::ConnectionAborted,
+                    "BLE device is disconnected",
+                ));
+            },
+        }
+    }
+}
+
+async fn run(
+    peripheral: Peripheral,
+    characteristics: Characteristics,
+    mut to_radio: mpsc::Receiver<Vec<u8>>,
+    mut from_radio: mpsc::Sender<Result<StreamRecvData, std::io::Error>>,
+) {
+    if let Err(e) = exchange(
+        &peripheral,
+        &characteristics,
+        &mut to_radio,
+        &mut from_radio,
+    )
+    .await
+    {
+        let _ = from_radio.send(Err(e)).await;
+    }
+    let _ = peripheral.disconnect().await;
+}
+
+// Connection to radio over BLE. GATT exchange is run by background task:
+// `ToRadio` is written as is, `FromRadio` is read after each write and
+// `fromNum` notification
+pub struct BleStream {
+    to_radio: mpsc::Sender<Vec<u8>>,
+    from_radio: mpsc::Receiver<Result<StreamRecvData, std::io::Error>>,
+    task: JoinHandle<()>,
+}
+
+impl Drop for BleStream {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl BleStream {
+    fn start_send_bytes(&mut self, bytes: Vec<u8>) -> Result<(), std::io::Error> {
+        if bytes.len() > TO_RADIO_SIZE_MAX {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "ToRadio is too long: {} (expected up to {})",
+                    bytes.len(),
+                    TO_RADIO_SIZE_MAX
+                ),
+            ));
+        }
+        self.to_radio.start_send(bytes).map_err(|_| closed_error())
+    }
+}
+
+impl futures::Sink<meshtastic::to_radio::PayloadVariant> for BleStream {
+    type Error = std::io::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.get_mut()
+            .to_radio
+            .poll_ready(cx)
+            .map_err(|_| closed_error())
+    }
+
+    fn start_send(
+        self: Pin<&mut Self>,
+        item: meshtastic::to_radio::PayloadVariant,
+    ) -> Result<(), Self::Error> {
+        let to_radio = meshtastic::ToRadio {
+            payload_variant: Some(item),
+        };
+        self.get_mut().start_send_bytes(to_radio.encode_to_vec())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.get_mut()
+            .to_radio
+            .poll_flush_unpin(cx)
+            .map_err(|_| closed_error())
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.get_mut()
+            .to_radio
+            .poll_close_unpin(cx)
+            .map_err(|_| closed_error())
+    }
+}
+
+impl futures::Sink<BytesSequence> for BleStream {
+    type Error = std::io::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.get_mut()
+            .to_radio
+            .poll_ready(cx)
+            .map_err(|_| closed_error())
+    }
+
+    // No framing over BLE: headed and unheaded bytes are the same `ToRadio`,
+    // wakeup is not needed
+    fn start_send(self: Pin<&mut Self>, item: BytesSequence) -> Result<(), Self::Error> {
+        match item {
+            BytesSequence::Wakeup => Ok(()),
+            BytesSequence::Unheaded(bytes) | BytesSequence::Headed(bytes) => {
+                self.get_mut().start_send_bytes(bytes.to_vec())
+            }
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.get_mut()
+            .to_radio
+            .poll_flush_unpin(cx)
+            .map_err(|_| closed_error())
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.get_mut()
+            .to_radio
+            .poll_close_unpin(cx)
+            .map_err(|_| closed_error())
+    }
+}
+
+impl futures::Stream for BleStream {
+    type Item = Result<StreamRecvData, std::io::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().from_radio.poll_next_unpin(cx)
+    }
+}
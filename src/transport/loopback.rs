@@ -0,0 +1,190 @@
+// In-memory transport for tests: `pair()` gives `Stream` to code under test
+// and `Radio` to act as a device on the other side
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use tokio::sync::mpsc;
+
+use crate::{
+    framing::{BytesSequence, PacketId, StreamRecvData},
+    meshtastic,
+    transport::stream::Stream,
+};
+
+// Data sent by code under test
+pub enum Sent {
+    ToRadio(meshtastic::to_radio::PayloadVariant),
+    BytesSequence(BytesSequence),
+}
+
+pub struct LoopbackStream {
+    to_radio: mpsc::UnboundedSender<Sent>,
+    from_radio: mpsc::UnboundedReceiver<Result<StreamRecvData, std::io::Error>>,
+}
+
+// Device's side of loopback. Stream ends when `Radio` is dropped
+pub struct Radio {
+    to_radio: mpsc::UnboundedReceiver<Sent>,
+    from_radio: mpsc::UnboundedSender<Result<StreamRecvData, std::io::Error>>,
+    packet_id: PacketId,
+}
+
+pub fn pair() -> (Stream, Radio) {
+    let (to_radio_tx, to_radio_rx) = mpsc::unbounded_channel();
+    let (from_radio_tx, from_radio_rx) = mpsc::unbounded_channel();
+
+    (
+        Stream::Loopback(LoopbackStream {
+            to_radio: to_radio_tx,
+            from_radio: from_radio_rx,
+        }),
+        Radio {
+            to_radio: to_radio_rx,
+            from_radio: from_radio_tx,
+            packet_id: 0,
+        },
+    )
+}
+
+impl Radio {
+    // Injects `FromRadio` with next packet id. False if stream is dropped
+    pub fn send(&mut self, payload_variant: meshtastic::from_radio::PayloadVariant) -> bool {
+        self.packet_id = self.packet_id.wrapping_add(1);
+        self.send_recv_data(Ok(StreamRecvData::FromRadio(
+            self.packet_id,
+            payload_variant,
+        )))
+    }
+
+    pub fn send_packet(&mut self, mesh_packet: meshtastic::MeshPacket) -> bool {
+        self.send(meshtastic::from_radio::PayloadVariant::Packet(mesh_packet))
+    }
+
+    // Injects data as is: unstructured bytes or transport's error
+    pub fn send_recv_data(&self, recv_data: Result<StreamRecvData, std::io::Error>) -> bool {
+        self.from_radio.send(recv_data).is_ok()
+    }
+
+    // Next data sent by stream, None if stream is dropped
+    pub async fn recv(&mut self) -> Option<Sent> {
+        self.to_radio.recv().await
+    }
+
+    // Data sent by stream without waiting
+    pub fn try_recv(&mut self) -> Option<Sent> {
+        self.to_radio.try_recv().ok()
+    }
+
+    // `ToRadio` messages sent by stream so far, other data is skipped
+    pub fn drain_to_radio(&mut self) -> Vec<meshtastic::to_radio::PayloadVariant> {
+        std::iter::from_fn(|| self.try_recv())
+            .filter_map(|sent| match sent {
+                Sent::ToRadio(payload_variant) => Some(payload_variant),
+                Sent::BytesSequence(_) => None,
+            })
+            .collect()
+    }
+}
+
+impl LoopbackStream {
+    fn send(&self, sent: Sent) -> Result<(), std::io::Error> {
+        self.to_radio.send(sent).map_err(|_| {
+            std::io::Error::new(std::io::ErrorKind::BrokenPipe, "Loopback radio is dropped")
+        })
+    }
+}
+
+impl futures::Sink<meshtastic::to_radio::PayloadVariant> for LoopbackStream {
+    type Error = std::io::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(
+        self: Pin<&mut Self>,
+        item: meshtastic::to_radio::PayloadVariant,
+    ) -> Result<(), Self::Error> {
+        self.send(Sent::ToRadio(item))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl futures::Sink<BytesSequence> for LoopbackStream {
+    type Error = std::io::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: BytesSequence) -> Result<(), Self::Error> {
+        self.send(Sent::BytesSequence(item))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl futures::Stream for LoopbackStream {
+    type Item = Result<StreamRecvData, std::io::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().from_radio.poll_recv(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{SinkExt, StreamExt, executor::block_on};
+
+    use super::*;
+    use crate::{
+        keyring::node_id::NodeId,
+        transport::mqtt_stream::{MqttStream, MqttStreamRecvData, MqttStreamSendData},
+    };
+
+    #[test]
+    fn mqtt_stream_over_loopback() {
+        let (stream, mut radio) = pair();
+        let mut stream = MqttStream::new(stream, NodeId::from(0x1234u32), "msh".into());
+
+        assert!(radio.send_packet(meshtastic::MeshPacket {
+            id: 42,
+            ..Default::default()
+        }));
+        let recv_data = block_on(stream.next());
+        assert!(matches!(
+            recv_data,
+            Some(Ok(MqttStreamRecvData::MeshPacket(
+                1,
+                meshtastic::MeshPacket { id: 42, .. }
+            )))
+        ));
+
+        block_on(stream.send(MqttStreamSendData::ToRadio(
+            meshtastic::to_radio::PayloadVariant::WantConfigId(7),
+        )))
+        .unwrap();
+        assert!(matches!(
+            radio.drain_to_radio()[..],
+            [meshtastic::to_radio::PayloadVariant::WantConfigId(7)]
+        ));
+
+        drop(radio);
+        assert!(block_on(stream.next()).is_none());
+    }
+}
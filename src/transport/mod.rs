@@ -1,11 +1,20 @@
+#[cfg(not(target_arch = "wasm32"))]
 use getifaddrs::{Interfaces, getifaddrs};
+#[cfg(not(target_arch = "wasm32"))]
 use std::net::IpAddr;
 
+#[cfg(feature = "ble")]
+pub mod ble;
+#[cfg(feature = "test-util")]
+pub mod loopback;
 pub mod mqtt;
 pub mod mqtt_stream;
 pub mod stream;
 pub mod udp;
+#[cfg(feature = "ws")]
+pub mod ws;
 
+#[cfg(not(target_arch = "wasm32"))]
 pub fn if_index_by_addr(if_address: &IpAddr) -> Result<u32, std::io::Error> {
     if if_address.is_unspecified() {
         return Ok(0);
@@ -1,4 +1,4 @@
-use crate::{keyring::node_id::NodeId, meshtastic};
+use crate::{keyring::node_id::NodeId, meshtastic, mqtt::decode_service_envelope};
 use prost::Message;
 use rumqttc::{AsyncClient, EventLoop, MqttOptions, QoS, SubscribeFilter};
 use std::{net::SocketAddr, time::Duration};
@@ -103,14 +103,23 @@ impl MqttReceiver {
                 )
             })?;
 
+            if let rumqttc::Event::Outgoing(rumqttc::Outgoing::Disconnect) = event {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::ConnectionAborted,
+                    "Disconnected",
+                ));
+            }
+
             if let rumqttc::Event::Incoming(rumqttc::Packet::Publish(publish)) = event {
-                let service_envelope = meshtastic::ServiceEnvelope::decode(publish.payload.clone())
-                    .map_err(|e| {
-                        std::io::Error::new(
-                            std::io::ErrorKind::InvalidData,
-                            format!("Decode error on {:?}: {:?}", publish, e),
-                        )
-                    })?;
+                let (service_envelope, packet) =
+                    decode_service_envelope(Some(&publish.topic), &publish.payload).map_err(
+                        |e| {
+                            std::io::Error::new(
+                                std::io::ErrorKind::InvalidData,
+                                format!("Decode error on {:?}: {}", publish, e),
+                            )
+                        },
+                    )?;
                 let gateway_id =
                     NodeId::try_from(service_envelope.gateway_id.as_str()).map_err(|e| {
                         std::io::Error::new(
@@ -122,7 +131,7 @@ impl MqttReceiver {
                         )
                     })?;
 
-                if let Some(packet) = service_envelope.packet {
+                if let Some(packet) = packet {
                     return Ok((packet, publish.topic, gateway_id));
                 } else {
                     return Err(std::io::Error::new(
@@ -159,6 +168,15 @@ impl MqttSender {
         }
         Ok(())
     }
+
+    // Queues DISCONNECT: it is sent while receiver's event loop is polled,
+    // then receiver's `next` fails with `ConnectionAborted`
+    pub async fn disconnect(&mut self) -> Result<(), std::io::Error> {
+        self.client
+            .disconnect()
+            .await
+            .map_err(std::io::Error::other)
+    }
 }
 
 impl Mqtt {
@@ -3,6 +3,8 @@ use std::{
     task::{Context, Poll},
 };
 
+#[cfg(feature = "json")]
+use crate::mqtt::{json, parse_topic};
 use crate::{
     keyring::node_id::NodeId,
     meshtastic::{self, to_radio},
@@ -59,6 +61,47 @@ impl MqttStream {
     }
 }
 
+// Text payload is a message from `/2/json/` topic
+#[cfg(feature = "json")]
+fn json_mesh_packet(
+    topic: &str,
+    text: &str,
+) -> Result<(meshtastic::MeshPacket, NodeId), std::io::Error> {
+    let invalid_data = |e: json::JsonError| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("MQTT proxy JSON message {:?}: {}", text, e),
+        )
+    };
+    let message = json::decode_json_message(text.as_bytes()).map_err(invalid_data)?;
+    let packet = message.to_mesh_packet().map_err(invalid_data)?;
+    let gateway = message
+        .sender_node()
+        .map_err(invalid_data)?
+        .or_else(|| parse_topic(topic).and_then(|v| v.gateway))
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("MQTT proxy JSON message has no gateway: {:?}", topic),
+            )
+        })?;
+    Ok((packet, gateway))
+}
+
+#[cfg(not(feature = "json"))]
+fn json_mesh_packet(
+    topic: &str,
+    _: &str,
+) -> Result<(meshtastic::MeshPacket, NodeId), std::io::Error> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!(
+            "MQTT proxy JSON message from {:?}: `json` feature is disabled",
+            topic
+        ),
+    ))
+}
+
 impl futures::Sink<MqttStreamSendData> for MqttStream {
     type Error = std::io::Error;
 
@@ -189,13 +232,16 @@ impl futures::Stream for MqttStream {
                                     }
                                     meshtastic::mqtt_client_proxy_message::PayloadVariant::Text(
                                         text,
-                                    ) => Err(std::io::Error::new(
-                                        std::io::ErrorKind::Other,
-                                        format!(
-                                            "MQTT proxy message has text payload (unsupported): {:?}",
-                                            text
-                                        ),
-                                    )),
+                                    ) => json_mesh_packet(&mqtt_proxy_msg.topic, text).map(
+                                        |(packet, gateway)| {
+                                            MqttStreamRecvData::MQTTMeshPacket(
+                                                packet_id,
+                                                packet,
+                                                mqtt_proxy_msg.topic.clone(),
+                                                gateway,
+                                            )
+                                        },
+                                    ),
                                 }
                                     } else {
                                         Err(std::io::Error::new(
@@ -0,0 +1,582 @@
+use std::{collections::VecDeque, fmt, time::Duration};
+
+use futures::{SinkExt, StreamExt};
+use prost::Message;
+
+use super::{Stream, StreamRecvData};
+use crate::{
+    keyring::node_id::NodeId,
+    meshtastic::{
+        self, admin_message, config, from_radio, mesh_packet, module_config, routing, to_radio,
+    },
+};
+
+#[derive(Debug)]
+pub enum AdminError {
+    Io(std::io::Error),
+    // Request is rejected by node or not delivered
+    Routing(routing::Error),
+    // No answer in `AdminSession::timeout`
+    Timeout,
+    Decode(prost::DecodeError),
+    // Answer does not match request
+    UnexpectedResponse(String),
+}
+
+impl fmt::Display for AdminError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AdminError::Io(e) => write!(f, "IO error: {}", e),
+            AdminError::Routing(e) => write!(f, "Routing error: {}", e.as_str_name()),
+            AdminError::Timeout => write!(f, "No response"),
+            AdminError::Decode(e) => write!(f, "Malformed response: {}", e),
+            AdminError::UnexpectedResponse(response) => {
+                write!(f, "Unexpected response: {}", response)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AdminError {}
+
+impl From<std::io::Error> for AdminError {
+    fn from(value: std::io::Error) -> Self {
+        AdminError::Io(value)
+    }
+}
+
+// Data of packet with answer to request `id`
+fn request_data(recv_data: &StreamRecvData, id: u32) -> Option<&meshtastic::Data> {
+    if let StreamRecvData::FromRadio(_, from_radio::PayloadVariant::Packet(mesh_packet)) = recv_data
+        && let Some(mesh_packet::PayloadVariant::Decoded(data)) = &mesh_packet.payload_variant
+        && data.request_id == id
+    {
+        Some(data)
+    } else {
+        None
+    }
+}
+
+fn unexpected(response: admin_message::PayloadVariant) -> AdminError {
+    AdminError::UnexpectedResponse(format!("{:?}", response))
+}
+
+// Unrelated data, received while waiting for answer, kept for `take_received`
+const RECEIVED_MAX: usize = 256;
+
+enum Answer {
+    Admin(Box<meshtastic::AdminMessage>),
+    // Routing's acknowledgement without error
+    Ack,
+}
+
+// Administration of node over radio connected to `Stream`.
+// Destination may be radio itself or remote node: remote node accepts
+// `set_*` requests only with session's passkey received in any response,
+// so passkey is requested before the first `set_*` and when it's expired.
+// Data received while waiting for answer, but not related to request,
+// is kept: only the last `RECEIVED_MAX` are kept until `take_received`
+pub struct AdminSession {
+    destination: NodeId,
+    session_passkey: Vec<u8>,
+    received: VecDeque<StreamRecvData>,
+    // Index of radio's channel to send requests
+    pub channel: u32,
+    pub hop_limit: u32,
+    pub timeout: Duration,
+}
+
+impl AdminSession {
+    pub fn new(destination: NodeId) -> Self {
+        Self {
+            destination,
+            session_passkey: Vec::new(),
+            received: VecDeque::new(),
+            channel: 0,
+            hop_limit: 3,
+            timeout: Duration::from_secs(30),
+        }
+    }
+
+    pub fn destination(&self) -> NodeId {
+        self.destination
+    }
+
+    pub fn session_passkey(&self) -> &[u8] {
+        &self.session_passkey
+    }
+
+    // Data received meanwhile requests, in order of receiving
+    pub fn take_received(&mut self) -> Vec<StreamRecvData> {
+        self.received.drain(..).collect()
+    }
+
+    fn keep_received(&mut self, recv_data: StreamRecvData) {
+        if self.received.len() == RECEIVED_MAX {
+            self.received.pop_front();
+        }
+        self.received.push_back(recv_data);
+    }
+
+    // Admin message with passkey of session
+    pub fn request(&self, payload: admin_message::PayloadVariant) -> meshtastic::AdminMessage {
+        meshtastic::AdminMessage {
+            session_passkey: self.session_passkey.clone(),
+            payload_variant: Some(payload),
+        }
+    }
+
+    async fn exchange(
+        &mut self,
+        stream: &mut Stream,
+        payload: admin_message::PayloadVariant,
+        want_response: bool,
+    ) -> Result<Answer, AdminError> {
+        // Zero is not valid packet's id
+        let id = rand::random::<u32>().max(1);
+        let data = meshtastic::Data {
+            portnum: meshtastic::PortNum::AdminApp as i32,
+            payload: self.request(payload).encode_to_vec(),
+            want_response,
+            ..Default::default()
+        };
+        let mesh_packet = meshtastic::MeshPacket {
+            to: self.destination.into(),
+            id,
+            channel: self.channel,
+            hop_limit: self.hop_limit,
+            want_ack: true,
+            priority: mesh_packet::Priority::Reliable as i32,
+            payload_variant: Some(mesh_packet::PayloadVariant::Decoded(data)),
+            ..Default::default()
+        };
+
+        stream
+            .send(to_radio::PayloadVariant::Packet(mesh_packet))
+            .await?;
+        tokio::time::timeout(self.timeout, self.wait_answer(stream, id, want_response))
+            .await
+            .map_err(|_| AdminError::Timeout)?
+    }
+
+    async fn wait_answer(
+        &mut self,
+        stream: &mut Stream,
+        id: u32,
+        want_response: bool,
+    ) -> Result<Answer, AdminError> {
+        while let Some(recv_data) = stream.next().await {
+            let recv_data = recv_data?;
+            let Some(data) = request_data(&recv_data, id) else {
+                self.keep_received(recv_data);
+                continue;
+            };
+
+            match data.portnum() {
+                meshtastic::PortNum::AdminApp => {
+                    let admin = meshtastic::AdminMessage::decode(data.payload.as_slice())
+                        .map_err(AdminError::Decode)?;
+                    if !admin.session_passkey.is_empty() {
+                        self.session_passkey = admin.session_passkey.clone();
+                    }
+                    return Ok(Answer::Admin(Box::new(admin)));
+                }
+                meshtastic::PortNum::RoutingApp => {
+                    let routing = meshtastic::Routing::decode(data.payload.as_slice())
+                        .map_err(AdminError::Decode)?;
+                    let Some(routing::Variant::ErrorReason(reason)) = routing.variant else {
+                        continue;
+                    };
+                    match routing::Error::try_from(reason) {
+                        // Response follows acknowledgement
+                        Ok(routing::Error::None) if want_response => continue,
+                        Ok(routing::Error::None) => return Ok(Answer::Ack),
+                        Ok(error) => return Err(AdminError::Routing(error)),
+                        Err(_) => {
+                            return Err(AdminError::UnexpectedResponse(format!(
+                                "Unknown routing error {}",
+                                reason
+                            )));
+                        }
+                    }
+                }
+                _ => continue,
+            }
+        }
+
+        Err(AdminError::Io(std::io::Error::new(
+            std::io::ErrorKind::UnexpectedEof,
+            "Stream closed before response",
+        )))
+    }
+
+    // Request with response, like `GetConfigRequest`
+    pub async fn get(
+        &mut self,
+        stream: &mut Stream,
+        payload: admin_message::PayloadVariant,
+    ) -> Result<admin_message::PayloadVariant, AdminError> {
+        match self.exchange(stream, payload, true).await? {
+            Answer::Admin(admin) => admin
+                .payload_variant
+                .ok_or(AdminError::UnexpectedResponse("Empty response".into())),
+            Answer::Ack => Err(AdminError::UnexpectedResponse(
+                "Acknowledgement without response".into(),
+            )),
+        }
+    }
+
+    // Request without response, like `SetConfig`: returns when node acknowledges it
+    pub async fn set(
+        &mut self,
+        stream: &mut Stream,
+        payload: admin_message::PayloadVariant,
+    ) -> Result<(), AdminError> {
+        if self.session_passkey.is_empty() {
+            self.metadata(stream).await?;
+        }
+        match self.exchange(stream, payload.clone(), false).await {
+            // Passkey is valid for limited time
+            Err(AdminError::Routing(routing::Error::AdminBadSessionKey)) => {
+                self.metadata(stream).await?;
+                self.exchange(stream, payload, false).await.map(|_| ())
+            }
+            result => result.map(|_| ()),
+        }
+    }
+
+    pub async fn metadata(
+        &mut self,
+        stream: &mut Stream,
+    ) -> Result<meshtastic::DeviceMetadata, AdminError> {
+        match self
+            .get(
+                stream,
+                admin_message::PayloadVariant::GetDeviceMetadataRequest(true),
+            )
+            .await?
+        {
+            admin_message::PayloadVariant::GetDeviceMetadataResponse(metadata) => Ok(metadata),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    pub async fn owner(&mut self, stream: &mut Stream) -> Result<meshtastic::User, AdminError> {
+        match self
+            .get(stream, admin_message::PayloadVariant::GetOwnerRequest(true))
+            .await?
+        {
+            admin_message::PayloadVariant::GetOwnerResponse(user) => Ok(user),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    pub async fn channel(
+        &mut self,
+        stream: &mut Stream,
+        index: u32,
+    ) -> Result<meshtastic::Channel, AdminError> {
+        // Index is sent increased by one: zero is protobuf's default
+        match self
+            .get(
+                stream,
+                admin_message::PayloadVariant::GetChannelRequest(index + 1),
+            )
+            .await?
+        {
+            admin_message::PayloadVariant::GetChannelResponse(channel) => Ok(channel),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    pub async fn config(
+        &mut self,
+        stream: &mut Stream,
+        config_type: admin_message::ConfigType,
+    ) -> Result<config::PayloadVariant, AdminError> {
+        match self
+            .get(
+                stream,
+                admin_message::PayloadVariant::GetConfigRequest(config_type as i32),
+            )
+            .await?
+        {
+            admin_message::PayloadVariant::GetConfigResponse(meshtastic::Config {
+                payload_variant: Some(config),
+            }) => Ok(config),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    pub async fn device_config(
+        &mut self,
+        stream: &mut Stream,
+    ) -> Result<config::DeviceConfig, AdminError> {
+        match self
+            .config(stream, admin_message::ConfigType::DeviceConfig)
+            .await?
+        {
+            config::PayloadVariant::Device(device) => Ok(device),
+            other => Err(AdminError::UnexpectedResponse(format!("{:?}", other))),
+        }
+    }
+
+    pub async fn position_config(
+        &mut self,
+        stream: &mut Stream,
+    ) -> Result<config::PositionConfig, AdminError> {
+        match self
+            .config(stream, admin_message::ConfigType::PositionConfig)
+            .await?
+        {
+            config::PayloadVariant::Position(position) => Ok(position),
+            other => Err(AdminError::UnexpectedResponse(format!("{:?}", other))),
+        }
+    }
+
+    pub async fn lora_config(
+        &mut self,
+        stream: &mut Stream,
+    ) -> Result<config::LoRaConfig, AdminError> {
+        match self
+            .config(stream, admin_message::ConfigType::LoraConfig)
+            .await?
+        {
+            config::PayloadVariant::Lora(lora) => Ok(lora),
+            other => Err(AdminError::UnexpectedResponse(format!("{:?}", other))),
+        }
+    }
+
+    pub async fn security_config(
+        &mut self,
+        stream: &mut Stream,
+    ) -> Result<config::SecurityConfig, AdminError> {
+        match self
+            .config(stream, admin_message::ConfigType::SecurityConfig)
+            .await?
+        {
+            config::PayloadVariant::Security(security) => Ok(security),
+            other => Err(AdminError::UnexpectedResponse(format!("{:?}", other))),
+        }
+    }
+
+    pub async fn module_config(
+        &mut self,
+        stream: &mut Stream,
+        config_type: admin_message::ModuleConfigType,
+    ) -> Result<module_config::PayloadVariant, AdminError> {
+        match self
+            .get(
+                stream,
+                admin_message::PayloadVariant::GetModuleConfigRequest(config_type as i32),
+            )
+            .await?
+        {
+            admin_message::PayloadVariant::GetModuleConfigResponse(meshtastic::ModuleConfig {
+                payload_variant: Some(module_config),
+            }) => Ok(module_config),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    pub async fn set_config(
+        &mut self,
+        stream: &mut Stream,
+        config: config::PayloadVariant,
+    ) -> Result<(), AdminError> {
+        self.set(
+            stream,
+            admin_message::PayloadVariant::SetConfig(meshtastic::Config {
+                payload_variant: Some(config),
+            }),
+        )
+        .await
+    }
+
+    pub async fn set_channel(
+        &mut self,
+        stream: &mut Stream,
+        channel: meshtastic::Channel,
+    ) -> Result<(), AdminError> {
+        self.set(stream, admin_message::PayloadVariant::SetChannel(channel))
+            .await
+    }
+
+    pub async fn set_owner(
+        &mut self,
+        stream: &mut Stream,
+        user: meshtastic::User,
+    ) -> Result<(), AdminError> {
+        self.set(stream, admin_message::PayloadVariant::SetOwner(user))
+            .await
+    }
+
+    pub async fn reboot(&mut self, stream: &mut Stream, seconds: i32) -> Result<(), AdminError> {
+        self.set(
+            stream,
+            admin_message::PayloadVariant::RebootSeconds(seconds),
+        )
+        .await
+    }
+}
+
+#[cfg(all(test, feature = "test-util"))]
+mod tests {
+    use super::*;
+    use crate::transport::loopback::{self, Radio, Sent};
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    // Id and admin message of next request sent to radio
+    async fn next_request(radio: &mut Radio) -> (u32, meshtastic::AdminMessage) {
+        let Some(Sent::ToRadio(to_radio::PayloadVariant::Packet(mesh_packet))) = radio.recv().await
+        else {
+            panic!("MeshPacket is expected");
+        };
+        let Some(mesh_packet::PayloadVariant::Decoded(data)) = mesh_packet.payload_variant else {
+            panic!("Decoded payload is expected");
+        };
+        let admin = meshtastic::AdminMessage::decode(data.payload.as_slice()).unwrap();
+        (mesh_packet.id, admin)
+    }
+
+    fn reply(radio: &mut Radio, request_id: u32, portnum: meshtastic::PortNum, payload: Vec<u8>) {
+        radio.send_packet(meshtastic::MeshPacket {
+            from: 0x1234abcd,
+            payload_variant: Some(mesh_packet::PayloadVariant::Decoded(meshtastic::Data {
+                portnum: portnum as i32,
+                payload,
+                request_id,
+                ..Default::default()
+            })),
+            ..Default::default()
+        });
+    }
+
+    fn reply_routing(radio: &mut Radio, request_id: u32, error: routing::Error) {
+        let routing = meshtastic::Routing {
+            variant: Some(routing::Variant::ErrorReason(error as i32)),
+        };
+        reply(
+            radio,
+            request_id,
+            meshtastic::PortNum::RoutingApp,
+            routing.encode_to_vec(),
+        );
+    }
+
+    fn reply_metadata(radio: &mut Radio, request_id: u32, session_passkey: &[u8]) {
+        let admin = meshtastic::AdminMessage {
+            session_passkey: session_passkey.to_vec(),
+            payload_variant: Some(admin_message::PayloadVariant::GetDeviceMetadataResponse(
+                meshtastic::DeviceMetadata {
+                    firmware_version: "2.6.11".into(),
+                    ..Default::default()
+                },
+            )),
+        };
+        reply(
+            radio,
+            request_id,
+            meshtastic::PortNum::AdminApp,
+            admin.encode_to_vec(),
+        );
+    }
+
+    #[test]
+    fn get_correlates_request_id() {
+        let (mut stream, mut radio) = loopback::pair();
+        let mut session = AdminSession::new(0x1234abcd.into());
+
+        let (metadata, _) = block_on(async {
+            futures::join!(session.metadata(&mut stream), async {
+                let (id, admin) = next_request(&mut radio).await;
+                assert!(matches!(
+                    admin.payload_variant,
+                    Some(admin_message::PayloadVariant::GetDeviceMetadataRequest(
+                        true
+                    ))
+                ));
+                // Unrelated to request: kept by session
+                reply(
+                    &mut radio,
+                    0,
+                    meshtastic::PortNum::TextMessageApp,
+                    b"hi".into(),
+                );
+                reply_metadata(&mut radio, id.wrapping_add(1), b"stale");
+                // Acknowledgement before response is skipped
+                reply_routing(&mut radio, id, routing::Error::None);
+                reply_metadata(&mut radio, id, b"passkey");
+            })
+        });
+
+        assert_eq!(metadata.unwrap().firmware_version, "2.6.11");
+        assert_eq!(session.session_passkey(), b"passkey");
+        assert_eq!(session.take_received().len(), 2);
+        assert!(session.take_received().is_empty());
+    }
+
+    #[test]
+    fn set_refreshes_expired_passkey() {
+        let (mut stream, mut radio) = loopback::pair();
+        let mut session = AdminSession::new(0x1234abcd.into());
+
+        let (result, _) = block_on(async {
+            futures::join!(session.reboot(&mut stream, 5), async {
+                // Passkey is requested before the first `set_*`
+                let (id, admin) = next_request(&mut radio).await;
+                assert!(admin.session_passkey.is_empty());
+                reply_metadata(&mut radio, id, b"first");
+
+                let (id, admin) = next_request(&mut radio).await;
+                assert_eq!(admin.session_passkey, b"first");
+                reply_routing(&mut radio, id, routing::Error::AdminBadSessionKey);
+
+                let (id, admin) = next_request(&mut radio).await;
+                assert!(matches!(
+                    admin.payload_variant,
+                    Some(admin_message::PayloadVariant::GetDeviceMetadataRequest(
+                        true
+                    ))
+                ));
+                reply_metadata(&mut radio, id, b"second");
+
+                let (id, admin) = next_request(&mut radio).await;
+                assert_eq!(admin.session_passkey, b"second");
+                assert!(matches!(
+                    admin.payload_variant,
+                    Some(admin_message::PayloadVariant::RebootSeconds(5))
+                ));
+                reply_routing(&mut radio, id, routing::Error::None);
+            })
+        });
+
+        assert!(result.is_ok());
+        assert_eq!(session.session_passkey(), b"second");
+    }
+
+    #[test]
+    fn rejected_request_is_error() {
+        let (mut stream, mut radio) = loopback::pair();
+        let mut session = AdminSession::new(0x1234abcd.into());
+
+        let (result, _) = block_on(async {
+            futures::join!(session.owner(&mut stream), async {
+                let (id, _) = next_request(&mut radio).await;
+                reply_routing(&mut radio, id, routing::Error::NoChannel);
+            })
+        });
+
+        assert!(matches!(
+            result,
+            Err(AdminError::Routing(routing::Error::NoChannel))
+        ));
+    }
+}
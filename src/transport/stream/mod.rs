@@ -1,38 +1,157 @@
-use std::{pin::Pin, task::Context};
+#[cfg(feature = "serial")]
+use std::{future::Future, time::Duration};
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
 
-use futures::TryStreamExt;
+use futures::{SinkExt, StreamExt, TryStreamExt};
 use tokio::net::TcpStream;
+#[cfg(feature = "serial")]
+use tokio::time::{Instant, Sleep};
+#[cfg(feature = "serial")]
 use tokio_serial::SerialStream;
 use tokio_util::codec::Framed;
 
 use crate::meshtastic;
 pub use codec::BytesSequence;
 pub use codec::StreamRecvData;
+pub use radio_config::RadioConfig;
+pub mod admin;
 pub mod codec;
+pub mod radio_config;
+#[cfg(feature = "serial")]
 pub mod serial;
 pub mod tcp;
 
+// Serial, BLE and WebSocket are available with `serial`, `ble` and `ws`
+// features only
 pub enum Stream {
-    Serial(Framed<SerialStream, codec::MeshtasticStreamCodec>),
+    #[cfg(feature = "serial")]
+    Serial(
+        Framed<SerialStream, codec::MeshtasticStreamCodec>,
+        Option<ReadTimeout>,
+    ),
     Tcp(Framed<TcpStream, codec::MeshtasticStreamCodec>),
+    #[cfg(feature = "ble")]
+    Ble(crate::transport::ble::BleStream),
+    #[cfg(feature = "ws")]
+    Ws(crate::transport::ws::WsStream),
+    // In-memory stream of tests, see `transport::loopback`
+    #[cfg(feature = "test-util")]
+    Loopback(crate::transport::loopback::LoopbackStream),
+}
+
+// Time without received data after which `ErrorKind::TimedOut` is returned
+#[cfg(feature = "serial")]
+pub struct ReadTimeout {
+    duration: Duration,
+    sleep: Pin<Box<Sleep>>,
+}
+
+#[cfg(feature = "serial")]
+impl ReadTimeout {
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            duration,
+            sleep: Box::pin(tokio::time::sleep(duration)),
+        }
+    }
+
+    fn reset(&mut self) {
+        self.sleep.as_mut().reset(Instant::now() + self.duration);
+    }
+
+    fn poll_expired(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<codec::StreamRecvData, std::io::Error>>> {
+        match self.sleep.as_mut().poll(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(()) => {
+                // Timer restarts: next timeout is reported after next `duration`
+                self.reset();
+                Poll::Ready(Some(Err(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    format!("No data received for {:?}", self.duration),
+                ))))
+            }
+        }
+    }
 }
 
 pub type PacketId = u32;
 
+impl Stream {
+    // Sends `WantConfigId` and collects configuration's dump until radio
+    // completes it with the same id. Packets received meanwhile are dropped
+    pub async fn configure(&mut self) -> Result<RadioConfig, std::io::Error> {
+        let config_id = rand::random::<u32>();
+        self.send(meshtastic::to_radio::PayloadVariant::WantConfigId(
+            config_id,
+        ))
+        .await?;
+
+        let mut radio_config = RadioConfig::default();
+        while let Some(recv_data) = self.next().await {
+            match recv_data? {
+                StreamRecvData::FromRadio(
+                    _,
+                    meshtastic::from_radio::PayloadVariant::ConfigCompleteId(complete_id),
+                ) if complete_id == config_id => return Ok(radio_config),
+                StreamRecvData::FromRadio(_, payload) => radio_config.apply(&payload),
+                StreamRecvData::Unstructured(_) => {}
+            }
+        }
+
+        Err(std::io::Error::new(
+            std::io::ErrorKind::UnexpectedEof,
+            "Stream closed before configuration is completed",
+        ))
+    }
+
+    // Frame of the last `FromRadio` returned by `next`, as received.
+    // WebSocket carries `FromRadio` without StreamAPI's framing: header is put before it.
+    // BLE's frames are not kept
+    pub fn last_frame(&self) -> Option<&[u8]> {
+        match self {
+            #[cfg(feature = "serial")]
+            Stream::Serial(s, _) => s.codec().last_frame().map(|frame| frame.as_ref()),
+            Stream::Tcp(t) => t.codec().last_frame().map(|frame| frame.as_ref()),
+            #[cfg(feature = "ble")]
+            Stream::Ble(_) => None,
+            #[cfg(feature = "ws")]
+            Stream::Ws(w) => w.last_frame().map(|frame| frame.as_ref()),
+            #[cfg(feature = "test-util")]
+            Stream::Loopback(_) => None,
+        }
+    }
+}
+
 impl futures::Sink<meshtastic::to_radio::PayloadVariant> for Stream {
     type Error = std::io::Error;
 
-    fn poll_ready(
-        self: Pin<&mut Self>,
-        cx: &mut Context<'_>,
-    ) -> std::task::Poll<Result<(), Self::Error>> {
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         match self.get_mut() {
-            Stream::Serial(s) => {
+            #[cfg(feature = "serial")]
+            Stream::Serial(s, _) => {
                 futures::Sink::<meshtastic::to_radio::PayloadVariant>::poll_ready(Pin::new(s), cx)
             }
             Stream::Tcp(t) => {
                 futures::Sink::<meshtastic::to_radio::PayloadVariant>::poll_ready(Pin::new(t), cx)
             }
+            #[cfg(feature = "ble")]
+            Stream::Ble(b) => {
+                futures::Sink::<meshtastic::to_radio::PayloadVariant>::poll_ready(Pin::new(b), cx)
+            }
+            #[cfg(feature = "ws")]
+            Stream::Ws(w) => {
+                futures::Sink::<meshtastic::to_radio::PayloadVariant>::poll_ready(Pin::new(w), cx)
+            }
+            #[cfg(feature = "test-util")]
+            Stream::Loopback(l) => {
+                futures::Sink::<meshtastic::to_radio::PayloadVariant>::poll_ready(Pin::new(l), cx)
+            }
         }
     }
 
@@ -41,36 +160,63 @@ impl futures::Sink<meshtastic::to_radio::PayloadVariant> for Stream {
         item: meshtastic::to_radio::PayloadVariant,
     ) -> Result<(), Self::Error> {
         match self.get_mut() {
-            Stream::Serial(s) => futures::Sink::start_send(Pin::new(s), item),
+            #[cfg(feature = "serial")]
+            Stream::Serial(s, _) => futures::Sink::start_send(Pin::new(s), item),
             Stream::Tcp(t) => futures::Sink::start_send(Pin::new(t), item),
+            #[cfg(feature = "ble")]
+            Stream::Ble(b) => futures::Sink::start_send(Pin::new(b), item),
+            #[cfg(feature = "ws")]
+            Stream::Ws(w) => futures::Sink::start_send(Pin::new(w), item),
+            #[cfg(feature = "test-util")]
+            Stream::Loopback(l) => futures::Sink::start_send(Pin::new(l), item),
         }
     }
 
-    fn poll_flush(
-        self: Pin<&mut Self>,
-        cx: &mut Context<'_>,
-    ) -> std::task::Poll<Result<(), Self::Error>> {
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         match self.get_mut() {
-            Stream::Serial(s) => {
+            #[cfg(feature = "serial")]
+            Stream::Serial(s, _) => {
                 futures::Sink::<meshtastic::to_radio::PayloadVariant>::poll_flush(Pin::new(s), cx)
             }
             Stream::Tcp(t) => {
                 futures::Sink::<meshtastic::to_radio::PayloadVariant>::poll_flush(Pin::new(t), cx)
             }
+            #[cfg(feature = "ble")]
+            Stream::Ble(b) => {
+                futures::Sink::<meshtastic::to_radio::PayloadVariant>::poll_flush(Pin::new(b), cx)
+            }
+            #[cfg(feature = "ws")]
+            Stream::Ws(w) => {
+                futures::Sink::<meshtastic::to_radio::PayloadVariant>::poll_flush(Pin::new(w), cx)
+            }
+            #[cfg(feature = "test-util")]
+            Stream::Loopback(l) => {
+                futures::Sink::<meshtastic::to_radio::PayloadVariant>::poll_flush(Pin::new(l), cx)
+            }
         }
     }
 
-    fn poll_close(
-        self: Pin<&mut Self>,
-        cx: &mut Context<'_>,
-    ) -> std::task::Poll<Result<(), Self::Error>> {
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         match self.get_mut() {
-            Stream::Serial(s) => {
+            #[cfg(feature = "serial")]
+            Stream::Serial(s, _) => {
                 futures::Sink::<meshtastic::to_radio::PayloadVariant>::poll_close(Pin::new(s), cx)
             }
             Stream::Tcp(t) => {
                 futures::Sink::<meshtastic::to_radio::PayloadVariant>::poll_close(Pin::new(t), cx)
             }
+            #[cfg(feature = "ble")]
+            Stream::Ble(b) => {
+                futures::Sink::<meshtastic::to_radio::PayloadVariant>::poll_close(Pin::new(b), cx)
+            }
+            #[cfg(feature = "ws")]
+            Stream::Ws(w) => {
+                futures::Sink::<meshtastic::to_radio::PayloadVariant>::poll_close(Pin::new(w), cx)
+            }
+            #[cfg(feature = "test-util")]
+            Stream::Loopback(l) => {
+                futures::Sink::<meshtastic::to_radio::PayloadVariant>::poll_close(Pin::new(l), cx)
+            }
         }
     }
 }
@@ -78,40 +224,71 @@ impl futures::Sink<meshtastic::to_radio::PayloadVariant> for Stream {
 impl futures::Sink<codec::BytesSequence> for Stream {
     type Error = std::io::Error;
 
-    fn poll_ready(
-        self: Pin<&mut Self>,
-        cx: &mut Context<'_>,
-    ) -> std::task::Poll<Result<(), Self::Error>> {
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         match self.get_mut() {
-            Stream::Serial(s) => futures::Sink::<codec::BytesSequence>::poll_ready(Pin::new(s), cx),
+            #[cfg(feature = "serial")]
+            Stream::Serial(s, _) => {
+                futures::Sink::<codec::BytesSequence>::poll_ready(Pin::new(s), cx)
+            }
             Stream::Tcp(t) => futures::Sink::<codec::BytesSequence>::poll_ready(Pin::new(t), cx),
+            #[cfg(feature = "ble")]
+            Stream::Ble(b) => futures::Sink::<codec::BytesSequence>::poll_ready(Pin::new(b), cx),
+            #[cfg(feature = "ws")]
+            Stream::Ws(w) => futures::Sink::<codec::BytesSequence>::poll_ready(Pin::new(w), cx),
+            #[cfg(feature = "test-util")]
+            Stream::Loopback(l) => {
+                futures::Sink::<codec::BytesSequence>::poll_ready(Pin::new(l), cx)
+            }
         }
     }
 
     fn start_send(self: Pin<&mut Self>, item: codec::BytesSequence) -> Result<(), Self::Error> {
         match self.get_mut() {
-            Stream::Serial(s) => futures::Sink::start_send(Pin::new(s), item),
+            #[cfg(feature = "serial")]
+            Stream::Serial(s, _) => futures::Sink::start_send(Pin::new(s), item),
             Stream::Tcp(t) => futures::Sink::start_send(Pin::new(t), item),
+            #[cfg(feature = "ble")]
+            Stream::Ble(b) => futures::Sink::start_send(Pin::new(b), item),
+            #[cfg(feature = "ws")]
+            Stream::Ws(w) => futures::Sink::start_send(Pin::new(w), item),
+            #[cfg(feature = "test-util")]
+            Stream::Loopback(l) => futures::Sink::start_send(Pin::new(l), item),
         }
     }
 
-    fn poll_flush(
-        self: Pin<&mut Self>,
-        cx: &mut Context<'_>,
-    ) -> std::task::Poll<Result<(), Self::Error>> {
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         match self.get_mut() {
-            Stream::Serial(s) => futures::Sink::<codec::BytesSequence>::poll_flush(Pin::new(s), cx),
+            #[cfg(feature = "serial")]
+            Stream::Serial(s, _) => {
+                futures::Sink::<codec::BytesSequence>::poll_flush(Pin::new(s), cx)
+            }
             Stream::Tcp(t) => futures::Sink::<codec::BytesSequence>::poll_flush(Pin::new(t), cx),
+            #[cfg(feature = "ble")]
+            Stream::Ble(b) => futures::Sink::<codec::BytesSequence>::poll_flush(Pin::new(b), cx),
+            #[cfg(feature = "ws")]
+            Stream::Ws(w) => futures::Sink::<codec::BytesSequence>::poll_flush(Pin::new(w), cx),
+            #[cfg(feature = "test-util")]
+            Stream::Loopback(l) => {
+                futures::Sink::<codec::BytesSequence>::poll_flush(Pin::new(l), cx)
+            }
         }
     }
 
-    fn poll_close(
-        self: Pin<&mut Self>,
-        cx: &mut Context<'_>,
-    ) -> std::task::Poll<Result<(), Self::Error>> {
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         match self.get_mut() {
-            Stream::Serial(s) => futures::Sink::<codec::BytesSequence>::poll_close(Pin::new(s), cx),
+            #[cfg(feature = "serial")]
+            Stream::Serial(s, _) => {
+                futures::Sink::<codec::BytesSequence>::poll_close(Pin::new(s), cx)
+            }
             Stream::Tcp(t) => futures::Sink::<codec::BytesSequence>::poll_close(Pin::new(t), cx),
+            #[cfg(feature = "ble")]
+            Stream::Ble(b) => futures::Sink::<codec::BytesSequence>::poll_close(Pin::new(b), cx),
+            #[cfg(feature = "ws")]
+            Stream::Ws(w) => futures::Sink::<codec::BytesSequence>::poll_close(Pin::new(w), cx),
+            #[cfg(feature = "test-util")]
+            Stream::Loopback(l) => {
+                futures::Sink::<codec::BytesSequence>::poll_close(Pin::new(l), cx)
+            }
         }
     }
 }
@@ -119,13 +296,28 @@ impl futures::Sink<codec::BytesSequence> for Stream {
 impl futures::Stream for Stream {
     type Item = Result<codec::StreamRecvData, std::io::Error>;
 
-    fn poll_next(
-        self: Pin<&mut Self>,
-        cx: &mut Context<'_>,
-    ) -> std::task::Poll<Option<Self::Item>> {
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         match self.get_mut() {
-            Stream::Serial(s) => s.try_poll_next_unpin(cx),
+            #[cfg(feature = "serial")]
+            Stream::Serial(s, read_timeout) => match s.try_poll_next_unpin(cx) {
+                Poll::Pending => match read_timeout {
+                    Some(read_timeout) => read_timeout.poll_expired(cx),
+                    None => Poll::Pending,
+                },
+                ready => {
+                    if let Some(read_timeout) = read_timeout {
+                        read_timeout.reset();
+                    }
+                    ready
+                }
+            },
             Stream::Tcp(t) => t.try_poll_next_unpin(cx),
+            #[cfg(feature = "ble")]
+            Stream::Ble(b) => b.try_poll_next_unpin(cx),
+            #[cfg(feature = "ws")]
+            Stream::Ws(w) => w.try_poll_next_unpin(cx),
+            #[cfg(feature = "test-util")]
+            Stream::Loopback(l) => l.try_poll_next_unpin(cx),
         }
     }
 }
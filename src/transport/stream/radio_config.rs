@@ -0,0 +1,172 @@
+use crate::{
+    keyring::{channel::preset_channel_name, node_id::NodeId},
+    meshtastic::{self, channel, config, from_radio},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RadioChannel {
+    pub index: i32,
+    pub role: channel::Role,
+    // Empty for channel named after modem's preset
+    pub name: String,
+    // As sent by radio: empty (no encryption), 1 byte (default key's index),
+    // 16 or 32 bytes of key
+    pub psk: Vec<u8>,
+}
+
+// Configuration's dump received after `WantConfigId`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RadioConfig {
+    pub node_id: Option<NodeId>,
+    // Owner of radio, from node's own `NodeInfo`
+    pub user: Option<meshtastic::User>,
+    // Enabled channels only
+    pub channels: Vec<RadioChannel>,
+    pub lora: Option<config::LoRaConfig>,
+    pub public_key: Option<Vec<u8>>,
+    pub metadata: Option<meshtastic::DeviceMetadata>,
+    // Radio's node database, including radio itself
+    pub nodes: Vec<meshtastic::NodeInfo>,
+}
+
+impl RadioConfig {
+    pub fn region(&self) -> Option<config::lo_ra_config::RegionCode> {
+        self.lora.as_ref().map(|lora| lora.region())
+    }
+
+    pub fn modem_preset(&self) -> Option<config::lo_ra_config::ModemPreset> {
+        self.lora.as_ref().map(|lora| lora.modem_preset())
+    }
+
+    // Channel without name is named after modem's preset, as firmware does
+    pub fn channel_name(&self, channel: &RadioChannel) -> String {
+        if !channel.name.is_empty() {
+            return channel.name.clone();
+        }
+        preset_channel_name(self.lora.as_ref()).into()
+    }
+
+    // Collects part of configuration's dump, other payloads are ignored
+    pub fn apply(&mut self, payload: &from_radio::PayloadVariant) {
+        match payload {
+            from_radio::PayloadVariant::MyInfo(my_info) => {
+                self.node_id = Some(my_info.my_node_num.into());
+                self.user = self.own_user();
+            }
+            from_radio::PayloadVariant::NodeInfo(node_info) => {
+                self.nodes.retain(|node| node.num != node_info.num);
+                self.nodes.push(node_info.clone());
+                self.user = self.own_user();
+            }
+            from_radio::PayloadVariant::Config(config) => match &config.payload_variant {
+                Some(config::PayloadVariant::Lora(lora)) => self.lora = Some(lora.clone()),
+                Some(config::PayloadVariant::Security(security)) => {
+                    self.public_key = Some(security.public_key.clone())
+                        .filter(|public_key| !public_key.is_empty());
+                }
+                _ => {}
+            },
+            from_radio::PayloadVariant::Channel(radio_channel) => {
+                self.channels
+                    .retain(|channel| channel.index != radio_channel.index);
+                if radio_channel.role() == channel::Role::Disabled {
+                    return;
+                }
+                let settings = radio_channel.settings.clone().unwrap_or_default();
+                self.channels.push(RadioChannel {
+                    index: radio_channel.index,
+                    role: radio_channel.role(),
+                    name: settings.name,
+                    psk: settings.psk,
+                });
+                self.channels.sort_by_key(|channel| channel.index);
+            }
+            from_radio::PayloadVariant::Metadata(metadata) => {
+                self.metadata = Some(metadata.clone())
+            }
+            _ => {}
+        }
+    }
+
+    fn own_user(&self) -> Option<meshtastic::User> {
+        let node_id = self.node_id?;
+        self.nodes
+            .iter()
+            .find(|node| NodeId::from(node.num) == node_id)
+            .and_then(|node| node.user.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channel(index: i32, role: channel::Role, name: &str) -> from_radio::PayloadVariant {
+        from_radio::PayloadVariant::Channel(meshtastic::Channel {
+            index,
+            settings: Some(meshtastic::ChannelSettings {
+                name: name.into(),
+                psk: vec![1],
+                ..Default::default()
+            }),
+            role: role.into(),
+        })
+    }
+
+    #[test]
+    fn collect_config_dump() {
+        let mut radio_config = RadioConfig::default();
+        radio_config.apply(&from_radio::PayloadVariant::NodeInfo(
+            meshtastic::NodeInfo {
+                num: 0x1234,
+                user: Some(meshtastic::User {
+                    long_name: "Radio".into(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        ));
+        radio_config.apply(&from_radio::PayloadVariant::MyInfo(
+            meshtastic::MyNodeInfo {
+                my_node_num: 0x1234,
+                ..Default::default()
+            },
+        ));
+        radio_config.apply(&channel(1, channel::Role::Secondary, "Second"));
+        radio_config.apply(&channel(0, channel::Role::Primary, ""));
+        radio_config.apply(&channel(2, channel::Role::Disabled, ""));
+
+        assert_eq!(radio_config.node_id, Some(NodeId::from(0x1234)));
+        assert_eq!(radio_config.user.unwrap().long_name, "Radio");
+        assert_eq!(
+            radio_config
+                .channels
+                .iter()
+                .map(|channel| (channel.index, channel.name.as_str()))
+                .collect::<Vec<_>>(),
+            vec![(0, ""), (1, "Second")]
+        );
+    }
+
+    #[test]
+    fn unnamed_channel_is_named_after_preset() {
+        let mut radio_config = RadioConfig::default();
+        radio_config.apply(&channel(0, channel::Role::Primary, ""));
+        let unnamed = radio_config.channels[0].clone();
+        assert_eq!(radio_config.channel_name(&unnamed), "LongFast");
+
+        for (modem_preset, name) in [
+            (config::lo_ra_config::ModemPreset::LongModerate, "LongMod"),
+            (config::lo_ra_config::ModemPreset::VeryLongSlow, "VLongSlow"),
+        ] {
+            radio_config.apply(&from_radio::PayloadVariant::Config(meshtastic::Config {
+                payload_variant: Some(config::PayloadVariant::Lora(config::LoRaConfig {
+                    use_preset: true,
+                    modem_preset: modem_preset.into(),
+                    ..Default::default()
+                })),
+            }));
+            assert_eq!(radio_config.channel_name(&unnamed), name);
+        }
+    }
+}
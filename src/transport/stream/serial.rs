@@ -1,29 +1,260 @@
-use tokio_serial::{SerialPort, SerialPortBuilderExt};
+use std::{fmt, time::Duration};
+
+use tokio_serial::{SerialPort, SerialPortBuilderExt, SerialPortType};
 use tokio_util::codec::Decoder;
 
-use super::{Stream, codec::MeshtasticStreamCodec};
+use super::{ReadTimeout, Stream, codec::MeshtasticStreamCodec};
+pub use tokio_serial::{DataBits, FlowControl, Parity, StopBits};
+
+// Value of `tty` in configs to detect port with `SerialBuilder::autodetect_meshtastic`
+pub const AUTODETECT_TTY: &str = "auto";
+
+// USB vendor and product ids of chips used by Meshtastic's boards.
+// Product id `None` matches any product of vendor
+const MESHTASTIC_USB_IDS: &[(u16, Option<u16>, &str)] = &[
+    (0x239a, None, "Adafruit nRF52 (RAK4631, T-Echo)"),
+    (0x303a, None, "Espressif native USB (ESP32-S3, ESP32-C3)"),
+    (0x2e8a, None, "Raspberry Pi RP2040"),
+    (0x10c4, Some(0xea60), "Silicon Labs CP210x"),
+    (0x1a86, Some(0x55d4), "WCH CH9102"),
+    (0x1a86, Some(0x7523), "WCH CH340"),
+];
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PortInfo {
+    pub tty: String,
+    // USB ids are known for USB ports only
+    pub vid: Option<u16>,
+    pub pid: Option<u16>,
+    pub manufacturer: Option<String>,
+    pub product: Option<String>,
+    pub serial_number: Option<String>,
+}
+
+impl PortInfo {
+    // Description of known chip, if port looks like Meshtastic device
+    pub fn meshtastic_chip(&self) -> Option<&'static str> {
+        let (vid, pid) = (self.vid?, self.pid?);
+        MESHTASTIC_USB_IDS
+            .iter()
+            .find(|(known_vid, known_pid, _)| {
+                *known_vid == vid && known_pid.is_none_or(|known_pid| known_pid == pid)
+            })
+            .map(|(_, _, chip)| *chip)
+    }
+}
+
+impl fmt::Display for PortInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.tty)?;
+        if let (Some(vid), Some(pid)) = (self.vid, self.pid) {
+            write!(f, " [{:04x}:{:04x}]", vid, pid)?;
+        }
+        let description = [&self.manufacturer, &self.product]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        if !description.is_empty() {
+            write!(f, " {}", description.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum AutodetectError {
+    // No port with known USB ids, all found ports are listed
+    NotFound(Vec<PortInfo>),
+    // More than one port with known USB ids
+    Ambiguous(Vec<PortInfo>),
+}
+
+impl fmt::Display for AutodetectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (message, ports) = match self {
+            AutodetectError::NotFound(ports) => ("No Meshtastic device found", ports),
+            AutodetectError::Ambiguous(ports) => (
+                "Several Meshtastic devices found, set port explicitly",
+                ports,
+            ),
+        };
+        if ports.is_empty() {
+            return write!(f, "{}: no serial ports", message);
+        }
+        write!(f, "{}, candidates:", message)?;
+        for port in ports {
+            write!(f, "\n  {}", port)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for AutodetectError {}
+
+// Serial ports of system. Enumeration errors are treated as no ports
+pub fn list_ports() -> Vec<PortInfo> {
+    tokio_serial::available_ports()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|port| match port.port_type {
+            SerialPortType::UsbPort(usb) => PortInfo {
+                tty: port.port_name,
+                vid: Some(usb.vid),
+                pid: Some(usb.pid),
+                manufacturer: usb.manufacturer,
+                product: usb.product,
+                serial_number: usb.serial_number,
+            },
+            _ => PortInfo {
+                tty: port.port_name,
+                ..Default::default()
+            },
+        })
+        .collect()
+}
+
+fn select_meshtastic(ports: Vec<PortInfo>) -> Result<PortInfo, AutodetectError> {
+    let mut candidates = ports
+        .iter()
+        .filter(|port| port.meshtastic_chip().is_some())
+        .cloned()
+        .collect::<Vec<_>>();
+
+    match candidates.len() {
+        0 => Err(AutodetectError::NotFound(ports)),
+        1 => Ok(candidates.remove(0)),
+        _ => Err(AutodetectError::Ambiguous(candidates)),
+    }
+}
 
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct SerialBuilder {
     pub tty: String,
     pub baudrate: u32,
+    pub data_bits: DataBits,
+    pub parity: Parity,
+    pub stop_bits: StopBits,
+    pub flow_control: FlowControl,
+    // Stream returns `ErrorKind::TimedOut` when nothing is received
+    // for this time. Stream is not closed and can be polled further
+    pub read_timeout: Option<Duration>,
 }
 
 impl SerialBuilder {
+    // 8N1 without flow control
     pub fn new(tty: String, baudrate: u32) -> Self {
-        SerialBuilder { tty, baudrate }
+        SerialBuilder {
+            tty,
+            baudrate,
+            data_bits: DataBits::Eight,
+            parity: Parity::None,
+            stop_bits: StopBits::One,
+            flow_control: FlowControl::None,
+            read_timeout: None,
+        }
+    }
+
+    pub fn data_bits(mut self, data_bits: DataBits) -> Self {
+        self.data_bits = data_bits;
+        self
+    }
+
+    pub fn parity(mut self, parity: Parity) -> Self {
+        self.parity = parity;
+        self
+    }
+
+    pub fn stop_bits(mut self, stop_bits: StopBits) -> Self {
+        self.stop_bits = stop_bits;
+        self
+    }
+
+    pub fn flow_control(mut self, flow_control: FlowControl) -> Self {
+        self.flow_control = flow_control;
+        self
+    }
+
+    pub fn read_timeout(mut self, read_timeout: Duration) -> Self {
+        self.read_timeout = Some(read_timeout);
+        self
+    }
+
+    // Picks the only port with USB ids of Meshtastic's boards
+    pub fn autodetect_meshtastic(baudrate: u32) -> Result<Self, AutodetectError> {
+        let port = select_meshtastic(list_ports())?;
+        Ok(Self::new(port.tty, baudrate))
+    }
+
+    // `tty` is a path or `AUTODETECT_TTY`
+    pub fn with_tty(tty: String, baudrate: u32) -> Result<Self, AutodetectError> {
+        if tty == AUTODETECT_TTY {
+            Self::autodetect_meshtastic(baudrate)
+        } else {
+            Ok(Self::new(tty, baudrate))
+        }
     }
 
     pub async fn connect(&self) -> Result<Stream, std::io::Error> {
         let mut serial = tokio_serial::new(self.tty.clone(), self.baudrate)
-            .data_bits(tokio_serial::DataBits::Eight)
-            .parity(tokio_serial::Parity::None)
-            .stop_bits(tokio_serial::StopBits::One)
-            .flow_control(tokio_serial::FlowControl::None)
+            .data_bits(self.data_bits)
+            .parity(self.parity)
+            .stop_bits(self.stop_bits)
+            .flow_control(self.flow_control)
             .open_native_async()?;
-        serial.write_request_to_send(true)?;
+        // RTS is controlled by port itself with hardware flow control
+        if self.flow_control != FlowControl::Hardware {
+            serial.write_request_to_send(true)?;
+        }
         serial.write_data_terminal_ready(true)?;
-        let codec = MeshtasticStreamCodec {}.framed(serial);
-        Ok(Stream::Serial(codec))
+        let codec = MeshtasticStreamCodec::default().framed(serial);
+        Ok(Stream::Serial(
+            codec,
+            self.read_timeout.map(ReadTimeout::new),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn port(tty: &str, ids: Option<(u16, u16)>) -> PortInfo {
+        PortInfo {
+            tty: tty.into(),
+            vid: ids.map(|(vid, _)| vid),
+            pid: ids.map(|(_, pid)| pid),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn select_single_candidate() {
+        let ports = vec![
+            port("/dev/ttyS0", None),
+            port("/dev/ttyUSB0", Some((0x0403, 0x6001))),
+            port("/dev/ttyACM0", Some((0x239a, 0x8029))),
+        ];
+        assert_eq!(select_meshtastic(ports).unwrap().tty, "/dev/ttyACM0");
+    }
+
+    #[test]
+    fn select_ambiguous() {
+        let ports = vec![
+            port("/dev/ttyUSB0", Some((0x10c4, 0xea60))),
+            port("/dev/ttyACM0", Some((0x303a, 0x1001))),
+        ];
+        let error = select_meshtastic(ports).unwrap_err();
+        assert!(matches!(&error, AutodetectError::Ambiguous(ports) if ports.len() == 2));
+        assert!(error.to_string().contains("/dev/ttyUSB0 [10c4:ea60]"));
+    }
+
+    #[test]
+    fn select_not_found() {
+        let ports = vec![port("/dev/ttyUSB0", Some((0x1a86, 0x0001)))];
+        assert!(matches!(
+            select_meshtastic(ports),
+            Err(AutodetectError::NotFound(ports)) if ports.len() == 1
+        ));
     }
 }
@@ -17,7 +17,7 @@ impl TcpBuilder {
 
     pub async fn connect(&self) -> Result<Stream, std::io::Error> {
         let tcp = TcpStream::connect(self.socket_addr).await?;
-        let framed = MeshtasticStreamCodec {}.framed(tcp);
+        let framed = MeshtasticStreamCodec::default().framed(tcp);
         Ok(Stream::Tcp(framed))
     }
 }
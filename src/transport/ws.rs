@@ -0,0 +1,314 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use bytes::{Bytes, BytesMut};
+use futures::{Sink, StreamExt};
+use prost::Message as _;
+use tokio::net::TcpStream;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, tungstenite::Message};
+
+use crate::{
+    framing::{self, BytesSequence, StreamRecvData},
+    meshtastic,
+    transport::stream::Stream,
+};
+
+fn ws_error(e: tokio_tungstenite::tungstenite::Error) -> std::io::Error {
+    std::io::Error::other(e.to_string())
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WsBuilder {
+    // `ws://host:port/path`
+    pub url: String,
+}
+
+impl WsBuilder {
+    pub fn new(url: String) -> Self {
+        Self { url }
+    }
+
+    pub async fn connect(&self) -> Result<Stream, std::io::Error> {
+        let (websocket, _) = tokio_tungstenite::connect_async(self.url.as_str())
+            .await
+            .map_err(ws_error)?;
+        Ok(Stream::Ws(WsStream {
+            websocket,
+            last_frame: None,
+        }))
+    }
+
+    // Gives up after `attempts` failures in a row
+    pub async fn connect_with_retry(
+        &self,
+        attempts: u32,
+        delay: Duration,
+    ) -> Result<Stream, std::io::Error> {
+        let mut attempt = 1;
+        loop {
+            match self.connect().await {
+                Ok(stream) => return Ok(stream),
+                Err(e) if attempt >= attempts => return Err(e),
+                Err(_) => {
+                    attempt += 1;
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+    }
+
+    // Builder is kept by caller: when `stream` ends or fails, it's replaced by
+    // new connection with the same settings. Radio doesn't keep API's session:
+    // `WantConfigId` is to be sent again
+    pub async fn reconnect(
+        &self,
+        stream: &mut Stream,
+        attempts: u32,
+        delay: Duration,
+    ) -> Result<(), std::io::Error> {
+        *stream = self.connect_with_retry(attempts, delay).await?;
+        Ok(())
+    }
+}
+
+// One binary message carries one `ToRadio` or `FromRadio`, without
+// StreamAPI's framing. Text messages are passed as unstructured data
+pub struct WsStream {
+    websocket: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    // Received `FromRadio` with StreamAPI's header put before it
+    last_frame: Option<Bytes>,
+}
+
+impl WsStream {
+    pub fn last_frame(&self) -> Option<&Bytes> {
+        self.last_frame.as_ref()
+    }
+
+    fn start_send_bytes(&mut self, bytes: Vec<u8>) -> Result<(), std::io::Error> {
+        Pin::new(&mut self.websocket)
+            .start_send(Message::binary(bytes))
+            .map_err(ws_error)
+    }
+}
+
+impl Sink<meshtastic::to_radio::PayloadVariant> for WsStream {
+    type Error = std::io::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.get_mut().websocket)
+            .poll_ready(cx)
+            .map_err(ws_error)
+    }
+
+    fn start_send(
+        self: Pin<&mut Self>,
+        item: meshtastic::to_radio::PayloadVariant,
+    ) -> Result<(), Self::Error> {
+        let to_radio = meshtastic::ToRadio {
+            payload_variant: Some(item),
+        };
+        self.get_mut().start_send_bytes(to_radio.encode_to_vec())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.get_mut().websocket)
+            .poll_flush(cx)
+            .map_err(ws_error)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.get_mut().websocket)
+            .poll_close(cx)
+            .map_err(ws_error)
+    }
+}
+
+impl Sink<BytesSequence> for WsStream {
+    type Error = std::io::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.get_mut().websocket)
+            .poll_ready(cx)
+            .map_err(ws_error)
+    }
+
+    // Message is frame by itself: wakeup is not needed, header is not added
+    fn start_send(self: Pin<&mut Self>, item: BytesSequence) -> Result<(), Self::Error> {
+        match item {
+            BytesSequence::Wakeup => Ok(()),
+            BytesSequence::Unheaded(bytes) | BytesSequence::Headed(bytes) => {
+                self.get_mut().start_send_bytes(bytes.to_vec())
+            }
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.get_mut().websocket)
+            .poll_flush(cx)
+            .map_err(ws_error)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.get_mut().websocket)
+            .poll_close(cx)
+            .map_err(ws_error)
+    }
+}
+
+impl futures::Stream for WsStream {
+    type Item = Result<StreamRecvData, std::io::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            let message = match this.websocket.poll_next_unpin(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(ws_error(e)))),
+                Poll::Ready(Some(Ok(message))) => message,
+            };
+
+            let recv_data = match message {
+                Message::Binary(bytes) => {
+                    let mut frame = BytesMut::new();
+                    framing::encode_sequence(BytesSequence::Headed(bytes.clone()), &mut frame);
+                    this.last_frame = Some(frame.freeze());
+                    framing::decode_from_radio(&bytes)
+                }
+                Message::Text(text) => Ok(StreamRecvData::Unstructured(BytesMut::from(
+                    text.as_bytes(),
+                ))),
+                Message::Close(_) => return Poll::Ready(None),
+                // Pings are answered by tungstenite itself
+                Message::Ping(_) | Message::Pong(_) | Message::Frame(_) => continue,
+            };
+            return Poll::Ready(Some(recv_data));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::SinkExt;
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    async fn listen() -> (TcpListener, WsBuilder) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}/", listener.local_addr().unwrap());
+        (listener, WsBuilder::new(url))
+    }
+
+    async fn accept(listener: &TcpListener) -> WebSocketStream<TcpStream> {
+        let (tcp, _) = listener.accept().await.unwrap();
+        tokio_tungstenite::accept_async(tcp).await.unwrap()
+    }
+
+    #[test]
+    fn messages_are_radio_frames() {
+        block_on(async {
+            let (listener, builder) = listen().await;
+            let (stream, server) = tokio::join!(builder.connect(), accept(&listener));
+            let (mut stream, mut server) = (stream.unwrap(), server);
+
+            let from_radio = meshtastic::FromRadio {
+                id: 7,
+                payload_variant: Some(meshtastic::from_radio::PayloadVariant::ConfigCompleteId(1)),
+            };
+            server
+                .send(Message::binary(from_radio.encode_to_vec()))
+                .await
+                .unwrap();
+            server
+                .send(Message::binary(
+                    meshtastic::FromRadio::default().encode_to_vec(),
+                ))
+                .await
+                .unwrap();
+            server.send(Message::text("log")).await.unwrap();
+
+            assert!(matches!(
+                stream.next().await,
+                Some(Ok(StreamRecvData::FromRadio(
+                    7,
+                    meshtastic::from_radio::PayloadVariant::ConfigCompleteId(1)
+                )))
+            ));
+            let last_frame = stream.last_frame().unwrap();
+            assert_eq!(&last_frame[..2], [0x94, 0xc3]);
+            assert_eq!(&last_frame[4..], from_radio.encode_to_vec());
+            assert!(matches!(stream.next().await, Some(Err(_))));
+            assert!(matches!(
+                stream.next().await,
+                Some(Ok(StreamRecvData::Unstructured(text))) if &text[..] == b"log"
+            ));
+
+            stream
+                .send(meshtastic::to_radio::PayloadVariant::WantConfigId(3))
+                .await
+                .unwrap();
+            let Some(Ok(Message::Binary(bytes))) = server.next().await else {
+                panic!("Binary message is expected");
+            };
+            assert_eq!(
+                meshtastic::ToRadio::decode(&bytes[..])
+                    .unwrap()
+                    .payload_variant,
+                Some(meshtastic::to_radio::PayloadVariant::WantConfigId(3))
+            );
+        });
+    }
+
+    #[test]
+    fn reconnect_after_close() {
+        block_on(async {
+            let (listener, builder) = listen().await;
+            let (stream, server) = tokio::join!(builder.connect(), accept(&listener));
+            let mut stream = stream.unwrap();
+
+            drop(server);
+            assert!(
+                stream
+                    .next()
+                    .await
+                    .is_none_or(|recv_data| recv_data.is_err())
+            );
+
+            let (result, mut server) = tokio::join!(
+                builder.reconnect(&mut stream, 3, Duration::from_millis(10)),
+                accept(&listener)
+            );
+            result.unwrap();
+            server.send(Message::text("again")).await.unwrap();
+            assert!(matches!(
+                stream.next().await,
+                Some(Ok(StreamRecvData::Unstructured(text))) if &text[..] == b"again"
+            ));
+        });
+    }
+
+    #[test]
+    fn retry_gives_up() {
+        let error = block_on(async {
+            let (listener, builder) = listen().await;
+            drop(listener);
+            builder
+                .connect_with_retry(2, Duration::from_millis(10))
+                .await
+                .err()
+        });
+        assert!(error.is_some());
+    }
+}